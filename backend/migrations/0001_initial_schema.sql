@@ -0,0 +1,336 @@
+-- Schema of the SQLite database as Database::new created it before migrations were
+-- introduced. IF NOT EXISTS, so databases of those releases are adopted as they are;
+-- Database::new brings older ones up to this state first (upgrade_legacy_schema).
+
+CREATE TABLE IF NOT EXISTS details (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    email TEXT UNIQUE NOT NULL,
+    password TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    disabled_at DATETIME,
+    password_reset_required_at DATETIME,
+    password_changed_at DATETIME,
+    member_id TEXT
+);
+
+CREATE TABLE IF NOT EXISTS reset_tokens (
+    token_hash TEXT PRIMARY KEY,
+    member_id TEXT NOT NULL UNIQUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS work_hour_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    member_id TEXT NOT NULL,
+    date TEXT NOT NULL,
+    description TEXT NOT NULL,
+    hours REAL NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    conflict_reason TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS activity_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    member_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    message TEXT NOT NULL,
+    reference_id TEXT,
+    actor_id TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_activity_log_member ON activity_log (member_id, id);
+
+-- Who changed what, with snapshots of the record before and after (JSON)
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    before_snapshot TEXT,
+    after_snapshot TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log (target_id, id);
+
+-- Daily totals of all members, rebuilt from Teable by the stats refresh job
+CREATE TABLE IF NOT EXISTS work_hour_daily_stats (
+    date TEXT PRIMARY KEY,
+    year INTEGER NOT NULL,
+    hours REAL NOT NULL,
+    entries INTEGER NOT NULL
+);
+
+-- How often each description was used per year, rebuilt along with the daily totals
+CREATE TABLE IF NOT EXISTS description_usage (
+    year INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (year, description)
+);
+
+-- Password reset emails and what the mail server answered, for the board; no tokens
+CREATE TABLE IF NOT EXISTS password_reset_requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    subject TEXT NOT NULL,
+    member_id TEXT NOT NULL,
+    email TEXT NOT NULL,
+    resent_by TEXT,
+    status TEXT NOT NULL,
+    error TEXT,
+    requested_at DATETIME NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_password_reset_requests_subject ON password_reset_requests (subject, requested_at);
+
+-- Descriptions the board suggests for the "Tätigkeit" field
+CREATE TABLE IF NOT EXISTS description_suggestions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    text TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    created_by TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS guest_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    member_id TEXT NOT NULL,
+    member_name TEXT NOT NULL,
+    date TEXT NOT NULL,
+    guest_name TEXT NOT NULL,
+    fee REAL NOT NULL,
+    paid_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS stats_refreshes (
+    year INTEGER PRIMARY KEY,
+    refreshed_at DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS member_requirements (
+    member_id TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    required_hours REAL NOT NULL,
+    exemption_reason TEXT,
+    notified_at DATETIME,
+    updated_at DATETIME NOT NULL,
+    PRIMARY KEY (member_id, year)
+);
+
+-- Work hour certificates; the summary (JSON) is frozen when a board member approves
+CREATE TABLE IF NOT EXISTS certificate_requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    member_id TEXT NOT NULL,
+    member_name TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    summary TEXT,
+    approved_by TEXT,
+    approved_at DATETIME,
+    requested_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (member_id, year)
+);
+
+-- Separate logins for family members sharing an email, approved by the board
+CREATE TABLE IF NOT EXISTS family_invitations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    invited_by TEXT NOT NULL,
+    member_id TEXT NOT NULL,
+    member_name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    status TEXT NOT NULL,
+    decided_by TEXT,
+    decided_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_family_invitations_pending_email ON family_invitations (email) WHERE status = 'pending';
+
+-- TOTP secrets of members with two-factor authentication, encrypted
+CREATE TABLE IF NOT EXISTS two_factor (
+    member_id TEXT PRIMARY KEY,
+    secret TEXT NOT NULL,
+    enabled_at DATETIME,
+    last_used_step INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Forced password rotations and their queued reset emails
+CREATE TABLE IF NOT EXISTS password_rotations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_by TEXT NOT NULL,
+    started_at DATETIME NOT NULL,
+    completed_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS password_rotation_emails (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rotation_id INTEGER NOT NULL,
+    email TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    sent_at DATETIME,
+    FOREIGN KEY (rotation_id) REFERENCES password_rotations(id) ON DELETE CASCADE
+);
+
+-- Work hours deleted from Teable, restorable until the retention period ends
+CREATE TABLE IF NOT EXISTS deleted_work_hours (
+    work_hour_id TEXT PRIMARY KEY,
+    member_id TEXT NOT NULL,
+    date TEXT NOT NULL,
+    description TEXT NOT NULL,
+    hours REAL NOT NULL,
+    status TEXT,
+    review_comment TEXT,
+    deleted_by TEXT NOT NULL,
+    deleted_at DATETIME NOT NULL
+);
+
+-- Background jobs of the board, processed in chunks; `processed` is the resume point
+CREATE TABLE IF NOT EXISTS jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    total INTEGER NOT NULL,
+    processed INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    finished_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS job_errors (
+    job_id INTEGER NOT NULL,
+    item_index INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    code TEXT,
+    retryable INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE
+);
+
+-- Single row holding the random instance ID of the opt-in telemetry
+CREATE TABLE IF NOT EXISTS telemetry (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    instance_id TEXT NOT NULL,
+    last_sent_at DATETIME
+);
+
+-- Old Teable record IDs of merged members, so their tokens keep working
+CREATE TABLE IF NOT EXISTS member_aliases (
+    old_id TEXT PRIMARY KEY,
+    new_id TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Results of the nightly integrity check and VACUUM
+CREATE TABLE IF NOT EXISTS maintenance_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at DATETIME NOT NULL,
+    finished_at DATETIME NOT NULL,
+    integrity_errors TEXT NOT NULL,
+    vacuumed INTEGER NOT NULL,
+    size_before INTEGER NOT NULL,
+    size_after INTEGER NOT NULL,
+    error TEXT
+);
+
+-- IDs of issued selection tokens; deleted on first use
+CREATE TABLE IF NOT EXISTS selection_tokens (
+    jti TEXT PRIMARY KEY,
+    expires_at DATETIME NOT NULL
+);
+
+-- Write freeze scheduled by the board; at most one at a time
+CREATE TABLE IF NOT EXISTS write_freeze (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    from_date TEXT NOT NULL,
+    until_date TEXT NOT NULL,
+    reason TEXT,
+    set_by TEXT NOT NULL,
+    set_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Links of the rating survey sent after a work event, one per helper. Answers are
+-- stored apart from the links, so they can't be traced back to a member.
+CREATE TABLE IF NOT EXISTS survey_invitations (
+    token_hash TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    event_title TEXT NOT NULL,
+    event_date TEXT NOT NULL,
+    member_id TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL,
+    answered_at DATETIME,
+    UNIQUE (event_id, member_id)
+);
+
+CREATE TABLE IF NOT EXISTS survey_responses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id TEXT NOT NULL,
+    rating INTEGER NOT NULL,
+    comment TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Monthly totals of feature usage for the board's analytics; nothing about who
+CREATE TABLE IF NOT EXISTS usage_counters (
+    month TEXT NOT NULL,
+    feature TEXT NOT NULL,
+    count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (month, feature)
+);
+
+-- Changelog of the club's rules shown to members; detected changes and the board's own
+CREATE TABLE IF NOT EXISTS rule_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    subject TEXT NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    effective_from TEXT NOT NULL,
+    note TEXT,
+    source TEXT NOT NULL,
+    recorded_by TEXT,
+    recorded_at DATETIME NOT NULL,
+    announced_at DATETIME
+);
+
+-- Emails the mail server didn't take, retried by the outbox worker; the bodies are
+-- cleared once an email is finished, as they may contain reset links
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    html TEXT NOT NULL,
+    text TEXT NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    last_error TEXT,
+    created_at DATETIME NOT NULL,
+    next_attempt_at DATETIME,
+    sent_at DATETIME
+);
+
+-- Single row holding the rules as JSON as they were at the last changelog check
+CREATE TABLE IF NOT EXISTS rules_snapshot (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    rules TEXT NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+-- Members and families reminded of missing hours, one row per reminder day
+CREATE TABLE IF NOT EXISTS hours_reminders (
+    recipient_key TEXT NOT NULL,
+    reminder_day TEXT NOT NULL,
+    sent_at DATETIME NOT NULL,
+    PRIMARY KEY (recipient_key, reminder_day)
+);
+
+-- Members whose bearer tokens were revoked, e.g. with `admin revoke-tokens`
+CREATE TABLE IF NOT EXISTS revoked_sessions (
+    member_id TEXT PRIMARY KEY,
+    revoked_before DATETIME NOT NULL
+);
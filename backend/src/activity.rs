@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// What happened to a member, as shown in the activity feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    WorkHourCreated,
+    WorkHourUpdated,
+    WorkHourDeleted,
+    /// A deleted entry was brought back from the trash
+    WorkHourRestored,
+    WorkHourApproved,
+    WorkHourRejected,
+    /// An entry edited or corrected by a board member
+    WorkHourCorrected,
+    PasswordChanged,
+    EventSignedUp,
+    EventSignupCancelled,
+    /// Attendance at a work event turned into a work hour entry
+    EventAttended,
+    /// The member's age-based work hour obligation changed with the new year
+    RequirementChanged,
+    /// The board approved a work hour certificate, which can now be downloaded
+    CertificateApproved,
+}
+
+impl ActivityKind {
+    /// Value stored in the `kind` column of the activity log
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::WorkHourCreated => "work_hour_created",
+            ActivityKind::WorkHourUpdated => "work_hour_updated",
+            ActivityKind::WorkHourDeleted => "work_hour_deleted",
+            ActivityKind::WorkHourRestored => "work_hour_restored",
+            ActivityKind::WorkHourApproved => "work_hour_approved",
+            ActivityKind::WorkHourRejected => "work_hour_rejected",
+            ActivityKind::WorkHourCorrected => "work_hour_corrected",
+            ActivityKind::PasswordChanged => "password_changed",
+            ActivityKind::EventSignedUp => "event_signed_up",
+            ActivityKind::EventSignupCancelled => "event_signup_cancelled",
+            ActivityKind::EventAttended => "event_attended",
+            ActivityKind::RequirementChanged => "requirement_changed",
+            ActivityKind::CertificateApproved => "certificate_approved",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+}
+
+/// One item of a member's activity feed
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ActivityEntry {
+    pub id: i32,
+    pub kind: ActivityKind,
+    /// German text for the "Was ist passiert" panel
+    pub message: String,
+    /// Work hour entry or event the activity refers to
+    pub reference_id: Option<String>,
+    /// Set when someone other than the member caused the activity, e.g. a board member
+    pub actor_id: Option<String>,
+    /// RFC 3339 timestamp
+    pub created_at: String,
+}
+
+/// An activity to record for `member_id`
+#[derive(Debug, Clone)]
+pub struct NewActivity<'a> {
+    pub member_id: &'a str,
+    pub kind: ActivityKind,
+    pub message: String,
+    pub reference_id: Option<&'a str>,
+    pub actor_id: Option<&'a str>,
+}
+
+impl<'a> NewActivity<'a> {
+    pub fn new(member_id: &'a str, kind: ActivityKind, message: String) -> Self {
+        NewActivity {
+            member_id,
+            kind,
+            message,
+            reference_id: None,
+            actor_id: None,
+        }
+    }
+
+    pub fn reference(mut self, reference_id: &'a str) -> Self {
+        self.reference_id = Some(reference_id);
+        self
+    }
+
+    /// Records who caused the activity, unless it was the member themselves
+    pub fn actor(mut self, actor_id: &'a str) -> Self {
+        if actor_id != self.member_id {
+            self.actor_id = Some(actor_id);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    pub limit: Option<u32>,
+    /// Only entries older than this ID, for loading further pages
+    pub before: Option<i32>,
+}
+
+pub const DEFAULT_ACTIVITY_LIMIT: u32 = 50;
+pub const MAX_ACTIVITY_LIMIT: u32 = 200;
+
+impl ActivityQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_ACTIVITY_LIMIT)
+            .clamp(1, MAX_ACTIVITY_LIMIT)
+    }
+}
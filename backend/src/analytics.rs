@@ -0,0 +1,121 @@
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use chrono_tz::Europe::Berlin;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+/// Months shown by default, one season and a bit
+const DEFAULT_MONTHS: u32 = 12;
+const MAX_MONTHS: u32 = 36;
+
+/// A feature whose use is counted, stored in the `feature` column of `usage_counters`. Only
+/// monthly totals are kept, never who used a feature, so no third party analytics are needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageFeature {
+    DashboardView,
+    /// Entered by the member in the app, one at a time or in bulk
+    EntryCreatedApp,
+    /// Imported by the board as a background job
+    EntryCreatedImport,
+    /// Credited to the helpers when the board completes a work event
+    EntryCreatedEvent,
+    EventSignup,
+}
+
+impl UsageFeature {
+    /// Every feature, in the order the trends are listed
+    pub const ALL: [UsageFeature; 5] = [
+        UsageFeature::DashboardView,
+        UsageFeature::EntryCreatedApp,
+        UsageFeature::EntryCreatedImport,
+        UsageFeature::EntryCreatedEvent,
+        UsageFeature::EventSignup,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageFeature::DashboardView => "dashboard_view",
+            UsageFeature::EntryCreatedApp => "entry_created_app",
+            UsageFeature::EntryCreatedImport => "entry_created_import",
+            UsageFeature::EntryCreatedEvent => "entry_created_event",
+            UsageFeature::EventSignup => "event_signup",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(value.to_string())).ok()
+    }
+}
+
+/// `?months=N` of `GET /admin/analytics`
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    pub months: Option<u32>,
+}
+
+impl AnalyticsQuery {
+    /// Between 1 and 36, 12 if not given
+    pub fn months(&self) -> u32 {
+        self.months.unwrap_or(DEFAULT_MONTHS).clamp(1, MAX_MONTHS)
+    }
+}
+
+/// Use of one feature per month
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct FeatureTrend {
+    pub feature: UsageFeature,
+    /// One count per month of `UsageAnalytics::months`
+    pub counts: Vec<u64>,
+    pub total: u64,
+}
+
+/// Monthly feature usage for the board, from `GET /admin/analytics`
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct UsageAnalytics {
+    /// YYYY-MM in club time, oldest first, up to the current month
+    pub months: Vec<String>,
+    pub features: Vec<FeatureTrend>,
+}
+
+/// Month of `at` in club time, e.g. `2025-03`; the key of the counters
+pub fn month_of(at: DateTime<Utc>) -> String {
+    at.with_timezone(&Berlin).format("%Y-%m").to_string()
+}
+
+/// The last `count` months up to the one of `now`, oldest first
+pub fn recent_months(now: DateTime<Utc>, count: u32) -> Vec<String> {
+    let today = now.with_timezone(&Berlin).date_naive();
+    let first = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap_or(today);
+    (0..count)
+        .rev()
+        .filter_map(|back| first.checked_sub_months(Months::new(back)))
+        .map(|month| month.format("%Y-%m").to_string())
+        .collect()
+}
+
+/// Lays the stored counters, as month, feature and count, out as one trend per feature.
+/// Months without counters are zero; counters outside `months` are left out.
+pub fn trends(months: Vec<String>, counters: &[(String, UsageFeature, u64)]) -> UsageAnalytics {
+    let features = UsageFeature::ALL
+        .iter()
+        .map(|&feature| {
+            let counts: Vec<u64> = months
+                .iter()
+                .map(|month| {
+                    counters
+                        .iter()
+                        .filter(|(m, f, _)| m == month && *f == feature)
+                        .map(|(_, _, count)| count)
+                        .sum()
+                })
+                .collect();
+            FeatureTrend {
+                feature,
+                total: counts.iter().sum(),
+                counts,
+            }
+        })
+        .collect();
+    UsageAnalytics { months, features }
+}
@@ -0,0 +1,381 @@
+use crate::models::{Member, WorkHour};
+use crate::schema::{FieldNames, MemberField, TeableField, WorkHourField};
+use chrono::{Datelike, Duration, NaiveDate};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{Row, SqlitePool};
+use std::path::Path;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Domain of pseudonymized email addresses; `.invalid` can never deliver, so a staging
+/// instance with working SMTP settings still can't mail members
+pub const EMAIL_DOMAIN: &str = "example.invalid";
+
+/// Stands in for free-text exemption reasons, which may mention health or family matters
+const EXEMPTION_REASON: &str = "Befreit (anonymisiert)";
+
+const FIRST_NAMES: [&str; 40] = [
+    "Anna", "Ben", "Clara", "David", "Emma", "Felix", "Greta", "Hannes", "Ida", "Jonas", "Karla",
+    "Lukas", "Marie", "Niklas", "Olivia", "Paul", "Rosa", "Simon", "Theresa", "Valentin", "Wiebke",
+    "Yannik", "Zoe", "Anton", "Bettina", "Carsten", "Doris", "Erik", "Frieda", "Gerhard", "Helga",
+    "Ingo", "Jutta", "Klaus", "Lena", "Moritz", "Nina", "Oskar", "Petra", "Ralf",
+];
+
+const LAST_NAMES: [&str; 40] = [
+    "Albers",
+    "Brandt",
+    "Claussen",
+    "Dietrich",
+    "Ehlers",
+    "Fischer",
+    "Grote",
+    "Hansen",
+    "Iversen",
+    "Jansen",
+    "Kruse",
+    "Lange",
+    "Meyer",
+    "Nielsen",
+    "Otte",
+    "Petersen",
+    "Quast",
+    "Rohde",
+    "Schulz",
+    "Thiessen",
+    "Ulrich",
+    "Voss",
+    "Wagner",
+    "Zimmermann",
+    "Bergmann",
+    "Carstens",
+    "Dreyer",
+    "Friedrichsen",
+    "Gerdes",
+    "Hinrichs",
+    "Jürgens",
+    "Kock",
+    "Lorenzen",
+    "Martens",
+    "Nagel",
+    "Ohlsen",
+    "Paulsen",
+    "Reimers",
+    "Sievers",
+    "Timm",
+];
+
+/// Replaces names, email addresses and birth dates by made-up ones. The same input and salt
+/// always give the same pseudonym, so a member keeps one name across tables and runs, and
+/// family members sharing an email address still share one. Without the salt the pseudonyms
+/// can't be traced back by hashing known names.
+pub struct Pseudonymizer {
+    key: Vec<u8>,
+}
+
+impl Pseudonymizer {
+    pub fn new(salt: &str) -> Self {
+        Pseudonymizer {
+            key: salt.as_bytes().to_vec(),
+        }
+    }
+
+    fn digest(&self, kind: &str, value: &str) -> [u8; 32] {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(kind.as_bytes());
+        mac.update(&[0]);
+        mac.update(value.trim().to_lowercase().as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    fn number(&self, kind: &str, value: &str) -> u64 {
+        let digest = self.digest(kind, value);
+        u64::from_be_bytes(digest[..8].try_into().expect("digest has 32 bytes"))
+    }
+
+    fn pick(&self, kind: &str, value: &str, names: &[&str]) -> String {
+        names[(self.number(kind, value) % names.len() as u64) as usize].to_string()
+    }
+
+    pub fn first_name(&self, first_name: &str) -> String {
+        self.pick("first_name", first_name, &FIRST_NAMES)
+    }
+
+    pub fn last_name(&self, last_name: &str) -> String {
+        self.pick("last_name", last_name, &LAST_NAMES)
+    }
+
+    /// `Vorname Nachname` as stored for guest sessions and certificates; the first word is
+    /// taken as first name, so the result matches the pseudonyms of the Teable record
+    pub fn full_name(&self, name: &str) -> String {
+        match name.trim().split_once(' ') {
+            Some((first, last)) => format!("{} {}", self.first_name(first), self.last_name(last)),
+            None => self.last_name(name),
+        }
+    }
+
+    /// Case doesn't matter, as for logins; empty addresses stay empty
+    pub fn email(&self, email: &str) -> String {
+        if email.trim().is_empty() {
+            return String::new();
+        }
+        let digest = self.digest("email", email);
+        format!("staging-{}@{}", hex::encode(&digest[..4]), EMAIL_DOMAIN)
+    }
+
+    /// Another day of the same year, so ages and age-based rules stay about the same;
+    /// anything that isn't `YYYY-MM-DD` is dropped
+    pub fn birth_date(&self, date: &str) -> String {
+        let Ok(date) = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") else {
+            return String::new();
+        };
+        let first = NaiveDate::from_ymd_opt(date.year(), 1, 1).expect("January 1st exists");
+        let days = if first.leap_year() { 366 } else { 365 };
+        let offset = self.number("birth_date", &date.to_string()) % days;
+        (first + Duration::days(offset as i64))
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+
+    /// Pseudonymizes the personal fields of an audit snapshot, at any depth
+    pub fn scrub_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields.iter_mut() {
+                    let replacement = match (key.as_str(), &*field) {
+                        ("Email" | "email", Value::String(s)) => Some(self.email(s)),
+                        ("Vorname" | "first_name", Value::String(s)) => Some(self.first_name(s)),
+                        ("Nachname" | "last_name", Value::String(s)) => Some(self.last_name(s)),
+                        ("member_name" | "guest_name", Value::String(s)) => Some(self.full_name(s)),
+                        ("Geburtsdatum" | "birth_date", Value::String(s)) => {
+                            Some(self.birth_date(s))
+                        }
+                        _ => None,
+                    };
+                    match replacement {
+                        Some(replacement) => *field = Value::String(replacement),
+                        None => self.scrub_json(field),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.scrub_json(item)),
+            _ => {}
+        }
+    }
+}
+
+/// Teable fields of the members table the staging replica is built from
+pub const MEMBER_FIELDS: [MemberField; 9] = [
+    MemberField::FirstName,
+    MemberField::LastName,
+    MemberField::Email,
+    MemberField::Family,
+    MemberField::BirthDate,
+    MemberField::JoinDate,
+    MemberField::Contribution,
+    MemberField::ContributionPaid,
+    MemberField::SepaDate,
+];
+
+/// A member as Teable record (`id` and `fields`) for importing into the staging base. Record
+/// IDs are kept, so they still match the `member_id` columns of the cloned database.
+pub fn member_record(pseudonymizer: &Pseudonymizer, member: &Member, names: &FieldNames) -> Value {
+    json!({
+        "id": member.id,
+        "fields": {
+            (MemberField::FirstName.name(names)): pseudonymizer.first_name(&member.first_name),
+            (MemberField::LastName.name(names)): pseudonymizer.last_name(&member.last_name),
+            (MemberField::Email.name(names)): pseudonymizer.email(&member.email),
+            (MemberField::Family.name(names)): member.family_id,
+            (MemberField::BirthDate.name(names)): pseudonymizer.birth_date(&member.birth_date),
+            (MemberField::JoinDate.name(names)): member.join_date,
+            (MemberField::Contribution.name(names)): member.contribution,
+            (MemberField::ContributionPaid.name(names)): member.contribution_paid,
+            (MemberField::SepaDate.name(names)): member.sepa_date,
+        }
+    })
+}
+
+/// A work hour entry as Teable record, with the looked up member name pseudonymized. Notes and
+/// attachments are left out, as they may name or show people.
+pub fn work_hour_record(
+    pseudonymizer: &Pseudonymizer,
+    work_hour: &WorkHour,
+    names: &FieldNames,
+) -> Value {
+    json!({
+        "id": work_hour.id,
+        "fields": {
+            (WorkHourField::Member.name(names)): work_hour.get_member_id(),
+            (WorkHourField::FirstName.name(names)): work_hour.first_name.as_deref().map(|name| pseudonymizer.first_name(name)),
+            (WorkHourField::LastName.name(names)): work_hour.last_name.as_deref().map(|name| pseudonymizer.last_name(name)),
+            (WorkHourField::Date.name(names)): work_hour.date,
+            (WorkHourField::Description.name(names)): work_hour.description,
+            (WorkHourField::Hours.name(names)): work_hour.duration_hours,
+            (WorkHourField::Status.name(names)): work_hour.status,
+            (WorkHourField::ReviewComment.name(names)): work_hour.review_comment,
+            (WorkHourField::Correction.name(names)): work_hour.corrects,
+            (WorkHourField::Event.name(names)): work_hour.event_id,
+        }
+    })
+}
+
+/// What `anonymize_database` changed
+#[derive(Debug, Default)]
+pub struct AnonymizeReport {
+    pub accounts: u64,
+    pub updated_rows: u64,
+    pub deleted_rows: u64,
+}
+
+/// Copies the SQLite database at `source` to `target` with `VACUUM INTO`, which gives a
+/// consistent snapshot even while the production instance keeps writing. `target` must not
+/// exist yet.
+pub async fn clone_database(source: &Path, target: &Path) -> Result<(), sqlx::Error> {
+    let pool =
+        SqlitePool::connect(&format!("sqlite://{}?mode=ro", source.to_string_lossy())).await?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(target.to_string_lossy().to_string())
+        .execute(&pool)
+        .await?;
+    pool.close().await;
+    Ok(())
+}
+
+/// Pseudonymizes a cloned database in place: names, email addresses and audit snapshots are
+/// replaced, every account gets `password_hash`, and secrets that would work against
+/// production (reset tokens, 2FA secrets) or identify it (the telemetry instance ID) are
+/// deleted. Never run this on the production database itself.
+pub async fn anonymize_database(
+    pool: &SqlitePool,
+    pseudonymizer: &Pseudonymizer,
+    password_hash: &str,
+) -> Result<AnonymizeReport, sqlx::Error> {
+    let mut report = AnonymizeReport::default();
+    let mut tx = pool.begin().await?;
+
+    for row in sqlx::query("SELECT id, email FROM details")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        let email: String = row.get("email");
+        sqlx::query("UPDATE details SET email = ?, password = ? WHERE id = ?")
+            .bind(pseudonymizer.email(&email))
+            .bind(password_hash)
+            .bind(row.get::<i64, _>("id"))
+            .execute(&mut *tx)
+            .await?;
+        report.accounts += 1;
+    }
+
+    for row in sqlx::query("SELECT id, email FROM password_rotation_emails")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        let email: String = row.get("email");
+        report.updated_rows += sqlx::query(
+            "UPDATE password_rotation_emails SET email = ?, last_error = NULL WHERE id = ?",
+        )
+        .bind(pseudonymizer.email(&email))
+        .bind(row.get::<i64, _>("id"))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    for row in sqlx::query("SELECT id, member_name, email FROM family_invitations")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        let member_name: String = row.get("member_name");
+        let email: String = row.get("email");
+        report.updated_rows +=
+            sqlx::query("UPDATE family_invitations SET member_name = ?, email = ? WHERE id = ?")
+                .bind(pseudonymizer.full_name(&member_name))
+                .bind(pseudonymizer.email(&email))
+                .bind(row.get::<i64, _>("id"))
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+    }
+
+    for row in sqlx::query("SELECT id, member_name, guest_name FROM guest_sessions")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        let member_name: String = row.get("member_name");
+        let guest_name: String = row.get("guest_name");
+        report.updated_rows +=
+            sqlx::query("UPDATE guest_sessions SET member_name = ?, guest_name = ? WHERE id = ?")
+                .bind(pseudonymizer.full_name(&member_name))
+                .bind(pseudonymizer.full_name(&guest_name))
+                .bind(row.get::<i64, _>("id"))
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+    }
+
+    for row in sqlx::query("SELECT id, member_name FROM certificate_requests")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        let member_name: String = row.get("member_name");
+        report.updated_rows +=
+            sqlx::query("UPDATE certificate_requests SET member_name = ? WHERE id = ?")
+                .bind(pseudonymizer.full_name(&member_name))
+                .bind(row.get::<i64, _>("id"))
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+    }
+
+    for row in sqlx::query("SELECT id, before_snapshot, after_snapshot FROM audit_log")
+        .fetch_all(&mut *tx)
+        .await?
+    {
+        let scrub = |snapshot: Option<String>| {
+            snapshot.map(|snapshot| match serde_json::from_str::<Value>(&snapshot) {
+                Ok(mut value) => {
+                    pseudonymizer.scrub_json(&mut value);
+                    value.to_string()
+                }
+                // Not ours to interpret; better lost than leaked
+                Err(_) => "null".to_string(),
+            })
+        };
+        report.updated_rows += sqlx::query(
+            "UPDATE audit_log SET before_snapshot = ?, after_snapshot = ? WHERE id = ?",
+        )
+        .bind(scrub(row.get("before_snapshot")))
+        .bind(scrub(row.get("after_snapshot")))
+        .bind(row.get::<i64, _>("id"))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    report.updated_rows += sqlx::query(
+        "UPDATE member_requirements SET exemption_reason = ? WHERE exemption_reason IS NOT NULL",
+    )
+    .bind(EXEMPTION_REASON)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    for table in [
+        "reset_tokens",
+        "selection_tokens",
+        "two_factor",
+        "telemetry",
+    ] {
+        report.deleted_rows += sqlx::query(&format!("DELETE FROM {table}"))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+
+    tx.commit().await?;
+    Ok(report)
+}
@@ -0,0 +1,77 @@
+use crate::error::ErrorCode;
+use axum::response::{IntoResponse, Json, Response};
+use serde::Serialize;
+use specta::Type;
+
+/// Body of every JSON response of the API. Successful requests set `data` (and `message` where
+/// there is something to confirm), failed ones `error` and `code`, so clients can handle all
+/// endpoints the same way instead of looking for endpoint-specific keys.
+#[derive(Debug, Serialize, Type)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    /// German text for display: a confirmation, or on failure the same text as `error`, which
+    /// clients written before `error` existed still read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// German text for display, on failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// On failure; see the error table of the README
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    /// On failure, to quote when reporting it, see `request_id`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn ok(data: T) -> Self {
+        ApiResponse {
+            success: true,
+            data: Some(data),
+            message: None,
+            error: None,
+            code: None,
+            request_id: None,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+impl ApiResponse<()> {
+    /// Success without data, e.g. "Passwort erfolgreich zurückgesetzt."
+    pub fn done(message: impl Into<String>) -> Self {
+        ApiResponse {
+            success: true,
+            data: None,
+            message: Some(message.into()),
+            error: None,
+            code: None,
+            request_id: None,
+        }
+    }
+
+    pub fn error(code: ErrorCode, error: impl Into<String>, request_id: Option<String>) -> Self {
+        let error = error.into();
+        ApiResponse {
+            success: false,
+            data: None,
+            message: Some(error.clone()),
+            error: Some(error),
+            code: Some(code),
+            request_id,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
@@ -0,0 +1,196 @@
+use crate::jobs::{Job, JobKind, JobStatus};
+use crate::member_change::MemberChangeField;
+use crate::models::{WorkHourEntry, WorkHourStatus};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+/// Version of the event contract, sent with every event. Adding a variant or an optional
+/// field keeps the version; consumers ignore event types and fields they don't know.
+/// Renaming or removing anything, or changing what a field means, needs a new version.
+pub const APP_EVENT_VERSION: u32 = 1;
+
+/// Something that happened in the app, as sent to outbound webhooks and the SPA's event
+/// stream. Both use these payloads, so external consumers and the frontend share one
+/// contract, exported to TypeScript with the other API types.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "type")]
+pub enum AppEvent {
+    #[serde(rename = "work_hour.created")]
+    WorkHourCreated(WorkHourEventData),
+    #[serde(rename = "work_hour.updated")]
+    WorkHourUpdated(WorkHourEventData),
+    #[serde(rename = "work_hour.deleted")]
+    WorkHourDeleted(WorkHourDeletedData),
+    /// A board member approved or rejected an entry
+    #[serde(rename = "work_hour.reviewed")]
+    WorkHourReviewed(WorkHourReviewedData),
+    /// A member signed up for or cancelled a work event (Arbeitseinsatz)
+    #[serde(rename = "event_signup.changed")]
+    EventSignupChanged(EventSignupData),
+    #[serde(rename = "job.progress")]
+    JobProgress(JobProgressData),
+    /// The board edited records directly in Teable; clients reload what they show of the
+    /// table
+    #[serde(rename = "records.changed")]
+    RecordsChanged(RecordsChangedData),
+    /// A board member changed the member's family or required hours
+    #[serde(rename = "member.updated")]
+    MemberUpdated(MemberUpdatedData),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct WorkHourEventData {
+    pub work_hour_id: String,
+    pub member_id: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    pub description: String,
+    pub hours: f64,
+    pub status: WorkHourStatus,
+    /// Set on corrections by the board, which have negative hours: the corrected entry
+    pub corrects: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct WorkHourDeletedData {
+    pub work_hour_id: String,
+    pub member_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct WorkHourReviewedData {
+    pub work_hour_id: String,
+    pub member_id: String,
+    pub status: WorkHourStatus,
+    pub comment: Option<String>,
+    /// Board member who reviewed the entry
+    pub reviewed_by: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct EventSignupData {
+    pub event_id: String,
+    pub member_id: String,
+    /// `false` if the sign-up was cancelled
+    pub signed_up: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct JobProgressData {
+    pub job_id: i64,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub processed: u32,
+    pub failed: u32,
+    pub total: u32,
+}
+
+/// A Teable table the backend reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum TeableTable {
+    Members,
+    WorkHours,
+    Events,
+    EventSignups,
+    Settings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RecordsChangedData {
+    pub table: TeableTable,
+    pub record_ids: Vec<String>,
+    /// Member the records belong to, if known
+    pub member_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct MemberUpdatedData {
+    pub member_id: String,
+    /// The audit log entry with the old and new values; the values themselves aren't sent
+    pub audit_id: i64,
+    pub fields: Vec<MemberChangeField>,
+}
+
+impl AppEvent {
+    /// The `type` tag, also used as SSE event name and webhook event header
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::WorkHourCreated(_) => "work_hour.created",
+            AppEvent::WorkHourUpdated(_) => "work_hour.updated",
+            AppEvent::WorkHourDeleted(_) => "work_hour.deleted",
+            AppEvent::WorkHourReviewed(_) => "work_hour.reviewed",
+            AppEvent::EventSignupChanged(_) => "event_signup.changed",
+            AppEvent::JobProgress(_) => "job.progress",
+            AppEvent::RecordsChanged(_) => "records.changed",
+            AppEvent::MemberUpdated(_) => "member.updated",
+        }
+    }
+
+    /// Member the event is about, for delivering it only to their own event stream; `None`
+    /// for events of the board
+    pub fn member_id(&self) -> Option<&str> {
+        match self {
+            AppEvent::WorkHourCreated(data) | AppEvent::WorkHourUpdated(data) => {
+                Some(&data.member_id)
+            }
+            AppEvent::WorkHourDeleted(data) => Some(&data.member_id),
+            AppEvent::WorkHourReviewed(data) => Some(&data.member_id),
+            AppEvent::EventSignupChanged(data) => Some(&data.member_id),
+            AppEvent::JobProgress(_) => None,
+            AppEvent::RecordsChanged(data) => data.member_id.as_deref(),
+            AppEvent::MemberUpdated(data) => Some(&data.member_id),
+        }
+    }
+}
+
+impl WorkHourEventData {
+    pub fn from_entry(member_id: &str, entry: &WorkHourEntry) -> Self {
+        WorkHourEventData {
+            work_hour_id: entry.id.clone(),
+            member_id: member_id.to_string(),
+            date: entry.date.clone(),
+            description: entry.description.clone(),
+            hours: entry.duration_hours,
+            status: entry.status,
+            corrects: entry.corrects.clone(),
+        }
+    }
+}
+
+impl From<&Job> for JobProgressData {
+    fn from(job: &Job) -> Self {
+        JobProgressData {
+            job_id: job.id,
+            kind: job.kind,
+            status: job.status,
+            processed: job.processed,
+            failed: job.failed,
+            total: job.total,
+        }
+    }
+}
+
+/// An event as delivered: the payload with the contract version, a unique ID for
+/// deduplicating redeliveries and the time it happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct AppEventEnvelope {
+    pub version: u32,
+    pub id: String,
+    /// RFC 3339
+    pub occurred_at: String,
+    pub event: AppEvent,
+}
+
+impl AppEventEnvelope {
+    pub fn new(event: AppEvent) -> Self {
+        AppEventEnvelope {
+            version: APP_EVENT_VERSION,
+            id: Uuid::new_v4().to_string(),
+            occurred_at: Utc::now().to_rfc3339(),
+            event,
+        }
+    }
+}
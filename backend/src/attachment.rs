@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+/// Larger files are refused; phone photos stay well below
+pub const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Body limit of upload requests: the file plus the multipart framing around it
+pub const UPLOAD_BODY_LIMIT: usize = MAX_ATTACHMENT_BYTES + 64 * 1024;
+
+/// Attachments per entry, enough for a photo before and after
+pub const MAX_ATTACHMENTS: usize = 5;
+
+pub const MAX_NOTE_CHARS: usize = 1000;
+
+/// A file attached to a work hour entry, as listed in `WorkHourEntry`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct WorkHourAttachment {
+    /// Teable's ID of the attachment, for deleting it
+    pub id: String,
+    pub name: String,
+    pub mimetype: String,
+    /// Bytes
+    pub size: Option<u64>,
+    /// Download URL signed by Teable, valid for a limited time only
+    pub url: Option<String>,
+}
+
+/// Body of `PUT /arbeitsstunden/{id}/note`
+#[derive(Debug, Deserialize, Type)]
+pub struct WorkHourNoteRequest {
+    /// Empty or `null` removes the note
+    pub note: Option<String>,
+}
+
+impl WorkHourNoteRequest {
+    /// The note to store, trimmed and `None` if empty, or the message for the member
+    pub fn validate(&self) -> Result<Option<String>, &'static str> {
+        let note = self
+            .note
+            .as_deref()
+            .map(str::trim)
+            .filter(|note| !note.is_empty());
+        if note.is_some_and(|note| note.chars().count() > MAX_NOTE_CHARS) {
+            return Err("Die Notiz darf höchstens 1000 Zeichen lang sein.");
+        }
+        Ok(note.map(str::to_string))
+    }
+}
+
+/// Reads the attachments from the value of the Teable attachment field; empty cells are `null`
+pub fn from_field(value: &Value) -> Vec<WorkHourAttachment> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|attachment| {
+            Some(WorkHourAttachment {
+                id: attachment["id"].as_str()?.to_string(),
+                name: attachment["name"].as_str().unwrap_or_default().to_string(),
+                mimetype: attachment["mimetype"]
+                    .as_str()
+                    .unwrap_or("application/octet-stream")
+                    .to_string(),
+                size: attachment["size"].as_u64(),
+                url: attachment["presignedUrl"].as_str().map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Photos and PDFs; anything else has no place as proof of work
+pub fn is_allowed_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    matches!(
+        mime.as_str(),
+        "image/jpeg" | "image/png" | "image/webp" | "image/heic" | "application/pdf"
+    )
+}
+
+/// File name as stored in Teable: the base name without directories or control characters,
+/// `anhang` if nothing is left
+pub fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base.chars().filter(|c| !c.is_control()).take(100).collect();
+    let cleaned = cleaned.trim();
+    if cleaned.is_empty() || cleaned.starts_with('.') {
+        "anhang".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
@@ -0,0 +1,197 @@
+use crate::models::WorkHour;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+/// What was changed, stored in the `action` column of the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    WorkHourCreated,
+    WorkHourUpdated,
+    WorkHourDeleted,
+    WorkHourRestored,
+    WorkHourApproved,
+    WorkHourRejected,
+    /// A board member booked a correction entry with negative hours
+    WorkHourCorrectionCreated,
+    PasswordReset,
+    /// A board member sent a member a new password reset link
+    PasswordResetResent,
+    AccountDeactivated,
+    MemberInvited,
+    EventCreated,
+    EventCompleted,
+    GuestFeePaid,
+    CertificateApproved,
+    PasswordRotationStarted,
+    JobStarted,
+    JobCancelled,
+    MemberAliasCreated,
+    FamilyInvitationApproved,
+    FamilyInvitationRejected,
+    TwoFactorEnabled,
+    TwoFactorDisabled,
+    ProfileUpdated,
+    /// A board member changed a member's family or required hours; the changed fields are
+    /// stored as `before` and `after`
+    MemberUpdated,
+    WriteFreezeSet,
+    WriteFreezeCleared,
+    DescriptionSuggestionCreated,
+    DescriptionSuggestionDeleted,
+    /// A board member added a change to the rules changelog
+    RuleChangeRecorded,
+    RuleChangeNoteUpdated,
+    /// A board member emailed a rule change to all members
+    RuleChangeAnnounced,
+    /// A board member emailed every member the summary of a year
+    YearSummarySent,
+    /// A board member started seeing the app as a member
+    ImpersonationStarted,
+    /// A change a board member made while seeing the app as a member; the method, path and
+    /// response status are stored as `after`
+    ImpersonatedRequest,
+}
+
+impl AuditAction {
+    /// Value stored in the `action` column of the audit log
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::WorkHourCreated => "work_hour_created",
+            AuditAction::WorkHourUpdated => "work_hour_updated",
+            AuditAction::WorkHourDeleted => "work_hour_deleted",
+            AuditAction::WorkHourRestored => "work_hour_restored",
+            AuditAction::WorkHourApproved => "work_hour_approved",
+            AuditAction::WorkHourRejected => "work_hour_rejected",
+            AuditAction::WorkHourCorrectionCreated => "work_hour_correction_created",
+            AuditAction::PasswordReset => "password_reset",
+            AuditAction::PasswordResetResent => "password_reset_resent",
+            AuditAction::AccountDeactivated => "account_deactivated",
+            AuditAction::MemberInvited => "member_invited",
+            AuditAction::EventCreated => "event_created",
+            AuditAction::EventCompleted => "event_completed",
+            AuditAction::GuestFeePaid => "guest_fee_paid",
+            AuditAction::CertificateApproved => "certificate_approved",
+            AuditAction::PasswordRotationStarted => "password_rotation_started",
+            AuditAction::JobStarted => "job_started",
+            AuditAction::JobCancelled => "job_cancelled",
+            AuditAction::MemberAliasCreated => "member_alias_created",
+            AuditAction::FamilyInvitationApproved => "family_invitation_approved",
+            AuditAction::FamilyInvitationRejected => "family_invitation_rejected",
+            AuditAction::TwoFactorEnabled => "two_factor_enabled",
+            AuditAction::TwoFactorDisabled => "two_factor_disabled",
+            AuditAction::ProfileUpdated => "profile_updated",
+            AuditAction::MemberUpdated => "member_updated",
+            AuditAction::WriteFreezeSet => "write_freeze_set",
+            AuditAction::WriteFreezeCleared => "write_freeze_cleared",
+            AuditAction::DescriptionSuggestionCreated => "description_suggestion_created",
+            AuditAction::DescriptionSuggestionDeleted => "description_suggestion_deleted",
+            AuditAction::RuleChangeRecorded => "rule_change_recorded",
+            AuditAction::RuleChangeNoteUpdated => "rule_change_note_updated",
+            AuditAction::RuleChangeAnnounced => "rule_change_announced",
+            AuditAction::YearSummarySent => "year_summary_sent",
+            AuditAction::ImpersonationStarted => "impersonation_started",
+            AuditAction::ImpersonatedRequest => "impersonated_request",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(value.to_string())).ok()
+    }
+}
+
+/// One row of the audit log
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Member who made the change
+    pub actor_id: String,
+    pub action: AuditAction,
+    /// Work hour entry, account, member, event, guest session or certificate that was changed
+    pub target_id: String,
+    /// The record before the change, `None` for creations
+    pub before: Option<Value>,
+    /// The record after the change, `None` for deletions
+    pub after: Option<Value>,
+    /// RFC 3339 timestamp
+    pub created_at: String,
+}
+
+/// A change to record in the audit log
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry<'a> {
+    pub actor_id: &'a str,
+    pub action: AuditAction,
+    pub target_id: &'a str,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl<'a> NewAuditEntry<'a> {
+    pub fn new(actor_id: &'a str, action: AuditAction, target_id: &'a str) -> Self {
+        NewAuditEntry {
+            actor_id,
+            action,
+            target_id,
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn before(mut self, snapshot: Value) -> Self {
+        self.before = Some(snapshot);
+        self
+    }
+
+    pub fn after(mut self, snapshot: Value) -> Self {
+        self.after = Some(snapshot);
+        self
+    }
+}
+
+/// The fields of a work hour entry kept in audit snapshots
+pub fn work_hour_snapshot(work_hour: &WorkHour) -> Value {
+    serde_json::json!({
+        "Mitglied_id": work_hour.get_member_id(),
+        "Datum": work_hour.date,
+        "Tätigkeit": work_hour.description,
+        "Stunden": work_hour.duration_hours,
+        "Status": work_hour.status,
+        "Prüfkommentar": work_hour.review_comment,
+        "Korrektur_von": work_hour.corrects,
+        "Arbeitseinsatz_id": work_hour.event_id,
+        "Notiz": work_hour.note,
+        "Anhänge": work_hour
+            .attachments
+            .iter()
+            .map(|attachment| attachment.name.as_str())
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Filters of `GET /admin/audit`; all of them are optional and combined with AND
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    pub actor_id: Option<String>,
+    pub action: Option<AuditAction>,
+    pub target_id: Option<String>,
+    /// First day to include (`YYYY-MM-DD`, UTC)
+    pub from: Option<chrono::NaiveDate>,
+    /// Last day to include (`YYYY-MM-DD`, UTC)
+    pub to: Option<chrono::NaiveDate>,
+    pub limit: Option<u32>,
+    /// Only entries older than this ID, for loading further pages
+    pub before: Option<i64>,
+}
+
+pub const DEFAULT_AUDIT_LIMIT: u32 = 100;
+pub const MAX_AUDIT_LIMIT: u32 = 500;
+
+impl AuditQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_AUDIT_LIMIT)
+            .clamp(1, MAX_AUDIT_LIMIT)
+    }
+}
@@ -1,14 +1,68 @@
 use crate::config::Config;
+use crate::token_store::SELECTION_TOKEN_TTL;
+use crate::utils;
+use axum::http::HeaderMap;
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Lifetime of login tokens
+pub const TOKEN_TTL_SECS: usize = 24 * 60 * 60;
+/// With sliding expiration, tokens with less time left are re-issued
+pub const TOKEN_REFRESH_THRESHOLD_SECS: u64 = 60 * 60;
+/// Response header with the seconds until the request's token expires, or the refreshed one
+pub const TOKEN_EXPIRES_IN_HEADER: &str = "x-token-expires-in";
+/// Response header carrying a new bearer token, when the old one named a merged member or is
+/// about to expire; the frontend replaces its stored token with it
+pub const REFRESHED_TOKEN_HEADER: &str = "x-refreshed-token";
+/// Lifetime of the tokens board members get to see the app as a member; they aren't refreshed
+pub const IMPERSONATION_TTL_SECS: usize = 30 * 60;
+
+/// Routes an impersonation token can't use, as they change how the member logs in, hand out
+/// longer-lived tokens or start another impersonation. Paths are relative to `/api`.
+const IMPERSONATION_BLOCKED_PATHS: [&str; 5] = [
+    "/2fa/",
+    "/user/profile",
+    "/switch-member",
+    "/arbeitsstunden/calendar-token",
+    "/admin/impersonate/",
+];
+
+/// Whether a board member seeing the app as a member may call `path`
+pub fn impersonation_allows(path: &str) -> bool {
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    !IMPERSONATION_BLOCKED_PATHS
+        .iter()
+        .any(|blocked| path.starts_with(blocked))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AuthClaims {
     pub sub: String, // User ID
     pub exp: usize,  // Expiration time
     pub iat: usize,  // Issued at
+    /// Board member acting as `sub`, see `create_impersonation_token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<String>,
+}
+
+impl AuthClaims {
+    /// Seconds until the token expires
+    pub fn expires_in(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        (self.exp as u64).saturating_sub(now)
+    }
+
+    /// Whether the token should be swapped for a fresh one: less than an hour left and
+    /// `TOKEN_SLIDING_EXPIRATION=true`, so active members aren't logged out mid-session
+    pub fn needs_refresh(&self, config: &Config) -> bool {
+        config.token_sliding_expiration && self.expires_in() < TOKEN_REFRESH_THRESHOLD_SECS
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,12 +70,11 @@ pub struct SelectionTokenClaims {
     pub sub: String, // email
     pub exp: usize,
     pub typ: String, // always "selection"
+    pub jti: String, // issued by the token store, which lets it be used once
+    pub fpr: String, // client_fingerprint of the login request
 }
 
-pub fn create_token(user_id: &str) -> Result<String, jsonwebtoken::errors::Error> {
-    let config = Config::from_env().map_err(|_| {
-        jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)
-    })?;
+pub fn create_token(config: &Config, user_id: &str) -> Result<String, jsonwebtoken::errors::Error> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -29,8 +82,9 @@ pub fn create_token(user_id: &str) -> Result<String, jsonwebtoken::errors::Error
 
     let claims = AuthClaims {
         sub: user_id.to_string(),
-        exp: now + 24 * 60 * 60, // 24 hours
+        exp: now + TOKEN_TTL_SECS,
         iat: now,
+        impersonator: None,
     };
 
     encode(
@@ -40,10 +94,31 @@ pub fn create_token(user_id: &str) -> Result<String, jsonwebtoken::errors::Error
     )
 }
 
-pub fn verify_token(token: &str) -> Result<AuthClaims, jsonwebtoken::errors::Error> {
-    let config = Config::from_env().map_err(|_| {
-        jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)
-    })?;
+/// Short-lived token with which the board member `impersonator_id` sees the app as
+/// `member_id`, e.g. to find out why a dashboard looks wrong
+pub fn create_impersonation_token(
+    config: &Config,
+    member_id: &str,
+    impersonator_id: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now().timestamp() as usize;
+    let claims = AuthClaims {
+        sub: member_id.to_string(),
+        exp: now + IMPERSONATION_TTL_SECS,
+        iat: now,
+        impersonator: Some(impersonator_id.to_string()),
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_ref()),
+    )
+}
+
+pub fn verify_token(
+    config: &Config,
+    token: &str,
+) -> Result<AuthClaims, jsonwebtoken::errors::Error> {
     decode::<AuthClaims>(
         token,
         &DecodingKey::from_secret(config.jwt_secret.as_ref()),
@@ -52,15 +127,41 @@ pub fn verify_token(token: &str) -> Result<AuthClaims, jsonwebtoken::errors::Err
     .map(|data| data.claims)
 }
 
-pub fn create_selection_token(email: &str) -> Result<String, jsonwebtoken::errors::Error> {
-    let config = Config::from_env().map_err(|_| {
-        jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)
-    })?;
-    let expiration = Utc::now() + Duration::minutes(5);
+/// Claims of the request's bearer token, if it is valid
+pub fn bearer_claims(config: &Config, headers: &HeaderMap) -> Option<AuthClaims> {
+    headers
+        .get("authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .and_then(|token| verify_token(config, token).ok())
+}
+
+/// Hash of the client's IP and User-Agent. A selection token only works from the client
+/// that logged in, so a leaked one can't be used elsewhere.
+pub fn client_fingerprint(headers: &HeaderMap) -> String {
+    let user_agent = headers
+        .get("user-agent")
+        .and_then(|ua| ua.to_str().ok())
+        .unwrap_or_default();
+    let ip = utils::client_ip(headers).unwrap_or_default();
+    hex::encode(Sha256::digest(format!("{ip}\n{user_agent}").as_bytes()))
+}
+
+/// Token for picking one of several members sharing `email`, with the `jti` from
+/// `ResetTokenStore::issue_selection_token`
+pub fn create_selection_token(
+    config: &Config,
+    email: &str,
+    jti: &str,
+    fingerprint: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let expiration = Utc::now() + SELECTION_TOKEN_TTL;
     let claims = SelectionTokenClaims {
         sub: email.to_string(),
         exp: expiration.timestamp() as usize,
         typ: "selection".to_string(),
+        jti: jti.to_string(),
+        fpr: fingerprint.to_string(),
     };
     encode(
         &Header::default(),
@@ -69,16 +170,70 @@ pub fn create_selection_token(email: &str) -> Result<String, jsonwebtoken::error
     )
 }
 
-pub fn verify_selection_token(token: &str) -> Result<String, jsonwebtoken::errors::Error> {
-    let config = Config::from_env().map_err(|_| {
-        jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)
-    })?;
+/// Checks signature, expiry and that the token was issued to the client with `fingerprint`.
+/// Whether it was used already is up to the token store, see `consume_selection_token`.
+pub fn verify_selection_token(
+    config: &Config,
+    token: &str,
+    fingerprint: &str,
+) -> Result<SelectionTokenClaims, jsonwebtoken::errors::Error> {
     let token_data: jsonwebtoken::TokenData<SelectionTokenClaims> = decode::<SelectionTokenClaims>(
         token,
         &DecodingKey::from_secret(config.jwt_secret.as_ref()),
         &Validation::default(),
     )?;
-    if token_data.claims.typ != "selection" {
+    if token_data.claims.typ != "selection" || token_data.claims.fpr != fingerprint {
+        return Err(jsonwebtoken::errors::Error::from(
+            jsonwebtoken::errors::ErrorKind::InvalidToken,
+        ));
+    }
+    Ok(token_data.claims)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeedTokenClaims {
+    pub sub: String, // Teable member ID
+    pub exp: usize,
+    pub iat: usize,
+    pub typ: String, // always "feed"
+}
+
+/// Feed tokens are signed with a key derived from the JWT secret, so a leaked
+/// calendar URL can never be used as a bearer token for the API
+fn feed_signing_key(config: &Config) -> Vec<u8> {
+    format!("{}:feed", config.jwt_secret).into_bytes()
+}
+
+/// Creates a long-lived, read-only token for the member's calendar feed
+pub fn create_feed_token(
+    config: &Config,
+    member_id: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let claims = FeedTokenClaims {
+        sub: member_id.to_string(),
+        exp: (now + Duration::days(config.feed_token_ttl_days)).timestamp() as usize,
+        iat: now.timestamp() as usize,
+        typ: "feed".to_string(),
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&feed_signing_key(config)),
+    )
+}
+
+/// Returns the member ID of a valid feed token
+pub fn verify_feed_token(
+    config: &Config,
+    token: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let token_data = decode::<FeedTokenClaims>(
+        token,
+        &DecodingKey::from_secret(&feed_signing_key(config)),
+        &Validation::default(),
+    )?;
+    if token_data.claims.typ != "feed" {
         return Err(jsonwebtoken::errors::Error::from(
             jsonwebtoken::errors::ErrorKind::InvalidToken,
         ));
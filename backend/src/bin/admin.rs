@@ -0,0 +1,290 @@
+//! Account maintenance for the board, working directly on the SQLite database and Teable
+//!
+//! For what the admin pages can't do, or while the server is down. Run on the server with:
+//! `cargo run --bin admin -- list-users`
+//!
+//! Passwords given without `--password` are read from the first line of stdin, so they
+//! don't end up in the shell history: `echo "$PASSWORD" | admin reset-password a@example.org`.
+//! The database is `DATABASE_URL`; `revoke-tokens` and `recalc-hours` also need the Teable
+//! configuration of the server.
+
+use chrono::{Datelike, Utc};
+use clap::{Parser, Subcommand};
+use std::io::BufRead;
+use std::process::ExitCode;
+use tsv_tennis_backend::config::{Config, EmailConfig};
+use tsv_tennis_backend::database::{CreateUserRequest, Database};
+use tsv_tennis_backend::email::EmailService;
+use tsv_tennis_backend::export::Locale;
+use tsv_tennis_backend::password_policy::PasswordPolicy;
+use tsv_tennis_backend::password_rotation::REVOKED_SESSIONS_REFRESH;
+use tsv_tennis_backend::requirements;
+use tsv_tennis_backend::teable::{self, TeableClient};
+
+/// Account maintenance for the board, on the SQLite database and Teable
+#[derive(Debug, Parser)]
+#[command(name = "admin")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, PartialEq, Subcommand)]
+enum Command {
+    /// Create a login
+    CreateUser {
+        #[arg(value_parser = email)]
+        email: String,
+        /// Read from the first line of stdin if not given
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Set a new password
+    ResetPassword {
+        #[arg(value_parser = email)]
+        email: String,
+        /// Read from the first line of stdin if not given
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// List all logins
+    ListUsers,
+    /// Log out everywhere and invalidate reset links
+    RevokeTokens {
+        #[arg(value_parser = email)]
+        email: String,
+    },
+    /// Rebuild the work hour requirements
+    RecalcHours {
+        /// Defaults to the current year
+        #[arg(long)]
+        year: Option<i32>,
+    },
+}
+
+/// Logins are stored with lowercase addresses
+fn email(value: &str) -> Result<String, String> {
+    let email = value.trim().to_lowercase();
+    if !email.contains('@') {
+        return Err(format!("{value} is not an email address"));
+    }
+    Ok(email)
+}
+
+/// The `--password` or the first line of stdin, checked against the password policy
+fn new_password(password: Option<String>, email: &str) -> anyhow::Result<String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+    Config::from_env()
+        .map(|config| PasswordPolicy::from_config(&config))
+        .unwrap_or_default()
+        .check(&password, Some(email))
+        .map_err(|problem| anyhow::anyhow!("{}", problem.message(Locale::De)))?;
+    Ok(password)
+}
+
+fn config() -> anyhow::Result<Config> {
+    Config::from_env().map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+fn teable_client() -> anyhow::Result<TeableClient> {
+    TeableClient::new(&config()?)
+}
+
+async fn run(command: Command, database: &Database) -> anyhow::Result<()> {
+    match command {
+        Command::CreateUser { email, password } => {
+            if database.get_user_by_email(&email).await?.is_some() {
+                anyhow::bail!("{email} already has a login");
+            }
+            let password = new_password(password, &email)?;
+            let id = database
+                .create_user(CreateUserRequest {
+                    email: email.clone(),
+                    password,
+                })
+                .await?;
+            println!("✅ Login {id} for {email} created");
+        }
+        Command::ResetPassword { email, password } => {
+            let user = database
+                .get_user_by_email(&email)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No login for {email}"))?;
+            let password = new_password(password, &email)?;
+            database.update_password(user.id, &password).await?;
+            println!("✅ Password of {email} set");
+            if user.disabled_at.is_some() {
+                println!("⚠️  The login is deactivated; reactivate it on the admin page");
+            }
+        }
+        Command::ListUsers => {
+            let users = database.list_users().await?;
+            for user in &users {
+                let status = if user.disabled_at.is_some() {
+                    "deactivated"
+                } else if user.password_reset_required_at.is_some() {
+                    "reset required"
+                } else {
+                    "active"
+                };
+                println!(
+                    "{:>5}  {:<40}  {:<16}  {:<14}  {}",
+                    user.id,
+                    user.email,
+                    user.member_id.as_deref().unwrap_or("-"),
+                    status,
+                    user.created_at.format("%Y-%m-%d")
+                );
+            }
+            println!("{} logins", users.len());
+        }
+        Command::RevokeTokens { email } => {
+            // Tokens are issued for members: the one of an invitation login, else all
+            // members with the address
+            let member_ids = match database
+                .get_user_by_email(&email)
+                .await?
+                .and_then(|user| user.member_id)
+            {
+                Some(member_id) => vec![member_id],
+                None => teable::get_members_by_email(&teable_client()?, &email)
+                    .await?
+                    .into_iter()
+                    .map(|member| member.id)
+                    .collect(),
+            };
+            if member_ids.is_empty() {
+                anyhow::bail!("No member with {email}");
+            }
+            let now = Utc::now();
+            for member_id in &member_ids {
+                database.revoke_member_sessions(member_id, now).await?;
+            }
+            println!(
+                "✅ Sessions and reset links of {} revoked, a running server rejects them within {}s",
+                member_ids.join(", "),
+                REVOKED_SESSIONS_REFRESH.as_secs()
+            );
+        }
+        Command::RecalcHours { year } => {
+            let year = year.unwrap_or_else(|| Utc::now().year());
+            let email_config = EmailConfig::from_env().map_err(|e| anyhow::anyhow!("{e}"))?;
+            let email_service = EmailService::new(email_config, &config()?)
+                .map_err(|e| anyhow::anyhow!("{e}"))?
+                .with_outbox(database.clone());
+            let summary =
+                requirements::recalculate_year(&teable_client()?, database, &email_service, year)
+                    .await?;
+            println!(
+                "✅ Requirements of {year} recalculated for {} members, {} notified about a changed status",
+                summary.members, summary.notified
+            );
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+
+    let Cli { command } = Cli::parse();
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL must be set");
+        return ExitCode::from(2);
+    };
+    let database = match Database::new(&database_url).await {
+        Ok(database) => database,
+        Err(e) => {
+            eprintln!("❌ Could not open {database_url}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(command, &database).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Result<Command, clap::Error> {
+        Cli::try_parse_from(std::iter::once("admin").chain(args.iter().copied()))
+            .map(|cli| cli.command)
+    }
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_commands_are_parsed() {
+        assert_eq!(
+            parse(&["create-user", " Anna@Example.org "]).unwrap(),
+            Command::CreateUser {
+                email: "anna@example.org".to_string(),
+                password: None,
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "reset-password",
+                "anna@example.org",
+                "--password",
+                "geheim123"
+            ])
+            .unwrap(),
+            Command::ResetPassword {
+                email: "anna@example.org".to_string(),
+                password: Some("geheim123".to_string()),
+            }
+        );
+        assert_eq!(parse(&["list-users"]).unwrap(), Command::ListUsers);
+        assert_eq!(
+            parse(&["revoke-tokens", "anna@example.org"]).unwrap(),
+            Command::RevokeTokens {
+                email: "anna@example.org".to_string(),
+            }
+        );
+        assert_eq!(
+            parse(&["recalc-hours"]).unwrap(),
+            Command::RecalcHours { year: None }
+        );
+        assert_eq!(
+            parse(&["recalc-hours", "--year", "2024"]).unwrap(),
+            Command::RecalcHours { year: Some(2024) }
+        );
+    }
+
+    #[test]
+    fn test_invalid_arguments_are_rejected() {
+        for args in [
+            &[][..],
+            &["unknown"],
+            &["create-user"],
+            &["create-user", "not-an-address"],
+            &["create-user", "a@example.org", "b@example.org"],
+            &["create-user", "a@example.org", "--year", "2024"],
+            &["list-users", "a@example.org"],
+            &["revoke-tokens", "a@example.org", "--password", "geheim123"],
+            &["recalc-hours", "--year", "letztes"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?} was accepted");
+        }
+    }
+}
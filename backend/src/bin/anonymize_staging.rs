@@ -0,0 +1,167 @@
+//! Builds a staging dataset from production data without member PII
+//!
+//! Clones the SQLite database, pseudonymizes names, email addresses and birth dates and sets
+//! one known password on every account. With `--teable-export` the members and work hours in
+//! Teable are written as pseudonymized JSON records for importing into the staging base.
+//! Run with: `cargo run --bin anonymize-staging -- --source data/auth.db --target staging.db`
+//!
+//! `ANONYMIZE_SALT` keys the pseudonyms; keep it secret and reuse it, so repeated runs give
+//! the same names. `STAGING_PASSWORD` becomes the password of all staging accounts.
+
+use chrono::Datelike;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tsv_tennis_backend::anonymize::{self, Pseudonymizer};
+use tsv_tennis_backend::config::Config;
+use tsv_tennis_backend::database::Database;
+use tsv_tennis_backend::teable;
+
+const USAGE: &str = "Usage: anonymize-staging --source PROD.db --target STAGING.db [--force] \
+                     [--teable-export DIR]";
+
+#[derive(Debug, Default)]
+struct Args {
+    source: Option<PathBuf>,
+    target: Option<PathBuf>,
+    /// Replace an existing target file
+    force: bool,
+    teable_export: Option<PathBuf>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Option<Args> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--source" => parsed.source = Some(args.next()?.into()),
+            "--target" => parsed.target = Some(args.next()?.into()),
+            "--teable-export" => parsed.teable_export = Some(args.next()?.into()),
+            "--force" => parsed.force = true,
+            _ => return None,
+        }
+    }
+    Some(parsed)
+}
+
+async fn clone_and_anonymize(
+    source: &Path,
+    target: &Path,
+    pseudonymizer: &Pseudonymizer,
+    password: &str,
+) -> anyhow::Result<anonymize::AnonymizeReport> {
+    anonymize::clone_database(source, target).await?;
+    let url = format!("sqlite://{}", target.to_string_lossy());
+    // Brings the clone up to the current schema, in case production runs an older release
+    drop(Database::new(&url).await?);
+
+    let password_hash = bcrypt::hash(password, bcrypt::DEFAULT_COST)?;
+    let pool = SqlitePool::connect(&url).await?;
+    let report = anonymize::anonymize_database(&pool, pseudonymizer, &password_hash).await?;
+    sqlx::query("VACUUM").execute(&pool).await?;
+    pool.close().await;
+    Ok(report)
+}
+
+/// Writes `members.json` and `work_hours.json` (this and last year) into `dir`
+async fn export_teable(dir: &Path, pseudonymizer: &Pseudonymizer) -> anyhow::Result<()> {
+    let config = Config::from_env().map_err(|e| anyhow::anyhow!("{e}"))?;
+    let client = teable::TeableClient::new(&config)?;
+    std::fs::create_dir_all(dir)?;
+
+    let members = teable::get_all_members_with_projection(&client, &anonymize::MEMBER_FIELDS)
+        .await?
+        .iter()
+        .map(|member| anonymize::member_record(pseudonymizer, member, client.fields()))
+        .collect::<Vec<_>>();
+    std::fs::write(
+        dir.join("members.json"),
+        serde_json::to_string_pretty(&members)?,
+    )?;
+    println!("✅ {} members exported", members.len());
+
+    let year = chrono::Utc::now().year();
+    let mut work_hours = Vec::new();
+    for year in [year - 1, year] {
+        for work_hour in teable::get_work_hours_by_year(&client, year).await? {
+            work_hours.push(anonymize::work_hour_record(
+                pseudonymizer,
+                &work_hour,
+                client.fields(),
+            ));
+        }
+    }
+    std::fs::write(
+        dir.join("work_hours.json"),
+        serde_json::to_string_pretty(&work_hours)?,
+    )?;
+    println!("✅ {} work hour entries exported", work_hours.len());
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+
+    let Some(Args {
+        source: Some(source),
+        target: Some(target),
+        force,
+        teable_export,
+    }) = parse_args(std::env::args().skip(1))
+    else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    let (Ok(salt), Ok(password)) = (
+        std::env::var("ANONYMIZE_SALT"),
+        std::env::var("STAGING_PASSWORD"),
+    ) else {
+        eprintln!("ANONYMIZE_SALT and STAGING_PASSWORD must be set");
+        return ExitCode::from(2);
+    };
+    if source == target {
+        eprintln!("❌ Source and target are the same file");
+        return ExitCode::from(2);
+    }
+    if target.exists() {
+        if !force {
+            eprintln!(
+                "❌ {} already exists, pass --force to replace it",
+                target.display()
+            );
+            return ExitCode::from(2);
+        }
+        if let Err(e) = std::fs::remove_file(&target) {
+            eprintln!("❌ Could not remove {}: {e}", target.display());
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let pseudonymizer = Pseudonymizer::new(&salt);
+    println!(
+        "🔄 Cloning {} into {}...",
+        source.display(),
+        target.display()
+    );
+    match clone_and_anonymize(&source, &target, &pseudonymizer, &password).await {
+        Ok(report) => println!(
+            "✅ {} accounts and {} other rows pseudonymized, {} secrets removed",
+            report.accounts, report.updated_rows, report.deleted_rows
+        ),
+        Err(e) => {
+            // A half-anonymized copy must not be mistaken for a finished one
+            let _ = std::fs::remove_file(&target);
+            eprintln!("❌ Anonymizing failed: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Some(dir) = teable_export {
+        println!("🔄 Exporting Teable members and work hours...");
+        if let Err(e) = export_teable(&dir, &pseudonymizer).await {
+            eprintln!("❌ Teable export failed: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
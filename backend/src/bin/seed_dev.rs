@@ -0,0 +1,278 @@
+//! Fills a local setup with demo data, so the full stack runs without production credentials
+//!
+//! Creates (or resets the password of) a login in the SQLite database and, if a development
+//! Teable base is configured, demo members of two families and some of their work hours.
+//! The login belongs to one of the demo members, so its dashboard has data.
+//! Run with: `cargo run --bin seed-dev -- --email dev@example.org --password entwicklung123`
+//!
+//! The database is `DATABASE_URL` (default: `sqlite://data/dev.db?mode=rwc`). The Teable base
+//! is read from `DEV_TEABLE_API_URL`, `DEV_TEABLE_TOKEN`, `DEV_MEMBERS_TABLE_ID` and
+//! `DEV_WORK_HOURS_TABLE_ID`, never from the variables the server uses, so a `.env` pointing
+//! at production can't be filled with demo data. Without them only the login is created.
+
+use chrono::{Datelike, Duration, Utc};
+use std::process::ExitCode;
+use tsv_tennis_backend::config::Config;
+use tsv_tennis_backend::database::{CreateUserRequest, Database};
+use tsv_tennis_backend::models::WorkHourStatus;
+use tsv_tennis_backend::teable::{self, NewMember, NewWorkHour, TeableClient};
+
+const USAGE: &str = "Usage: seed-dev [--email EMAIL] [--password PASSWORD]";
+
+const DEFAULT_DATABASE_URL: &str = "sqlite://data/dev.db?mode=rwc";
+const DEFAULT_EMAIL: &str = "dev@example.org";
+const DEFAULT_PASSWORD: &str = "entwicklung123";
+
+/// Variables of the Teable base, each read with a `DEV_` prefix
+const TEABLE_VARS: [&str; 4] = [
+    "TEABLE_API_URL",
+    "TEABLE_TOKEN",
+    "MEMBERS_TABLE_ID",
+    "WORK_HOURS_TABLE_ID",
+];
+
+#[derive(Debug)]
+struct Args {
+    email: String,
+    password: String,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Option<Args> {
+    let mut parsed = Args {
+        email: DEFAULT_EMAIL.to_string(),
+        password: DEFAULT_PASSWORD.to_string(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--email" => parsed.email = args.next()?.to_lowercase(),
+            "--password" => parsed.password = args.next()?,
+            _ => return None,
+        }
+    }
+    Some(parsed)
+}
+
+/// Demo member: first name, last name, family, birth year
+type DemoMember = (&'static str, &'static str, Option<&'static str>, i32);
+
+/// Two families and a single member; the first one gets the login
+const DEMO_MEMBERS: [DemoMember; 6] = [
+    ("Max", "Mustermann", Some("DEMO-1"), 1980),
+    ("Erika", "Mustermann", Some("DEMO-1"), 1982),
+    ("Lena", "Mustermann", Some("DEMO-1"), 2008),
+    ("Jonas", "Beispiel", Some("DEMO-2"), 1975),
+    ("Sophie", "Beispiel", Some("DEMO-2"), 2016),
+    ("Anna", "Schmidt", None, 1990),
+];
+
+/// Work hours by index into `DEMO_MEMBERS`: days ago, activity, hours, status
+const DEMO_WORK_HOURS: [(usize, i64, &str, f64, WorkHourStatus); 7] = [
+    (0, 3, "Plätze abziehen", 2.0, WorkHourStatus::Submitted),
+    (
+        0,
+        20,
+        "Frühjahrsputz Clubhaus",
+        3.5,
+        WorkHourStatus::Approved,
+    ),
+    (0, 400, "Hecke schneiden", 4.0, WorkHourStatus::Approved),
+    (
+        1,
+        12,
+        "Kuchen für das Sommerfest",
+        1.5,
+        WorkHourStatus::Approved,
+    ),
+    (2, 8, "Bälle sortieren", 1.0, WorkHourStatus::Rejected),
+    (3, 30, "Netze aufhängen", 2.5, WorkHourStatus::Approved),
+    (5, 5, "Linien nachziehen", 3.0, WorkHourStatus::Submitted),
+];
+
+fn demo_email(first_name: &str, last_name: &str, login: &str, index: usize) -> String {
+    if index == 0 {
+        return login.to_string();
+    }
+    format!(
+        "{}.{}@example.org",
+        first_name.to_lowercase(),
+        last_name.to_lowercase()
+    )
+}
+
+/// Creates the login, or gives an existing one the password, so the printed one always works
+async fn seed_user(database: &Database, email: &str, password: &str) -> anyhow::Result<()> {
+    match database.get_user_by_email(email).await? {
+        Some(user) => {
+            database.update_password(user.id, password).await?;
+            println!("✅ Login {email} exists, password reset");
+        }
+        None => {
+            database
+                .create_user(CreateUserRequest {
+                    email: email.to_string(),
+                    password: password.to_string(),
+                })
+                .await?;
+            println!("✅ Login {email} created");
+        }
+    }
+    Ok(())
+}
+
+/// Config with the Teable base of the `DEV_` variables; `None` if they aren't all set
+fn dev_teable_config() -> anyhow::Result<Option<Config>> {
+    let dev_var = |name: &str| std::env::var(format!("DEV_{name}")).ok();
+    let missing: Vec<_> = TEABLE_VARS
+        .iter()
+        .filter(|name| dev_var(name).filter(|v| !v.is_empty()).is_none())
+        .collect();
+    if missing.len() == TEABLE_VARS.len() {
+        return Ok(None);
+    }
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "Incomplete development Teable base, missing: {}",
+            missing
+                .iter()
+                .map(|name| format!("DEV_{name}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    if dev_var("MEMBERS_TABLE_ID") == std::env::var("MEMBERS_TABLE_ID").ok() {
+        anyhow::bail!("DEV_MEMBERS_TABLE_ID is the members table of the server (MEMBERS_TABLE_ID)");
+    }
+
+    let config = Config::from_vars(|name| {
+        if TEABLE_VARS.contains(&name) {
+            dev_var(name)
+        } else if name == "DATABASE_URL" {
+            Some(String::new())
+        } else {
+            std::env::var(name).ok()
+        }
+    })
+    .map_err(|e| anyhow::anyhow!("{e}"))?;
+    Ok(Some(config))
+}
+
+async fn seed_teable(client: &TeableClient, login: &str) -> anyhow::Result<()> {
+    if let Some(member) = teable::get_member_by_email(client, login).await? {
+        println!(
+            "✅ Member {} with {login} exists, Teable already seeded",
+            member.id
+        );
+        return Ok(());
+    }
+
+    let emails: Vec<String> = DEMO_MEMBERS
+        .iter()
+        .enumerate()
+        .map(|(index, (first_name, last_name, _, _))| {
+            demo_email(first_name, last_name, login, index)
+        })
+        .collect();
+    let birth_dates: Vec<String> = DEMO_MEMBERS
+        .iter()
+        .map(|(_, _, _, year)| format!("{year}-06-15"))
+        .collect();
+    let new_members: Vec<NewMember> = DEMO_MEMBERS
+        .iter()
+        .zip(emails.iter().zip(&birth_dates))
+        .map(
+            |((first_name, last_name, family, _), (email, birth_date))| NewMember {
+                first_name,
+                last_name,
+                email,
+                family: *family,
+                birth_date,
+                join_date: Some("2015-04-01"),
+            },
+        )
+        .collect();
+    let members = teable::create_members(client, &new_members).await?;
+    println!("✅ {} demo members created", members.len());
+
+    let today = Utc::now().date_naive();
+    let dates: Vec<String> = DEMO_WORK_HOURS
+        .iter()
+        .map(|(_, days_ago, ..)| {
+            // Entries of this year stay within it, whatever the day of the run
+            let date = today - Duration::days(*days_ago);
+            let date = if *days_ago < 365 && date.year() < today.year() {
+                today
+            } else {
+                date
+            };
+            date.format("%Y-%m-%d").to_string()
+        })
+        .collect();
+    let new_work_hours: Vec<NewWorkHour> = DEMO_WORK_HOURS
+        .iter()
+        .zip(&dates)
+        .map(
+            |((member, _, description, hours, status), date)| NewWorkHour {
+                member: &members[*member],
+                date,
+                description,
+                hours: *hours,
+                status: *status,
+                event_id: None,
+            },
+        )
+        .collect();
+    let work_hours = teable::create_work_hours_batch(client, &new_work_hours).await?;
+    println!("✅ {} demo work hour entries created", work_hours.len());
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+
+    let Some(Args { email, password }) = parse_args(std::env::args().skip(1)) else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    let teable_config = match dev_teable_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let database_url =
+        std::env::var("DATABASE_URL").unwrap_or_else(|_| DEFAULT_DATABASE_URL.to_string());
+    if database_url.starts_with("sqlite://data/") {
+        std::fs::create_dir_all("data").ok();
+    }
+    let database = match Database::new(&database_url).await {
+        Ok(database) => database,
+        Err(e) => {
+            eprintln!("❌ Could not open {database_url}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(e) = seed_user(&database, &email, &password).await {
+        eprintln!("❌ Could not create the login: {e}");
+        return ExitCode::FAILURE;
+    }
+
+    match teable_config {
+        Some(config) => {
+            let result = match TeableClient::new(&config) {
+                Ok(client) => seed_teable(&client, &email).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                eprintln!("❌ Could not seed Teable: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+        None => println!("ℹ️  No DEV_TEABLE_* variables set, Teable left alone"),
+    }
+
+    println!("🎾 Log in with {email} / {password}");
+    ExitCode::SUCCESS
+}
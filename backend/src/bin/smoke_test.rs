@@ -0,0 +1,263 @@
+//! Smoke test against a running instance of the TSV Tennis backend
+//!
+//! Checks health, logs in with a dedicated test account, creates, edits and deletes a
+//! flagged test entry and checks the dashboard. Exits non-zero on the first failure.
+//! Run with: `cargo run --bin smoke-test -- --base-url https://app.example`
+//!
+//! The test account is read from `SMOKE_TEST_EMAIL` and `SMOKE_TEST_PASSWORD`. It has to
+//! belong to exactly one member.
+
+use chrono::{Datelike, Duration, NaiveDate};
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::process::ExitCode;
+
+/// Marks entries created by the smoke test; leftovers of aborted runs are deleted first
+const SMOKE_TEST_DESCRIPTION: &str = "Smoke-Test (automatisch, wird gelöscht)";
+
+/// Write endpoints allow one request per second; rate limited requests are retried this often
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+type StepResult<T> = Result<T, String>;
+
+struct SmokeTest {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl SmokeTest {
+    /// Sends a request to `/api{path}` and returns the status and JSON body
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> StepResult<(StatusCode, Value)> {
+        let url = format!("{}/api{}", self.base_url, path);
+        for attempt in 0..=MAX_RATE_LIMIT_RETRIES {
+            let mut req = self.client.request(method.clone(), &url);
+            if let Some(token) = &self.token {
+                req = req.bearer_auth(token);
+            }
+            if let Some(body) = body {
+                req = req.json(body);
+            }
+
+            let response = req
+                .send()
+                .await
+                .map_err(|e| format!("{method} {path} failed: {e}"))?;
+            let status = response.status();
+            if status == StatusCode::TOO_MANY_REQUESTS && attempt < MAX_RATE_LIMIT_RETRIES {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                continue;
+            }
+            let json = response.json().await.unwrap_or(Value::Null);
+            return Ok((status, json));
+        }
+        unreachable!("the last attempt always returns")
+    }
+
+    /// Like `request`, but fails unless the response is 200 with `"success": true`
+    async fn expect_success(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> StepResult<Value> {
+        let (status, json) = self.request(method.clone(), path, body).await?;
+        if status != StatusCode::OK || json["success"] != true {
+            return Err(format!("{method} {path} returned {status}: {json}"));
+        }
+        Ok(json)
+    }
+
+    async fn check_health(&self) -> StepResult<()> {
+        let (status, json) = self.request(Method::GET, "/health", None).await?;
+        if status != StatusCode::OK || json["status"] != "healthy" {
+            return Err(format!("Health check returned {status}: {json}"));
+        }
+        Ok(())
+    }
+
+    async fn login(&mut self, email: &str, password: &str) -> StepResult<()> {
+        let body = json!({ "email": email, "password": password });
+        let json = self
+            .expect_success(Method::POST, "/login", Some(&body))
+            .await?;
+        if json["data"]["multiple"] == true {
+            return Err(format!(
+                "{email} belongs to several members; use an account of a single member"
+            ));
+        }
+        let token = json["data"]["token"]
+            .as_str()
+            .ok_or_else(|| format!("Login response has no token: {json}"))?;
+        self.token = Some(token.to_string());
+        Ok(())
+    }
+
+    /// The own entries of a year as listed on the dashboard
+    async fn dashboard_entries(&self, year: i32) -> StepResult<Vec<Value>> {
+        let json = self
+            .expect_success(Method::GET, &format!("/dashboard/{year}"), None)
+            .await?;
+        Ok(json["data"]["personal"]["entries"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn delete_entry(&self, id: &str) -> StepResult<()> {
+        self.expect_success(Method::DELETE, &format!("/arbeitsstunden/{id}"), None)
+            .await
+            .map(|_| ())
+    }
+
+    async fn create_entry(&self, date: &str) -> StepResult<String> {
+        let body = json!({ "Datum": date, "Tätigkeit": SMOKE_TEST_DESCRIPTION, "Stunden": 1 });
+        let json = self
+            .expect_success(Method::POST, "/arbeitsstunden", Some(&body))
+            .await?;
+        if json["pending_sync"] == true {
+            return Err("Entry was queued locally, Teable is not reachable".to_string());
+        }
+        json["data"]["id"]
+            .as_str()
+            .map(|id| id.to_string())
+            .ok_or_else(|| format!("Create response has no entry ID: {json}"))
+    }
+
+    async fn edit_entry(&self, id: &str, date: &str) -> StepResult<()> {
+        let body = json!({ "Datum": date, "Tätigkeit": SMOKE_TEST_DESCRIPTION, "Stunden": "1,5" });
+        self.expect_success(Method::PUT, &format!("/arbeitsstunden/{id}"), Some(&body))
+            .await?;
+
+        let json = self
+            .expect_success(Method::GET, &format!("/arbeitsstunden/{id}"), None)
+            .await?;
+        if json["data"]["Stunden"] != 1.5 {
+            return Err(format!("Edited entry has unexpected hours: {json}"));
+        }
+        Ok(())
+    }
+}
+
+/// Today or the latest earlier day of the year without an entry; the backend allows only
+/// one entry per day
+fn free_date(today: NaiveDate, entries: &[Value]) -> Option<String> {
+    let taken: HashSet<&str> = entries
+        .iter()
+        .filter_map(|entry| entry["Datum"].as_str())
+        .collect();
+    (0..=today.ordinal0())
+        .map(|days| today - Duration::days(days.into()))
+        .map(|date| date.to_string())
+        .find(|date| !taken.contains(date.as_str()))
+}
+
+async fn run(smoke: &mut SmokeTest, email: &str, password: &str) -> StepResult<()> {
+    smoke.check_health().await?;
+    println!("✅ Health check");
+
+    smoke.login(email, password).await?;
+    println!("✅ Login as {email}");
+
+    let today = chrono::Utc::now()
+        .with_timezone(&chrono_tz::Europe::Berlin)
+        .date_naive();
+    let year = today.year();
+    let entries = smoke.dashboard_entries(year).await?;
+    let leftovers: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry["Tätigkeit"] == SMOKE_TEST_DESCRIPTION)
+        .filter_map(|entry| entry["id"].as_str())
+        .collect();
+    for id in &leftovers {
+        smoke.delete_entry(id).await?;
+    }
+    println!(
+        "✅ Dashboard loaded ({} leftover test entries removed)",
+        leftovers.len()
+    );
+
+    let remaining: Vec<Value> = entries
+        .into_iter()
+        .filter(|entry| entry["Tätigkeit"] != SMOKE_TEST_DESCRIPTION)
+        .collect();
+    let date = free_date(today, &remaining)
+        .ok_or_else(|| format!("No day in {year} without an entry left for the test entry"))?;
+    let id = smoke.create_entry(&date).await?;
+    println!("✅ Created test entry {id} on {date}");
+
+    // Delete the entry even if a later step fails, so runs don't pile up test entries
+    let result = async {
+        smoke.edit_entry(&id, &date).await?;
+        println!("✅ Edited test entry");
+
+        let entries = smoke.dashboard_entries(year).await?;
+        let listed = entries
+            .iter()
+            .any(|entry| entry["id"] == id.as_str() && entry["Stunden"] == 1.5);
+        if !listed {
+            return Err(format!("Test entry {id} is missing on the dashboard"));
+        }
+        println!("✅ Test entry listed on the dashboard");
+        Ok(())
+    }
+    .await;
+
+    let deleted = smoke.delete_entry(&id).await;
+    result?;
+    deleted?;
+    println!("✅ Deleted test entry");
+    Ok(())
+}
+
+fn parse_base_url(mut args: impl Iterator<Item = String>) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == "--base-url" {
+            return args.next();
+        }
+        if let Some(url) = arg.strip_prefix("--base-url=") {
+            return Some(url.to_string());
+        }
+    }
+    None
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+
+    let Some(base_url) = parse_base_url(std::env::args().skip(1)) else {
+        eprintln!("Usage: smoke-test --base-url https://app.example");
+        return ExitCode::from(2);
+    };
+    let (Ok(email), Ok(password)) = (
+        std::env::var("SMOKE_TEST_EMAIL"),
+        std::env::var("SMOKE_TEST_PASSWORD"),
+    ) else {
+        eprintln!("SMOKE_TEST_EMAIL and SMOKE_TEST_PASSWORD must be set");
+        return ExitCode::from(2);
+    };
+
+    println!("🔄 Running smoke test against {base_url}...");
+    let mut smoke = SmokeTest {
+        client: reqwest::Client::new(),
+        base_url: base_url.trim_end_matches('/').to_string(),
+        token: None,
+    };
+    match run(&mut smoke, &email, &password).await {
+        Ok(()) => {
+            println!("✅ Smoke test passed");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("❌ Smoke test failed: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use specta::ts::{self, BigIntExportBehavior, ExportConfiguration, TsExportError};
+use specta::{DefOpts, ExportError, Type, TypeDefs};
+
+use crate::activity::*;
+use crate::analytics::*;
+use crate::api_response::*;
+use crate::app_events::*;
+use crate::attachment::*;
+use crate::audit::*;
+use crate::bulk::*;
+use crate::certificate::*;
+use crate::circuit_breaker::*;
+use crate::consistency::*;
+use crate::correction::*;
+use crate::dry_run::*;
+use crate::error::*;
+use crate::events::*;
+use crate::family_invitation::*;
+use crate::guests::*;
+use crate::health::*;
+use crate::history::*;
+use crate::jobs::*;
+use crate::maintenance::*;
+use crate::member_alias::*;
+use crate::member_change::*;
+use crate::member_selection::*;
+use crate::models::*;
+use crate::outbox::*;
+use crate::password_resets::*;
+use crate::password_rotation::*;
+use crate::profile::*;
+use crate::rule_changes::*;
+use crate::rules::*;
+use crate::simulation::*;
+use crate::stats::*;
+use crate::suggestions::*;
+use crate::survey::*;
+use crate::teable_throttle::*;
+use crate::teable_webhook::*;
+use crate::telemetry::*;
+use crate::trash::*;
+use crate::two_factor::*;
+use crate::verification::*;
+use crate::work_hour_list::*;
+use crate::write_freeze::*;
+use crate::year_summary::*;
+
+/// Header of the generated file
+const HEADER: &str = "// Auto-generated TypeScript definitions from Rust using Specta
+// Generated with: cargo run --bin generate-types
+
+";
+
+/// Every request, response and event type, passed one by one to `$export`. A type a route
+/// takes or returns belongs here; a test checks the list against the route table and the
+/// frontend's imports.
+macro_rules! exported_types {
+    ($export:ident) => {
+        $export!(ErrorCode);
+        $export!(ApiResponse<()>);
+        $export!(PlannedEmail);
+        $export!(PlannedChanges);
+        $export!(LoginRequest);
+        $export!(LoginResponse);
+        $export!(ImpersonationResponse);
+        $export!(LoginResponseVariant);
+        $export!(MemberSelectionResponse);
+        $export!(TwoFactorRequiredResponse);
+        $export!(SelectMemberRequest);
+        $export!(SwitchMemberRequest);
+        $export!(RegisterRequest);
+        $export!(ForgotPasswordRequest);
+        $export!(ResetPasswordRequest);
+        $export!(DeliveryStatus);
+        $export!(ResetRequestEntry);
+        $export!(ResendResult);
+        $export!(OutboxStatus);
+        $export!(OutboxEmail);
+        $export!(UserResponse);
+        $export!(SessionProfile);
+        $export!(SessionUser);
+        $export!(SessionResponse);
+        $export!(CreateWorkHourRequest);
+        $export!(AdminCreateWorkHourRequest);
+        $export!(WorkHourResponse);
+        $export!(WorkHourNote);
+        $export!(WorkHourAttachments);
+        $export!(ReviewedWorkHour);
+        $export!(AdminViewRecords);
+        $export!(CalendarFeedLink);
+        $export!(FileLink);
+        $export!(StoredFileInfo);
+        $export!(CreateCorrectionRequest);
+        $export!(DescriptionSuggestion);
+        $export!(CreateSuggestionRequest);
+        $export!(WorkHourNoteRequest);
+        $export!(BulkWorkHourEntry);
+        $export!(BulkCreateWorkHoursRequest);
+        $export!(BulkWorkHourItem);
+        $export!(BulkItemError);
+        $export!(BulkItemResult<BulkWorkHourItem>);
+        $export!(BulkResult<BulkWorkHourItem>);
+        $export!(DashboardResponse);
+        $export!(FamilyData);
+        $export!(PersonalData);
+        $export!(FamilyMember);
+        $export!(MemberContribution);
+        $export!(DuesStatus);
+        $export!(DuesResponse);
+        $export!(WorkHourAttachment);
+        $export!(WorkHourEntry);
+        $export!(WorkHourStatus);
+        $export!(ReviewWorkHourRequest);
+        $export!(ReviewQueueEntry);
+        $export!(OrphanedAccount);
+        $export!(MemberWithoutAccount);
+        $export!(ConsistencyReport);
+        $export!(ActivityKind);
+        $export!(ActivityEntry);
+        $export!(AuditAction);
+        $export!(AuditEntry);
+        $export!(HeatmapWeek);
+        $export!(UsageFeature);
+        $export!(FeatureTrend);
+        $export!(UsageAnalytics);
+        $export!(WorkHourHeatmap);
+        $export!(Contributor);
+        $export!(MonthHours);
+        $export!(ClubStatistics);
+        $export!(GuestSession);
+        $export!(CreateGuestSessionRequest);
+        $export!(GuestFeeSummary);
+        $export!(GuestSessions);
+        $export!(GuestFeeReport);
+        $export!(EventStatus);
+        $export!(WorkEvent);
+        $export!(EventSignup);
+        $export!(EventOverview);
+        $export!(CreateEventRequest);
+        $export!(EventAttendance);
+        $export!(CompleteEventRequest);
+        $export!(EventCompletion<BulkWorkHourItem>);
+        $export!(EventHours);
+        $export!(SurveyInvitation);
+        $export!(SurveyAnswerRequest);
+        $export!(SurveyResults);
+        $export!(WorkHourRules);
+        $export!(YearRules);
+        $export!(SimulateRulesRequest);
+        $export!(RuleChangeSource);
+        $export!(RuleChange);
+        $export!(CreateRuleChangeRequest);
+        $export!(RuleChangeNoteRequest);
+        $export!(YearSummaryRequest);
+        $export!(FamilyYearSummary);
+        $export!(YearSummary);
+        $export!(YearTotals);
+        $export!(YearStatistics);
+        $export!(MemberStatistics);
+        $export!(WorkHourPage);
+        $export!(SegmentImpact);
+        $export!(RuleSimulation);
+        $export!(CertificateCategory);
+        $export!(CertificateSummary);
+        $export!(CertificateStatus);
+        $export!(CertificateRequest);
+        $export!(PasswordRotationStats);
+        $export!(StartedRotation);
+        $export!(DeletedWorkHour);
+        $export!(WorkHourDeletion);
+        $export!(Trash);
+        $export!(ThrottleStats);
+        $export!(CircuitState);
+        $export!(BreakerStats);
+        $export!(HostStats);
+        $export!(ThrottleOverview);
+        $export!(DependencyStatus);
+        $export!(DependencyCheck);
+        $export!(PoolStatus);
+        $export!(ReadinessReport);
+        $export!(TelemetryFeatures);
+        $export!(TelemetryPayload);
+        $export!(TelemetryPreview);
+        $export!(MaintenanceRun);
+        $export!(MaintenanceOverview);
+        $export!(MemberAlias);
+        $export!(CreateMemberAliasRequest);
+        $export!(MemberChangeField);
+        $export!(MemberChangeRequest);
+        $export!(MemberData);
+        $export!(FamilyInvitationStatus);
+        $export!(FamilyInvitation);
+        $export!(CreateFamilyInvitationRequest);
+        $export!(TwoFactorStatus);
+        $export!(TwoFactorSetupResponse);
+        $export!(TwoFactorCodeRequest);
+        $export!(ProfileUpdateRequest);
+        $export!(MemberProfile);
+        $export!(WorkHourEventData);
+        $export!(WorkHourDeletedData);
+        $export!(WorkHourReviewedData);
+        $export!(EventSignupData);
+        $export!(JobProgressData);
+        $export!(TeableTable);
+        $export!(RecordsChangedData);
+        $export!(MemberUpdatedData);
+        $export!(AppEvent);
+        $export!(AppEventEnvelope);
+        $export!(WebhookReceipt);
+        $export!(WriteFreeze);
+        $export!(SetWriteFreezeRequest);
+        $export!(WriteFreezeStatus);
+        $export!(DiscrepancyKind);
+        $export!(Discrepancy);
+        $export!(TotalsVerification);
+        $export!(JobKind);
+        $export!(JobStatus);
+        $export!(JobItemError);
+        $export!(Job);
+        $export!(JobDetail);
+    };
+}
+
+/// The TypeScript definitions of all exported types, as written to `bindings/types.ts` by
+/// `generate-types`
+pub fn typescript() -> Result<String, TsExportError> {
+    // IDs, counts and sizes are `i64`/`u64` in Rust but far below 2^53, so a plain
+    // `number` holds them
+    let config = ExportConfiguration::new().bigint(BigIntExportBehavior::Number);
+    let mut typescript_code = String::from(HEADER);
+
+    macro_rules! export_type {
+        ($type:ty) => {
+            typescript_code.push_str(&ts::export::<$type>(&config)?);
+            typescript_code.push_str("\n\n");
+        };
+    }
+    exported_types!(export_type);
+
+    Ok(typescript_code)
+}
+
+/// The specta definitions of the same types and the types they use, by ID, from which the
+/// OpenAPI spec's schemas are built
+pub fn definitions() -> Result<TypeDefs, ExportError> {
+    let mut type_map = TypeDefs::new();
+
+    macro_rules! define_type {
+        ($type:ty) => {
+            <$type as Type>::reference(
+                DefOpts {
+                    parent_inline: false,
+                    type_map: &mut type_map,
+                },
+                &[],
+            )?;
+        };
+    }
+    exported_types!(define_type);
+
+    Ok(type_map)
+}
@@ -0,0 +1,188 @@
+use crate::error::ErrorCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::collections::BTreeSet;
+
+/// Why an item of a bulk request failed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct BulkItemError {
+    pub code: ErrorCode,
+    /// German, for display next to the item
+    pub message: String,
+    /// Sending the item again unchanged may succeed, e.g. after Teable was unreachable;
+    /// validation errors need a corrected item instead
+    pub retryable: bool,
+}
+
+impl BulkItemError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        BulkItemError {
+            code,
+            message: message.into(),
+            retryable: is_retryable(code),
+        }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        BulkItemError::new(ErrorCode::InvalidRequest, message)
+    }
+
+    /// Teable failed while processing the item
+    pub fn teable(error: impl std::fmt::Display) -> Self {
+        BulkItemError::new(
+            ErrorCode::TeableUnavailable,
+            format!("Teable error: {error}"),
+        )
+    }
+}
+
+/// Errors caused by the server or its dependencies rather than by the item
+pub fn is_retryable(code: ErrorCode) -> bool {
+    matches!(
+        code,
+        ErrorCode::TeableUnavailable
+            | ErrorCode::RateLimitExceeded
+            | ErrorCode::DatabaseError
+            | ErrorCode::InternalError
+    )
+}
+
+/// Outcome of one item, `index` refers to the order of the request
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct BulkItemResult<T> {
+    pub index: u32,
+    pub success: bool,
+    pub item: T,
+    pub error: Option<BulkItemError>,
+}
+
+impl<T> BulkItemResult<T> {
+    /// An item that hasn't been processed yet
+    pub fn pending(index: u32, item: T) -> Self {
+        BulkItemResult {
+            index,
+            success: false,
+            item,
+            error: None,
+        }
+    }
+
+    pub fn fail(&mut self, error: BulkItemError) {
+        self.success = false;
+        self.error = Some(error);
+    }
+}
+
+/// Response of every bulk endpoint. Items succeed or fail on their own; `retry_token`
+/// lets the client resubmit the same request so only the retryable failures run again.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct BulkResult<T> {
+    /// All items succeeded
+    pub all_succeeded: bool,
+    pub succeeded: u32,
+    pub failed: u32,
+    pub results: Vec<BulkItemResult<T>>,
+    /// Send it back as `retry_token` with the unchanged request; missing if no failure
+    /// can be retried
+    pub retry_token: Option<String>,
+}
+
+impl<T> BulkResult<T> {
+    /// `fingerprint` identifies the request, see [`fingerprint`]
+    pub fn new(results: Vec<BulkItemResult<T>>, fingerprint: &str) -> Self {
+        let succeeded = results.iter().filter(|result| result.success).count() as u32;
+        let failed = results.len() as u32 - succeeded;
+        let retry_token = retry_token(
+            fingerprint,
+            results
+                .iter()
+                .filter(|result| result.error.as_ref().is_some_and(|error| error.retryable))
+                .map(|result| result.index),
+        );
+        BulkResult {
+            all_succeeded: failed == 0,
+            succeeded,
+            failed,
+            results,
+            retry_token,
+        }
+    }
+}
+
+/// Short hash of the items of a request, so a retry token can't be applied to a
+/// different request
+pub fn fingerprint<T: Serialize>(items: &[T]) -> String {
+    let json = serde_json::to_vec(items).unwrap_or_default();
+    hex::encode(&Sha256::digest(&json)[..8])
+}
+
+/// `<fingerprint>.<indexes>` with runs of indexes compressed, e.g. `3f9a...e1.0-4,7`
+pub fn retry_token(fingerprint: &str, indexes: impl IntoIterator<Item = u32>) -> Option<String> {
+    let indexes: BTreeSet<u32> = indexes.into_iter().collect();
+    if indexes.is_empty() {
+        return None;
+    }
+
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for index in indexes {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == index => *end = index,
+            _ => runs.push((index, index)),
+        }
+    }
+    let runs: Vec<String> = runs
+        .iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .collect();
+    Some(format!("{fingerprint}.{}", runs.join(",")))
+}
+
+/// The items a request processes: all of them, or those of a retry token
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetrySelection(Option<BTreeSet<u32>>);
+
+impl RetrySelection {
+    /// Fails if the token is malformed or belongs to another request of `len` items
+    pub fn parse(token: Option<&str>, fingerprint: &str, len: usize) -> Result<Self, String> {
+        let Some(token) = token else {
+            return Ok(RetrySelection(None));
+        };
+        let invalid = || "Ungültiges Wiederholungs-Token.".to_string();
+        let (token_fingerprint, runs) = token.split_once('.').ok_or_else(invalid)?;
+        if token_fingerprint != fingerprint {
+            return Err(
+                "Das Wiederholungs-Token passt nicht zu dieser Anfrage. Bitte die ursprüngliche Anfrage unverändert senden."
+                    .to_string(),
+            );
+        }
+
+        let mut indexes = BTreeSet::new();
+        for run in runs.split(',') {
+            let (start, end) = run.split_once('-').unwrap_or((run, run));
+            let start: u32 = start.parse().map_err(|_| invalid())?;
+            let end: u32 = end.parse().map_err(|_| invalid())?;
+            if start > end || end as usize >= len {
+                return Err(invalid());
+            }
+            indexes.extend(start..=end);
+        }
+        Ok(RetrySelection(Some(indexes)))
+    }
+
+    pub fn includes(&self, index: u32) -> bool {
+        self.0
+            .as_ref()
+            .is_none_or(|indexes| indexes.contains(&index))
+    }
+
+    pub fn is_retry(&self) -> bool {
+        self.0.is_some()
+    }
+}
@@ -0,0 +1,309 @@
+use crate::export::{Column, Locale, TextBlock, TOTAL};
+use crate::models::{WorkHour, WorkHourStatus};
+use crate::pdf::{
+    parse_color, wrap, write_pdf, Font, JpegImage, PageContent, PAGE_HEIGHT, PAGE_WIDTH,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+
+/// Approved hours of one activity on a certificate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct CertificateCategory {
+    pub name: String,
+    pub hours: f64,
+}
+
+/// The work a certificate confirms, frozen when the board approves it so the letter matches
+/// what was checked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct CertificateSummary {
+    pub total_hours: f64,
+    /// Activities with the most hours first
+    pub categories: Vec<CertificateCategory>,
+}
+
+/// Sums approved hours by activity (`Tätigkeit`); pending and rejected entries are left out.
+/// Corrections reduce the activity of the entry they correct and are left out without it.
+pub fn summarize_work_hours(work_hours: &[WorkHour]) -> CertificateSummary {
+    let counts = |work_hour: &WorkHour| {
+        WorkHourStatus::from_teable(work_hour.status.as_deref()).counts_toward_total()
+    };
+    let counted: HashMap<&str, &WorkHour> = work_hours
+        .iter()
+        .filter(|work_hour| counts(work_hour))
+        .map(|work_hour| (work_hour.id.as_str(), work_hour))
+        .collect();
+    let mut by_activity: HashMap<String, f64> = HashMap::new();
+    for work_hour in work_hours.iter().filter(|work_hour| counts(work_hour)) {
+        let Some(hours) = work_hour.duration_hours else {
+            continue;
+        };
+        let activity_of = match work_hour.corrects.as_deref() {
+            Some(original) => match counted.get(original) {
+                Some(original) if original.corrects.is_none() => *original,
+                _ => continue,
+            },
+            None => work_hour,
+        };
+        let name = activity_of
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or("Sonstiges");
+        *by_activity.entry(name.to_string()).or_default() += hours;
+    }
+
+    let mut categories: Vec<CertificateCategory> = by_activity
+        .into_iter()
+        .filter(|(_, hours)| *hours > 0.005) // fully corrected
+        .map(|(name, hours)| CertificateCategory { name, hours })
+        .collect();
+    categories.sort_by(|a, b| b.hours.total_cmp(&a.hours).then(a.name.cmp(&b.name)));
+    CertificateSummary {
+        total_hours: categories.iter().map(|category| category.hours).sum(),
+        categories,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateStatus {
+    /// Waiting for a board member to check the hours
+    Requested,
+    Approved,
+}
+
+impl CertificateStatus {
+    /// Value stored in the `status` column of the certificate requests
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CertificateStatus::Requested => "requested",
+            CertificateStatus::Approved => "approved",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+}
+
+/// A member's request for a certificate of one year's work hours
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct CertificateRequest {
+    pub id: i32,
+    pub member_id: String,
+    pub member_name: String,
+    pub year: i32,
+    pub status: CertificateStatus,
+    /// Set once approved
+    pub summary: Option<CertificateSummary>,
+    /// Board member who approved the certificate
+    pub approved_by: Option<String>,
+    /// RFC 3339 timestamps
+    pub requested_at: String,
+    pub approved_at: Option<String>,
+}
+
+/// `?status=requested` of the admin certificate list
+#[derive(Debug, Deserialize)]
+pub struct CertificateListQuery {
+    pub status: Option<CertificateStatus>,
+}
+
+/// The year of a certificate file name like `2025.pdf`
+pub fn parse_certificate_file(file: &str) -> Option<i32> {
+    let year = file.strip_suffix(".pdf")?;
+    if year.len() != 4 {
+        return None;
+    }
+    year.parse().ok()
+}
+
+/// Everything printed on a certificate letter
+#[derive(Debug, Clone)]
+pub struct CertificateDocument<'a> {
+    pub number: i32,
+    pub club_name: &'a str,
+    pub club_address: Option<&'a str>,
+    /// `#rrggbb` or `#rgb`, used for the letterhead
+    pub primary_color: &'a str,
+    pub member_name: &'a str,
+    pub year: i32,
+    pub summary: &'a CertificateSummary,
+    pub issued_on: NaiveDate,
+    /// Name and role printed below the signature
+    pub signatory: &'a str,
+    pub signature: Option<&'a JpegImage>,
+    /// Language of the letter, its dates and numbers
+    pub locale: Locale,
+}
+
+/// Columns of the table of activities on a certificate
+pub const COLUMNS: [Column; 2] = [
+    Column::new("Tätigkeit", "Activity"),
+    Column::new("Stunden", "Hours"),
+];
+
+const TITLE: TextBlock = TextBlock::new(
+    "Bescheinigung über ehrenamtliche Tätigkeit",
+    "Certificate of Voluntary Work",
+);
+const INTRO: TextBlock = TextBlock::new(
+    "Hiermit bestätigen wir, dass {member} im Jahr {year} insgesamt {hours} Stunden ehrenamtliche Arbeit für den {club} geleistet hat. Die Stunden verteilen sich wie folgt auf die einzelnen Tätigkeiten:",
+    "We hereby confirm that {member} did a total of {hours} hours of voluntary work for {club} in {year}. The hours were spent on the following activities:",
+);
+const CLOSING: TextBlock = TextBlock::new(
+    "Die Stunden wurden vom Vorstand geprüft. Wir danken für das Engagement.",
+    "The hours have been checked by the board. Thank you for your commitment.",
+);
+const NUMBER: TextBlock = TextBlock::new("Bescheinigung Nr. {number}", "Certificate no. {number}");
+
+const MARGIN: f32 = 70.0;
+/// Characters per line of 11pt body text; Helvetica averages about half the font size
+const BODY_LINE_CHARS: usize = 88;
+/// Longer activity names are cut off so they don't run into the hours column
+const CATEGORY_NAME_CHARS: usize = 60;
+
+/// Renders the certificate as a single A4 page PDF using the standard Helvetica fonts
+pub fn render_pdf(document: &CertificateDocument) -> Vec<u8> {
+    let mut content = PageContent::default();
+    let (r, g, b) = parse_color(document.primary_color);
+
+    // Letterhead
+    let mut y = PAGE_HEIGHT - 70.0;
+    content.fill_color(r, g, b);
+    content.text(MARGIN, y, Font::Bold, 18.0, document.club_name);
+    content.fill_color(0.4, 0.4, 0.4);
+    if let Some(address) = document.club_address {
+        y -= 16.0;
+        content.text(MARGIN, y, Font::Regular, 9.0, address);
+    }
+    y -= 12.0;
+    content.stroke_color(r, g, b);
+    content.line(MARGIN, y, PAGE_WIDTH - MARGIN, y, 1.5);
+
+    content.fill_color(0.0, 0.0, 0.0);
+    y -= 40.0;
+    content.text(
+        PAGE_WIDTH - MARGIN - 110.0,
+        y,
+        Font::Regular,
+        10.0,
+        &document.locale.format_date(document.issued_on),
+    );
+
+    y -= 40.0;
+    content.text(
+        MARGIN,
+        y,
+        Font::Bold,
+        14.0,
+        &TITLE.render(document.locale, &[]),
+    );
+
+    y -= 30.0;
+    let intro = INTRO.render(
+        document.locale,
+        &[
+            ("member", document.member_name),
+            ("year", &document.year.to_string()),
+            (
+                "hours",
+                &document.locale.format_number(document.summary.total_hours),
+            ),
+            ("club", document.club_name),
+        ],
+    );
+    for line in wrap(&intro, BODY_LINE_CHARS) {
+        content.text(MARGIN, y, Font::Regular, 11.0, &line);
+        y -= 15.0;
+    }
+
+    y -= 10.0;
+    let hours_x = PAGE_WIDTH - MARGIN - 80.0;
+    let [activity_column, hours_column] = COLUMNS;
+    content.text(
+        MARGIN,
+        y,
+        Font::Bold,
+        11.0,
+        activity_column.header(document.locale),
+    );
+    content.text(
+        hours_x,
+        y,
+        Font::Bold,
+        11.0,
+        hours_column.header(document.locale),
+    );
+    y -= 6.0;
+    content.stroke_color(0.6, 0.6, 0.6);
+    content.line(MARGIN, y, PAGE_WIDTH - MARGIN, y, 0.5);
+    y -= 14.0;
+    for category in &document.summary.categories {
+        let name: String = category.name.chars().take(CATEGORY_NAME_CHARS).collect();
+        content.text(MARGIN, y, Font::Regular, 11.0, &name);
+        content.text(
+            hours_x,
+            y,
+            Font::Regular,
+            11.0,
+            &document.locale.format_number(category.hours),
+        );
+        y -= 15.0;
+        // Keep room for the signature; remaining activities are only part of the total
+        if y < 250.0 {
+            content.text(MARGIN, y, Font::Regular, 11.0, "…");
+            y -= 15.0;
+            break;
+        }
+    }
+    y += 9.0;
+    content.line(MARGIN, y, PAGE_WIDTH - MARGIN, y, 0.5);
+    y -= 15.0;
+    content.text(MARGIN, y, Font::Bold, 11.0, TOTAL.header(document.locale));
+    content.text(
+        hours_x,
+        y,
+        Font::Bold,
+        11.0,
+        &document.locale.format_number(document.summary.total_hours),
+    );
+
+    y -= 35.0;
+    content.text(
+        MARGIN,
+        y,
+        Font::Regular,
+        11.0,
+        &CLOSING.render(document.locale, &[]),
+    );
+
+    y -= 75.0;
+    if let Some(signature) = document.signature {
+        let height = 50.0_f32;
+        let width = (height * signature.width as f32 / signature.height as f32).min(200.0);
+        let height = width * signature.height as f32 / signature.width as f32;
+        content.image(MARGIN, y + 4.0, width, height);
+    }
+    content.stroke_color(0.0, 0.0, 0.0);
+    content.line(MARGIN, y, MARGIN + 200.0, y, 0.5);
+    y -= 14.0;
+    content.text(MARGIN, y, Font::Regular, 10.0, document.signatory);
+    y -= 13.0;
+    content.text(MARGIN, y, Font::Regular, 10.0, document.club_name);
+
+    content.fill_color(0.4, 0.4, 0.4);
+    content.text(
+        MARGIN,
+        50.0,
+        Font::Regular,
+        8.0,
+        &NUMBER.render(document.locale, &[("number", &document.number.to_string())]),
+    );
+
+    write_pdf(&content.bytes, document.signature)
+}
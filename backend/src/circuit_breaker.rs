@@ -0,0 +1,183 @@
+use crate::teable_throttle::{self, SendPolicy};
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Returned instead of sending a request while the circuit of its host is open
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircuitOpen {
+    /// Time until the next trial request is let through
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Teable is unreachable, not sending requests for another {}s",
+            self.retry_after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests are sent
+    #[default]
+    Closed,
+    /// Requests fail right away until the cooldown is over
+    Open,
+    /// One trial request is on its way; its outcome closes or reopens the circuit
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Type)]
+pub struct BreakerStats {
+    pub state: CircuitState,
+    /// Failed requests in a row; the circuit opens at the threshold
+    pub consecutive_failures: u32,
+    /// How often the circuit opened
+    pub opened: u64,
+    /// Requests that failed right away because the circuit was open
+    pub rejected: u64,
+}
+
+#[derive(Debug)]
+enum State {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { since: Instant },
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: State,
+    stats: BreakerStats,
+}
+
+/// Stops sending requests to a Teable host after `threshold` requests in a row failed
+/// with a connection error, a timeout or a `502`/`503`/`504`, so members get an error right
+/// away instead of waiting for each request to time out. After `cooldown` one trial request
+/// is let through to find out whether Teable is back.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            threshold: threshold.max(1),
+            cooldown,
+            inner: Mutex::new(Inner {
+                state: State::Closed,
+                stats: BreakerStats::default(),
+            }),
+        }
+    }
+
+    /// Whether a request may be sent now
+    pub fn allow(&self) -> Result<(), CircuitOpen> {
+        let mut inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        let retry_after = match inner.state {
+            State::Closed => return Ok(()),
+            State::Open { until } if now >= until => {
+                inner.state = State::HalfOpen { since: now };
+                return Ok(());
+            }
+            State::Open { until } => until - now,
+            // A trial that never reported back was cancelled; let the next one through
+            State::HalfOpen { since } if now - since >= self.cooldown => {
+                inner.state = State::HalfOpen { since: now };
+                return Ok(());
+            }
+            State::HalfOpen { since } => self.cooldown - (now - since),
+        };
+        inner.stats.rejected += 1;
+        Err(CircuitOpen { retry_after })
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if !matches!(inner.state, State::Closed) {
+            info!("Teable: Reachable again, closing the circuit");
+        }
+        inner.state = State::Closed;
+        inner.stats.consecutive_failures = 0;
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.stats.consecutive_failures += 1;
+        let trip = match inner.state {
+            State::HalfOpen { .. } => true,
+            State::Closed => inner.stats.consecutive_failures >= self.threshold,
+            State::Open { .. } => false,
+        };
+        if trip {
+            warn!(
+                "Teable: {} failed requests in a row, failing fast for {:?}",
+                inner.stats.consecutive_failures, self.cooldown
+            );
+            inner.state = State::Open {
+                until: Instant::now() + self.cooldown,
+            };
+            inner.stats.opened += 1;
+        }
+    }
+
+    pub fn stats(&self) -> BreakerStats {
+        let inner = self.inner.lock().unwrap();
+        BreakerStats {
+            state: match inner.state {
+                State::Closed => CircuitState::Closed,
+                State::Open { .. } => CircuitState::Open,
+                State::HalfOpen { .. } => CircuitState::HalfOpen,
+            },
+            ..inner.stats.clone()
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<String, Arc<CircuitBreaker>>> {
+    static BREAKERS: OnceLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> = OnceLock::new();
+    BREAKERS.get_or_init(Default::default)
+}
+
+/// The breaker of the host of `url`, like the throttles one per host. The first request to
+/// a host sets its threshold and cooldown from `policy`.
+pub fn for_url(url: &reqwest::Url, policy: &SendPolicy) -> Arc<CircuitBreaker> {
+    registry()
+        .lock()
+        .unwrap()
+        .entry(teable_throttle::host_key(url))
+        .or_insert_with(|| {
+            Arc::new(CircuitBreaker::new(
+                policy.breaker_threshold,
+                policy.breaker_cooldown,
+            ))
+        })
+        .clone()
+}
+
+/// State of the breaker of every Teable host contacted so far
+pub fn all_stats() -> Vec<(String, BreakerStats)> {
+    let mut stats: Vec<(String, BreakerStats)> = registry()
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(host, breaker)| (host.clone(), breaker.stats()))
+        .collect();
+    stats.sort_by(|a, b| a.0.cmp(&b.0));
+    stats
+}
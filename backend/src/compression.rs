@@ -0,0 +1,26 @@
+use tower_http::compression::predicate::{
+    DefaultPredicate, NotForContentType, Predicate, SizeAbove,
+};
+use tower_http::compression::CompressionLayer;
+
+/// Smaller bodies gain too little to be worth the CPU, and the headers outweigh them anyway
+const MIN_SIZE: u16 = 1024;
+
+/// Which responses to compress: everything but images, PDFs, event streams and small bodies.
+/// PDFs (certificates, timesheets) are compressed already; `DefaultPredicate` skips images,
+/// gRPC and `text/event-stream`, whose events must reach the browser unbuffered.
+pub fn predicate() -> impl Predicate {
+    DefaultPredicate::new()
+        .and(SizeAbove::new(MIN_SIZE))
+        .and(NotForContentType::const_new("application/pdf"))
+        .and(NotForContentType::const_new("application/zip"))
+}
+
+/// gzip or brotli, whichever the client prefers in `Accept-Encoding`. The dashboard and the
+/// member lists are JSON that shrinks to a fraction, which counts on mobile connections.
+pub fn layer() -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new()
+        .gzip(true)
+        .br(true)
+        .compress_when(predicate())
+}
@@ -0,0 +1,93 @@
+use crate::database::{AuthUser, Database};
+use crate::models::Member;
+use crate::teable;
+use crate::teable::TeableClient;
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Login account whose email no longer belongs to any Teable member
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct OrphanedAccount {
+    pub user_id: i32,
+    pub email: String,
+    pub disabled: bool,
+}
+
+/// Teable member that has no login account for their email yet
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct MemberWithoutAccount {
+    pub member_id: String,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ConsistencyReport {
+    pub generated_at: String,
+    pub orphaned_accounts: Vec<OrphanedAccount>,
+    pub members_without_account: Vec<MemberWithoutAccount>,
+}
+
+/// Latest report of the scheduled check, shared with the admin endpoints
+pub type ConsistencyReportCache = Arc<RwLock<Option<ConsistencyReport>>>;
+
+/// Compares all login accounts against all Teable members
+pub async fn check_consistency(
+    client: &TeableClient,
+    database: &Database,
+) -> anyhow::Result<ConsistencyReport> {
+    let users = database.list_users().await?;
+    let members = teable::get_all_members(client).await?;
+    Ok(build_report(&users, &members))
+}
+
+/// Emails are compared case-insensitively. Members without an email can't log in
+/// anyway and are left out; several members sharing one email need just one account.
+/// Accounts of family invitations belong to their linked member instead of an email.
+pub fn build_report(users: &[AuthUser], members: &[Member]) -> ConsistencyReport {
+    let member_emails: HashSet<String> = members
+        .iter()
+        .map(|m| m.email.trim().to_lowercase())
+        .filter(|email| !email.is_empty())
+        .collect();
+    let member_ids: HashSet<&str> = members.iter().map(|m| m.id.as_str()).collect();
+    let account_emails: HashSet<String> = users
+        .iter()
+        .map(|u| u.email.trim().to_lowercase())
+        .collect();
+
+    let orphaned_accounts = users
+        .iter()
+        .filter(|u| match &u.member_id {
+            Some(member_id) => !member_ids.contains(member_id.as_str()),
+            None => !member_emails.contains(&u.email.trim().to_lowercase()),
+        })
+        .map(|u| OrphanedAccount {
+            user_id: u.id,
+            email: u.email.clone(),
+            disabled: u.disabled_at.is_some(),
+        })
+        .collect();
+
+    let members_without_account = members
+        .iter()
+        .filter(|m| {
+            let email = m.email.trim().to_lowercase();
+            !email.is_empty() && !account_emails.contains(&email)
+        })
+        .map(|m| MemberWithoutAccount {
+            member_id: m.id.clone(),
+            name: m.name(),
+            email: m.email.clone(),
+        })
+        .collect();
+
+    ConsistencyReport {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        orphaned_accounts,
+        members_without_account,
+    }
+}
@@ -0,0 +1,58 @@
+use crate::export::Locale;
+use crate::models::{WorkHour, WorkHourStatus};
+use serde::Deserialize;
+use specta::Type;
+
+/// Body of `POST /admin/arbeitsstunden/{id}/correction`
+#[derive(Debug, Deserialize, Type)]
+pub struct CreateCorrectionRequest {
+    /// Negative, e.g. `-1.5` if 1.5 hours too many were credited
+    pub hours: f64,
+    /// Why the hours are corrected, shown to the member and in the audit log
+    pub reason: String,
+}
+
+/// Hours of `original` still credited after the corrections already made to it
+pub fn remaining_hours(original: &WorkHour, work_hours: &[WorkHour]) -> f64 {
+    let corrected: f64 = work_hours
+        .iter()
+        .filter(|work_hour| work_hour.corrects.as_deref() == Some(original.id.as_str()))
+        .filter(|work_hour| {
+            WorkHourStatus::from_teable(work_hour.status.as_deref()).counts_toward_total()
+        })
+        .filter_map(|work_hour| work_hour.duration_hours)
+        .sum();
+    original.duration_hours.unwrap_or(0.0) + corrected
+}
+
+/// Checks a correction of `original` by `hours`, given the hours still credited for it; the
+/// message explains to the board member what is wrong
+pub fn validate(original: &WorkHour, remaining: f64, hours: f64) -> Result<(), String> {
+    if original.corrects.is_some() {
+        return Err("Korrekturen können nicht selbst korrigiert werden.".to_string());
+    }
+    if !WorkHourStatus::from_teable(original.status.as_deref()).counts_toward_total() {
+        return Err(
+            "Nur genehmigte Einträge können korrigiert werden. Offene Einträge bitte bearbeiten oder ablehnen."
+                .to_string(),
+        );
+    }
+    if !hours.is_finite() || hours >= 0.0 {
+        return Err("Korrekturen müssen negative Stunden haben.".to_string());
+    }
+    if -hours > remaining + f64::EPSILON {
+        return Err(format!(
+            "Der Eintrag hat nur noch {} Stunden, die korrigiert werden können.",
+            Locale::De.format_number(remaining.max(0.0))
+        ));
+    }
+    Ok(())
+}
+
+/// Description of a correction entry on statements, e.g. `Korrektur: doppelt eingetragen`
+pub fn label(reason: &str, locale: Locale) -> String {
+    match locale {
+        Locale::De => format!("Korrektur: {reason}"),
+        Locale::En => format!("Correction: {reason}"),
+    }
+}
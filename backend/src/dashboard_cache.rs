@@ -0,0 +1,103 @@
+use crate::api_response::ApiResponse;
+use crate::etag;
+use crate::models::DashboardResponse;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// A dashboard as served, with the time it was built
+struct Entry {
+    built_at: DateTime<Utc>,
+    /// `DashboardCache::generation` when the build started
+    generation: u64,
+    etag: String,
+    dashboard: Value,
+}
+
+/// Dashboards by member and year
+type Entries = HashMap<(String, i32), Entry>;
+
+/// The last dashboard served to each member per year, served again while Teable is
+/// unreachable, and its `ETag` for answering polls without asking Teable. Kept in memory
+/// only, so it is empty after a restart.
+#[derive(Clone, Default)]
+pub struct DashboardCache {
+    entries: Arc<RwLock<Entries>>,
+    /// Bumped by every change that may show on dashboards; tags of dashboards built before
+    /// it are no longer trusted to be current
+    generation: Arc<AtomicU64>,
+}
+
+impl DashboardCache {
+    /// Taken before building a dashboard and passed to `store`, so a change made during the
+    /// build isn't hidden behind the tag of the older data
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    pub async fn store(
+        &self,
+        member_id: &str,
+        year: i32,
+        dashboard: &DashboardResponse,
+        generation: u64,
+    ) {
+        // Tagged like `etag::json_with_etag` tags the response, from the same bytes
+        let Ok(body) = serde_json::to_vec(&ApiResponse::ok(dashboard)) else {
+            return;
+        };
+        if let Ok(value) = serde_json::to_value(dashboard) {
+            self.entries.write().await.insert(
+                (member_id.to_string(), year),
+                Entry {
+                    built_at: Utc::now(),
+                    generation,
+                    etag: etag::etag_of(&body),
+                    dashboard: value,
+                },
+            );
+        }
+    }
+
+    /// Marks all cached tags as outdated, e.g. after a work hour entry was changed. The
+    /// dashboards themselves are kept for outages.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Drops all dashboards, e.g. after the board edited records in Teable, so stale data
+    /// isn't served during the next outage
+    pub async fn clear(&self) {
+        self.invalidate();
+        self.entries.write().await.clear();
+    }
+
+    /// The cached dashboard with `cached_at` set, if it is at most `max_age_secs` old
+    pub async fn get(&self, member_id: &str, year: i32, max_age_secs: u64) -> Option<Value> {
+        let entries = self.entries.read().await;
+        let entry = entries.get(&(member_id.to_string(), year))?;
+        if (Utc::now() - entry.built_at).num_seconds() > max_age_secs as i64 {
+            return None;
+        }
+        let mut dashboard = entry.dashboard.clone();
+        dashboard["cached_at"] = Value::String(entry.built_at.to_rfc3339());
+        Some(dashboard)
+    }
+
+    /// The `ETag` of the cached dashboard, if it is at most `max_age_secs` old and nothing
+    /// changed since it was built, so a client having it can get a `304` without a rebuild
+    pub async fn current_etag(
+        &self,
+        member_id: &str,
+        year: i32,
+        max_age_secs: u64,
+    ) -> Option<String> {
+        let entries = self.entries.read().await;
+        let entry = entries.get(&(member_id.to_string(), year))?;
+        let fresh = (Utc::now() - entry.built_at).num_seconds() < max_age_secs as i64;
+        (fresh && entry.generation == self.generation()).then(|| entry.etag.clone())
+    }
+}
@@ -1,7 +1,38 @@
+use crate::activity::{ActivityEntry, ActivityKind, NewActivity};
+use crate::analytics::UsageFeature;
+use crate::audit::{AuditAction, AuditEntry, AuditQuery, NewAuditEntry};
+use crate::certificate::{CertificateRequest, CertificateStatus, CertificateSummary};
+use crate::config::Config;
+use crate::error::ErrorCode;
+use crate::family_invitation::{FamilyInvitation, FamilyInvitationStatus};
+use crate::guests::GuestSession;
+use crate::health::PoolStatus;
+use crate::jobs::{Job, JobItemError, JobKind, JobStatus};
+use crate::maintenance::MaintenanceRun;
+use crate::member_alias::MemberAlias;
+use crate::models::WorkHour;
+use crate::outbox::{OutboxEmail, OutboxQuery, OutboxStatus, QueuedEmail};
+use crate::password_resets::{
+    DeliveryStatus, NewResetRequest, ResetRequestEntry, ResetRequestQuery,
+};
+use crate::password_rotation::{PasswordRotationStats, RotationEmail, MAX_EMAIL_ATTEMPTS};
+use crate::requirements::MemberRequirement;
+use crate::rule_changes::{NewRuleChange, RuleChange, RuleChangeSource};
+use crate::stats::DailyHours;
+use crate::survey::SurveyInvitation;
+use crate::trash::{self, DeletedWorkHour};
+use crate::two_factor::TwoFactorRecord;
+use crate::write_freeze::WriteFreeze;
 use bcrypt::{hash, verify, DEFAULT_COST};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use log::LevelFilter;
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePool, Row};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::{migrate::Migrator, ConnectOptions, Row};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::info;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthUser {
@@ -9,6 +40,13 @@ pub struct AuthUser {
     pub email: String,
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
+    /// Set when the board deactivated the account, e.g. because the member left the club
+    pub disabled_at: Option<DateTime<Utc>>,
+    /// Set by a forced password rotation until the member sets a new password
+    pub password_reset_required_at: Option<DateTime<Utc>>,
+    /// Teable record the account logs in as, for accounts created by a family invitation;
+    /// other accounts log in as the members with their email
+    pub member_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,65 +55,176 @@ pub struct CreateUserRequest {
     pub password: String,
 }
 
+/// A work hour entry accepted while Teable was unreachable, waiting to be replayed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedWorkHour {
+    pub id: i64,
+    pub member_id: String,
+    pub date: String,
+    pub description: String,
+    pub hours: f64,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    /// Set when replay found a conflicting entry in Teable; such entries are not retried
+    pub conflict_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Settings of the connection pool; the defaults are the ones of sqlx
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    pub acquire_timeout: Duration,
+    pub log_statements: LevelFilter,
+    /// `None` doesn't log slow statements
+    pub slow_statement: Option<Duration>,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        PoolSettings {
+            max_connections: 10,
+            acquire_timeout: Duration::from_secs(30),
+            log_statements: LevelFilter::Debug,
+            slow_statement: Some(Duration::from_secs(1)),
+        }
+    }
+}
+
+impl PoolSettings {
+    pub fn from_config(config: &Config) -> Self {
+        PoolSettings {
+            max_connections: config.database_max_connections,
+            acquire_timeout: Duration::from_secs(config.database_acquire_timeout_secs),
+            log_statements: config.database_log_statements,
+            slow_statement: (config.database_slow_statement_ms > 0)
+                .then(|| Duration::from_millis(config.database_slow_statement_ms)),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Database {
     pool: SqlitePool,
 }
 
 impl Database {
+    /// With the default pool settings, for tools and tests
     pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
-        let pool = SqlitePool::connect(database_url).await?;
+        Self::with_settings(database_url, PoolSettings::default()).await
+    }
 
-        // Create tables if they don't exist (SQLite syntax)
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS details (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                email TEXT UNIQUE NOT NULL,
-                password TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
+    /// The database of `DATABASE_URL` with the pool settings of `config`
+    pub async fn connect(config: &Config) -> Result<Self, sqlx::Error> {
+        Self::with_settings(&config.database_url, PoolSettings::from_config(config)).await
+    }
+
+    pub async fn with_settings(
+        database_url: &str,
+        settings: PoolSettings,
+    ) -> Result<Self, sqlx::Error> {
+        let mut options =
+            SqliteConnectOptions::from_str(database_url)?.log_statements(settings.log_statements);
+        options = match settings.slow_statement {
+            Some(threshold) => options.log_slow_statements(LevelFilter::Warn, threshold),
+            None => options.log_slow_statements(LevelFilter::Off, Duration::ZERO),
+        };
+        let pool = SqlitePoolOptions::new()
+            .max_connections(settings.max_connections)
+            .acquire_timeout(settings.acquire_timeout)
+            .connect_with(options)
+            .await?;
+        info!(
+            "Database: Pool of up to {} connections, waiting up to {:?} for one",
+            settings.max_connections, settings.acquire_timeout
+        );
+        upgrade_legacy_schema(&pool).await?;
+        run_migrations(&pool).await?;
+
+        Ok(Database { pool })
+    }
+
+    /// Round trip to SQLite, for the readiness check
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Open and idle connections of the pool right now
+    pub fn pool_status(&self) -> PoolStatus {
+        PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+            max_connections: self.pool.options().get_max_connections(),
+        }
+    }
+
+    /// Waits for running queries and closes all connections
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    pub async fn get_user_by_email(&self, email: &str) -> Result<Option<AuthUser>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT id, email, password, created_at, disabled_at, password_reset_required_at, member_id FROM details WHERE LOWER(email) = LOWER(?)",
         )
-        .execute(&pool)
+        .bind(email)
+        .fetch_optional(&self.pool)
         .await?;
 
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS reset_tokens (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                token TEXT NOT NULL,
-                user_id INTEGER NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                expires_at DATETIME NOT NULL,
-                FOREIGN KEY (user_id) REFERENCES details(id) ON DELETE CASCADE
-            )
-            "#,
+        Ok(row.as_ref().map(auth_user_from_row))
+    }
+
+    pub async fn get_user_by_id(&self, user_id: i32) -> Result<Option<AuthUser>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT id, email, password, created_at, disabled_at, password_reset_required_at, member_id FROM details WHERE id = ?",
         )
-        .execute(&pool)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(Database { pool })
+        Ok(row.as_ref().map(auth_user_from_row))
     }
 
-    pub async fn get_user_by_email(&self, email: &str) -> Result<Option<AuthUser>, sqlx::Error> {
+    pub async fn list_users(&self) -> Result<Vec<AuthUser>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, email, password, created_at, disabled_at, password_reset_required_at, member_id FROM details ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(auth_user_from_row).collect())
+    }
+
+    /// The account created for a member by a family invitation
+    pub async fn get_user_by_member_id(
+        &self,
+        member_id: &str,
+    ) -> Result<Option<AuthUser>, sqlx::Error> {
         let row = sqlx::query(
-            "SELECT id, email, password, created_at FROM details WHERE LOWER(email) = LOWER(?)",
+            "SELECT id, email, password, created_at, disabled_at, password_reset_required_at, member_id FROM details WHERE member_id = ? ORDER BY id LIMIT 1",
         )
-        .bind(email)
+        .bind(member_id)
         .fetch_optional(&self.pool)
         .await?;
 
-        if let Some(row) = row {
-            Ok(Some(AuthUser {
-                id: row.get("id"),
-                email: row.get("email"),
-                password_hash: row.get("password"),
-                created_at: row.get("created_at"),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(row.as_ref().map(auth_user_from_row))
+    }
+
+    /// Deactivates or reactivates an account, returning false if no such account exists
+    pub async fn set_user_disabled(
+        &self,
+        user_id: i32,
+        disabled: bool,
+    ) -> Result<bool, sqlx::Error> {
+        let disabled_at = disabled.then(Utc::now);
+        let result = sqlx::query("UPDATE details SET disabled_at = ? WHERE id = ?")
+            .bind(disabled_at)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
     }
 
     #[allow(dead_code)]
@@ -120,86 +269,2298 @@ impl Database {
         let password_hash = hash(new_password, DEFAULT_COST)
             .map_err(|e| sqlx::Error::Configuration(Box::new(e)))?;
 
-        sqlx::query("UPDATE details SET password = ? WHERE id = ?")
-            .bind(&password_hash)
-            .bind(user_id)
+        sqlx::query(
+            "UPDATE details SET password = ?, password_changed_at = ?, password_reset_required_at = NULL WHERE id = ?",
+        )
+        .bind(&password_hash)
+        .bind(Utc::now())
+        .bind(user_id)
             .execute(&self.pool)
             .await?;
 
         Ok(())
     }
 
-    #[allow(dead_code)]
+    /// Stores the hash of a member's reset token, replacing their previous one
     pub async fn create_reset_token(
         &self,
-        user_id: i32,
-        token: &str,
+        member_id: &str,
+        token_hash: &str,
         expires_at: DateTime<Utc>,
     ) -> Result<(), sqlx::Error> {
-        // Delete any existing tokens for this user
-        sqlx::query("DELETE FROM reset_tokens WHERE user_id = ?")
-            .bind(user_id)
+        sqlx::query(
+            "INSERT INTO reset_tokens (token_hash, member_id, expires_at) VALUES (?, ?, ?)
+             ON CONFLICT(member_id) DO UPDATE SET token_hash = excluded.token_hash,
+                 created_at = CURRENT_TIMESTAMP, expires_at = excluded.expires_at",
+        )
+        .bind(token_hash)
+        .bind(member_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Member and expiry of a reset token, by its hash
+    #[allow(dead_code)]
+    pub async fn get_reset_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<(String, DateTime<Utc>)>, sqlx::Error> {
+        let row =
+            sqlx::query("SELECT member_id, expires_at FROM reset_tokens WHERE token_hash = ?")
+                .bind(token_hash)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.map(|row| (row.get("member_id"), row.get("expires_at"))))
+    }
+
+    /// Deletes a reset token by its hash, returning its member if it hadn't expired. The
+    /// delete decides, so a link clicked twice at once resets the password only once.
+    pub async fn consume_reset_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let row = sqlx::query(
+            "DELETE FROM reset_tokens WHERE token_hash = ? RETURNING member_id, expires_at",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|row| {
+            let expires_at: DateTime<Utc> = row.get("expires_at");
+            (expires_at > Utc::now()).then(|| row.get("member_id"))
+        }))
+    }
+
+    /// Removes all reset tokens whose expiry lies in the past, returning how many were deleted
+    pub async fn delete_expired_reset_tokens(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM reset_tokens WHERE expires_at <= ?")
+            .bind(Utc::now())
             .execute(&self.pool)
             .await?;
 
-        // Insert new token
-        sqlx::query("INSERT INTO reset_tokens (token, user_id, expires_at) VALUES (?, ?, ?)")
-            .bind(token)
-            .bind(user_id)
+        Ok(result.rows_affected())
+    }
+
+    pub async fn create_selection_token(
+        &self,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO selection_tokens (jti, expires_at) VALUES (?, ?)")
+            .bind(jti)
             .bind(expires_at)
             .execute(&self.pool)
             .await?;
+        Ok(())
+    }
+
+    /// Deletes the selection token, `true` if it existed and hadn't expired. Deleting makes
+    /// concurrent attempts to use the same token fail.
+    pub async fn consume_selection_token(&self, jti: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM selection_tokens WHERE jti = ? AND expires_at > ?")
+            .bind(jti)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn delete_expired_selection_tokens(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM selection_tokens WHERE expires_at <= ?")
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Stores a work hour entry for later replay to Teable, returning the queue ID
+    pub async fn enqueue_work_hour(
+        &self,
+        member_id: &str,
+        date: &str,
+        description: &str,
+        hours: f64,
+    ) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO work_hour_queue (member_id, date, description, hours) VALUES (?, ?, ?, ?)",
+        )
+        .bind(member_id)
+        .bind(date)
+        .bind(description)
+        .bind(hours)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// All queued entries of a member, including ones stuck on a conflict
+    pub async fn get_queued_work_hours_for_member(
+        &self,
+        member_id: &str,
+    ) -> Result<Vec<QueuedWorkHour>, sqlx::Error> {
+        let rows =
+            sqlx::query("SELECT * FROM work_hour_queue WHERE member_id = ? ORDER BY date, id")
+                .bind(member_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.iter().map(queued_work_hour_from_row).collect())
+    }
+
+    /// Queued entries that still need to be replayed, oldest first
+    pub async fn get_replayable_work_hours(&self) -> Result<Vec<QueuedWorkHour>, sqlx::Error> {
+        let rows =
+            sqlx::query("SELECT * FROM work_hour_queue WHERE conflict_reason IS NULL ORDER BY id")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.iter().map(queued_work_hour_from_row).collect())
+    }
+
+    pub async fn record_work_hour_sync_failure(
+        &self,
+        id: i64,
+        error: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE work_hour_queue SET attempts = attempts + 1, last_error = ? WHERE id = ?",
+        )
+        .bind(error)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_queued_work_hour_conflict(
+        &self,
+        id: i64,
+        reason: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE work_hour_queue SET attempts = attempts + 1, conflict_reason = ? WHERE id = ?",
+        )
+        .bind(reason)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_queued_work_hour(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM work_hour_queue WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
 
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub async fn get_reset_token(
+    /// Deletes a queued entry only if it belongs to the given member
+    pub async fn delete_queued_work_hour_for_member(
         &self,
-        token: &str,
-    ) -> Result<Option<(i32, DateTime<Utc>)>, sqlx::Error> {
-        let row = sqlx::query("SELECT user_id, expires_at FROM reset_tokens WHERE token = ?")
-            .bind(token)
-            .fetch_optional(&self.pool)
+        id: i64,
+        member_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM work_hour_queue WHERE id = ? AND member_id = ?")
+            .bind(id)
+            .bind(member_id)
+            .execute(&self.pool)
             .await?;
 
-        if let Some(row) = row {
-            Ok(Some((row.get("user_id"), row.get("expires_at"))))
-        } else {
-            Ok(None)
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn record_activity(&self, activity: &NewActivity<'_>) -> Result<i32, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO activity_log (member_id, kind, message, reference_id, actor_id) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(activity.member_id)
+        .bind(activity.kind.as_str())
+        .bind(&activity.message)
+        .bind(activity.reference_id)
+        .bind(activity.actor_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid() as i32)
+    }
+
+    pub async fn record_audit(&self, entry: &NewAuditEntry<'_>) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO audit_log (actor_id, action, target_id, before_snapshot, after_snapshot) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(entry.actor_id)
+        .bind(entry.action.as_str())
+        .bind(entry.target_id)
+        .bind(entry.before.as_ref().map(|snapshot| snapshot.to_string()))
+        .bind(entry.after.as_ref().map(|snapshot| snapshot.to_string()))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn get_audit_entry(&self, id: i64) -> Result<Option<AuditEntry>, sqlx::Error> {
+        let query = AuditQuery {
+            before: Some(id + 1),
+            limit: Some(1),
+            ..Default::default()
+        };
+        Ok(self
+            .get_audit_log(&query)
+            .await?
+            .into_iter()
+            .find(|entry| entry.id == id))
+    }
+
+    /// Audit log entries matching the query, newest first
+    pub async fn get_audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM audit_log
+            WHERE id < ?
+                AND (? IS NULL OR actor_id = ?)
+                AND (? IS NULL OR action = ?)
+                AND (? IS NULL OR target_id = ?)
+                AND (? IS NULL OR date(created_at) >= ?)
+                AND (? IS NULL OR date(created_at) <= ?)
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(query.before.unwrap_or(i64::MAX))
+        .bind(&query.actor_id)
+        .bind(&query.actor_id)
+        .bind(query.action.map(|action| action.as_str()))
+        .bind(query.action.map(|action| action.as_str()))
+        .bind(&query.target_id)
+        .bind(&query.target_id)
+        .bind(query.from.map(|date| date.to_string()))
+        .bind(query.from.map(|date| date.to_string()))
+        .bind(query.to.map(|date| date.to_string()))
+        .bind(query.to.map(|date| date.to_string()))
+        .bind(query.limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Actions written by a newer version are skipped rather than failing the log
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let action = AuditAction::from_db(row.get::<String, _>("action").as_str())?;
+                let created_at: DateTime<Utc> = row.get("created_at");
+                let snapshot = |column: &str| {
+                    row.get::<Option<String>, _>(column)
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                };
+                Some(AuditEntry {
+                    id: row.get("id"),
+                    actor_id: row.get("actor_id"),
+                    action,
+                    target_id: row.get("target_id"),
+                    before: snapshot("before_snapshot"),
+                    after: snapshot("after_snapshot"),
+                    created_at: created_at.to_rfc3339(),
+                })
+            })
+            .collect())
+    }
+
+    /// Replaces the materialized daily totals of a year in one transaction
+    pub async fn replace_daily_stats(
+        &self,
+        year: i32,
+        days: &[DailyHours],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM work_hour_daily_stats WHERE year = ?")
+            .bind(year)
+            .execute(&mut *tx)
+            .await?;
+        for day in days {
+            sqlx::query(
+                "INSERT INTO work_hour_daily_stats (date, year, hours, entries) VALUES (?, ?, ?, ?)",
+            )
+            .bind(day.date.to_string())
+            .bind(year)
+            .bind(day.hours)
+            .bind(day.entries)
+            .execute(&mut *tx)
+            .await?;
         }
+        sqlx::query("INSERT OR REPLACE INTO stats_refreshes (year, refreshed_at) VALUES (?, ?)")
+            .bind(year)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
     }
 
-    #[allow(dead_code)]
-    pub async fn consume_reset_token(&self, token: &str) -> Result<Option<i32>, sqlx::Error> {
+    /// Replaces the description counts of a year, keyed by `suggestions::normalize`
+    pub async fn replace_description_usage(
+        &self,
+        year: i32,
+        counts: &BTreeMap<String, u32>,
+    ) -> Result<(), sqlx::Error> {
         let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM description_usage WHERE year = ?")
+            .bind(year)
+            .execute(&mut *tx)
+            .await?;
+        for (description, count) in counts {
+            sqlx::query(
+                "INSERT INTO description_usage (year, description, count) VALUES (?, ?, ?)",
+            )
+            .bind(year)
+            .bind(description)
+            .bind(count)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Uses per description in the years from `first_year` on
+    pub async fn get_description_usage(
+        &self,
+        first_year: i32,
+    ) -> Result<HashMap<String, u32>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT description, SUM(count) AS count FROM description_usage WHERE year >= ? GROUP BY description",
+        )
+        .bind(first_year)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("description"), row.get::<i64, _>("count") as u32))
+            .collect())
+    }
 
-        let row = sqlx::query("SELECT user_id, expires_at FROM reset_tokens WHERE token = ?")
-            .bind(token)
-            .fetch_optional(&mut *tx)
+    /// The board's description suggestions as ID and text
+    pub async fn list_description_suggestions(&self) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        let rows = sqlx::query("SELECT id, text FROM description_suggestions ORDER BY id")
+            .fetch_all(&self.pool)
             .await?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("id"), row.get("text")))
+            .collect())
+    }
 
-        if let Some(row) = row {
-            let user_id: i32 = row.get("user_id");
-            let expires_at: DateTime<Utc> = row.get("expires_at");
+    /// Fails with a unique violation if the suggestion exists already, ignoring case
+    pub async fn create_description_suggestion(
+        &self,
+        text: &str,
+        created_by: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let result =
+            sqlx::query("INSERT INTO description_suggestions (text, created_by) VALUES (?, ?)")
+                .bind(text)
+                .bind(created_by)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.last_insert_rowid())
+    }
 
-            if expires_at > Utc::now() {
-                // Token is valid, delete it
-                sqlx::query("DELETE FROM reset_tokens WHERE token = ?")
-                    .bind(token)
-                    .execute(&mut *tx)
-                    .await?;
+    /// The text of the deleted suggestion, `None` if it didn't exist
+    pub async fn delete_description_suggestion(
+        &self,
+        id: i64,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("DELETE FROM description_suggestions WHERE id = ? RETURNING text")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
 
-                tx.commit().await?;
-                Ok(Some(user_id))
-            } else {
-                // Token expired
-                tx.rollback().await?;
-                Ok(None)
-            }
-        } else {
-            tx.rollback().await?;
-            Ok(None)
+    pub async fn get_daily_stats(&self, year: i32) -> Result<Vec<DailyHours>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT date, hours, entries FROM work_hour_daily_stats WHERE year = ? ORDER BY date",
+        )
+        .bind(year)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let date = row.get::<String, _>("date").parse().ok()?;
+                Some(DailyHours {
+                    date,
+                    hours: row.get("hours"),
+                    entries: row.get("entries"),
+                })
+            })
+            .collect())
+    }
+
+    /// When the daily totals of a year were last rebuilt, `None` if never
+    pub async fn get_stats_refreshed_at(
+        &self,
+        year: i32,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let row = sqlx::query("SELECT refreshed_at FROM stats_refreshes WHERE year = ?")
+            .bind(year)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("refreshed_at")))
+    }
+
+    /// Stores the required hours of a year in one transaction, keeping whether a member
+    /// was already notified about the year
+    pub async fn upsert_member_requirements(
+        &self,
+        year: i32,
+        requirements: &[MemberRequirement],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now();
+
+        for requirement in requirements {
+            sqlx::query(
+                r#"
+                INSERT INTO member_requirements (member_id, year, required_hours, exemption_reason, updated_at)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT (member_id, year) DO UPDATE SET
+                    required_hours = excluded.required_hours,
+                    exemption_reason = excluded.exemption_reason,
+                    updated_at = excluded.updated_at
+                "#,
+            )
+            .bind(&requirement.member_id)
+            .bind(year)
+            .bind(requirement.required_hours)
+            .bind(&requirement.exemption_reason)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
         }
+
+        tx.commit().await
+    }
+
+    /// Marks the member as notified about the year; false if that already happened,
+    /// so concurrent or repeated runs notify only once
+    pub async fn claim_requirement_notification(
+        &self,
+        member_id: &str,
+        year: i32,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE member_requirements SET notified_at = ? WHERE member_id = ? AND year = ? AND notified_at IS NULL",
+        )
+        .bind(Utc::now())
+        .bind(member_id)
+        .bind(year)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn create_guest_session(
+        &self,
+        member_id: &str,
+        member_name: &str,
+        date: &str,
+        guest_name: &str,
+        fee: f64,
+    ) -> Result<GuestSession, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO guest_sessions (member_id, member_name, date, guest_name, fee) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(member_id)
+        .bind(member_name)
+        .bind(date)
+        .bind(guest_name)
+        .bind(fee)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(GuestSession {
+            id: result.last_insert_rowid() as i32,
+            member_id: member_id.to_string(),
+            member_name: member_name.to_string(),
+            date: date.to_string(),
+            guest_name: guest_name.to_string(),
+            fee,
+            paid: false,
+        })
+    }
+
+    pub async fn get_guest_session(&self, id: i32) -> Result<Option<GuestSession>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM guest_sessions WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(guest_session_from_row))
+    }
+
+    /// Guest sessions of a season, optionally limited to one member, oldest first
+    pub async fn get_guest_sessions(
+        &self,
+        season: i32,
+        member_id: Option<&str>,
+    ) -> Result<Vec<GuestSession>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM guest_sessions WHERE date LIKE ? AND (? IS NULL OR member_id = ?) ORDER BY date, id",
+        )
+        .bind(format!("{season}-%"))
+        .bind(member_id)
+        .bind(member_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(guest_session_from_row).collect())
+    }
+
+    pub async fn mark_guest_session_paid(&self, id: i32) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("UPDATE guest_sessions SET paid_at = ? WHERE id = ? AND paid_at IS NULL")
+                .bind(Utc::now())
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Requests a certificate; asking again for the same year returns the existing request
+    pub async fn request_certificate(
+        &self,
+        member_id: &str,
+        member_name: &str,
+        year: i32,
+    ) -> Result<CertificateRequest, sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO certificate_requests (member_id, member_name, year, status) VALUES (?, ?, ?, ?) ON CONFLICT (member_id, year) DO NOTHING",
+        )
+        .bind(member_id)
+        .bind(member_name)
+        .bind(year)
+        .bind(CertificateStatus::Requested.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        self.get_certificate_request(member_id, year)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    pub async fn get_certificate_request(
+        &self,
+        member_id: &str,
+        year: i32,
+    ) -> Result<Option<CertificateRequest>, sqlx::Error> {
+        let row =
+            sqlx::query("SELECT * FROM certificate_requests WHERE member_id = ? AND year = ?")
+                .bind(member_id)
+                .bind(year)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.as_ref().and_then(certificate_request_from_row))
+    }
+
+    pub async fn get_certificate_request_by_id(
+        &self,
+        id: i32,
+    ) -> Result<Option<CertificateRequest>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM certificate_requests WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().and_then(certificate_request_from_row))
+    }
+
+    /// Certificate requests, optionally of one status, oldest first
+    pub async fn get_certificate_requests(
+        &self,
+        status: Option<CertificateStatus>,
+    ) -> Result<Vec<CertificateRequest>, sqlx::Error> {
+        let status = status.map(|status| status.as_str());
+        let rows = sqlx::query(
+            "SELECT * FROM certificate_requests WHERE (? IS NULL OR status = ?) ORDER BY id",
+        )
+        .bind(status)
+        .bind(status)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(certificate_request_from_row)
+            .collect())
+    }
+
+    /// Approves a pending request; returns false if it was approved already
+    pub async fn approve_certificate(
+        &self,
+        id: i32,
+        approved_by: &str,
+        summary: &CertificateSummary,
+    ) -> Result<bool, sqlx::Error> {
+        let summary = serde_json::json!(summary).to_string();
+        let result = sqlx::query(
+            "UPDATE certificate_requests SET status = ?, summary = ?, approved_by = ?, approved_at = ? WHERE id = ? AND status = ?",
+        )
+        .bind(CertificateStatus::Approved.as_str())
+        .bind(summary)
+        .bind(approved_by)
+        .bind(Utc::now())
+        .bind(id)
+        .bind(CertificateStatus::Requested.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Stores a pending family invitation; `None` if `email` already has one
+    pub async fn create_family_invitation(
+        &self,
+        invited_by: &str,
+        member_id: &str,
+        member_name: &str,
+        email: &str,
+    ) -> Result<Option<FamilyInvitation>, sqlx::Error> {
+        let pending = FamilyInvitationStatus::Pending.as_str();
+        let result = sqlx::query(
+            "INSERT INTO family_invitations (invited_by, member_id, member_name, email, status) SELECT ?, ?, ?, ?, ? WHERE NOT EXISTS (SELECT 1 FROM family_invitations WHERE email = ? AND status = ?)",
+        )
+        .bind(invited_by)
+        .bind(member_id)
+        .bind(member_name)
+        .bind(email.to_lowercase())
+        .bind(pending)
+        .bind(email.to_lowercase())
+        .bind(pending)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        self.get_family_invitation(result.last_insert_rowid() as i32)
+            .await
+    }
+
+    pub async fn get_family_invitation(
+        &self,
+        id: i32,
+    ) -> Result<Option<FamilyInvitation>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM family_invitations WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().and_then(family_invitation_from_row))
+    }
+
+    /// Family invitations, optionally of one status or by one member, newest first
+    pub async fn get_family_invitations(
+        &self,
+        status: Option<FamilyInvitationStatus>,
+        invited_by: Option<&str>,
+    ) -> Result<Vec<FamilyInvitation>, sqlx::Error> {
+        let status = status.map(|status| status.as_str());
+        let rows = sqlx::query(
+            "SELECT * FROM family_invitations WHERE (? IS NULL OR status = ?) AND (? IS NULL OR invited_by = ?) ORDER BY id DESC",
+        )
+        .bind(status)
+        .bind(status)
+        .bind(invited_by)
+        .bind(invited_by)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().filter_map(family_invitation_from_row).collect())
+    }
+
+    /// Approves a pending invitation and creates its account in one transaction. The account
+    /// gets a random password until the invited person sets one with the emailed link.
+    /// Returns `None` if the invitation was decided already.
+    pub async fn approve_family_invitation(
+        &self,
+        id: i32,
+        approved_by: &str,
+    ) -> Result<Option<i32>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            "UPDATE family_invitations SET status = ?, decided_by = ?, decided_at = ? WHERE id = ? AND status = ? RETURNING email, member_id",
+        )
+        .bind(FamilyInvitationStatus::Approved.as_str())
+        .bind(approved_by)
+        .bind(Utc::now())
+        .bind(id)
+        .bind(FamilyInvitationStatus::Pending.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(row) = row else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+
+        let password_hash = hash(uuid::Uuid::new_v4().to_string(), DEFAULT_COST)
+            .map_err(|e| sqlx::Error::Configuration(Box::new(e)))?;
+        let user_id =
+            sqlx::query("INSERT INTO details (email, password, member_id) VALUES (?, ?, ?)")
+                .bind(row.get::<String, _>("email"))
+                .bind(password_hash)
+                .bind(row.get::<String, _>("member_id"))
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+
+        tx.commit().await?;
+        Ok(Some(user_id as i32))
+    }
+
+    /// Rejects a pending invitation; returns false if it was decided already
+    pub async fn reject_family_invitation(
+        &self,
+        id: i32,
+        rejected_by: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE family_invitations SET status = ?, decided_by = ?, decided_at = ? WHERE id = ? AND status = ?",
+        )
+        .bind(FamilyInvitationStatus::Rejected.as_str())
+        .bind(rejected_by)
+        .bind(Utc::now())
+        .bind(id)
+        .bind(FamilyInvitationStatus::Pending.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Flags all active accounts for a mandatory password reset and queues a reset email
+    /// for each; returns the rotation ID and the number of flagged accounts
+    pub async fn start_password_rotation(
+        &self,
+        started_by: &str,
+        started_at: DateTime<Utc>,
+    ) -> Result<(i64, u64), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let rotation_id =
+            sqlx::query("INSERT INTO password_rotations (started_by, started_at) VALUES (?, ?)")
+                .bind(started_by)
+                .bind(started_at)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+        let flagged = sqlx::query(
+            "UPDATE details SET password_reset_required_at = ? WHERE disabled_at IS NULL",
+        )
+        .bind(started_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query(
+            "INSERT INTO password_rotation_emails (rotation_id, email) SELECT ?, email FROM details WHERE disabled_at IS NULL ORDER BY id",
+        )
+        .bind(rotation_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok((rotation_id, flagged))
+    }
+
+    /// Start of the latest password rotation; bearer tokens issued before it are invalid
+    pub async fn get_sessions_revoked_before(&self) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar("SELECT MAX(started_at) FROM password_rotations")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Invalidates the bearer tokens of a member issued before `at`, and their reset link
+    pub async fn revoke_member_sessions(
+        &self,
+        member_id: &str,
+        at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO revoked_sessions (member_id, revoked_before) VALUES (?, ?)
+             ON CONFLICT(member_id) DO UPDATE SET revoked_before = MAX(revoked_before, excluded.revoked_before)",
+        )
+        .bind(member_id)
+        .bind(at)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM reset_tokens WHERE member_id = ?")
+            .bind(member_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+
+    /// All members with revoked sessions, with the time before which their tokens are invalid
+    pub async fn get_revoked_sessions(&self) -> Result<Vec<(String, DateTime<Utc>)>, sqlx::Error> {
+        sqlx::query_as("SELECT member_id, revoked_before FROM revoked_sessions")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Reset emails that were neither sent nor given up on, oldest first
+    pub async fn get_pending_rotation_emails(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<RotationEmail>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, rotation_id, email, attempts FROM password_rotation_emails WHERE sent_at IS NULL AND attempts < ? ORDER BY id LIMIT ?",
+        )
+        .bind(MAX_EMAIL_ATTEMPTS)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| RotationEmail {
+                id: row.get("id"),
+                rotation_id: row.get("rotation_id"),
+                email: row.get("email"),
+                attempts: row.get("attempts"),
+            })
+            .collect())
+    }
+
+    pub async fn mark_rotation_email_sent(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE password_rotation_emails SET sent_at = ?, attempts = attempts + 1 WHERE id = ?",
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_rotation_email_failure(
+        &self,
+        id: i64,
+        error: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE password_rotation_emails SET attempts = attempts + 1, last_error = ? WHERE id = ?",
+        )
+        .bind(error)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Marks rotations without pending emails as completed; returns how many were completed
+    pub async fn complete_password_rotations(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE password_rotations SET completed_at = ?
+            WHERE completed_at IS NULL AND NOT EXISTS (
+                SELECT 1 FROM password_rotation_emails e
+                WHERE e.rotation_id = password_rotations.id AND e.sent_at IS NULL AND e.attempts < ?
+            )
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(MAX_EMAIL_ATTEMPTS)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_password_rotation_stats(
+        &self,
+        id: i64,
+    ) -> Result<Option<PasswordRotationStats>, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT r.id, r.started_by, r.started_at, r.completed_at,
+                COUNT(e.id) AS accounts,
+                COUNT(e.sent_at) AS emails_sent,
+                SUM(e.sent_at IS NULL AND e.attempts >= ?) AS emails_failed,
+                SUM(e.sent_at IS NULL AND e.attempts < ?) AS emails_pending,
+                SUM(d.password_changed_at >= r.started_at) AS passwords_reset
+            FROM password_rotations r
+            LEFT JOIN password_rotation_emails e ON e.rotation_id = r.id
+            LEFT JOIN details d ON LOWER(d.email) = LOWER(e.email)
+            WHERE r.id = ?
+            GROUP BY r.id
+            "#,
+        )
+        .bind(MAX_EMAIL_ATTEMPTS)
+        .bind(MAX_EMAIL_ATTEMPTS)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let started_at: DateTime<Utc> = row.get("started_at");
+            let completed_at: Option<DateTime<Utc>> = row.get("completed_at");
+            let count = |column: &str| row.get::<Option<i64>, _>(column).unwrap_or(0) as u32;
+            PasswordRotationStats {
+                id: row.get("id"),
+                started_by: row.get("started_by"),
+                started_at: started_at.to_rfc3339(),
+                completed_at: completed_at.map(|at| at.to_rfc3339()),
+                accounts: count("accounts"),
+                emails_sent: count("emails_sent"),
+                emails_failed: count("emails_failed"),
+                emails_pending: count("emails_pending"),
+                passwords_reset: count("passwords_reset"),
+            }
+        }))
+    }
+
+    /// Keeps a snapshot of a work hour entry that is about to be deleted from Teable.
+    /// Returns None without storing anything if the record lacks the fields needed to
+    /// re-create it.
+    pub async fn trash_work_hour(
+        &self,
+        work_hour: &WorkHour,
+        deleted_by: &str,
+    ) -> Result<Option<DeletedWorkHour>, sqlx::Error> {
+        let deleted_at = Utc::now();
+        let Some(entry) = DeletedWorkHour::from_work_hour(work_hour, deleted_by, deleted_at) else {
+            return Ok(None);
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO deleted_work_hours
+                (work_hour_id, member_id, date, description, hours, status, review_comment, deleted_by, deleted_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (work_hour_id) DO UPDATE SET deleted_by = excluded.deleted_by, deleted_at = excluded.deleted_at
+            "#,
+        )
+        .bind(&entry.work_hour_id)
+        .bind(&entry.member_id)
+        .bind(&entry.date)
+        .bind(&entry.description)
+        .bind(entry.hours)
+        .bind(&entry.status)
+        .bind(&entry.review_comment)
+        .bind(&entry.deleted_by)
+        .bind(deleted_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(Some(entry))
+    }
+
+    /// A deleted entry, unless it was deleted before `since`
+    pub async fn get_deleted_work_hour(
+        &self,
+        work_hour_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<DeletedWorkHour>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT * FROM deleted_work_hours WHERE work_hour_id = ? AND deleted_at >= ?",
+        )
+        .bind(work_hour_id)
+        .bind(since)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(deleted_work_hour_from_row))
+    }
+
+    /// A member's entries deleted since `since`, most recently deleted first
+    pub async fn get_deleted_work_hours_for_member(
+        &self,
+        member_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DeletedWorkHour>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM deleted_work_hours WHERE member_id = ? AND deleted_at >= ? ORDER BY deleted_at DESC",
+        )
+        .bind(member_id)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(deleted_work_hour_from_row).collect())
+    }
+
+    /// Removes an entry from the trash, returning false if it wasn't there
+    pub async fn remove_deleted_work_hour(&self, work_hour_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM deleted_work_hours WHERE work_hour_id = ?")
+            .bind(work_hour_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Permanently drops entries deleted before `before`; returns how many were dropped
+    pub async fn purge_deleted_work_hours(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM deleted_work_hours WHERE deleted_at < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// A member's activities, newest first; `before` pages past the given ID
+    pub async fn create_job(
+        &self,
+        kind: JobKind,
+        created_by: &str,
+        payload: &str,
+        total: u32,
+    ) -> Result<Job, sqlx::Error> {
+        let now = Utc::now();
+        let id = sqlx::query(
+            "INSERT INTO jobs (kind, status, created_by, payload, total, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(kind.as_str())
+        .bind(JobStatus::Queued.as_str())
+        .bind(created_by)
+        .bind(payload)
+        .bind(total)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        self.get_job(id).await?.ok_or(sqlx::Error::RowNotFound)
+    }
+
+    pub async fn get_job(&self, id: i64) -> Result<Option<Job>, sqlx::Error> {
+        let Some(row) = sqlx::query("SELECT * FROM jobs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let errors = self.get_job_errors(id).await?;
+        Ok(job_from_row(&row, errors))
+    }
+
+    async fn get_job_errors(&self, id: i64) -> Result<Vec<JobItemError>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT item_index, code, message, retryable FROM job_errors WHERE job_id = ? ORDER BY item_index",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| JobItemError {
+                index: row.get("item_index"),
+                // Errors recorded before codes were stored
+                code: row
+                    .get::<Option<String>, _>("code")
+                    .and_then(|code| ErrorCode::from_db(&code))
+                    .unwrap_or(ErrorCode::InternalError),
+                message: row.get("message"),
+                retryable: row.get("retryable"),
+            })
+            .collect())
+    }
+
+    /// The JSON payload a job was started with
+    pub async fn get_job_payload(&self, id: i64) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT payload FROM jobs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Queued and running jobs with their payload, oldest first
+    pub async fn get_unfinished_jobs(&self) -> Result<Vec<(Job, String)>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM jobs WHERE status IN (?, ?) ORDER BY id")
+            .bind(JobStatus::Queued.as_str())
+            .bind(JobStatus::Running.as_str())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut jobs = Vec::new();
+        for row in &rows {
+            let errors = self.get_job_errors(row.get("id")).await?;
+            if let Some(job) = job_from_row(row, errors) {
+                jobs.push((job, row.get("payload")));
+            }
+        }
+        Ok(jobs)
+    }
+
+    /// Moves the job `count` items on; returns `None` if it was cancelled or finished meanwhile
+    pub async fn record_job_progress(
+        &self,
+        id: i64,
+        count: u32,
+        errors: &[JobItemError],
+    ) -> Result<Option<Job>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let updated = sqlx::query(
+            "UPDATE jobs SET status = ?, processed = processed + ?, failed = failed + ?, updated_at = ? WHERE id = ? AND status IN (?, ?)",
+        )
+        .bind(JobStatus::Running.as_str())
+        .bind(count)
+        .bind(errors.len() as u32)
+        .bind(Utc::now())
+        .bind(id)
+        .bind(JobStatus::Queued.as_str())
+        .bind(JobStatus::Running.as_str())
+        .execute(&mut *tx)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        for error in errors {
+            sqlx::query(
+                "INSERT INTO job_errors (job_id, item_index, code, message, retryable) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(error.index)
+            .bind(error.code.as_str())
+            .bind(&error.message)
+            .bind(error.retryable)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        self.get_job(id).await
+    }
+
+    pub async fn complete_job(&self, id: i64) -> Result<bool, sqlx::Error> {
+        self.finish_job(id, JobStatus::Completed, None).await
+    }
+
+    pub async fn fail_job(&self, id: i64, error: &str) -> Result<bool, sqlx::Error> {
+        self.finish_job(id, JobStatus::Failed, Some(error)).await
+    }
+
+    /// Returns false if the job had finished already
+    pub async fn cancel_job(&self, id: i64) -> Result<bool, sqlx::Error> {
+        self.finish_job(id, JobStatus::Cancelled, None).await
+    }
+
+    async fn finish_job(
+        &self,
+        id: i64,
+        status: JobStatus,
+        error: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        let now = Utc::now();
+        let result = sqlx::query(
+            "UPDATE jobs SET status = ?, error = ?, updated_at = ?, finished_at = ? WHERE id = ? AND status IN (?, ?)",
+        )
+        .bind(status.as_str())
+        .bind(error)
+        .bind(now)
+        .bind(now)
+        .bind(id)
+        .bind(JobStatus::Queued.as_str())
+        .bind(JobStatus::Running.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_activity_for_member(
+        &self,
+        member_id: &str,
+        limit: u32,
+        before: Option<i32>,
+    ) -> Result<Vec<ActivityEntry>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM activity_log WHERE member_id = ? AND id < ? ORDER BY id DESC LIMIT ?",
+        )
+        .bind(member_id)
+        .bind(before.unwrap_or(i32::MAX))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Kinds written by a newer version are skipped rather than failing the feed
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let kind = ActivityKind::from_db(row.get::<String, _>("kind").as_str())?;
+                let created_at: DateTime<Utc> = row.get("created_at");
+                Some(ActivityEntry {
+                    id: row.get("id"),
+                    kind,
+                    message: row.get("message"),
+                    reference_id: row.get("reference_id"),
+                    actor_id: row.get("actor_id"),
+                    created_at: created_at.to_rfc3339(),
+                })
+            })
+            .collect())
+    }
+
+    /// Login accounts, including deactivated ones
+    pub async fn count_accounts(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM details")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// How often each audit action was recorded since `since`
+    pub async fn count_audit_actions_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<(AuditAction, i64)>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT action, COUNT(*) AS count FROM audit_log WHERE datetime(created_at) >= datetime(?) GROUP BY action ORDER BY action",
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let action = AuditAction::from_db(row.get::<String, _>("action").as_str())?;
+                Some((action, row.get("count")))
+            })
+            .collect())
+    }
+
+    /// Instance ID of the telemetry, generated on first use, and when a report was last sent
+    pub async fn get_telemetry_state(
+        &self,
+    ) -> Result<(String, Option<DateTime<Utc>>), sqlx::Error> {
+        sqlx::query("INSERT OR IGNORE INTO telemetry (id, instance_id) VALUES (1, ?)")
+            .bind(uuid::Uuid::new_v4().to_string())
+            .execute(&self.pool)
+            .await?;
+        let row = sqlx::query("SELECT instance_id, last_sent_at FROM telemetry WHERE id = 1")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok((row.get("instance_id"), row.get("last_sent_at")))
+    }
+
+    pub async fn record_telemetry_sent(&self, at: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE telemetry SET last_sent_at = ? WHERE id = 1")
+            .bind(at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_write_freeze(&self) -> Result<Option<WriteFreeze>, sqlx::Error> {
+        let row =
+            sqlx::query("SELECT from_date, until_date, reason FROM write_freeze WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.and_then(|row| {
+            Some(WriteFreeze {
+                from: row.get::<String, _>("from_date").parse().ok()?,
+                until: row.get::<String, _>("until_date").parse().ok()?,
+                reason: row.get("reason"),
+            })
+        }))
+    }
+
+    /// Replaces the scheduled write freeze, if any
+    pub async fn set_write_freeze(
+        &self,
+        freeze: &WriteFreeze,
+        set_by: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO write_freeze (id, from_date, until_date, reason, set_by, set_at) VALUES (1, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+        )
+        .bind(freeze.from.to_string())
+        .bind(freeze.until.to_string())
+        .bind(&freeze.reason)
+        .bind(set_by)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// `false` if no write freeze was scheduled
+    pub async fn clear_write_freeze(&self) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM write_freeze WHERE id = 1")
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The record `old_id` was merged into, if any
+    /// Stores the survey link of a helper, `false` if the helper got one for the event already
+    pub async fn create_survey_invitation(
+        &self,
+        token_hash: &str,
+        event_id: &str,
+        event_title: &str,
+        event_date: &str,
+        member_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO survey_invitations (token_hash, event_id, event_title, event_date, member_id, expires_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(token_hash)
+        .bind(event_id)
+        .bind(event_title)
+        .bind(event_date)
+        .bind(member_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// The survey behind a link, `None` if unknown or expired
+    pub async fn get_survey_invitation(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<SurveyInvitation>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT event_title, event_date, expires_at, answered_at FROM survey_invitations WHERE token_hash = ? AND expires_at > ?",
+        )
+        .bind(token_hash)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| SurveyInvitation {
+            event_title: row.get("event_title"),
+            event_date: row.get("event_date"),
+            question: crate::survey::SURVEY_QUESTION.to_string(),
+            answered: row.get::<Option<DateTime<Utc>>, _>("answered_at").is_some(),
+            expires_at: row.get::<DateTime<Utc>, _>("expires_at").to_rfc3339(),
+        }))
+    }
+
+    /// Stores the answer given through a link, `false` if the link was used already. Call
+    /// `get_survey_invitation` first to tell unknown links apart.
+    pub async fn answer_survey(
+        &self,
+        token_hash: &str,
+        rating: u8,
+        comment: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let event_id: Option<String> = sqlx::query_scalar(
+            "UPDATE survey_invitations SET answered_at = ? WHERE token_hash = ? AND answered_at IS NULL AND expires_at > ? RETURNING event_id",
+        )
+        .bind(Utc::now())
+        .bind(token_hash)
+        .bind(Utc::now())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(event_id) = event_id else {
+            return Ok(false);
+        };
+        sqlx::query("INSERT INTO survey_responses (event_id, rating, comment) VALUES (?, ?, ?)")
+            .bind(&event_id)
+            .bind(i64::from(rating))
+            .bind(comment)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// How many helpers got the survey of an event, and the answers in the order they came in
+    pub async fn get_survey_answers(
+        &self,
+        event_id: &str,
+    ) -> Result<(u32, Vec<(u8, Option<String>)>), sqlx::Error> {
+        let invited: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM survey_invitations WHERE event_id = ?")
+                .bind(event_id)
+                .fetch_one(&self.pool)
+                .await?;
+        let answers = sqlx::query(
+            "SELECT rating, comment FROM survey_responses WHERE event_id = ? ORDER BY id",
+        )
+        .bind(event_id)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| (row.get::<i64, _>("rating") as u8, row.get("comment")))
+        .collect();
+        Ok((invited as u32, answers))
+    }
+
+    /// Adds `count` uses of `feature` to the counter of `month` (YYYY-MM)
+    pub async fn count_usage(
+        &self,
+        feature: UsageFeature,
+        month: &str,
+        count: u64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO usage_counters (month, feature, count) VALUES (?, ?, ?) ON CONFLICT (month, feature) DO UPDATE SET count = count + excluded.count",
+        )
+        .bind(month)
+        .bind(feature.as_str())
+        .bind(count as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Counters from `first_month` (YYYY-MM) on, as month, feature and count
+    pub async fn get_usage_since(
+        &self,
+        first_month: &str,
+    ) -> Result<Vec<(String, UsageFeature, u64)>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT month, feature, count FROM usage_counters WHERE month >= ? ORDER BY month",
+        )
+        .bind(first_month)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let feature = UsageFeature::from_db(row.get::<String, _>("feature").as_str())?;
+                Some((row.get("month"), feature, row.get::<i64, _>("count") as u64))
+            })
+            .collect())
+    }
+
+    /// The rules stored by the last changelog check, as JSON
+    pub async fn get_rules_snapshot(&self) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT rules FROM rules_snapshot WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Replaces the stored rules and adds the changes found against the previous ones, so
+    /// a change is never recorded twice
+    pub async fn record_rule_changes(
+        &self,
+        snapshot: &str,
+        changes: &[NewRuleChange],
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO rules_snapshot (id, rules, updated_at) VALUES (1, ?, ?)",
+        )
+        .bind(snapshot)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        for change in changes {
+            insert_rule_change(&mut tx, change, None, now).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Adds a change entered by the board
+    pub async fn create_rule_change(
+        &self,
+        change: &NewRuleChange,
+        recorded_by: &str,
+    ) -> Result<RuleChange, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let id = insert_rule_change(&mut tx, change, Some(recorded_by), Utc::now()).await?;
+        tx.commit().await?;
+        self.get_rule_change(id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    pub async fn get_rule_change(&self, id: i64) -> Result<Option<RuleChange>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM rule_changes WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.as_ref().and_then(rule_change_from_row))
+    }
+
+    /// The latest changes, by the day they take effect
+    pub async fn get_rule_changes(&self, limit: u32) -> Result<Vec<RuleChange>, sqlx::Error> {
+        let rows =
+            sqlx::query("SELECT * FROM rule_changes ORDER BY effective_from DESC, id DESC LIMIT ?")
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows.iter().filter_map(rule_change_from_row).collect())
+    }
+
+    /// `false` if there is no such change
+    pub async fn set_rule_change_note(
+        &self,
+        id: i64,
+        note: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE rule_changes SET note = ? WHERE id = ?")
+            .bind(note)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// `false` if `recipient_key` was reminded on `day` already, so a reminder isn't sent
+    /// twice when the job runs again
+    pub async fn claim_hours_reminder(
+        &self,
+        recipient_key: &str,
+        day: NaiveDate,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO hours_reminders (recipient_key, reminder_day, sent_at) VALUES (?, ?, ?)",
+        )
+        .bind(recipient_key)
+        .bind(day.format("%Y-%m-%d").to_string())
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// `false` if the change was announced already, so members get each announcement once
+    pub async fn mark_rule_change_announced(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE rule_changes SET announced_at = ? WHERE id = ? AND announced_at IS NULL",
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Queues an email whose first attempt failed with `error`
+    pub async fn queue_email(
+        &self,
+        recipient: &str,
+        subject: &str,
+        html: &str,
+        text: &str,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            "INSERT INTO email_outbox (recipient, subject, html, text, status, attempts, last_error, created_at, next_attempt_at) VALUES (?, ?, ?, ?, ?, 1, ?, ?, ?)",
+        )
+        .bind(recipient)
+        .bind(subject)
+        .bind(html)
+        .bind(text)
+        .bind(OutboxStatus::Pending.as_str())
+        .bind(error)
+        .bind(Utc::now())
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Pending emails whose next attempt is due, oldest first
+    pub async fn get_due_emails(
+        &self,
+        now: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<QueuedEmail>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, recipient, subject, html, text, attempts FROM email_outbox WHERE status = ? AND next_attempt_at <= ? ORDER BY id LIMIT ?",
+        )
+        .bind(OutboxStatus::Pending.as_str())
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| QueuedEmail {
+                id: row.get("id"),
+                recipient: row.get("recipient"),
+                subject: row.get("subject"),
+                html: row.get("html"),
+                text: row.get("text"),
+                attempts: row.get("attempts"),
+            })
+            .collect())
+    }
+
+    pub async fn mark_email_sent(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE email_outbox SET status = ?, attempts = attempts + 1, sent_at = ?, next_attempt_at = NULL, html = '', text = '' WHERE id = ?",
+        )
+        .bind(OutboxStatus::Sent.as_str())
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records a failed retry; without `next_attempt_at` the email is given up
+    pub async fn record_email_failure(
+        &self,
+        id: i64,
+        error: &str,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<(), sqlx::Error> {
+        let status = match next_attempt_at {
+            Some(_) => OutboxStatus::Pending,
+            None => OutboxStatus::Failed,
+        };
+        sqlx::query(
+            "UPDATE email_outbox SET status = ?, attempts = attempts + 1, last_error = ?, next_attempt_at = ?, html = CASE WHEN ? THEN html ELSE '' END, text = CASE WHEN ? THEN text ELSE '' END WHERE id = ?",
+        )
+        .bind(status.as_str())
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(next_attempt_at.is_some())
+        .bind(next_attempt_at.is_some())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Queued emails for the board, newest first
+    pub async fn get_outbox_emails(
+        &self,
+        query: &OutboxQuery,
+    ) -> Result<Vec<OutboxEmail>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, recipient, subject, status, attempts, last_error, created_at, next_attempt_at, sent_at FROM email_outbox WHERE (? IS NULL OR status = ?) ORDER BY id DESC LIMIT ?",
+        )
+        .bind(query.status.map(|status| status.as_str()))
+        .bind(query.status.map(|status| status.as_str()))
+        .bind(query.limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let status = OutboxStatus::from_db(row.get::<String, _>("status").as_str())?;
+                let created_at: DateTime<Utc> = row.get("created_at");
+                let next_attempt_at: Option<DateTime<Utc>> = row.get("next_attempt_at");
+                let sent_at: Option<DateTime<Utc>> = row.get("sent_at");
+                Some(OutboxEmail {
+                    id: row.get("id"),
+                    recipient: row.get("recipient"),
+                    subject: row.get("subject"),
+                    status,
+                    attempts: row.get("attempts"),
+                    last_error: row.get("last_error"),
+                    created_at: created_at.to_rfc3339(),
+                    next_attempt_at: next_attempt_at.map(|at| at.to_rfc3339()),
+                    sent_at: sent_at.map(|at| at.to_rfc3339()),
+                })
+            })
+            .collect())
+    }
+
+    /// Removes sent and failed emails queued before `before`; pending ones are kept
+    pub async fn purge_outbox_emails(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM email_outbox WHERE status != ? AND created_at < ?")
+            .bind(OutboxStatus::Pending.as_str())
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn record_reset_request(
+        &self,
+        request: &NewResetRequest<'_>,
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            "INSERT INTO password_reset_requests (subject, member_id, email, resent_by, status, error, requested_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(request.subject)
+        .bind(request.member_id)
+        .bind(request.email)
+        .bind(request.resent_by)
+        .bind(request.status.as_str())
+        .bind(&request.error)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Logged reset emails, newest first
+    pub async fn get_reset_requests(
+        &self,
+        query: &ResetRequestQuery,
+    ) -> Result<Vec<ResetRequestEntry>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM password_reset_requests
+            WHERE (? IS NULL OR member_id = ?)
+                AND (? IS NULL OR status = ?)
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&query.member_id)
+        .bind(&query.member_id)
+        .bind(query.status.map(|status| status.as_str()))
+        .bind(query.status.map(|status| status.as_str()))
+        .bind(query.limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let status = DeliveryStatus::from_db(row.get::<String, _>("status").as_str())?;
+                let requested_at: DateTime<Utc> = row.get("requested_at");
+                Some(ResetRequestEntry {
+                    id: row.get("id"),
+                    member_id: row.get("member_id"),
+                    email: row.get("email"),
+                    resent_by: row.get("resent_by"),
+                    status,
+                    error: row.get("error"),
+                    requested_at: requested_at.to_rfc3339(),
+                })
+            })
+            .collect())
+    }
+
+    /// Token subject and member of a logged reset email
+    pub async fn get_reset_request_subject(
+        &self,
+        id: i64,
+    ) -> Result<Option<(String, String)>, sqlx::Error> {
+        let row =
+            sqlx::query("SELECT subject, member_id FROM password_reset_requests WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|row| (row.get("subject"), row.get("member_id"))))
+    }
+
+    /// When the last reset email for the subject was sent or attempted
+    pub async fn last_reset_request_at(
+        &self,
+        subject: &str,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT MAX(requested_at) FROM password_reset_requests WHERE subject = ?",
+        )
+        .bind(subject)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn purge_reset_requests(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM password_reset_requests WHERE requested_at < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_member_alias(&self, old_id: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT new_id FROM member_aliases WHERE old_id = ?")
+            .bind(old_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Fails with a unique violation if `old_id` already has an alias
+    pub async fn create_member_alias(
+        &self,
+        old_id: &str,
+        new_id: &str,
+        created_by: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO member_aliases (old_id, new_id, created_by) VALUES (?, ?, ?)")
+            .bind(old_id)
+            .bind(new_id)
+            .bind(created_by)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_member_aliases(&self) -> Result<Vec<MemberAlias>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM member_aliases ORDER BY created_at DESC, old_id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let created_at: DateTime<Utc> = row.get("created_at");
+                MemberAlias {
+                    old_id: row.get("old_id"),
+                    new_id: row.get("new_id"),
+                    created_by: row.get("created_by"),
+                    created_at: created_at.to_rfc3339(),
+                }
+            })
+            .collect())
+    }
+
+    /// Size of the database file, without the write-ahead log
+    pub async fn size_bytes(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Problems found by `PRAGMA integrity_check`, at most 100; empty if the file is intact
+    pub async fn integrity_check(&self) -> Result<Vec<String>, sqlx::Error> {
+        let messages: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check(100)")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(messages
+            .into_iter()
+            .filter(|message| message != "ok")
+            .collect())
+    }
+
+    /// Rewrites the file without free pages. Needs as much free disk space as the file.
+    pub async fn vacuum(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Moves the write-ahead log into the database file and truncates it; a no-op unless
+    /// the database is in WAL mode
+    pub async fn wal_checkpoint(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Stores a run and returns its ID; the `id` of `run` is ignored
+    pub async fn record_maintenance_run(&self, run: &MaintenanceRun) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO maintenance_runs (started_at, finished_at, integrity_errors, vacuumed, size_before, size_after, error) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&run.started_at)
+        .bind(&run.finished_at)
+        .bind(serde_json::json!(run.integrity_errors).to_string())
+        .bind(run.vacuumed)
+        .bind(run.size_before_bytes)
+        .bind(run.size_after_bytes)
+        .bind(&run.error)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// The latest maintenance runs, newest first
+    pub async fn list_maintenance_runs(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<MaintenanceRun>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM maintenance_runs ORDER BY id DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let started_at: DateTime<Utc> = row.get("started_at");
+                let finished_at: DateTime<Utc> = row.get("finished_at");
+                let integrity_errors: Vec<String> =
+                    serde_json::from_str(row.get("integrity_errors")).unwrap_or_default();
+                MaintenanceRun {
+                    id: row.get("id"),
+                    started_at: started_at.to_rfc3339(),
+                    finished_at: finished_at.to_rfc3339(),
+                    integrity_ok: integrity_errors.is_empty(),
+                    integrity_errors,
+                    vacuumed: row.get("vacuumed"),
+                    size_before_bytes: row.get("size_before"),
+                    size_after_bytes: row.get("size_after"),
+                    error: row.get("error"),
+                }
+            })
+            .collect())
+    }
+
+    pub async fn get_two_factor(
+        &self,
+        member_id: &str,
+    ) -> Result<Option<TwoFactorRecord>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM two_factor WHERE member_id = ?")
+            .bind(member_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| {
+            let enabled_at: Option<DateTime<Utc>> = row.get("enabled_at");
+            TwoFactorRecord {
+                member_id: row.get("member_id"),
+                encrypted_secret: row.get("secret"),
+                enabled_at: enabled_at.map(|at| at.to_rfc3339()),
+                last_used_step: row.get("last_used_step"),
+            }
+        }))
+    }
+
+    /// Stores a new secret waiting to be confirmed, replacing an unconfirmed one. Returns false
+    /// if the member already has two-factor authentication enabled.
+    pub async fn set_pending_two_factor_secret(
+        &self,
+        member_id: &str,
+        encrypted_secret: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO two_factor (member_id, secret, created_at) VALUES (?, ?, ?)
+            ON CONFLICT (member_id) DO UPDATE SET secret = excluded.secret, created_at = excluded.created_at
+            WHERE two_factor.enabled_at IS NULL
+            "#,
+        )
+        .bind(member_id)
+        .bind(encrypted_secret)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Enables two-factor authentication with the code of `step`; false if it already was
+    pub async fn enable_two_factor(&self, member_id: &str, step: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE two_factor SET enabled_at = ?, last_used_step = ? WHERE member_id = ? AND enabled_at IS NULL",
+        )
+        .bind(Utc::now())
+        .bind(step)
+        .bind(member_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Marks the code of `step` as used; false if it or a later one already was, i.e. the
+    /// code is replayed
+    pub async fn use_two_factor_step(
+        &self,
+        member_id: &str,
+        step: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE two_factor SET last_used_step = ? WHERE member_id = ? AND (last_used_step IS NULL OR last_used_step < ?)",
+        )
+        .bind(step)
+        .bind(member_id)
+        .bind(step)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes the member's secret, returning false if there was none
+    pub async fn delete_two_factor(&self, member_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM two_factor WHERE member_id = ?")
+            .bind(member_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn guest_session_from_row(row: &sqlx::sqlite::SqliteRow) -> GuestSession {
+    let paid_at: Option<DateTime<Utc>> = row.get("paid_at");
+    GuestSession {
+        id: row.get("id"),
+        member_id: row.get("member_id"),
+        member_name: row.get("member_name"),
+        date: row.get("date"),
+        guest_name: row.get("guest_name"),
+        fee: row.get("fee"),
+        paid: paid_at.is_some(),
+    }
+}
+
+/// Statuses written by a newer version are skipped
+fn certificate_request_from_row(row: &sqlx::sqlite::SqliteRow) -> Option<CertificateRequest> {
+    let status = CertificateStatus::from_db(row.get::<String, _>("status").as_str())?;
+    let requested_at: DateTime<Utc> = row.get("requested_at");
+    let approved_at: Option<DateTime<Utc>> = row.get("approved_at");
+    Some(CertificateRequest {
+        id: row.get("id"),
+        member_id: row.get("member_id"),
+        member_name: row.get("member_name"),
+        year: row.get("year"),
+        status,
+        summary: row
+            .get::<Option<String>, _>("summary")
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        approved_by: row.get("approved_by"),
+        requested_at: requested_at.to_rfc3339(),
+        approved_at: approved_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+fn family_invitation_from_row(row: &sqlx::sqlite::SqliteRow) -> Option<FamilyInvitation> {
+    let status = FamilyInvitationStatus::from_db(row.get::<String, _>("status").as_str())?;
+    let created_at: DateTime<Utc> = row.get("created_at");
+    let decided_at: Option<DateTime<Utc>> = row.get("decided_at");
+    Some(FamilyInvitation {
+        id: row.get("id"),
+        invited_by: row.get("invited_by"),
+        member_id: row.get("member_id"),
+        member_name: row.get("member_name"),
+        email: row.get("email"),
+        status,
+        decided_by: row.get("decided_by"),
+        created_at: created_at.to_rfc3339(),
+        decided_at: decided_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+/// Jobs of kinds or statuses written by a newer version are skipped
+fn job_from_row(row: &sqlx::sqlite::SqliteRow, errors: Vec<JobItemError>) -> Option<Job> {
+    let kind = JobKind::from_db(row.get::<String, _>("kind").as_str())?;
+    let status = JobStatus::from_db(row.get::<String, _>("status").as_str())?;
+    let created_at: DateTime<Utc> = row.get("created_at");
+    let updated_at: DateTime<Utc> = row.get("updated_at");
+    let finished_at: Option<DateTime<Utc>> = row.get("finished_at");
+    Some(Job {
+        id: row.get("id"),
+        kind,
+        status,
+        created_by: row.get("created_by"),
+        total: row.get("total"),
+        processed: row.get("processed"),
+        failed: row.get("failed"),
+        errors,
+        error: row.get("error"),
+        created_at: created_at.to_rfc3339(),
+        updated_at: updated_at.to_rfc3339(),
+        finished_at: finished_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+async fn insert_rule_change(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    change: &NewRuleChange,
+    recorded_by: Option<&str>,
+    recorded_at: DateTime<Utc>,
+) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO rule_changes (subject, old_value, new_value, effective_from, note, source, recorded_by, recorded_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&change.subject)
+    .bind(&change.old_value)
+    .bind(&change.new_value)
+    .bind(change.effective_from.to_string())
+    .bind(&change.note)
+    .bind(change.source.as_str())
+    .bind(recorded_by)
+    .bind(recorded_at)
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Sources written by a newer version are skipped
+fn rule_change_from_row(row: &sqlx::sqlite::SqliteRow) -> Option<RuleChange> {
+    let source = RuleChangeSource::from_db(row.get::<String, _>("source").as_str())?;
+    let recorded_at: DateTime<Utc> = row.get("recorded_at");
+    let announced_at: Option<DateTime<Utc>> = row.get("announced_at");
+    Some(RuleChange {
+        id: row.get("id"),
+        subject: row.get("subject"),
+        old_value: row.get("old_value"),
+        new_value: row.get("new_value"),
+        effective_from: row.get::<String, _>("effective_from").parse().ok()?,
+        note: row.get("note"),
+        source,
+        recorded_at: recorded_at.to_rfc3339(),
+        announced_at: announced_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+fn deleted_work_hour_from_row(row: &sqlx::sqlite::SqliteRow) -> DeletedWorkHour {
+    let deleted_at: DateTime<Utc> = row.get("deleted_at");
+    DeletedWorkHour {
+        work_hour_id: row.get("work_hour_id"),
+        member_id: row.get("member_id"),
+        date: row.get("date"),
+        description: row.get("description"),
+        hours: row.get("hours"),
+        status: row.get("status"),
+        review_comment: row.get("review_comment"),
+        deleted_by: row.get("deleted_by"),
+        deleted_at: deleted_at.to_rfc3339(),
+        restorable_until: trash::restorable_until(deleted_at).to_rfc3339(),
+    }
+}
+
+fn auth_user_from_row(row: &sqlx::sqlite::SqliteRow) -> AuthUser {
+    AuthUser {
+        id: row.get("id"),
+        email: row.get("email"),
+        password_hash: row.get("password"),
+        created_at: row.get("created_at"),
+        disabled_at: row.get("disabled_at"),
+        password_reset_required_at: row.get("password_reset_required_at"),
+        member_id: row.get("member_id"),
+    }
+}
+
+/// The SQL files in `migrations/`, embedded at compile time
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Applies the migrations the database hasn't seen yet, each in its own transaction, and
+/// logs which ones ran and the resulting schema version
+async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let applied: Vec<i64> = if table_exists(pool, "_sqlx_migrations").await? {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    MIGRATOR
+        .run(pool)
+        .await
+        .map_err(|e| sqlx::Error::Migrate(Box::new(e)))?;
+
+    for migration in MIGRATOR
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+    {
+        info!(
+            "Database: Applied migration {} ({})",
+            migration.version, migration.description
+        );
+    }
+    info!(
+        "Database: Schema at version {}",
+        MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .max()
+            .unwrap_or(0)
+    );
+    Ok(())
+}
+
+/// Brings a database of a release before the migrations up to the state the first one
+/// expects: its tables exist already, so `CREATE TABLE IF NOT EXISTS` wouldn't add the
+/// columns that came later. New and already migrated databases are left alone.
+async fn upgrade_legacy_schema(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    if table_exists(pool, "_sqlx_migrations").await? || !table_exists(pool, "details").await? {
+        return Ok(());
+    }
+    info!("Database: Adopting a database created before migrations");
+
+    add_column_if_missing(pool, "details", "disabled_at", "DATETIME").await?;
+    add_column_if_missing(pool, "details", "password_reset_required_at", "DATETIME").await?;
+    add_column_if_missing(pool, "details", "password_changed_at", "DATETIME").await?;
+    add_column_if_missing(pool, "details", "member_id", "TEXT").await?;
+    if table_exists(pool, "job_errors").await? {
+        add_column_if_missing(pool, "job_errors", "code", "TEXT").await?;
+        add_column_if_missing(
+            pool,
+            "job_errors",
+            "retryable",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+    }
+
+    // Reset tokens used to be keyed by the local account ID, but they are issued for Teable
+    // members. Nothing wrote to the old table, so it is replaced instead of migrated.
+    let reset_token_columns = sqlx::query("PRAGMA table_info(reset_tokens)")
+        .fetch_all(pool)
+        .await?;
+    if reset_token_columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "user_id")
+    {
+        sqlx::query("DROP TABLE reset_tokens").execute(pool).await?;
+    }
+    Ok(())
+}
+
+async fn table_exists(pool: &SqlitePool, table: &str) -> Result<bool, sqlx::Error> {
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_one(pool)
+            .await?;
+    Ok(count > 0)
+}
+
+/// Adds a column to an existing table; `CREATE TABLE IF NOT EXISTS` won't touch
+/// tables created by older versions
+async fn add_column_if_missing(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), sqlx::Error> {
+    let columns = sqlx::query(&format!("PRAGMA table_info({table})"))
+        .fetch_all(pool)
+        .await?;
+    let exists = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == column);
+
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn queued_work_hour_from_row(row: &sqlx::sqlite::SqliteRow) -> QueuedWorkHour {
+    QueuedWorkHour {
+        id: row.get("id"),
+        member_id: row.get("member_id"),
+        date: row.get("date"),
+        description: row.get("description"),
+        hours: row.get("hours"),
+        attempts: row.get("attempts"),
+        last_error: row.get("last_error"),
+        conflict_reason: row.get("conflict_reason"),
+        created_at: row.get("created_at"),
     }
 }
@@ -0,0 +1,108 @@
+use crate::metrics;
+use axum::extract::{MatchedPath, Request};
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{NaiveDate, NaiveTime};
+
+/// Timestamp the route was deprecated at, as `@<unix seconds>` (RFC 9745)
+pub const DEPRECATION_HEADER: &str = "deprecation";
+/// Date after which the route may be removed, as HTTP date (RFC 8594)
+pub const SUNSET_HEADER: &str = "sunset";
+
+/// A route kept for older frontends that has a replacement
+#[derive(Debug, Clone, Copy)]
+pub struct DeprecatedRoute {
+    /// Lowercase, like the methods in `openapi::OPERATIONS`
+    pub method: &'static str,
+    /// Router syntax below `/api`, like the paths in `openapi::OPERATIONS`
+    pub path: &'static str,
+    /// `YYYY-MM-DD`
+    pub since: &'static str,
+    /// `YYYY-MM-DD`; the route may be removed once the metrics show no more calls after it
+    pub sunset: &'static str,
+    /// Path to use instead, sent as `Link: <...>; rel="successor-version"`
+    pub successor: &'static str,
+}
+
+/// Every deprecated route. The routes stay in `server.rs` until their sunset; watch
+/// `tsv_deprecated_requests_total` to see whether anything still calls them.
+pub const DEPRECATED_ROUTES: &[DeprecatedRoute] = &[
+    DeprecatedRoute {
+        method: "post",
+        path: "/forgotPassword",
+        since: "2026-10-16",
+        sunset: "2027-04-01",
+        successor: "/forgot-password",
+    },
+    DeprecatedRoute {
+        method: "post",
+        path: "/resetPassword",
+        since: "2026-10-16",
+        sunset: "2027-04-01",
+        successor: "/reset-password",
+    },
+    DeprecatedRoute {
+        method: "get",
+        path: "/verify-token",
+        since: "2026-10-16",
+        sunset: "2027-04-01",
+        successor: "/user",
+    },
+];
+
+/// The entry of `path` (below `/api`) if it is deprecated; `method` is matched ignoring case
+pub fn find(method: &str, path: &str) -> Option<&'static DeprecatedRoute> {
+    DEPRECATED_ROUTES
+        .iter()
+        .find(|route| route.path == path && route.method.eq_ignore_ascii_case(method))
+}
+
+impl DeprecatedRoute {
+    fn headers(&self) -> Vec<(HeaderName, HeaderValue)> {
+        let mut headers = Vec::new();
+        if let Some(since) = midnight(self.since) {
+            let value = format!("@{}", since.and_utc().timestamp());
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.push((HeaderName::from_static(DEPRECATION_HEADER), value));
+            }
+        }
+        if let Some(sunset) = midnight(self.sunset) {
+            let value = sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.push((HeaderName::from_static(SUNSET_HEADER), value));
+            }
+        }
+        let link = format!("</api{}>; rel=\"successor-version\"", self.successor);
+        if let Ok(value) = HeaderValue::from_str(&link) {
+            headers.push((axum::http::header::LINK, value));
+        }
+        headers
+    }
+}
+
+fn midnight(date: &str) -> Option<chrono::NaiveDateTime> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.and_time(NaiveTime::MIN))
+}
+
+/// Middleware adding `Deprecation`, `Sunset` and `Link` headers to responses of deprecated
+/// routes and counting their calls, so we know when the old frontend calls are gone
+pub async fn mark_deprecated(request: Request, next: Next) -> Response {
+    let deprecated = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|matched| matched.as_str().strip_prefix("/api"))
+        .and_then(|path| find(request.method().as_str(), path));
+    let Some(route) = deprecated else {
+        return next.run(request).await;
+    };
+
+    metrics::record_deprecated_request(request.method().as_str(), &format!("/api{}", route.path));
+    let mut response = next.run(request).await;
+    for (name, value) in route.headers() {
+        response.headers_mut().insert(name, value);
+    }
+    response
+}
@@ -0,0 +1,104 @@
+use crate::api_response::ApiResponse;
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query, Request},
+    http::{request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tracing::debug;
+
+/// Header set on every response to a `?dry_run=true` request, so clients can't
+/// mistake a preview for a performed change
+pub const DRY_RUN_HEADER: &str = "x-dry-run";
+
+#[derive(Debug, Default, Deserialize)]
+struct DryRunParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// `?dry_run=true` on a mutating admin endpoint. Handlers taking this extractor must
+/// compute their changes as usual but skip every side effect when it is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DryRun(pub bool);
+
+impl DryRun {
+    pub fn is_enabled(&self) -> bool {
+        self.0
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for DryRun {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<DryRunParams>::try_from_uri(&parts.uri).map_err(|e| {
+            debug!("Dry Run: Invalid query string: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+        Ok(DryRun(params.dry_run))
+    }
+}
+
+/// Middleware marking dry-run responses with the `X-Dry-Run` header
+pub async fn mark_dry_run_responses(request: Request, next: Next) -> Response {
+    let dry_run = Query::<DryRunParams>::try_from_uri(request.uri())
+        .map(|Query(params)| params.dry_run)
+        .unwrap_or(false);
+
+    let mut response = next.run(request).await;
+    if dry_run {
+        response
+            .headers_mut()
+            .insert(DRY_RUN_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// An email that would have been sent
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct PlannedEmail {
+    pub to: String,
+    pub subject: String,
+}
+
+/// Everything a mutating request would have changed, returned instead of performing it
+#[derive(Debug, Default, Serialize, Type)]
+pub struct PlannedChanges {
+    pub records_created: Vec<serde_json::Value>,
+    pub records_updated: Vec<serde_json::Value>,
+    pub emails: Vec<PlannedEmail>,
+}
+
+/// What a request would have changed, next to what it would have answered otherwise, e.g.
+/// the results of the items of a bulk request
+#[derive(Debug, Serialize)]
+pub struct PlannedOutcome<T> {
+    pub changes: PlannedChanges,
+    #[serde(flatten)]
+    pub outcome: T,
+}
+
+/// Outcome of requests that answer with nothing but the changes
+#[derive(Debug, Serialize)]
+struct NoOutcome {}
+
+impl PlannedChanges {
+    /// `data.changes`; the `X-Dry-Run` header tells them apart from performed ones
+    pub fn into_response(self) -> Response {
+        self.into_response_with(NoOutcome {})
+    }
+
+    /// The changes and the outcome (a struct, its fields are merged in) as `data`
+    pub fn into_response_with<T: Serialize>(self, outcome: T) -> Response {
+        ApiResponse::ok(PlannedOutcome {
+            changes: self,
+            outcome,
+        })
+        .into_response()
+    }
+}
@@ -1,20 +1,135 @@
-use crate::config::{Config, EmailConfig};
+use crate::config::{BrandingConfig, Config, EmailConfig};
+use crate::database::Database;
+use crate::email_templates::{
+    EmailTemplate, EventSurvey, ForcedPasswordReset, IntegrityAlert, Invitation, MemberDataChange,
+    MissingHours, PasswordReset, RequirementChange, RuleChangeAnnouncement, WorkHourStatus,
+    YearEndSummary,
+};
+use crate::export::Locale;
+use crate::hours_reminder::{HoursReminder, ReminderEvent};
+use crate::member_change::ChangedField;
+use crate::metrics;
+use crate::outbox;
+use crate::password_resets::DeliveryStatus;
+use crate::rule_changes::RuleChange;
+use crate::year_summary::{self, YearSummary};
+use chrono::Utc;
 use lettre::{
     message::{header::ContentType, Mailbox},
     transport::smtp::{authentication::Credentials, PoolConfig},
     Message, SmtpTransport, Transport,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+/// Why a member is being notified about one of their work hour entries
+#[derive(Debug, Clone)]
+pub enum WorkHourNotice {
+    Approved,
+    Rejected,
+    /// A board member changed the entry; holds a summary of the previous values
+    EditedByAdmin {
+        previous: String,
+    },
+}
+
+impl WorkHourNotice {
+    /// Subject line of the notification, without the club name suffix
+    pub fn subject(&self) -> &'static str {
+        match self {
+            WorkHourNotice::Approved => "Arbeitsstunden genehmigt",
+            WorkHourNotice::Rejected => "Arbeitsstunden abgelehnt",
+            WorkHourNotice::EditedByAdmin { .. } => "Arbeitsstunden geändert",
+        }
+    }
+
+    /// Heading of the notification
+    pub fn headline(&self) -> &'static str {
+        match self {
+            WorkHourNotice::Approved => "Ihre Arbeitsstunden wurden genehmigt",
+            WorkHourNotice::Rejected => "Ihre Arbeitsstunden wurden abgelehnt",
+            WorkHourNotice::EditedByAdmin { .. } => {
+                "Ihre Arbeitsstunden wurden vom Vorstand geändert"
+            }
+        }
+    }
+
+    /// What the board did and what it means for the member
+    pub fn explanation(&self) -> String {
+        match self {
+            WorkHourNotice::Approved => {
+                "Der Vorstand hat den folgenden Eintrag geprüft und genehmigt. Die Stunden werden Ihrem Konto angerechnet.".to_string()
+            }
+            WorkHourNotice::Rejected => {
+                "Der Vorstand hat den folgenden Eintrag geprüft und abgelehnt. Die Stunden werden nicht angerechnet.".to_string()
+            }
+            WorkHourNotice::EditedByAdmin { previous } => format!(
+                "Der Vorstand hat den folgenden Eintrag bearbeitet. Vorherige Angaben: {previous}"
+            ),
+        }
+    }
+}
+
+/// The work hour entry a notification refers to
+#[derive(Debug, Clone)]
+pub struct WorkHourSummary {
+    pub date: String,
+    pub description: String,
+    pub hours: f64,
+}
+
+/// Subject line of the invitation email, without the club name suffix
+pub const INVITATION_SUBJECT: &str = "Einladung";
+
+/// Subject line of the notification about a changed work hour obligation
+pub const REQUIREMENT_CHANGE_SUBJECT: &str = "Änderung Ihrer Arbeitsstunden-Pflicht";
+
+/// Subject line of the notification about member data the board changed
+pub const MEMBER_CHANGE_SUBJECT: &str = "Änderung Ihrer Mitgliedsdaten";
+
+/// Subject line of the password reset email, without the club name suffix
+pub const PASSWORD_RESET_SUBJECT: &str = "Passwort zurücksetzen";
+
+/// Subject line of the email sent by a forced password rotation, without the club name suffix
+pub const FORCED_PASSWORD_RESET_SUBJECT: &str = "Bitte vergeben Sie ein neues Passwort";
+
+/// Subject line of the survey sent to helpers after a work event, without the club name suffix
+pub const EVENT_SURVEY_SUBJECT: &str = "Wie war der Arbeitseinsatz?";
+
+/// Subject line of the announcement of a rule change, without the club name suffix
+pub const RULE_CHANGE_SUBJECT: &str = "Änderung der Vereinsregeln";
+
+/// Subject line of the reminder of missing work hours, without the club name suffix
+pub const HOURS_REMINDER_SUBJECT: &str = "Erinnerung: Offene Arbeitsstunden";
+
+/// Subject line of the summary of a finished year, without the club name suffix
+pub const YEAR_SUMMARY_SUBJECT: &str = "Ihre Arbeitsstunden im Überblick";
+
+/// Subject line of the alert about a failed database integrity check
+pub const INTEGRITY_ALERT_SUBJECT: &str = "Warnung: Datenbank beschädigt";
+
+/// What became of an email the mail server didn't refuse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Sent,
+    /// The mail server was unreachable; the email waits in the outbox under this ID
+    Queued(i64),
+}
 
 pub struct EmailService {
     transport: SmtpTransport,
     from_email: String,
+    branding: BrandingConfig,
+    /// Base of the links in emails
+    frontend_url: String,
+    /// Where emails go that the mail server didn't take, `None` to fail instead
+    outbox: Option<Database>,
 }
 
 impl EmailService {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let email_config = EmailConfig::from_env()?;
-
+    pub fn new(
+        email_config: EmailConfig,
+        config: &Config,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let creds = Credentials::new(email_config.user.clone(), email_config.password);
 
         let transport = if email_config.use_implicit_tls {
@@ -36,9 +151,75 @@ impl EmailService {
         Ok(EmailService {
             transport,
             from_email: email_config.from_email,
+            branding: config.branding.clone(),
+            frontend_url: config.frontend_url.clone(),
+            outbox: None,
         })
     }
 
+    /// Connects to the mail server and logs in, for the readiness check
+    pub async fn probe(&self, timeout: std::time::Duration) -> Result<(), String> {
+        let transport = self.transport.clone();
+        let connected = tokio::task::spawn_blocking(move || transport.test_connection());
+        match tokio::time::timeout(timeout, connected).await {
+            Ok(Ok(Ok(true))) => Ok(()),
+            Ok(Ok(Ok(false))) => Err("Mail server closed the connection".to_string()),
+            Ok(Ok(Err(e))) => Err(e.to_string()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("Timed out".to_string()),
+        }
+    }
+
+    /// Keeps emails the mail server didn't take in the outbox, see `send_template`
+    pub fn with_outbox(mut self, database: Database) -> Self {
+        self.outbox = Some(database);
+        self
+    }
+
+    /// Renders both bodies of `email` and sends them. If the mail server can't be reached
+    /// and there is an outbox, the email is queued there for retries instead of failing;
+    /// refused addresses fail right away.
+    async fn send_template(
+        &self,
+        to: &str,
+        subject: &str,
+        email: &impl EmailTemplate,
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let html_content = email.render_html(&self.branding)?;
+        let text_content = email.render_text(&self.branding)?;
+        let subject = format!("{subject} - {}", self.branding.name);
+        let error = match self
+            .send_email(to, &subject, &html_content, &text_content)
+            .await
+        {
+            Ok(()) => return Ok(Delivery::Sent),
+            Err(e) => e,
+        };
+        let Some(database) = &self.outbox else {
+            return Err(error);
+        };
+        if DeliveryStatus::of_error(&*error) != DeliveryStatus::Failed {
+            return Err(error);
+        }
+
+        let next_attempt_at = outbox::next_attempt_at(1, Utc::now()).unwrap_or_else(Utc::now);
+        let id = database
+            .queue_email(
+                to,
+                &subject,
+                &html_content,
+                &text_content,
+                &error.to_string(),
+                next_attempt_at,
+            )
+            .await?;
+        warn!(
+            "Email to {} failed, queued as {} for a retry at {}: {}",
+            to, id, next_attempt_at, error
+        );
+        Ok(Delivery::Queued(id))
+    }
+
     pub async fn send_email(
         &self,
         to: &str,
@@ -46,34 +227,40 @@ impl EmailService {
         html_content: &str,
         text_content: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let from_mailbox: Mailbox = format!("TSV BÜ Tennis App <{}>", self.from_email).parse()?;
+        // Nothing below awaits, so the guard can be held for the whole SMTP exchange
+        let _span = tracing::info_span!("smtp", subject).entered();
+        let from_mailbox: Mailbox =
+            format!("{} <{}>", self.branding.name, self.from_email).parse()?;
         let to_mailbox: Mailbox = to.parse()?;
 
-        let email = Message::builder()
-            .from(from_mailbox)
-            .to(to_mailbox)
-            .subject(subject)
-            .multipart(
-                lettre::message::MultiPart::alternative()
-                    .singlepart(
-                        lettre::message::SinglePart::builder()
-                            .header(ContentType::TEXT_PLAIN)
-                            .body(text_content.to_string()),
-                    )
-                    .singlepart(
-                        lettre::message::SinglePart::builder()
-                            .header(ContentType::TEXT_HTML)
-                            .body(html_content.to_string()),
-                    ),
-            )?;
+        let mut builder = Message::builder().from(from_mailbox).to(to_mailbox);
+        if let Some(reply_to) = &self.branding.reply_to {
+            builder = builder.reply_to(reply_to.parse()?);
+        }
+
+        let email = builder.subject(subject).multipart(
+            lettre::message::MultiPart::alternative()
+                .singlepart(
+                    lettre::message::SinglePart::builder()
+                        .header(ContentType::TEXT_PLAIN)
+                        .body(text_content.to_string()),
+                )
+                .singlepart(
+                    lettre::message::SinglePart::builder()
+                        .header(ContentType::TEXT_HTML)
+                        .body(html_content.to_string()),
+                ),
+        )?;
 
         match self.transport.send(&email) {
             Ok(response) => {
                 info!("Email sent successfully: {:?}", response);
+                metrics::record_email(true);
                 Ok(())
             }
             Err(e) => {
                 error!("Failed to send email: {}", e);
+                metrics::record_email(false);
                 Err(Box::new(e))
             }
         }
@@ -84,48 +271,259 @@ impl EmailService {
         email: &str,
         reset_token: &str,
         user_id: String, // Changed from u32 to String
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let config = Config::from_env()?;
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
         let reset_url = format!(
             "{}/resetPassword?token={}&id={}",
-            config.frontend_url, reset_token, user_id
+            self.frontend_url, reset_token, user_id
         );
 
-        let html_content = format!(
-            r#"
-            <div style="font-family: Arial, sans-serif; max-width: 600px; margin: 0 auto;">
-                <h2 style="color: #333;">Passwort zurücksetzen</h2>
-                <p>Sie haben eine Passwort-Zurücksetzung für Ihr TSV BÜ Tennis App Konto angefordert.</p>
-                <p>Klicken Sie auf die Schaltfläche unten, um Ihr Passwort zurückzusetzen:</p>
-                <a href="{reset_url}" style="background-color: #007bff; color: white; padding: 12px 24px; text-decoration: none; border-radius: 4px; display: inline-block; margin: 16px 0;">Passwort zurücksetzen</a>
-                <p>Oder kopieren Sie diese URL und fügen Sie sie in Ihren Browser ein:</p>
-                <p style="word-break: break-all; color: #666;">{reset_url}</p>
-                <p style="color: #666; font-size: 14px;">Dieser Link läuft in 24 Stunden ab.</p>
-                <p style="color: #666; font-size: 14px;">Falls Sie diese Anfrage nicht gestellt haben, ignorieren Sie diese E-Mail bitte.</p>
-            </div>
-            "#
+        self.send_template(
+            email,
+            PASSWORD_RESET_SUBJECT,
+            &PasswordReset {
+                reset_url: &reset_url,
+            },
+        )
+        .await
+    }
+
+    /// Sent when the board forces all members to set a new password, e.g. after a suspected
+    /// leak; unlike `send_password_reset_email` the member didn't ask for it
+    pub async fn send_forced_password_reset_email(
+        &self,
+        email: &str,
+        reset_token: &str,
+        member_id: &str,
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let reset_url = format!(
+            "{}/resetPassword?token={}&id={}",
+            self.frontend_url, reset_token, member_id
+        );
+
+        self.send_template(
+            email,
+            FORCED_PASSWORD_RESET_SUBJECT,
+            &ForcedPasswordReset {
+                reset_url: &reset_url,
+            },
+        )
+        .await
+    }
+
+    /// Invites a member without login account; the link leads to the password reset page,
+    /// which creates the account on first use
+    pub async fn send_invitation_email(
+        &self,
+        email: &str,
+        member_name: &str,
+        reset_token: &str,
+        member_id: &str,
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let invite_url = format!(
+            "{}/resetPassword?token={}&id={}",
+            self.frontend_url, reset_token, member_id
         );
 
-        let text_content = format!(
-            r#"
-Passwort zurücksetzen
+        self.send_template(
+            email,
+            INVITATION_SUBJECT,
+            &Invitation {
+                member_name,
+                invite_url: &invite_url,
+            },
+        )
+        .await
+    }
 
-Sie haben eine Passwort-Zurücksetzung für Ihr TSV BÜ Tennis App Konto angefordert.
+    pub async fn send_work_hour_status_email(
+        &self,
+        email: &str,
+        member_name: &str,
+        entry: &WorkHourSummary,
+        notice: &WorkHourNotice,
+        comment: Option<&str>,
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let dashboard_url = format!("{}/dashboard", self.frontend_url);
 
-Klicken Sie auf diesen Link, um Ihr Passwort zurückzusetzen: {reset_url}
+        self.send_template(
+            email,
+            notice.subject(),
+            &WorkHourStatus {
+                member_name,
+                entry,
+                notice,
+                comment,
+                dashboard_url: &dashboard_url,
+            },
+        )
+        .await
+    }
 
-Dieser Link läuft in 24 Stunden ab.
+    /// Tells a member that their age-based work hour obligation changed with the new year
+    pub async fn send_requirement_change_email(
+        &self,
+        email: &str,
+        member_name: &str,
+        year: i32,
+        message: &str,
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let dashboard_url = format!("{}/dashboard", self.frontend_url);
 
-Falls Sie diese Anfrage nicht gestellt haben, ignorieren Sie diese E-Mail bitte.
-            "#
-        );
+        self.send_template(
+            email,
+            REQUIREMENT_CHANGE_SUBJECT,
+            &RequirementChange {
+                member_name,
+                year,
+                message,
+                dashboard_url: &dashboard_url,
+            },
+        )
+        .await
+    }
+
+    /// Asks a helper of a completed work event to rate its organization
+    pub async fn send_event_survey_email(
+        &self,
+        email: &str,
+        member_name: &str,
+        event_title: &str,
+        survey_token: &str,
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let survey_url = format!("{}/umfrage?token={}", self.frontend_url, survey_token);
+
+        self.send_template(
+            email,
+            EVENT_SURVEY_SUBJECT,
+            &EventSurvey {
+                member_name,
+                event_title,
+                survey_url: &survey_url,
+            },
+        )
+        .await
+    }
+
+    /// Announces a change of the club's rules, sent by the board from the changelog
+    pub async fn send_rule_change_email(
+        &self,
+        email: &str,
+        member_name: &str,
+        change: &RuleChange,
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let dashboard_url = format!("{}/dashboard", self.frontend_url);
+
+        self.send_template(
+            email,
+            RULE_CHANGE_SUBJECT,
+            &RuleChangeAnnouncement {
+                member_name,
+                subject: &change.subject,
+                old_value: change.old_value.as_deref(),
+                new_value: change.new_value.as_deref(),
+                effective_from: &change.effective_from.format("%d.%m.%Y").to_string(),
+                note: change.note.as_deref(),
+                dashboard_url: &dashboard_url,
+            },
+        )
+        .await
+    }
+
+    /// Tells a member which of their data the board changed, see `member_change::notify`
+    pub async fn send_member_change_email(
+        &self,
+        email: &str,
+        member_name: &str,
+        changes: &[ChangedField],
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let dashboard_url = format!("{}/dashboard", self.frontend_url);
 
-        self.send_email(
+        self.send_template(
             email,
-            "Passwort zurücksetzen - TSV BÜ Tennis App",
-            &html_content,
-            &text_content,
+            MEMBER_CHANGE_SUBJECT,
+            &MemberDataChange {
+                member_name,
+                changes,
+                dashboard_url: &dashboard_url,
+            },
         )
         .await
     }
+
+    /// Reminds a member of the hours they, or their family, still owe this year
+    pub async fn send_hours_reminder_email(
+        &self,
+        email: &str,
+        member_name: &str,
+        year: i32,
+        reminder: &HoursReminder,
+        events: &[ReminderEvent],
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let dashboard_url = format!("{}/dashboard", self.frontend_url);
+
+        self.send_template(
+            email,
+            HOURS_REMINDER_SUBJECT,
+            &MissingHours {
+                member_name,
+                year,
+                family: reminder.family.as_deref(),
+                required: &Locale::De.format_number(reminder.required),
+                completed: &Locale::De.format_number(reminder.completed),
+                missing: &Locale::De.format_number(reminder.missing),
+                events,
+                dashboard_url: &dashboard_url,
+            },
+        )
+        .await
+    }
+
+    /// Sends a member their hours of a finished year and the charge for missing ones
+    pub async fn send_year_summary_email(
+        &self,
+        year: i32,
+        summary: &YearSummary,
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        let dashboard_url = format!("{}/dashboard", self.frontend_url);
+        let hours = |value: f64| Locale::De.format_number(value);
+        let charge = (summary.charge > 0.0).then(|| year_summary::format_euros(summary.charge));
+
+        self.send_template(
+            &summary.email,
+            YEAR_SUMMARY_SUBJECT,
+            &YearEndSummary {
+                member_name: &summary.name,
+                year,
+                hours: &hours(summary.hours),
+                required: &hours(summary.required),
+                exemption_reason: summary.exemption_reason.as_deref(),
+                family: summary.family.as_ref().map(|family| family.name.as_str()),
+                family_completed: &summary
+                    .family
+                    .as_ref()
+                    .map(|family| hours(family.completed))
+                    .unwrap_or_default(),
+                family_required: &summary
+                    .family
+                    .as_ref()
+                    .map(|family| hours(family.required))
+                    .unwrap_or_default(),
+                missing: &hours(summary.missing),
+                fulfilled: summary.missing <= 0.0,
+                charge: charge.as_deref(),
+                dashboard_url: &dashboard_url,
+            },
+        )
+        .await
+    }
+
+    /// Tells a board member that the nightly integrity check found problems
+    pub async fn send_integrity_alert_email(
+        &self,
+        email: &str,
+        problems: &[String],
+    ) -> Result<Delivery, Box<dyn std::error::Error + Send + Sync>> {
+        self.send_template(email, INTEGRITY_ALERT_SUBJECT, &IntegrityAlert { problems })
+            .await
+    }
 }
@@ -0,0 +1,212 @@
+use crate::config::BrandingConfig;
+use crate::email::{WorkHourNotice, WorkHourSummary};
+use crate::hours_reminder::ReminderEvent;
+use crate::member_change::ChangedField;
+use askama::Template;
+
+/// The HTML and plain text body of an email, rendered from `templates/email/<name>.html` and
+/// `.txt`. Both extend the layout there, which adds the club branding; the HTML one escapes
+/// all values. A new email needs a struct with its values, the two templates and a line
+/// `email_template!` below.
+pub trait EmailTemplate {
+    fn render_html(&self, branding: &BrandingConfig) -> askama::Result<String>;
+    fn render_text(&self, branding: &BrandingConfig) -> askama::Result<String>;
+}
+
+/// Implements `EmailTemplate` for `$email` with the given templates, which see the struct as
+/// `email` and the club branding as `branding`
+macro_rules! email_template {
+    ($email:ident, $html:tt, $text:tt) => {
+        const _: () = {
+            #[derive(Template)]
+            #[template(path = $html)]
+            struct Html<'t> {
+                branding: &'t BrandingConfig,
+                email: &'t $email<'t>,
+            }
+
+            #[derive(Template)]
+            #[template(path = $text)]
+            struct Text<'t> {
+                branding: &'t BrandingConfig,
+                email: &'t $email<'t>,
+            }
+
+            impl EmailTemplate for $email<'_> {
+                fn render_html(&self, branding: &BrandingConfig) -> askama::Result<String> {
+                    Html {
+                        branding,
+                        email: self,
+                    }
+                    .render()
+                }
+
+                fn render_text(&self, branding: &BrandingConfig) -> askama::Result<String> {
+                    Text {
+                        branding,
+                        email: self,
+                    }
+                    .render()
+                }
+            }
+        };
+    };
+}
+
+/// The link a member asked for with "Passwort vergessen"
+pub struct PasswordReset<'a> {
+    pub reset_url: &'a str,
+}
+
+email_template!(
+    PasswordReset,
+    "email/password_reset.html",
+    "email/password_reset.txt"
+);
+
+/// Sent when the board forces all members to set a new password
+pub struct ForcedPasswordReset<'a> {
+    pub reset_url: &'a str,
+}
+
+email_template!(
+    ForcedPasswordReset,
+    "email/forced_password_reset.html",
+    "email/forced_password_reset.txt"
+);
+
+/// Invites a member without login account to set a password
+pub struct Invitation<'a> {
+    pub member_name: &'a str,
+    pub invite_url: &'a str,
+}
+
+email_template!(Invitation, "email/invitation.html", "email/invitation.txt");
+
+/// Tells a member that the board reviewed or changed one of their entries
+pub struct WorkHourStatus<'a> {
+    pub member_name: &'a str,
+    pub entry: &'a WorkHourSummary,
+    pub notice: &'a WorkHourNotice,
+    pub comment: Option<&'a str>,
+    pub dashboard_url: &'a str,
+}
+
+email_template!(
+    WorkHourStatus,
+    "email/work_hour_status.html",
+    "email/work_hour_status.txt"
+);
+
+/// Tells a member that their work hour obligation changed with the new year
+pub struct RequirementChange<'a> {
+    pub member_name: &'a str,
+    pub year: i32,
+    pub message: &'a str,
+    pub dashboard_url: &'a str,
+}
+
+email_template!(
+    RequirementChange,
+    "email/requirement_change.html",
+    "email/requirement_change.txt"
+);
+
+/// Lists the member data the board changed, with the values where they may be sent
+pub struct MemberDataChange<'a> {
+    pub member_name: &'a str,
+    pub changes: &'a [ChangedField],
+    pub dashboard_url: &'a str,
+}
+
+email_template!(
+    MemberDataChange,
+    "email/member_change.html",
+    "email/member_change.txt"
+);
+
+/// Asks a helper of a completed work event to rate it
+pub struct EventSurvey<'a> {
+    pub member_name: &'a str,
+    pub event_title: &'a str,
+    pub survey_url: &'a str,
+}
+
+email_template!(
+    EventSurvey,
+    "email/event_survey.html",
+    "email/event_survey.txt"
+);
+
+/// Announces a change of the club's rules to a member
+pub struct RuleChangeAnnouncement<'a> {
+    pub member_name: &'a str,
+    pub subject: &'a str,
+    pub old_value: Option<&'a str>,
+    pub new_value: Option<&'a str>,
+    /// DD.MM.YYYY
+    pub effective_from: &'a str,
+    pub note: Option<&'a str>,
+    pub dashboard_url: &'a str,
+}
+
+email_template!(
+    RuleChangeAnnouncement,
+    "email/rule_change.html",
+    "email/rule_change.txt"
+);
+
+/// Reminds a member, or each member of a family, of the hours still missing this year
+pub struct MissingHours<'a> {
+    pub member_name: &'a str,
+    pub year: i32,
+    pub family: Option<&'a str>,
+    pub required: &'a str,
+    pub completed: &'a str,
+    pub missing: &'a str,
+    pub events: &'a [ReminderEvent],
+    pub dashboard_url: &'a str,
+}
+
+email_template!(
+    MissingHours,
+    "email/hours_reminder.html",
+    "email/hours_reminder.txt"
+);
+
+/// A member's hours of a finished year and what they owe for the missing ones; the hours
+/// are formatted for display
+pub struct YearEndSummary<'a> {
+    pub member_name: &'a str,
+    pub year: i32,
+    pub hours: &'a str,
+    pub required: &'a str,
+    pub exemption_reason: Option<&'a str>,
+    /// Name of the family, whose quota the missing hours refer to
+    pub family: Option<&'a str>,
+    pub family_completed: &'a str,
+    pub family_required: &'a str,
+    pub missing: &'a str,
+    /// No hours are missing
+    pub fulfilled: bool,
+    /// Only set if a fee is charged
+    pub charge: Option<&'a str>,
+    pub dashboard_url: &'a str,
+}
+
+email_template!(
+    YearEndSummary,
+    "email/year_summary.html",
+    "email/year_summary.txt"
+);
+
+/// Tells a board member that the nightly integrity check found problems
+pub struct IntegrityAlert<'a> {
+    pub problems: &'a [String],
+}
+
+email_template!(
+    IntegrityAlert,
+    "email/integrity_alert.html",
+    "email/integrity_alert.txt"
+);
@@ -0,0 +1,315 @@
+use crate::api_response::ApiResponse;
+use crate::request_id;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fmt;
+use tracing::error;
+
+/// Machine-readable error codes, sent as `code` with every error response so the frontend
+/// can branch on them instead of parsing the German messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// Missing, invalid or revoked bearer token
+    Unauthorized,
+    /// Logged in, but not allowed to do this (e.g. not a board member)
+    Forbidden,
+    NotFound,
+    /// Malformed or invalid request; the message says what to fix
+    InvalidRequest,
+    /// The request body is over the size limit
+    PayloadTooLarge,
+    InvalidCredentials,
+    /// Two-factor code wrong, expired or already used
+    InvalidTwoFactorCode,
+    AccountDisabled,
+    /// The account was flagged by a forced password rotation
+    PasswordResetRequired,
+    /// Reset, selection or feed token unknown or expired
+    InvalidToken,
+    /// The new password doesn't meet the password policy; the message says why
+    WeakPassword,
+    /// A work hour entry already exists for that member and day
+    DuplicateEntry,
+    /// Work hours are frozen, e.g. during the audit week; the message says until when
+    WriteFrozen,
+    /// The request conflicts with the current state (already approved, already exists, ...)
+    Conflict,
+    /// The feature isn't configured on this installation
+    FeatureDisabled,
+    RateLimitExceeded,
+    /// Too many requests in flight; retry after the `Retry-After` header
+    Overloaded,
+    /// Teable is unreachable or returned an error
+    TeableUnavailable,
+    DatabaseError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 20] = [
+        ErrorCode::Unauthorized,
+        ErrorCode::Forbidden,
+        ErrorCode::NotFound,
+        ErrorCode::InvalidRequest,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::InvalidCredentials,
+        ErrorCode::InvalidTwoFactorCode,
+        ErrorCode::AccountDisabled,
+        ErrorCode::PasswordResetRequired,
+        ErrorCode::InvalidToken,
+        ErrorCode::WeakPassword,
+        ErrorCode::DuplicateEntry,
+        ErrorCode::WriteFrozen,
+        ErrorCode::Conflict,
+        ErrorCode::FeatureDisabled,
+        ErrorCode::RateLimitExceeded,
+        ErrorCode::Overloaded,
+        ErrorCode::TeableUnavailable,
+        ErrorCode::DatabaseError,
+        ErrorCode::InternalError,
+    ];
+
+    /// Value stored with failed job items, as sent in responses
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::InvalidRequest => "INVALID_REQUEST",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::InvalidCredentials => "INVALID_CREDENTIALS",
+            ErrorCode::InvalidTwoFactorCode => "INVALID_TWO_FACTOR_CODE",
+            ErrorCode::AccountDisabled => "ACCOUNT_DISABLED",
+            ErrorCode::PasswordResetRequired => "PASSWORD_RESET_REQUIRED",
+            ErrorCode::InvalidToken => "INVALID_TOKEN",
+            ErrorCode::WeakPassword => "WEAK_PASSWORD",
+            ErrorCode::DuplicateEntry => "DUPLICATE_ENTRY",
+            ErrorCode::WriteFrozen => "WRITE_FROZEN",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::FeatureDisabled => "FEATURE_DISABLED",
+            ErrorCode::RateLimitExceeded => "RATE_LIMIT_EXCEEDED",
+            ErrorCode::Overloaded => "OVERLOADED",
+            ErrorCode::TeableUnavailable => "TEABLE_UNAVAILABLE",
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::Unauthorized
+            | ErrorCode::InvalidCredentials
+            | ErrorCode::InvalidTwoFactorCode => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden
+            | ErrorCode::AccountDisabled
+            | ErrorCode::PasswordResetRequired => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound | ErrorCode::FeatureDisabled => StatusCode::NOT_FOUND,
+            ErrorCode::InvalidRequest | ErrorCode::InvalidToken | ErrorCode::WeakPassword => {
+                StatusCode::BAD_REQUEST
+            }
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::DuplicateEntry | ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::WriteFrozen => StatusCode::LOCKED,
+            ErrorCode::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Overloaded => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::TeableUnavailable => StatusCode::BAD_GATEWAY,
+            ErrorCode::DatabaseError | ErrorCode::InternalError => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Used for errors without a message of their own
+    fn default_message(&self) -> &'static str {
+        match self {
+            ErrorCode::Unauthorized => "Bitte melde dich erneut an.",
+            ErrorCode::Forbidden => "Keine Berechtigung.",
+            ErrorCode::NotFound => "Nicht gefunden.",
+            ErrorCode::InvalidRequest => "Ungültige Anfrage.",
+            ErrorCode::PayloadTooLarge => "Die Anfrage ist zu groß.",
+            ErrorCode::InvalidCredentials => "E-Mail oder Passwort ist falsch.",
+            ErrorCode::InvalidTwoFactorCode => {
+                "Der Code ist falsch oder abgelaufen. Bitte gib den aktuellen Code aus deiner Authenticator-App ein."
+            }
+            ErrorCode::AccountDisabled => "Dieses Konto wurde deaktiviert.",
+            ErrorCode::PasswordResetRequired => {
+                "Bitte setze dein Passwort über den Link in der E-Mail zurück."
+            }
+            ErrorCode::InvalidToken => "Der Link ist ungültig oder abgelaufen.",
+            ErrorCode::WeakPassword => "Das Passwort ist zu unsicher.",
+            ErrorCode::DuplicateEntry => {
+                "Für dieses Datum existiert bereits ein Eintrag. Pro Person und Tag ist nur ein Eintrag erlaubt."
+            }
+            ErrorCode::WriteFrozen => "Arbeitsstunden können gerade nicht geändert werden.",
+            ErrorCode::Conflict => "Die Anfrage steht im Konflikt mit dem aktuellen Stand.",
+            ErrorCode::FeatureDisabled => "Diese Funktion ist nicht eingerichtet.",
+            ErrorCode::RateLimitExceeded => {
+                "Zu viele Anfragen. Bitte versuche es in einigen Augenblicken erneut."
+            }
+            ErrorCode::Overloaded => {
+                "Gerade sind sehr viele Anfragen unterwegs. Bitte versuche es in ein paar Sekunden erneut."
+            }
+            ErrorCode::TeableUnavailable => {
+                "Datenquelle nicht erreichbar. Bitte später erneut versuchen."
+            }
+            ErrorCode::DatabaseError | ErrorCode::InternalError => {
+                "Ein interner Fehler ist aufgetreten."
+            }
+        }
+    }
+
+    fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::CONFLICT => ErrorCode::Conflict,
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+            StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimitExceeded,
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => {
+                ErrorCode::TeableUnavailable
+            }
+            status if status.is_client_error() => ErrorCode::InvalidRequest,
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+/// Error of an API handler, rendered as an `ApiResponse` with `error` and `code`, and the
+/// status of its code
+#[derive(Debug)]
+pub enum AppError {
+    /// An error the member can act on, with a German message for display
+    Api { code: ErrorCode, message: String },
+    /// Teable request failed; details are logged, not sent
+    Teable(anyhow::Error),
+    /// SQLite query failed; details are logged, not sent
+    Database(sqlx::Error),
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        AppError::Api {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// An error with the code's default message
+    pub fn code(code: ErrorCode) -> Self {
+        AppError::new(code, code.default_message())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::InvalidRequest, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::Forbidden, message)
+    }
+
+    pub fn internal() -> Self {
+        AppError::code(ErrorCode::InternalError)
+    }
+
+    /// For `map_err` on results of `teable::` functions
+    pub fn teable(e: anyhow::Error) -> Self {
+        AppError::Teable(e)
+    }
+
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            AppError::Api { code, .. } => *code,
+            AppError::Teable(e) => {
+                if let Some(app_error) = e.downcast_ref::<AppError>() {
+                    return app_error.error_code();
+                }
+                match e.downcast_ref::<TeableApiError>() {
+                    Some(api_error) if api_error.status == StatusCode::NOT_FOUND.as_u16() => {
+                        ErrorCode::NotFound
+                    }
+                    _ => ErrorCode::TeableUnavailable,
+                }
+            }
+            AppError::Database(sqlx::Error::RowNotFound) => ErrorCode::NotFound,
+            AppError::Database(e)
+                if e.as_database_error()
+                    .is_some_and(|e| e.is_unique_violation()) =>
+            {
+                ErrorCode::Conflict
+            }
+            AppError::Database(_) => ErrorCode::DatabaseError,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Api { code, message } => write!(f, "{code:?}: {message}"),
+            AppError::Teable(e) => write!(f, "Teable: {e}"),
+            AppError::Database(e) => write!(f, "Database: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// Handlers that predate `AppError` return bare status codes
+impl From<StatusCode> for AppError {
+    fn from(status: StatusCode) -> Self {
+        AppError::code(ErrorCode::from_status(status))
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        AppError::Database(e)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let code = self.error_code();
+        let message = match &self {
+            AppError::Api { message, .. } => message.as_str(),
+            AppError::Teable(e) => {
+                error!("Teable request failed: {}", e);
+                code.default_message()
+            }
+            AppError::Database(e) => {
+                error!("Database query failed: {}", e);
+                code.default_message()
+            }
+        };
+        let body = ApiResponse::error(code, message, request_id::current());
+        (code.status(), body).into_response()
+    }
+}
+
+/// Non-success response of the Teable API; kept typed inside `anyhow::Error` so handlers
+/// can tell a missing record from an outage
+#[derive(Debug)]
+pub struct TeableApiError {
+    pub status: u16,
+    pub body: String,
+}
+
+impl fmt::Display for TeableApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Teable API error {}: {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for TeableApiError {}
@@ -0,0 +1,74 @@
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// JSON answered with an `ETag` of its content, or with an empty `304 Not Modified` if the
+/// client already has it (`If-None-Match`). Saves sending and re-rendering a dashboard that
+/// didn't change since the last poll.
+pub fn json_with_etag<T: Serialize>(request_headers: &HeaderMap, value: &T) -> Response {
+    let body = match serde_json::to_vec(value) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("ETag: Failed to serialize response: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let etag = etag_of(&body);
+    if if_none_match(request_headers, &etag) {
+        return not_modified(&etag);
+    }
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            ),
+            (header::ETAG, etag_header(&etag)),
+            (header::CACHE_CONTROL, cache_control()),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Empty `304 Not Modified` for a client that already has the response tagged `etag`
+pub fn not_modified(etag: &str) -> Response {
+    (
+        StatusCode::NOT_MODIFIED,
+        [
+            (header::ETAG, etag_header(etag)),
+            (header::CACHE_CONTROL, cache_control()),
+        ],
+    )
+        .into_response()
+}
+
+/// Browsers must ask again each time, but may reuse the body on a 304
+fn cache_control() -> HeaderValue {
+    HeaderValue::from_static("private, no-cache")
+}
+
+fn etag_header(etag: &str) -> HeaderValue {
+    HeaderValue::from_str(etag).expect("hex digest is a valid header value")
+}
+
+/// Weak entity tag of a response body, e.g. `W/"3f2a…"` (the first 16 bytes of its SHA-256).
+/// Weak, as compression changes the bytes sent but not what they mean; a strong tag would
+/// have to differ between the gzip, brotli and uncompressed response.
+pub fn etag_of(body: &[u8]) -> String {
+    format!("W/\"{}\"", hex::encode(&Sha256::digest(body)[..16]))
+}
+
+/// Whether `If-None-Match` lists `etag` or is `*`. The comparison for `If-None-Match` is the
+/// weak one, so tags match with or without `W/`.
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
@@ -0,0 +1,50 @@
+use crate::app_events::{AppEvent, AppEventEnvelope};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+/// Events a slow client can fall behind before it misses some
+const CAPACITY: usize = 256;
+
+/// Hands events to the open event streams of the SPA. Events published while nobody is
+/// connected are dropped; clients reload their data after reconnecting.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<AppEventEnvelope>,
+    closed: CancellationToken,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus {
+            sender: broadcast::channel(CAPACITY).0,
+            closed: CancellationToken::new(),
+        }
+    }
+}
+
+impl EventBus {
+    pub fn publish(&self, event: AppEvent) {
+        let name = event.name();
+        match self.sender.send(AppEventEnvelope::new(event)) {
+            Ok(streams) => debug!("Event Bus: Sent {} to {} streams", name, streams),
+            Err(_) => debug!("Event Bus: No stream open for {}", name),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AppEventEnvelope> {
+        self.sender.subscribe()
+    }
+
+    /// Ends all open event streams, so a graceful shutdown isn't held up by them. The
+    /// sender lives on in every clone of the bus, so its channel never closes by itself.
+    pub fn close(&self) {
+        debug!("Event Bus: Closing all streams");
+        self.closed.cancel();
+    }
+
+    /// Resolves once `close` was called
+    pub async fn closed(&self) {
+        self.closed.cancelled().await
+    }
+}
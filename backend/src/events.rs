@@ -0,0 +1,249 @@
+use crate::bulk::BulkResult;
+use crate::models::{string_or_f64, WorkHour, WorkHourStatus};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+
+/// Lifecycle of a work event (Arbeitseinsatz)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub enum EventStatus {
+    #[serde(rename = "geplant")]
+    Planned,
+    #[serde(rename = "abgeschlossen")]
+    Completed,
+    #[serde(rename = "abgesagt")]
+    Cancelled,
+}
+
+impl EventStatus {
+    /// Value stored in the Teable "Status" field of the events table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventStatus::Planned => "geplant",
+            EventStatus::Completed => "abgeschlossen",
+            EventStatus::Cancelled => "abgesagt",
+        }
+    }
+
+    /// Events without a status are treated as planned
+    pub fn from_teable(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            Some("abgeschlossen") => EventStatus::Completed,
+            Some("abgesagt") => EventStatus::Cancelled,
+            _ => EventStatus::Planned,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct WorkEvent {
+    pub id: String,
+    pub title: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    pub description: Option<String>,
+    pub needed_helpers: u32,
+    /// Hours credited to each helper unless the coordinator records otherwise
+    pub hours: f64,
+    pub status: EventStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct EventSignup {
+    pub id: String,
+    pub event_id: Option<String>,
+    pub member_id: Option<String>,
+    pub member_name: String,
+}
+
+/// An upcoming event as shown to members
+#[derive(Debug, Serialize, Type)]
+pub struct EventOverview {
+    pub event: WorkEvent,
+    pub signed_up: u32,
+    pub spots_left: u32,
+    pub signed_up_by_me: bool,
+}
+
+#[derive(Debug, Deserialize, Type)]
+pub struct CreateEventRequest {
+    #[serde(rename = "Titel")]
+    pub title: String,
+    #[serde(rename = "Datum")]
+    pub date: String,
+    #[serde(rename = "Beschreibung")]
+    pub description: Option<String>,
+    #[serde(rename = "Helfer")]
+    pub needed_helpers: u32,
+    #[serde(rename = "Stunden", deserialize_with = "string_or_f64")]
+    pub hours: f64,
+}
+
+impl CreateEventRequest {
+    /// Returns the German error message for the first invalid field
+    pub fn validate(&self) -> Result<(), String> {
+        if self.title.trim().is_empty() {
+            return Err("Titel fehlt.".to_string());
+        }
+        if NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").is_err() {
+            return Err("Ungültiges Datumsformat. Bitte verwenden Sie YYYY-MM-DD.".to_string());
+        }
+        if self.needed_helpers == 0 {
+            return Err("Es wird mindestens ein Helfer benötigt.".to_string());
+        }
+        if self.hours <= 0.0 {
+            return Err("Stunden müssen größer als 0 sein.".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// One helper who showed up; `hours` defaults to the event's hours
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct EventAttendance {
+    #[serde(rename = "Mitglied_id")]
+    pub member_id: String,
+    #[serde(rename = "Stunden")]
+    pub hours: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Type)]
+pub struct CompleteEventRequest {
+    pub attendance: Vec<EventAttendance>,
+    /// From the response to the same request; only its failed helpers are processed again
+    #[serde(default)]
+    pub retry_token: Option<String>,
+    /// Email the helpers a one-question survey about the event's organization once it is
+    /// completed
+    #[serde(default)]
+    pub send_survey: bool,
+}
+
+/// Response of completing an event: the result of crediting each helper
+#[derive(Debug, Serialize, Type)]
+pub struct EventCompletion<T> {
+    #[serde(flatten)]
+    pub result: BulkResult<T>,
+    /// Surveys emailed, if `send_survey` was set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub surveys_sent: Option<u32>,
+}
+
+/// Hours booked for an event, for accounting per event
+#[derive(Debug, Serialize, Type)]
+pub struct EventHours {
+    pub event_id: String,
+    pub title: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    /// Linked entries, corrections included
+    pub entries: u32,
+    /// Members with a linked entry
+    pub members: u32,
+    /// Approved hours after corrections
+    pub approved_hours: f64,
+    /// Hours of linked entries still waiting for review
+    pub pending_hours: f64,
+}
+
+/// Description of the work hour entries created for an event's helpers
+pub fn work_hour_description(event: &WorkEvent) -> String {
+    format!("Arbeitseinsatz: {}", event.title)
+}
+
+/// Planned events from today on, soonest first, with the sign-up state of the given member
+pub fn upcoming_events(
+    events: Vec<WorkEvent>,
+    signups: &[EventSignup],
+    member_id: &str,
+    today: NaiveDate,
+) -> Vec<EventOverview> {
+    let mut signups_by_event: HashMap<&str, Vec<&EventSignup>> = HashMap::new();
+    for signup in signups {
+        if let Some(event_id) = signup.event_id.as_deref() {
+            signups_by_event.entry(event_id).or_default().push(signup);
+        }
+    }
+
+    let mut overview: Vec<EventOverview> = events
+        .into_iter()
+        .filter(|event| event.status == EventStatus::Planned)
+        .filter(|event| {
+            NaiveDate::parse_from_str(&event.date, "%Y-%m-%d").is_ok_and(|date| date >= today)
+        })
+        .map(|event| {
+            let event_signups = signups_by_event
+                .get(event.id.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let signed_up = event_signups.len() as u32;
+            EventOverview {
+                spots_left: event.needed_helpers.saturating_sub(signed_up),
+                signed_up_by_me: event_signups
+                    .iter()
+                    .any(|s| s.member_id.as_deref() == Some(member_id)),
+                signed_up,
+                event,
+            }
+        })
+        .collect();
+
+    overview.sort_by(|a, b| a.event.date.cmp(&b.event.date));
+    overview
+}
+
+/// The event a member's entry on `date` belongs to: the one event on that day the member
+/// signed up for. `None` if there is none, or several and the entry can't be told apart.
+pub fn matching_event<'a>(
+    events: &'a [WorkEvent],
+    signups: &[EventSignup],
+    member_id: &str,
+    date: &str,
+) -> Option<&'a WorkEvent> {
+    let mut candidates = events.iter().filter(|event| {
+        event.date == date
+            && event.status != EventStatus::Cancelled
+            && signups.iter().any(|signup| {
+                signup.event_id.as_deref() == Some(event.id.as_str())
+                    && signup.member_id.as_deref() == Some(member_id)
+            })
+    });
+    let event = candidates.next()?;
+    candidates.next().is_none().then_some(event)
+}
+
+/// Sums the entries linked to `event`; `work_hours` may hold entries of other events
+pub fn event_hours(event: &WorkEvent, work_hours: &[WorkHour]) -> EventHours {
+    let linked: Vec<&WorkHour> = work_hours
+        .iter()
+        .filter(|work_hour| work_hour.event_id.as_deref() == Some(event.id.as_str()))
+        .collect();
+    let mut members: Vec<String> = linked
+        .iter()
+        .filter_map(|work_hour| work_hour.get_member_id())
+        .collect();
+    members.sort();
+    members.dedup();
+
+    let mut approved_hours = 0.0;
+    let mut pending_hours = 0.0;
+    for work_hour in &linked {
+        let hours = work_hour.duration_hours.unwrap_or(0.0);
+        match WorkHourStatus::from_teable(work_hour.status.as_deref()) {
+            WorkHourStatus::Approved => approved_hours += hours,
+            WorkHourStatus::Submitted => pending_hours += hours,
+            WorkHourStatus::Rejected => {}
+        }
+    }
+
+    EventHours {
+        event_id: event.id.clone(),
+        title: event.title.clone(),
+        date: event.date.clone(),
+        entries: linked.len() as u32,
+        members: members.len() as u32,
+        approved_hours: (approved_hours * 100.0).round() / 100.0,
+        pending_hours: (pending_hours * 100.0).round() / 100.0,
+    }
+}
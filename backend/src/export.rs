@@ -0,0 +1,174 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+/// Language of the column headers and number format of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    De,
+    /// For the Hauptverein and auditors
+    En,
+}
+
+/// `?lang=de|en` of export endpoints; German without the parameter
+#[derive(Debug, Default, Deserialize)]
+pub struct LocaleQuery {
+    #[serde(default)]
+    pub lang: Locale,
+}
+
+impl Locale {
+    /// English if the browser prefers it, e.g. `Accept-Language: en-GB,en;q=0.9`
+    pub fn from_accept_language(header: Option<&str>) -> Self {
+        match header.and_then(|header| header.split([',', ';', '-']).next()) {
+            Some(language) if language.trim().eq_ignore_ascii_case("en") => Locale::En,
+            _ => Locale::De,
+        }
+    }
+
+    /// Excel opens CSV with the list separator of the system locale
+    pub fn csv_separator(&self) -> char {
+        match self {
+            Locale::De => ';',
+            Locale::En => ',',
+        }
+    }
+
+    /// `03.05.2025` in German, `03 May 2025` in English, which auditors can't misread as
+    /// March 5th
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        match self {
+            Locale::De => date.format("%d.%m.%Y").to_string(),
+            Locale::En => date.format("%d %b %Y").to_string(),
+        }
+    }
+
+    /// Name of the month, 1 to 12; other values are clamped
+    pub fn month_name(&self, month: u32) -> &'static str {
+        const DE: [&str; 12] = [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ];
+        const EN: [&str; 12] = [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ];
+        let index = (month.clamp(1, 12) - 1) as usize;
+        match self {
+            Locale::De => DE[index],
+            Locale::En => EN[index],
+        }
+    }
+
+    /// Numbers with at most two decimals and without trailing zeros, e.g. `2,5` in German
+    pub fn format_number(&self, value: f64) -> String {
+        let formatted = format!("{:.2}", value);
+        let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+        match self {
+            Locale::De => formatted.replace('.', ","),
+            Locale::En => formatted.to_string(),
+        }
+    }
+}
+
+/// A column of a report with its header in every language. Each report keeps one table of
+/// these, so the CSV and PDF outputs of a report can't drift apart.
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub de: &'static str,
+    pub en: &'static str,
+}
+
+impl Column {
+    pub const fn new(de: &'static str, en: &'static str) -> Self {
+        Column { de, en }
+    }
+
+    pub fn header(&self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::De => self.de,
+            Locale::En => self.en,
+        }
+    }
+}
+
+/// A text block of a document in every language, with `{name}` placeholders for the values.
+/// Like the columns, each document keeps its text blocks side by side in one table.
+#[derive(Debug, Clone, Copy)]
+pub struct TextBlock {
+    pub de: &'static str,
+    pub en: &'static str,
+}
+
+impl TextBlock {
+    pub const fn new(de: &'static str, en: &'static str) -> Self {
+        TextBlock { de, en }
+    }
+
+    /// The text in `locale` with every `{name}` replaced by its value
+    pub fn render(&self, locale: Locale, values: &[(&str, &str)]) -> String {
+        let template = match locale {
+            Locale::De => self.de,
+            Locale::En => self.en,
+        };
+        values
+            .iter()
+            .fold(template.to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+}
+
+/// Label of the sum row below a table
+pub const TOTAL: Column = Column::new("Gesamt", "Total");
+
+/// Renders a report as CSV with a byte order mark, so Excel detects UTF-8 and umlauts
+/// survive. Rows must have one value per column.
+pub fn to_csv(columns: &[Column], locale: Locale, rows: &[Vec<String>]) -> String {
+    let separator = locale.csv_separator();
+    let line = |values: Vec<&str>| -> String {
+        values
+            .into_iter()
+            .map(|value| escape_csv(value, separator))
+            .collect::<Vec<_>>()
+            .join(&separator.to_string())
+    };
+
+    let mut csv = String::from('\u{feff}');
+    csv.push_str(&line(columns.iter().map(|c| c.header(locale)).collect()));
+    csv.push_str("\r\n");
+    for row in rows {
+        csv.push_str(&line(row.iter().map(String::as_str).collect()));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+fn escape_csv(value: &str, separator: char) -> String {
+    if value.contains([separator, '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum FamilyInvitationStatus {
+    /// Waiting for a board member
+    Pending,
+    /// The account was created and the invitation email sent
+    Approved,
+    Rejected,
+}
+
+impl FamilyInvitationStatus {
+    /// Value stored in the `status` column of the family invitations
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FamilyInvitationStatus::Pending => "pending",
+            FamilyInvitationStatus::Approved => "approved",
+            FamilyInvitationStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+}
+
+/// A member asking for a separate login for someone of their family who shares their email
+/// address, e.g. a spouse. Once a board member approves it, an account for `email` is created
+/// that logs in as `member_id`, and `email` gets an invitation link to set its password.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct FamilyInvitation {
+    pub id: i32,
+    /// Member who asked for the account
+    pub invited_by: String,
+    /// Teable record the new account logs in as
+    pub member_id: String,
+    pub member_name: String,
+    /// Login of the new account, in lowercase
+    pub email: String,
+    pub status: FamilyInvitationStatus,
+    /// Board member who approved or rejected the invitation
+    pub decided_by: Option<String>,
+    /// RFC 3339 timestamps
+    pub created_at: String,
+    pub decided_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Type)]
+pub struct CreateFamilyInvitationRequest {
+    pub member_id: String,
+    pub email: String,
+}
+
+/// `?status=pending` of the admin invitation list
+#[derive(Debug, Deserialize)]
+pub struct FamilyInvitationQuery {
+    pub status: Option<FamilyInvitationStatus>,
+}
+
+/// A plausible email address: one `@`, something before it and a dot in the domain
+pub fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain
+            .split_once('.')
+            .is_some_and(|(host, tld)| !host.is_empty() && !tld.is_empty())
+        && !email.chars().any(char::is_whitespace)
+}
@@ -0,0 +1,133 @@
+use crate::export::{Column, Locale};
+use crate::models::string_or_f64;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::BTreeMap;
+
+/// A guest a member brought to play; the fee is owed by the member
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct GuestSession {
+    pub id: i32,
+    pub member_id: String,
+    pub member_name: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    pub guest_name: String,
+    /// Euros
+    pub fee: f64,
+    pub paid: bool,
+}
+
+/// A member's guests of a season and the fees still to pay
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct GuestSessions {
+    pub season: i32,
+    pub sessions: Vec<GuestSession>,
+    /// Euros
+    pub outstanding: f64,
+}
+
+#[derive(Debug, Deserialize, Type)]
+pub struct CreateGuestSessionRequest {
+    #[serde(rename = "Datum")]
+    pub date: String,
+    #[serde(rename = "Gast")]
+    pub guest_name: String,
+    #[serde(rename = "Gebühr", deserialize_with = "string_or_f64")]
+    pub fee: f64,
+}
+
+impl CreateGuestSessionRequest {
+    /// Returns the German error message for the first invalid field
+    pub fn validate(&self, today: NaiveDate) -> Result<(), String> {
+        let date = NaiveDate::parse_from_str(&self.date, "%Y-%m-%d")
+            .map_err(|_| "Ungültiges Datumsformat. Bitte verwenden Sie YYYY-MM-DD.".to_string())?;
+        if date > today {
+            return Err("Gastspiele können erst nach dem Spiel eingetragen werden.".to_string());
+        }
+        if self.guest_name.trim().is_empty() {
+            return Err("Name des Gastes fehlt.".to_string());
+        }
+        if !self.fee.is_finite() || self.fee < 0.0 {
+            return Err("Die Gebühr darf nicht negativ sein.".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// `?season=YYYY`; a season is a calendar year, like the work hour requirement
+#[derive(Debug, Deserialize)]
+pub struct SeasonQuery {
+    pub season: Option<i32>,
+}
+
+/// Guest fees of one member in a season
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct GuestFeeSummary {
+    pub member_id: String,
+    pub member_name: String,
+    pub sessions: u32,
+    pub outstanding: f64,
+    pub paid: f64,
+}
+
+/// Guest fees of all members in a season, for the treasurer
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct GuestFeeReport {
+    pub season: i32,
+    pub members: Vec<GuestFeeSummary>,
+    /// Euros, of all members
+    pub outstanding: f64,
+}
+
+/// Sums fees per member, members with the highest outstanding amount first
+pub fn summarize_fees(sessions: &[GuestSession]) -> Vec<GuestFeeSummary> {
+    let mut by_member: BTreeMap<&str, GuestFeeSummary> = BTreeMap::new();
+    for session in sessions {
+        let summary = by_member
+            .entry(session.member_id.as_str())
+            .or_insert_with(|| GuestFeeSummary {
+                member_id: session.member_id.clone(),
+                member_name: session.member_name.clone(),
+                sessions: 0,
+                outstanding: 0.0,
+                paid: 0.0,
+            });
+        summary.sessions += 1;
+        if session.paid {
+            summary.paid += session.fee;
+        } else {
+            summary.outstanding += session.fee;
+        }
+    }
+
+    let mut summaries: Vec<GuestFeeSummary> = by_member.into_values().collect();
+    summaries.sort_by(|a, b| b.outstanding.total_cmp(&a.outstanding));
+    summaries
+}
+
+/// Columns of the guest fee report export
+pub const FEE_REPORT_COLUMNS: [Column; 5] = [
+    Column::new("Mitglieds-ID", "Member ID"),
+    Column::new("Mitglied", "Member"),
+    Column::new("Gastspiele", "Guest sessions"),
+    Column::new("Offen (€)", "Outstanding (€)"),
+    Column::new("Bezahlt (€)", "Paid (€)"),
+];
+
+/// Rows of the guest fee report export, in the order of `FEE_REPORT_COLUMNS`
+pub fn fee_report_rows(members: &[GuestFeeSummary], locale: Locale) -> Vec<Vec<String>> {
+    members
+        .iter()
+        .map(|member| {
+            vec![
+                member.member_id.clone(),
+                member.member_name.clone(),
+                member.sessions.to_string(),
+                locale.format_number(member.outstanding),
+                locale.format_number(member.paid),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,146 @@
+use crate::database::Database;
+use crate::email::EmailService;
+use crate::teable;
+use crate::teable::TeableClient;
+use serde::Serialize;
+use specta::Type;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+/// Probes taking longer than this count as failed, so a hanging dependency can't stall
+/// the proxy's health checks
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Up,
+    Down,
+}
+
+/// Result of probing one dependency
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct DependencyCheck {
+    /// `sqlite`, `teable` or `smtp`
+    pub name: String,
+    pub status: DependencyStatus,
+    /// The backend isn't ready while a critical dependency is down
+    pub critical: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+    /// Reused from an earlier request, see `TEABLE_PROBE_CACHE_SECS` and `SMTP_PROBE_CACHE_SECS`
+    pub cached: bool,
+}
+
+impl DependencyCheck {
+    fn new(name: &str, critical: bool, started: Instant, result: Result<(), String>) -> Self {
+        DependencyCheck {
+            name: name.to_string(),
+            status: if result.is_ok() {
+                DependencyStatus::Up
+            } else {
+                DependencyStatus::Down
+            },
+            critical,
+            latency_ms: started.elapsed().as_millis() as u64,
+            error: result.err(),
+            cached: false,
+        }
+    }
+}
+
+/// Connections of the SQLite pool at the time of a check. Idle connections near zero while
+/// `size` is at `max_connections` means requests are queuing for one, see
+/// `DATABASE_MAX_CONNECTIONS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+pub struct PoolStatus {
+    pub size: u32,
+    pub idle: u32,
+    pub max_connections: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checks: Vec<DependencyCheck>,
+    pub database_pool: PoolStatus,
+    pub timestamp: String,
+}
+
+/// Last probe of a dependency. Proxies poll the readiness check every few seconds, which
+/// would otherwise add a Teable request or an SMTP connection each time.
+#[derive(Clone)]
+pub struct ProbeCache {
+    ttl: Duration,
+    last: Arc<Mutex<Option<(Instant, DependencyCheck)>>>,
+}
+
+impl ProbeCache {
+    pub fn new(ttl_secs: u64) -> Self {
+        ProbeCache {
+            ttl: Duration::from_secs(ttl_secs),
+            last: Arc::default(),
+        }
+    }
+
+    /// Runs `probe` unless the last probe is recent enough. Concurrent checks wait for
+    /// the running probe instead of starting their own.
+    async fn check<F>(&self, name: &str, critical: bool, probe: F) -> DependencyCheck
+    where
+        F: Future<Output = Result<(), String>>,
+    {
+        let mut last = self.last.lock().await;
+        if let Some((probed_at, check)) = last.as_ref() {
+            if probed_at.elapsed() < self.ttl {
+                return DependencyCheck {
+                    cached: true,
+                    ..check.clone()
+                };
+            }
+        }
+
+        let started = Instant::now();
+        let check = DependencyCheck::new(name, critical, started, probe.await);
+        *last = Some((Instant::now(), check.clone()));
+        check
+    }
+}
+
+/// Probes SQLite and Teable, which every request needs, and the mail server, which only
+/// emails need
+pub async fn check_readiness(
+    client: &TeableClient,
+    database: &Database,
+    email_service: &EmailService,
+    teable_probe: &ProbeCache,
+    smtp_probe: &ProbeCache,
+) -> ReadinessReport {
+    let sqlite = async {
+        let started = Instant::now();
+        let result = match tokio::time::timeout(PROBE_TIMEOUT, database.ping()).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("Timed out".to_string()),
+        };
+        DependencyCheck::new("sqlite", true, started, result)
+    };
+    let teable = teable_probe.check("teable", true, async {
+        teable::probe(client, PROBE_TIMEOUT)
+            .await
+            .map_err(|e| e.to_string())
+    });
+    let smtp = smtp_probe.check("smtp", false, email_service.probe(PROBE_TIMEOUT));
+    let (sqlite, teable, smtp) = tokio::join!(sqlite, teable, smtp);
+
+    let checks = vec![sqlite, teable, smtp];
+    ReadinessReport {
+        ready: checks
+            .iter()
+            .all(|check| !check.critical || check.status == DependencyStatus::Up),
+        checks,
+        database_pool: database.pool_status(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
@@ -0,0 +1,153 @@
+use crate::models::{Member, WorkHour, WorkHourEntry};
+use crate::rules::RequirementRules;
+use crate::utils::{calculate_total_hours, convert_work_hours_to_entries, family_quota};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// Years shown when `from` is left out, including the current one
+const DEFAULT_YEARS: i32 = 4;
+
+/// Most years one request may span, to keep the Teable query bounded
+pub const MAX_YEARS: i32 = 10;
+
+/// Query of `GET /statistics`, e.g. `?from=2022&to=2025`
+#[derive(Debug, Default, Deserialize)]
+pub struct StatisticsQuery {
+    pub from: Option<i32>,
+    /// Defaults to the current year
+    pub to: Option<i32>,
+}
+
+impl StatisticsQuery {
+    /// The requested years, or the German error message for an invalid range
+    pub fn years(&self, current_year: i32) -> Result<RangeInclusive<i32>, String> {
+        let to = self.to.unwrap_or(current_year);
+        let from = self.from.unwrap_or(to - DEFAULT_YEARS + 1);
+        if from < 2000 || to > current_year {
+            return Err(format!(
+                "Die Jahre müssen zwischen 2000 und {current_year} liegen."
+            ));
+        }
+        if from > to {
+            return Err("Das Startjahr darf nicht nach dem Endjahr liegen.".to_string());
+        }
+        if to - from >= MAX_YEARS {
+            return Err(format!(
+                "Es können höchstens {MAX_YEARS} Jahre auf einmal abgefragt werden."
+            ));
+        }
+        Ok(from..=to)
+    }
+}
+
+/// Hours of a member or family in one year
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct YearTotals {
+    /// Approved hours, as on the dashboard
+    pub hours: f64,
+    pub required: f64,
+    /// `hours` in percent of `required`; 100 if nothing was required
+    pub fulfillment: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct YearStatistics {
+    pub year: i32,
+    pub personal: YearTotals,
+    /// Combined quota of the member's family, if they belong to one
+    pub family: Option<YearTotals>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct MemberStatistics {
+    pub from: i32,
+    pub to: i32,
+    pub family: Option<String>,
+    /// One entry per year, oldest first
+    pub years: Vec<YearStatistics>,
+}
+
+fn fulfillment(hours: f64, required: f64) -> f64 {
+    if required > 0.0 {
+        (hours / required * 1000.0).round() / 10.0
+    } else {
+        100.0
+    }
+}
+
+/// Per-year totals of `member` and of the `family` they belong to (including themselves),
+/// from the work hours of all of them over the requested years
+pub fn per_year(
+    member: &Member,
+    family: &[Member],
+    work_hours: &[WorkHour],
+    years: RangeInclusive<i32>,
+    rules: &RequirementRules,
+) -> MemberStatistics {
+    let mut entries: HashMap<(String, i32), Vec<WorkHourEntry>> = HashMap::new();
+    for work_hour in work_hours {
+        let Some(member_id) = work_hour.get_member_id() else {
+            continue;
+        };
+        for entry in convert_work_hours_to_entries(std::slice::from_ref(work_hour), "Statistics") {
+            if let Some(year) = entry.date.get(..4).and_then(|year| year.parse().ok()) {
+                entries
+                    .entry((member_id.clone(), year))
+                    .or_default()
+                    .push(entry);
+            }
+        }
+    }
+    let hours_of = |member_id: &str, year: i32| {
+        entries
+            .get(&(member_id.to_string(), year))
+            .map(|entries| calculate_total_hours(entries))
+            .unwrap_or(0.0)
+    };
+
+    let family_name = member
+        .family_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|family| !family.is_empty())
+        .map(str::to_string);
+
+    let statistics = years
+        .clone()
+        .map(|year| {
+            let hours = hours_of(&member.id, year);
+            let (required, _) = rules.get_required_hours_for_member(member, year);
+            let family = family_name.as_ref().map(|_| {
+                let member_hours: Vec<f64> = family.iter().map(|m| hours_of(&m.id, year)).collect();
+                let member_required: Vec<f64> = family
+                    .iter()
+                    .map(|m| rules.get_required_hours_for_member(m, year).0)
+                    .collect();
+                let quota = family_quota(&member_hours, &member_required, rules.for_year(year));
+                YearTotals {
+                    hours: quota.completed,
+                    required: quota.required,
+                    fulfillment: fulfillment(quota.completed, quota.required),
+                }
+            });
+            YearStatistics {
+                year,
+                personal: YearTotals {
+                    hours,
+                    required,
+                    fulfillment: fulfillment(hours, required),
+                },
+                family,
+            }
+        })
+        .collect();
+
+    MemberStatistics {
+        from: *years.start(),
+        to: *years.end(),
+        family: family_name,
+        years: statistics,
+    }
+}
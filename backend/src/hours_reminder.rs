@@ -0,0 +1,260 @@
+use crate::database::Database;
+use crate::email::EmailService;
+use crate::events::{self, EventOverview};
+use crate::export::Locale;
+use crate::models::{Member, WorkHour, WorkHourStatus};
+use crate::rules::{self, WorkHourRules};
+use crate::schema::MEMBER_DEFAULT_PROJECTION;
+use crate::teable;
+use crate::teable::TeableClient;
+use crate::utils::{family_quota, get_member_work_hours_info};
+use chrono::{Datelike, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{error, info, warn};
+
+/// A reminder day missed while the backend was down is made up for this many days later
+const CATCH_UP_DAYS: i64 = 7;
+
+/// Upcoming events listed in a reminder
+const MAX_EVENTS: usize = 5;
+
+/// Days of the year (`MM-DD`) on which members behind on their hours are reminded, from
+/// `HOURS_REMINDER_DAYS`, e.g. `10-01,11-01`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReminderSchedule {
+    days: Vec<(u32, u32)>,
+}
+
+impl ReminderSchedule {
+    /// `None` if no day is a valid `MM-DD`, which turns the reminders off. Invalid days are
+    /// skipped; February 29th isn't allowed, as it doesn't exist every year.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut days: Vec<(u32, u32)> = value
+            .split(',')
+            .filter_map(|day| {
+                let (month, day) = day.trim().split_once('-')?;
+                let day = (month.parse().ok()?, day.parse().ok()?);
+                NaiveDate::from_ymd_opt(2023, day.0, day.1).map(|_| day)
+            })
+            .collect();
+        days.sort();
+        days.dedup();
+        (!days.is_empty()).then_some(ReminderSchedule { days })
+    }
+
+    /// The latest reminder day of `today`'s year that is due today, if it wasn't longer
+    /// than `CATCH_UP_DAYS` ago
+    pub fn due_day(&self, today: NaiveDate) -> Option<NaiveDate> {
+        self.days
+            .iter()
+            .filter_map(|(month, day)| NaiveDate::from_ymd_opt(today.year(), *month, *day))
+            .filter(|day| *day <= today && (today - *day).num_days() <= CATCH_UP_DAYS)
+            .max()
+    }
+}
+
+/// A member with an email address who receives a reminder
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReminderRecipient {
+    pub member_id: String,
+    pub name: String,
+    pub email: String,
+}
+
+/// A member, or a family as a whole, that hasn't done enough hours yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoursReminder {
+    /// `family:<name>` or `member:<id>`; each is reminded once per reminder day
+    pub key: String,
+    pub family: Option<String>,
+    /// Members of the family (or the member) with an email address
+    pub recipients: Vec<ReminderRecipient>,
+    pub required: f64,
+    /// Approved hours and hours still waiting for review
+    pub completed: f64,
+    pub missing: f64,
+}
+
+/// Hours of a year per member that aren't rejected. Submitted hours count, so members
+/// aren't reminded of hours the board just hasn't reviewed yet.
+pub fn submitted_hours_by_member(work_hours: &[WorkHour]) -> HashMap<String, f64> {
+    let mut hours: HashMap<String, f64> = HashMap::new();
+    for work_hour in work_hours {
+        if WorkHourStatus::from_teable(work_hour.status.as_deref()) == WorkHourStatus::Rejected {
+            continue;
+        }
+        if let (Some(member_id), Some(duration)) =
+            (work_hour.get_member_id(), work_hour.duration_hours)
+        {
+            *hours.entry(member_id).or_default() += duration;
+        }
+    }
+    hours
+}
+
+/// Members and families whose hours in `year` fall short of their requirement. Families are
+/// compared against their combined quota, like on the dashboard.
+pub fn behind_on_hours(
+    members: &[Member],
+    hours_by_member: &HashMap<String, f64>,
+    year: i32,
+    rules: &WorkHourRules,
+) -> Vec<HoursReminder> {
+    let mut groups: BTreeMap<String, Vec<&Member>> = BTreeMap::new();
+    for member in members {
+        let key = match member.family_id.as_deref().map(str::trim) {
+            Some(family) if !family.is_empty() => format!("family:{family}"),
+            _ => format!("member:{}", member.id),
+        };
+        groups.entry(key).or_default().push(member);
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|(key, group)| {
+            let member_hours: Vec<f64> = group
+                .iter()
+                .map(|member| hours_by_member.get(&member.id).copied().unwrap_or(0.0))
+                .collect();
+            let member_required: Vec<f64> = group
+                .iter()
+                .map(|member| get_member_work_hours_info(member, year, rules).0)
+                .collect();
+            let quota = family_quota(&member_hours, &member_required, rules);
+            if quota.remaining <= 0.0 {
+                return None;
+            }
+            Some(HoursReminder {
+                family: key.strip_prefix("family:").map(|family| family.to_string()),
+                key,
+                recipients: group
+                    .iter()
+                    .filter(|member| !member.email.trim().is_empty())
+                    .map(|member| ReminderRecipient {
+                        member_id: member.id.clone(),
+                        name: member.name(),
+                        email: member.email.clone(),
+                    })
+                    .collect(),
+                required: quota.required,
+                completed: quota.completed,
+                missing: quota.remaining,
+            })
+        })
+        .collect()
+}
+
+/// An upcoming event as listed in a reminder
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReminderEvent {
+    pub title: String,
+    /// DD.MM.YYYY
+    pub date: String,
+    pub hours: String,
+}
+
+impl ReminderEvent {
+    fn from_overview(overview: &EventOverview) -> Self {
+        let date = NaiveDate::parse_from_str(&overview.event.date, "%Y-%m-%d")
+            .map(|date| date.format("%d.%m.%Y").to_string())
+            .unwrap_or_else(|_| overview.event.date.clone());
+        ReminderEvent {
+            title: overview.event.title.clone(),
+            date,
+            hours: Locale::De.format_number(overview.event.hours),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ReminderSummary {
+    /// Members and families behind on their hours
+    pub behind: usize,
+    /// Emails sent or queued in this run
+    pub emailed: usize,
+}
+
+/// Emails every member and family behind on their hours in the year of `today`, with the
+/// missing hours and the events with open spots until the end of the year. Each is reminded
+/// once per reminder day, even if the backend runs the job again.
+pub async fn send_reminders(
+    client: &TeableClient,
+    database: &Database,
+    email_service: &EmailService,
+    today: NaiveDate,
+    day: NaiveDate,
+) -> anyhow::Result<ReminderSummary> {
+    let year = today.year();
+    let members =
+        teable::get_all_members_with_projection(client, &MEMBER_DEFAULT_PROJECTION).await?;
+    let work_hours = teable::get_work_hours_by_year(client, year).await?;
+    let all_rules = rules::try_load(client).await?;
+    let reminders = behind_on_hours(
+        &members,
+        &submitted_hours_by_member(&work_hours),
+        year,
+        all_rules.for_year(year),
+    );
+
+    // The events feature is optional; reminders go out without events while it is off
+    let (events, signups) = match teable::get_events(client).await {
+        Ok(events) => (
+            events,
+            teable::get_event_signups(client, None)
+                .await
+                .unwrap_or_default(),
+        ),
+        Err(_) => (Vec::new(), Vec::new()),
+    };
+
+    let mut summary = ReminderSummary {
+        behind: reminders.len(),
+        emailed: 0,
+    };
+    for reminder in &reminders {
+        if reminder.recipients.is_empty() {
+            warn!(
+                "Hours reminder: {} is missing {} hours but has no email address",
+                reminder.key, reminder.missing
+            );
+            continue;
+        }
+        if !database.claim_hours_reminder(&reminder.key, day).await? {
+            continue;
+        }
+
+        for recipient in &reminder.recipients {
+            let upcoming: Vec<ReminderEvent> =
+                events::upcoming_events(events.clone(), &signups, &recipient.member_id, today)
+                    .iter()
+                    .filter(|overview| overview.spots_left > 0 && !overview.signed_up_by_me)
+                    .filter(|overview| overview.event.date.starts_with(&year.to_string()))
+                    .take(MAX_EVENTS)
+                    .map(ReminderEvent::from_overview)
+                    .collect();
+
+            match email_service
+                .send_hours_reminder_email(
+                    &recipient.email,
+                    &recipient.name,
+                    year,
+                    reminder,
+                    &upcoming,
+                )
+                .await
+            {
+                Ok(_) => summary.emailed += 1,
+                Err(e) => error!(
+                    "Hours reminder: Failed to remind member {}: {}",
+                    recipient.member_id, e
+                ),
+            }
+        }
+    }
+
+    info!(
+        "Hours reminder: Sent {} emails to the {} members and families behind on their hours of {}",
+        summary.emailed, summary.behind, year
+    );
+    Ok(summary)
+}
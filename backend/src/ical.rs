@@ -0,0 +1,99 @@
+use crate::models::{WorkHourEntry, WorkHourStatus};
+use chrono::{NaiveDate, Utc};
+
+/// Entry IDs are unique within Teable; the domain makes the UIDs globally unique
+const UID_DOMAIN: &str = "arbeitsstunden.tsv-bue-tennis";
+
+/// Renders work hour entries as an iCalendar (RFC 5545) feed of all-day events.
+/// Rejected entries and corrections are left out since they aren't work done.
+pub fn render_work_hours_calendar(calendar_name: &str, entries: &[WorkHourEntry]) -> String {
+    let dtstamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//TSV BUE Tennis//Arbeitsstunden//DE".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(calendar_name)),
+        "X-WR-TIMEZONE:Europe/Berlin".to_string(),
+    ];
+
+    for entry in entries {
+        if entry.status == WorkHourStatus::Rejected || entry.corrects.is_some() {
+            continue;
+        }
+        let Ok(date) = NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d") else {
+            continue;
+        };
+        let Some(next_day) = date.succ_opt() else {
+            continue;
+        };
+
+        let status = match entry.status {
+            WorkHourStatus::Approved => "genehmigt",
+            _ if entry.pending_sync => "wird synchronisiert",
+            _ => "eingereicht",
+        };
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@{}", entry.id, UID_DOMAIN));
+        lines.push(format!("DTSTAMP:{dtstamp}"));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")));
+        lines.push(format!("DTEND;VALUE=DATE:{}", next_day.format("%Y%m%d")));
+        lines.push(format!(
+            "SUMMARY:{}",
+            escape_text(&format!(
+                "Arbeitsstunden: {} ({} Std.)",
+                entry.description, entry.duration_hours
+            ))
+        ));
+        lines.push(format!(
+            "DESCRIPTION:{}",
+            escape_text(&format!(
+                "{} Stunden, Status: {}",
+                entry.duration_hours, status
+            ))
+        ));
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut calendar = String::new();
+    for line in lines {
+        calendar.push_str(&fold_line(&line));
+        calendar.push_str("\r\n");
+    }
+    calendar
+}
+
+/// Escapes TEXT values: backslash, semicolon, comma and newlines
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Folds content lines longer than 75 octets without splitting UTF-8 characters
+fn fold_line(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_OCTETS * 3);
+    let mut current_len = 0;
+    for ch in line.chars() {
+        let ch_len = ch.len_utf8();
+        if current_len + ch_len > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space of a continuation line counts toward its length
+            current_len = 1;
+        }
+        folded.push(ch);
+        current_len += ch_len;
+    }
+    folded
+}
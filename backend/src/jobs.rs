@@ -0,0 +1,207 @@
+use crate::database::Database;
+use crate::error::ErrorCode;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tracing::{debug, error, info, warn};
+
+/// Entries of a work hour import; imports are processed in chunks, so this is far above
+/// the limit of the synchronous bulk endpoint
+pub const MAX_IMPORT_ENTRIES: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Work hours of many members, e.g. from a spreadsheet; the payload is a
+    /// `BulkCreateWorkHoursRequest`
+    WorkHourImport,
+    /// Emails announcing a rule change to all members; the payload is a
+    /// `RuleAnnouncement`
+    RuleAnnouncement,
+    /// Emails with each member's hours of a finished year; the payload is a
+    /// `YearSummaryBatch`
+    YearSummary,
+}
+
+impl JobKind {
+    /// Value stored in the `kind` column of the jobs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::WorkHourImport => "work_hour_import",
+            JobKind::RuleAnnouncement => "rule_announcement",
+            JobKind::YearSummary => "year_summary",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    /// At least one chunk was processed
+    Running,
+    /// All items were processed; some of them may have failed
+    Completed,
+    /// The job couldn't be processed at all, see `error`
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Value stored in the `status` column of the jobs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+}
+
+/// An item of a job that failed; the job itself goes on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct JobItemError {
+    /// Position of the item in the job's payload
+    pub index: u32,
+    pub code: ErrorCode,
+    pub message: String,
+    /// See `BulkItemError::retryable`
+    pub retryable: bool,
+}
+
+/// A long-running board action, processed in chunks by a background worker
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct Job {
+    pub id: i64,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    /// Board member who started the job
+    pub created_by: String,
+    /// Items of the job and how many of them were processed so far
+    pub total: u32,
+    pub processed: u32,
+    pub failed: u32,
+    pub errors: Vec<JobItemError>,
+    /// Why a failed job couldn't be processed
+    pub error: Option<String>,
+    /// RFC 3339 timestamps
+    pub created_at: String,
+    pub updated_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// A job as shown to the board
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct JobDetail {
+    pub job: Job,
+    /// For a completed import with retryable failures: starts the import again for only
+    /// those items, see `bulk::retry_token`
+    pub retry_token: Option<String>,
+}
+
+/// Result of processing the items `offset..offset + count` of a job
+#[derive(Debug, Default)]
+pub struct ChunkOutcome {
+    pub errors: Vec<JobItemError>,
+}
+
+/// Does the actual work of each job kind. Implemented by the server, which holds the
+/// clients and services the jobs need.
+#[async_trait]
+pub trait ChunkProcessor: Send + Sync {
+    /// Processes `count` items of the JSON `payload` starting at `offset`. Failed items go
+    /// into the outcome; an error fails the whole job.
+    async fn process_chunk(
+        &self,
+        job: &Job,
+        payload: &str,
+        offset: u32,
+        count: u32,
+    ) -> anyhow::Result<ChunkOutcome>;
+}
+
+/// Works off all unfinished jobs, oldest first, one chunk at a time. Progress is stored
+/// after every chunk, so a restart continues where the job stopped, and a cancelled job
+/// stops after its current chunk.
+pub async fn run_jobs(database: &Database, processor: &dyn ChunkProcessor, chunk_size: u32) {
+    let jobs = match database.get_unfinished_jobs().await {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            error!("Jobs: Failed to load unfinished jobs: {}", e);
+            return;
+        }
+    };
+    if jobs.is_empty() {
+        debug!("Jobs: No jobs to process");
+        return;
+    }
+
+    for (job, payload) in jobs {
+        run_job(database, processor, job, &payload, chunk_size.max(1)).await;
+    }
+}
+
+async fn run_job(
+    database: &Database,
+    processor: &dyn ChunkProcessor,
+    mut job: Job,
+    payload: &str,
+    chunk_size: u32,
+) {
+    info!(
+        "Jobs: Processing job {} ({:?}) from item {} of {}",
+        job.id, job.kind, job.processed, job.total
+    );
+
+    while job.processed < job.total {
+        let count = chunk_size.min(job.total - job.processed);
+        let outcome = match processor
+            .process_chunk(&job, payload, job.processed, count)
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                error!("Jobs: Job {} failed: {}", job.id, e);
+                if let Err(e) = database.fail_job(job.id, &e.to_string()).await {
+                    error!("Jobs: Failed to record failure of job {}: {}", job.id, e);
+                }
+                return;
+            }
+        };
+
+        job = match database
+            .record_job_progress(job.id, count, &outcome.errors)
+            .await
+        {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                info!("Jobs: Job {} was cancelled", job.id);
+                return;
+            }
+            Err(e) => {
+                // The chunk is processed again by the next run
+                error!("Jobs: Failed to record progress of job {}: {}", job.id, e);
+                return;
+            }
+        };
+    }
+
+    match database.complete_job(job.id).await {
+        Ok(_) if job.failed > 0 => warn!(
+            "Jobs: Completed job {} with {} of {} failed items",
+            job.id, job.failed, job.total
+        ),
+        Ok(_) => info!("Jobs: Completed job {} ({} items)", job.id, job.total),
+        Err(e) => error!("Jobs: Failed to complete job {}: {}", job.id, e),
+    }
+}
@@ -0,0 +1,270 @@
+use crate::bindings;
+use serde_json::{json, Map, Value};
+use specta::{
+    DataType, DataTypeReference, EnumRepr, EnumType, EnumVariant, GenericType, LiteralType,
+    NamedDataType, NamedDataTypeItem, ObjectType, PrimitiveType, TupleType, TypeDefs,
+};
+use std::collections::HashMap;
+use tracing::error;
+
+/// Schemas of generic arguments, by parameter name
+type Generics = HashMap<&'static str, Value>;
+
+/// JSON Schemas of the types in `bindings`, by name, for `components.schemas` of the OpenAPI
+/// spec. Built from the same definitions as the TypeScript types, so the documented
+/// responses are what the frontend is compiled against.
+pub fn component_schemas() -> Map<String, Value> {
+    let definitions = match bindings::definitions() {
+        Ok(definitions) => definitions,
+        Err(e) => {
+            error!("OpenAPI: Failed to collect the API types: {:?}", e);
+            return Map::new();
+        }
+    };
+    let converter = Converter {
+        definitions: &definitions,
+    };
+    definitions
+        .values()
+        .flatten()
+        .map(|definition| {
+            (
+                definition.name.to_string(),
+                converter.named(definition, &Generics::new()),
+            )
+        })
+        .collect()
+}
+
+/// A `$ref` to the schema `name` in `components.schemas`
+pub fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+struct Converter<'a> {
+    definitions: &'a TypeDefs,
+}
+
+impl Converter<'_> {
+    fn schema(&self, data_type: &DataType, generics: &Generics) -> Value {
+        match data_type {
+            DataType::Any => json!({}),
+            DataType::Primitive(primitive) => primitive_schema(primitive),
+            DataType::Literal(literal) => literal_schema(literal),
+            DataType::List(item) => {
+                json!({ "type": "array", "items": self.schema(item, generics) })
+            }
+            DataType::Nullable(inner) => {
+                json!({ "anyOf": [self.schema(inner, generics), { "type": "null" }] })
+            }
+            DataType::Record(entry) => json!({
+                "type": "object",
+                "additionalProperties": self.schema(&entry.1, generics)
+            }),
+            DataType::Named(definition) => self.named(definition, generics),
+            DataType::Object(object) => self.object(object, generics),
+            DataType::Enum(enumeration) => self.enumeration(enumeration, generics),
+            DataType::Tuple(tuple) => self.tuple(tuple, generics),
+            DataType::Reference(reference) => self.reference(reference, generics),
+            DataType::Generic(GenericType(name)) => {
+                generics.get(name).cloned().unwrap_or_else(|| json!({}))
+            }
+        }
+    }
+
+    fn named(&self, definition: &NamedDataType, generics: &Generics) -> Value {
+        let mut schema = match &definition.item {
+            NamedDataTypeItem::Object(object) => self.object(object, generics),
+            NamedDataTypeItem::Enum(enumeration) => self.enumeration(enumeration, generics),
+            NamedDataTypeItem::Tuple(tuple) => self.tuple(tuple, generics),
+        };
+        let description = definition
+            .comments
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !description.is_empty() {
+            schema["description"] = json!(description);
+        }
+        if definition.deprecated.is_some() {
+            schema["deprecated"] = json!(true);
+        }
+        schema
+    }
+
+    /// Plain types are referenced; generic ones are inlined with their arguments, as JSON
+    /// Schema has no type parameters
+    fn reference(&self, reference: &DataTypeReference, generics: &Generics) -> Value {
+        if reference.generics.is_empty() {
+            return self::reference(reference.name);
+        }
+        let Some(Some(definition)) = self.definitions.get(&reference.sid) else {
+            return self::reference(reference.name);
+        };
+        let parameters = match &definition.item {
+            NamedDataTypeItem::Object(object) => &object.generics,
+            NamedDataTypeItem::Enum(
+                EnumType::Untagged { generics, .. } | EnumType::Tagged { generics, .. },
+            ) => generics,
+            NamedDataTypeItem::Tuple(tuple) => &tuple.generics,
+        };
+        let arguments = parameters
+            .iter()
+            .copied()
+            .zip(
+                reference
+                    .generics
+                    .iter()
+                    .map(|argument| self.schema(argument, generics)),
+            )
+            .collect();
+        self.named(definition, &arguments)
+    }
+
+    /// Fields flattened with `#[serde(flatten)]` become a further `allOf` part
+    fn object(&self, object: &ObjectType, generics: &Generics) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut flattened = Vec::new();
+        if let Some(tag) = object.tag {
+            properties.insert(tag.to_string(), json!({ "type": "string" }));
+            required.push(tag);
+        }
+        for field in &object.fields {
+            let schema = self.schema(&field.ty, generics);
+            if field.flatten {
+                flattened.push(schema);
+                continue;
+            }
+            if !field.optional {
+                required.push(field.key);
+            }
+            properties.insert(field.key.to_string(), schema);
+        }
+        let schema = json!({ "type": "object", "properties": properties, "required": required });
+        if flattened.is_empty() {
+            return schema;
+        }
+        flattened.insert(0, schema);
+        json!({ "allOf": flattened })
+    }
+
+    fn enumeration(&self, enumeration: &EnumType, generics: &Generics) -> Value {
+        match enumeration {
+            EnumType::Untagged { variants, .. } => {
+                let variants: Vec<Value> = variants
+                    .iter()
+                    .map(|variant| self.variant_data(variant, generics))
+                    .collect();
+                json!({ "anyOf": variants })
+            }
+            // Like most enums of the API: plain strings, e.g. `WorkHourStatus`
+            EnumType::Tagged {
+                variants,
+                repr: EnumRepr::External,
+                ..
+            } if variants
+                .iter()
+                .all(|(_, variant)| matches!(variant, EnumVariant::Unit)) =>
+            {
+                let names: Vec<&str> = variants.iter().map(|(name, _)| *name).collect();
+                json!({ "type": "string", "enum": names })
+            }
+            EnumType::Tagged { variants, repr, .. } => {
+                let variants: Vec<Value> = variants
+                    .iter()
+                    .map(|(name, variant)| self.tagged_variant(name, variant, repr, generics))
+                    .collect();
+                json!({ "anyOf": variants })
+            }
+        }
+    }
+
+    fn tagged_variant(
+        &self,
+        name: &str,
+        variant: &EnumVariant,
+        repr: &EnumRepr,
+        generics: &Generics,
+    ) -> Value {
+        let tag_object = |tag: &str| {
+            json!({
+                "type": "object",
+                "properties": { tag: { "const": name } },
+                "required": [tag]
+            })
+        };
+        match (repr, variant) {
+            (EnumRepr::External, EnumVariant::Unit) => json!({ "const": name }),
+            (EnumRepr::External, _) => json!({
+                "type": "object",
+                "properties": { name: self.variant_data(variant, generics) },
+                "required": [name]
+            }),
+            (EnumRepr::Internal { tag }, EnumVariant::Unit) => tag_object(tag),
+            (EnumRepr::Internal { tag }, _) => {
+                json!({ "allOf": [tag_object(tag), self.variant_data(variant, generics)] })
+            }
+            (EnumRepr::Adjacent { tag, .. }, EnumVariant::Unit) => tag_object(tag),
+            (EnumRepr::Adjacent { tag, content }, _) => json!({
+                "type": "object",
+                "properties": {
+                    *tag: { "const": name },
+                    *content: self.variant_data(variant, generics)
+                },
+                "required": [tag, content]
+            }),
+        }
+    }
+
+    fn variant_data(&self, variant: &EnumVariant, generics: &Generics) -> Value {
+        match variant {
+            EnumVariant::Unit => json!({ "type": "null" }),
+            EnumVariant::Named(object) => self.object(object, generics),
+            EnumVariant::Unnamed(tuple) => self.tuple(tuple, generics),
+        }
+    }
+
+    /// Newtypes are their field; longer tuples are arrays
+    fn tuple(&self, tuple: &TupleType, generics: &Generics) -> Value {
+        match tuple.fields.as_slice() {
+            [] => json!({ "type": "null" }),
+            [field] => self.schema(field, generics),
+            fields => {
+                let items: Vec<Value> = fields
+                    .iter()
+                    .map(|field| self.schema(field, generics))
+                    .collect();
+                json!({ "type": "array", "prefixItems": items, "items": false })
+            }
+        }
+    }
+}
+
+fn primitive_schema(primitive: &PrimitiveType) -> Value {
+    use PrimitiveType::*;
+    match primitive {
+        i8 | i16 | i32 | i64 | i128 | isize => json!({ "type": "integer" }),
+        u8 | u16 | u32 | u64 | u128 | usize => json!({ "type": "integer", "minimum": 0 }),
+        f32 | f64 => json!({ "type": "number" }),
+        bool => json!({ "type": "boolean" }),
+        char | String => json!({ "type": "string" }),
+    }
+}
+
+fn literal_schema(literal: &LiteralType) -> Value {
+    match literal {
+        LiteralType::i8(value) => json!({ "const": value }),
+        LiteralType::i16(value) => json!({ "const": value }),
+        LiteralType::i32(value) => json!({ "const": value }),
+        LiteralType::u8(value) => json!({ "const": value }),
+        LiteralType::u16(value) => json!({ "const": value }),
+        LiteralType::u32(value) => json!({ "const": value }),
+        LiteralType::f32(value) => json!({ "const": value }),
+        LiteralType::f64(value) => json!({ "const": value }),
+        LiteralType::bool(value) => json!({ "const": value }),
+        LiteralType::String(value) => json!({ "const": value }),
+        LiteralType::None => json!({ "type": "null" }),
+    }
+}
@@ -1,12 +1,84 @@
 // Library exports for TSV Tennis Backend
 // This allows other binaries to access the modules
 
+pub mod activity;
+pub mod analytics;
+pub mod anonymize;
+pub mod api_response;
+pub mod app_events;
+pub mod attachment;
+pub mod audit;
 pub mod auth;
+pub mod bindings;
+pub mod bulk;
+pub mod certificate;
+pub mod circuit_breaker;
+pub mod compression;
 pub mod config;
+pub mod consistency;
+pub mod correction;
+pub mod dashboard_cache;
 pub mod database;
+pub mod deprecation;
+pub mod dry_run;
 pub mod email;
+pub mod email_templates;
+pub mod error;
+pub mod etag;
+pub mod event_bus;
+pub mod events;
+pub mod export;
+pub mod family_invitation;
+pub mod guests;
+pub mod health;
+pub mod history;
+pub mod hours_reminder;
+pub mod ical;
+pub mod jobs;
+pub mod json_schema;
+pub mod load_shed;
+pub mod maintenance;
+pub mod member_alias;
+pub mod member_change;
 pub mod member_selection;
+pub mod metrics;
 pub mod models;
+pub mod openapi;
+pub mod outbox;
+pub mod password_policy;
+pub mod password_resets;
+pub mod password_rotation;
+pub mod pdf;
+pub mod profile;
+pub mod rate_limit;
+pub mod request_id;
+pub mod requirements;
+pub mod rule_changes;
+pub mod rules;
+pub mod scheduler;
+pub mod schema;
+pub mod server;
+pub mod simulation;
+pub mod static_files;
+pub mod stats;
+pub mod storage;
+pub mod suggestions;
+pub mod survey;
 pub mod teable;
+pub mod teable_filter;
+pub mod teable_record;
+pub mod teable_throttle;
+pub mod teable_webhook;
+pub mod telemetry;
+pub mod timesheet;
 pub mod token_store;
+pub mod trash;
+pub mod two_factor;
 pub mod utils;
+pub mod validation;
+pub mod verification;
+pub mod work_hour_list;
+pub mod write_freeze;
+pub mod year_summary;
+
+pub use server::{app, AppState};
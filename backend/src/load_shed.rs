@@ -0,0 +1,84 @@
+use crate::error::{AppError, ErrorCode};
+use crate::metrics;
+use axum::error_handling::HandleErrorLayer;
+use axum::http::HeaderValue;
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use rand::Rng;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::load_shed::error::Overloaded;
+use tower::{BoxError, ServiceBuilder};
+use tracing::{error, warn};
+
+/// Limit on the requests to Teable-heavy routes in flight at once. Bursts beyond it (a
+/// newsletter link clicked by half the club) are turned away with `503 OVERLOADED` and a
+/// `Retry-After` hint right away, instead of queueing up in front of Teable until its rate
+/// limit and our timeouts fail them all. Health checks and metrics are never limited, so a
+/// busy instance still reports itself as up.
+#[derive(Clone)]
+pub struct LoadShedder {
+    /// Shared by all routes the limit is applied to; `None` if disabled
+    semaphore: Option<Arc<Semaphore>>,
+    retry_after_secs: u64,
+}
+
+impl LoadShedder {
+    /// `max_concurrent` of 0 disables the limit
+    pub fn new(max_concurrent: usize, retry_after_secs: u64) -> Self {
+        LoadShedder {
+            semaphore: (max_concurrent > 0).then(|| Arc::new(Semaphore::new(max_concurrent))),
+            retry_after_secs,
+        }
+    }
+
+    /// Puts the routes of `router` under the limit; all routers given to the same shedder
+    /// share it
+    pub fn apply<S>(&self, router: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let Some(semaphore) = &self.semaphore else {
+            return router;
+        };
+        let retry_after_secs = self.retry_after_secs;
+        router.layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(move |error: BoxError| async move {
+                    overloaded_response(error, retry_after_secs)
+                }))
+                .load_shed()
+                .layer(GlobalConcurrencyLimitLayer::with_semaphore(
+                    semaphore.clone(),
+                )),
+        )
+    }
+}
+
+/// Seconds from `base` to twice `base` (at least 1), so turned away clients don't all come
+/// back in the same second
+pub fn jittered_retry_after(base_secs: u64) -> u64 {
+    let base = base_secs.max(1);
+    rand::thread_rng().gen_range(base..=base * 2)
+}
+
+fn overloaded_response(error: BoxError, retry_after_secs: u64) -> Response {
+    if !error.is::<Overloaded>() {
+        error!("Load Shedding: Unexpected error: {}", error);
+        return AppError::internal().into_response();
+    }
+
+    metrics::record_shed_request();
+    let retry_after = jittered_retry_after(retry_after_secs);
+    warn!(
+        "Load Shedding: Too many requests in flight, asking to retry in {}s",
+        retry_after
+    );
+    let mut response = AppError::code(ErrorCode::Overloaded).into_response();
+    response.headers_mut().insert(
+        axum::http::header::RETRY_AFTER,
+        HeaderValue::from(retry_after),
+    );
+    response
+}
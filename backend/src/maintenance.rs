@@ -0,0 +1,139 @@
+use crate::database::Database;
+use crate::email::EmailService;
+use crate::metrics;
+use crate::teable;
+use crate::teable::TeableClient;
+use chrono::{DateTime, Days, NaiveTime, TimeZone, Utc};
+use chrono_tz::Europe::Berlin;
+use serde::Serialize;
+use specta::Type;
+use tracing::{error, info, warn};
+
+/// The latest maintenance runs for the system dashboard
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct MaintenanceOverview {
+    /// Result of the latest integrity check, `None` before the first run
+    pub integrity_ok: Option<bool>,
+    /// RFC 3339
+    pub next_run: String,
+    /// Newest first
+    pub runs: Vec<MaintenanceRun>,
+}
+
+/// Result of one run of the nightly database maintenance
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct MaintenanceRun {
+    pub id: i64,
+    pub started_at: String,
+    pub finished_at: String,
+    /// `PRAGMA integrity_check` found no problems
+    pub integrity_ok: bool,
+    /// Problems reported by the integrity check, at most 100
+    pub integrity_errors: Vec<String>,
+    /// Skipped when the integrity check fails, since rewriting a damaged file can lose
+    /// more data
+    pub vacuumed: bool,
+    pub size_before_bytes: i64,
+    pub size_after_bytes: i64,
+    /// What stopped the run early, e.g. `VACUUM` failing while the database was busy
+    pub error: Option<String>,
+}
+
+/// The run of the maintenance job after `now`, at `hour` o'clock club time
+pub fn next_run(now: DateTime<Utc>, hour: u32) -> DateTime<Utc> {
+    let time = NaiveTime::from_hms_opt(hour.min(23), 0, 0).unwrap_or_default();
+    let mut date = now.with_timezone(&Berlin).date_naive();
+    loop {
+        // `hour` doesn't exist on the day the clocks go forward; run an hour later then
+        let at = Berlin
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .or_else(|| {
+                Berlin
+                    .from_local_datetime(&(date.and_time(time) + chrono::Duration::hours(1)))
+                    .earliest()
+            });
+        if let Some(at) = at.map(|at| at.with_timezone(&Utc)).filter(|at| *at > now) {
+            return at;
+        }
+        date = date + Days::new(1);
+    }
+}
+
+/// Checks the integrity of the database and, if it is intact, compacts it and empties the
+/// write-ahead log. The run is stored for the board in any case.
+pub async fn run(database: &Database) -> anyhow::Result<MaintenanceRun> {
+    let started_at = Utc::now();
+    let size_before = database.size_bytes().await?;
+    let integrity_errors = database.integrity_check().await?;
+    let integrity_ok = integrity_errors.is_empty();
+
+    let mut vacuumed = false;
+    let mut failure = None;
+    if integrity_ok {
+        match database.vacuum().await {
+            Ok(()) => vacuumed = true,
+            Err(e) => failure = Some(format!("VACUUM failed: {e}")),
+        }
+        if let Err(e) = database.wal_checkpoint().await {
+            failure.get_or_insert(format!("WAL checkpoint failed: {e}"));
+        }
+    }
+    let size_after = database.size_bytes().await?;
+
+    let mut run = MaintenanceRun {
+        id: 0,
+        started_at: started_at.to_rfc3339(),
+        finished_at: Utc::now().to_rfc3339(),
+        integrity_ok,
+        integrity_errors,
+        vacuumed,
+        size_before_bytes: size_before,
+        size_after_bytes: size_after,
+        error: failure,
+    };
+    run.id = database.record_maintenance_run(&run).await?;
+    metrics::record_maintenance(integrity_ok, Utc::now());
+    Ok(run)
+}
+
+/// Emails every board member in `board` (`ADMIN_MEMBER_IDS`) that the integrity check failed
+pub async fn alert_board(
+    client: &TeableClient,
+    email_service: &EmailService,
+    board: &[String],
+    run: &MaintenanceRun,
+) {
+    if board.is_empty() {
+        warn!("Maintenance: ADMIN_MEMBER_IDS is empty, nobody to alert");
+        return;
+    }
+
+    for member_id in board {
+        let member = match teable::get_member_by_id(client, member_id).await {
+            Ok(Some(member)) if !member.email.trim().is_empty() => member,
+            Ok(_) => {
+                warn!("Maintenance: Board member {} has no email", member_id);
+                continue;
+            }
+            Err(e) => {
+                error!(
+                    "Maintenance: Failed to look up board member {}: {}",
+                    member_id, e
+                );
+                continue;
+            }
+        };
+        if let Err(e) = email_service
+            .send_integrity_alert_email(&member.email, &run.integrity_errors)
+            .await
+        {
+            error!(
+                "Maintenance: Failed to alert board member {}: {}",
+                member_id, e
+            );
+        } else {
+            info!("Maintenance: Alerted board member {}", member_id);
+        }
+    }
+}
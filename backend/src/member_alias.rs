@@ -0,0 +1,34 @@
+use crate::database::Database;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Aliases followed at most, so a misconfigured chain can't loop
+const MAX_HOPS: usize = 5;
+
+/// Points the record ID of a merged or re-created Teable member to the surviving record
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct MemberAlias {
+    pub old_id: String,
+    pub new_id: String,
+    /// Board member who added the alias
+    pub created_by: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, Type)]
+pub struct CreateMemberAliasRequest {
+    pub old_id: String,
+    pub new_id: String,
+}
+
+/// The surviving record of `member_id`, following chains of merges; `None` if it has no alias
+pub async fn resolve(database: &Database, member_id: &str) -> Result<Option<String>, sqlx::Error> {
+    let mut current = member_id.to_string();
+    for _ in 0..MAX_HOPS {
+        match database.get_member_alias(&current).await? {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    Ok((current != member_id).then_some(current))
+}
@@ -0,0 +1,318 @@
+use crate::app_events::MemberUpdatedData;
+use crate::audit::AuditEntry;
+use crate::database::Database;
+use crate::email::EmailService;
+use crate::export::Locale;
+use crate::models::Member;
+use crate::schema::{FieldNames, MemberField, TeableField};
+use crate::teable::{self, TeableClient};
+use crate::utils::parse_hours;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use specta::Type;
+use tracing::{debug, error, info, warn};
+
+/// Member data the board changes through the app; everything else is kept in Teable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberChangeField {
+    /// The family the member shares their hours with
+    Family,
+    /// Hours the board set for the member, replacing the ones by age and entry date
+    RequiredHours,
+}
+
+impl MemberChangeField {
+    pub const ALL: [MemberChangeField; 2] =
+        [MemberChangeField::Family, MemberChangeField::RequiredHours];
+
+    /// Key in the audit snapshots and in `MEMBER_CHANGE_NOTIFICATIONS`
+    pub fn key(self) -> &'static str {
+        match self {
+            MemberChangeField::Family => "family",
+            MemberChangeField::RequiredHours => "required_hours",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        MemberChangeField::ALL
+            .into_iter()
+            .find(|field| field.key() == key)
+    }
+
+    pub fn teable_field(self) -> MemberField {
+        match self {
+            MemberChangeField::Family => MemberField::Family,
+            MemberChangeField::RequiredHours => MemberField::RequiredHours,
+        }
+    }
+
+    /// Name of the field in the notification email
+    pub fn label(self) -> &'static str {
+        match self {
+            MemberChangeField::Family => "Familie",
+            MemberChangeField::RequiredHours => "Arbeitsstunden-Pflicht",
+        }
+    }
+
+    /// The value of the field as kept in audit snapshots
+    pub fn value_of(self, member: &Member) -> Value {
+        match self {
+            MemberChangeField::Family => member.family_id.clone().into(),
+            MemberChangeField::RequiredHours => member.required_hours.into(),
+        }
+    }
+
+    /// The value for the member to read; no value means the default applies
+    fn display(self, value: &Value) -> String {
+        match (self, value) {
+            (MemberChangeField::Family, Value::String(family)) => family.clone(),
+            (MemberChangeField::Family, _) => "keine".to_string(),
+            (MemberChangeField::RequiredHours, Value::Number(hours)) => format!(
+                "{} Stunden",
+                Locale::De.format_number(hours.as_f64().unwrap_or_default())
+            ),
+            (MemberChangeField::RequiredHours, _) => "nach den Vereinsregeln".to_string(),
+        }
+    }
+}
+
+/// Teable fields of the members table the board may change through the app
+pub const BOARD_FIELDS: [MemberField; 2] = [MemberField::Family, MemberField::RequiredHours];
+
+/// Most hours the board can require of a single member
+const MAX_REQUIRED_HOURS: f64 = 100.0;
+const MAX_FAMILY_CHARS: usize = 100;
+
+/// Body of `PUT /admin/members/{id}`. Fields left out stay as they are, empty strings clear
+/// them: without a family the member counts alone, without required hours the rules apply.
+#[derive(Debug, Default, Deserialize, Type)]
+pub struct MemberChangeRequest {
+    pub family: Option<String>,
+    /// Hours as typed, e.g. `4,5`; `0` exempts the member
+    pub required_hours: Option<String>,
+}
+
+impl MemberChangeRequest {
+    /// The Teable fields to write, or the message for the board member if a value is invalid
+    pub fn to_fields(&self, names: &FieldNames) -> Result<Map<String, Value>, String> {
+        let mut fields = Map::new();
+        if let Some(family) = &self.family {
+            let family = family.trim();
+            if family.chars().count() > MAX_FAMILY_CHARS || family.chars().any(char::is_control) {
+                return Err("Bitte gib eine gültige Familie an.".to_string());
+            }
+            let value = if family.is_empty() {
+                Value::Null
+            } else {
+                Value::String(family.to_string())
+            };
+            fields.insert(MemberField::Family.name(names).to_string(), value);
+        }
+        if let Some(hours) = &self.required_hours {
+            let value = if hours.trim().is_empty() {
+                Value::Null
+            } else {
+                let hours = parse_hours(hours)?;
+                if !(0.0..=MAX_REQUIRED_HOURS).contains(&hours) {
+                    return Err(format!(
+                        "Die Arbeitsstunden-Pflicht muss zwischen 0 und {MAX_REQUIRED_HOURS} Stunden liegen."
+                    ));
+                }
+                Value::from(hours)
+            };
+            fields.insert(MemberField::RequiredHours.name(names).to_string(), value);
+        }
+        if fields.is_empty() {
+            return Err("Bitte gib mindestens ein Feld an, das geändert werden soll.".to_string());
+        }
+        Ok(fields)
+    }
+}
+
+/// The fields the board changes of a member, as answered by `PUT /admin/members/{id}`
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct MemberData {
+    pub member_id: String,
+    pub family: Option<String>,
+    pub required_hours: Option<f64>,
+    /// Fields whose value changed with the request
+    pub changed: Vec<MemberChangeField>,
+}
+
+/// The changeable fields of `member` by key, for the audit log
+pub fn snapshot(member: &Member, fields: &[MemberChangeField]) -> Value {
+    fields
+        .iter()
+        .map(|field| (field.key().to_string(), field.value_of(member)))
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+/// The fields whose value differs between two members
+pub fn changed_fields(before: &Member, after: &Member) -> Vec<MemberChangeField> {
+    MemberChangeField::ALL
+        .into_iter()
+        .filter(|field| field.value_of(before) != field.value_of(after))
+        .collect()
+}
+
+/// How much a member is told about a change of one of the fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sensitivity {
+    /// The change isn't mentioned
+    Off,
+    /// Only the field is named, e.g. when the values concern other members too
+    Field,
+    /// The field with its old and new value
+    Values,
+}
+
+/// Per-field sensitivity of the notifications, from `MEMBER_CHANGE_NOTIFICATIONS`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationSettings {
+    family: Sensitivity,
+    required_hours: Sensitivity,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            family: Sensitivity::Values,
+            required_hours: Sensitivity::Values,
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Parses comma-separated entries `field=off|field|values`, e.g.
+    /// `family=field,required_hours=values`; fields left out keep the default `values`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut settings = NotificationSettings::default();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid = || format!("Invalid MEMBER_CHANGE_NOTIFICATIONS entry: '{entry}'");
+            let (key, level) = entry.split_once('=').ok_or_else(invalid)?;
+            let field = MemberChangeField::from_key(key.trim()).ok_or_else(invalid)?;
+            let sensitivity = match level.trim() {
+                "off" => Sensitivity::Off,
+                "field" => Sensitivity::Field,
+                "values" => Sensitivity::Values,
+                _ => return Err(invalid()),
+            };
+            match field {
+                MemberChangeField::Family => settings.family = sensitivity,
+                MemberChangeField::RequiredHours => settings.required_hours = sensitivity,
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn sensitivity(&self, field: MemberChangeField) -> Sensitivity {
+        match field {
+            MemberChangeField::Family => self.family,
+            MemberChangeField::RequiredHours => self.required_hours,
+        }
+    }
+}
+
+/// A line of the notification email
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedField {
+    pub label: &'static str,
+    /// Left out for fields whose values aren't sent
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+/// What the member is told about the change recorded in `entry`, diffed field by field from
+/// its snapshots; empty if nothing is to be mentioned
+pub fn notification(entry: &AuditEntry, settings: &NotificationSettings) -> Vec<ChangedField> {
+    let empty = Value::Object(Map::new());
+    let before = entry.before.as_ref().unwrap_or(&empty);
+    let after = entry.after.as_ref().unwrap_or(&empty);
+    MemberChangeField::ALL
+        .into_iter()
+        .filter(|field| before[field.key()] != after[field.key()])
+        .filter_map(|field| {
+            let values = match settings.sensitivity(field) {
+                Sensitivity::Off => return None,
+                Sensitivity::Field => None,
+                Sensitivity::Values => Some((
+                    field.display(&before[field.key()]),
+                    field.display(&after[field.key()]),
+                )),
+            };
+            let (old_value, new_value) = values.unzip();
+            Some(ChangedField {
+                label: field.label(),
+                old_value,
+                new_value,
+            })
+        })
+        .collect()
+}
+
+/// Emails the member about the change announced by `data`. The audit entry is the source of
+/// what changed, so a notification never tells more than the log holds.
+pub async fn notify(
+    client: &TeableClient,
+    database: &Database,
+    email_service: &EmailService,
+    settings: &NotificationSettings,
+    data: &MemberUpdatedData,
+) {
+    let entry = match database.get_audit_entry(data.audit_id).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            warn!(
+                "Member Change: Audit entry {} of member {} not found",
+                data.audit_id, data.member_id
+            );
+            return;
+        }
+        Err(e) => {
+            error!(
+                "Member Change: Failed to read audit entry {}: {}",
+                data.audit_id, e
+            );
+            return;
+        }
+    };
+    let changes = notification(&entry, settings);
+    if changes.is_empty() {
+        debug!(
+            "Member Change: Nothing to tell member {} about audit entry {}",
+            data.member_id, data.audit_id
+        );
+        return;
+    }
+
+    let member = match teable::get_member_by_id(client, &data.member_id).await {
+        Ok(Some(member)) if !member.email.is_empty() => member,
+        Ok(_) => {
+            warn!(
+                "Member Change: No email address of member {}",
+                data.member_id
+            );
+            return;
+        }
+        Err(e) => {
+            error!(
+                "Member Change: Failed to load member {}: {}",
+                data.member_id, e
+            );
+            return;
+        }
+    };
+    match email_service
+        .send_member_change_email(&member.email, &member.name(), &changes)
+        .await
+    {
+        Ok(_) => info!(
+            "Member Change: Told member {} about {} changed fields",
+            member.id,
+            changes.len()
+        ),
+        Err(e) => error!("Member Change: Failed to email member {}: {}", member.id, e),
+    }
+}
@@ -9,19 +9,48 @@ pub enum LoginResponseVariant {
     SingleUser(LoginResponse),
     #[serde(rename = "multiple")]
     MultipleUsers(MemberSelectionResponse),
+    /// The password was right, but the member has two-factor authentication enabled; send
+    /// the request again with `totp_code`
+    #[serde(rename = "two_factor_required")]
+    TwoFactorRequired(TwoFactorRequiredResponse),
 }
 
 #[derive(Debug, Serialize, Type)]
 pub struct MemberSelectionResponse {
-    pub success: bool,
     pub multiple: bool,
     pub users: Vec<UserResponse>,
     pub selection_token: String,
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Type)]
+pub struct TwoFactorRequiredResponse {
+    pub two_factor_required: bool,
+    pub message: String,
+}
+
+impl Default for TwoFactorRequiredResponse {
+    fn default() -> Self {
+        TwoFactorRequiredResponse {
+            two_factor_required: true,
+            message: "Bitte gib den Code aus deiner Authenticator-App ein.".to_string(),
+        }
+    }
+}
+
+/// Body of `POST /switch-member`
+#[derive(Debug, Deserialize, Type)]
+pub struct SwitchMemberRequest {
+    /// Another member with the email of the logged in one
+    pub member_id: String,
+    /// Current code of that member's authenticator app, if two-factor authentication is enabled
+    pub totp_code: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Type)]
 pub struct SelectMemberRequest {
     pub member_id: String,
     pub selection_token: Option<String>,
+    /// Current code of the member's authenticator app, if two-factor authentication is enabled
+    pub totp_code: Option<String>,
 }
@@ -0,0 +1,332 @@
+use crate::teable_throttle;
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the latency histogram buckets in seconds
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+/// Route label of requests no route matched (SPA pages, static files, unknown paths), so
+/// scanners can't blow up the number of series
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (le, count) in LATENCY_BUCKETS.iter().zip(self.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{le}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// By method, route and status
+    http_requests: BTreeMap<(String, String, u16), u64>,
+    /// By method and route
+    http_latency: BTreeMap<(String, String), Histogram>,
+    /// 429s of the governor rate limits, by route
+    rate_limited: BTreeMap<String, u64>,
+    /// Requests turned away by `load_shed`
+    shed_requests: u64,
+    /// By method and status, `error` if Teable couldn't be reached
+    teable_calls: BTreeMap<(String, String), u64>,
+    /// By method
+    teable_latency: BTreeMap<String, Histogram>,
+    emails_sent: u64,
+    email_failures: u64,
+    /// Calls of deprecated routes by method and route, with the time of the last one as
+    /// Unix timestamp
+    deprecated_requests: BTreeMap<(String, String), (u64, i64)>,
+    /// Outcome of the last integrity check and when it finished, as Unix timestamp
+    maintenance: Option<(bool, i64)>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Middleware counting requests and their latency by route pattern (`/api/dashboard/:year`),
+/// not by path, so the number of series stays bounded
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, |path| path.as_str())
+        .to_string();
+    let started = Instant::now();
+    let response = next.run(request).await;
+    record_request(method, route, response.status().as_u16(), started.elapsed());
+    response
+}
+
+fn record_request(method: String, route: String, status: u16, elapsed: Duration) {
+    let mut registry = registry().lock().unwrap();
+    if status == 429 {
+        *registry.rate_limited.entry(route.clone()).or_default() += 1;
+    }
+    *registry
+        .http_requests
+        .entry((method.clone(), route.clone(), status))
+        .or_default() += 1;
+    registry
+        .http_latency
+        .entry((method, route))
+        .or_default()
+        .observe(elapsed);
+}
+
+/// A request to Teable; `status` is `None` if it failed before a response arrived
+pub fn record_teable_call(method: &str, status: Option<u16>, elapsed: Duration) {
+    let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+    let mut registry = registry().lock().unwrap();
+    *registry
+        .teable_calls
+        .entry((method.to_string(), status))
+        .or_default() += 1;
+    registry
+        .teable_latency
+        .entry(method.to_string())
+        .or_default()
+        .observe(elapsed);
+}
+
+pub fn record_email(sent: bool) {
+    let mut registry = registry().lock().unwrap();
+    if sent {
+        registry.emails_sent += 1;
+    } else {
+        registry.email_failures += 1;
+    }
+}
+
+/// A request turned away by `load_shed` because too many were in flight
+pub fn record_shed_request() {
+    registry().lock().unwrap().shed_requests += 1;
+}
+
+/// A call of a route listed in `deprecation::DEPRECATED_ROUTES`
+pub fn record_deprecated_request(method: &str, route: &str) {
+    let mut registry = registry().lock().unwrap();
+    let (count, last_seen) = registry
+        .deprecated_requests
+        .entry((method.to_string(), route.to_string()))
+        .or_default();
+    *count += 1;
+    *last_seen = chrono::Utc::now().timestamp();
+}
+
+/// A finished run of the database maintenance
+pub fn record_maintenance(integrity_ok: bool, finished_at: chrono::DateTime<chrono::Utc>) {
+    registry().lock().unwrap().maintenance = Some((integrity_ok, finished_at.timestamp()));
+}
+
+/// All metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let registry = registry().lock().unwrap();
+    let mut out = String::new();
+
+    header(
+        &mut out,
+        "tsv_http_requests_total",
+        "counter",
+        "HTTP requests",
+    );
+    for ((method, route, status), count) in &registry.http_requests {
+        let _ = writeln!(
+            out,
+            "tsv_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{status}\"}} {count}",
+            escape(method),
+            escape(route)
+        );
+    }
+    header(
+        &mut out,
+        "tsv_http_request_duration_seconds",
+        "histogram",
+        "Latency of HTTP requests",
+    );
+    for ((method, route), histogram) in &registry.http_latency {
+        let labels = format!("method=\"{}\",route=\"{}\"", escape(method), escape(route));
+        histogram.render(&mut out, "tsv_http_request_duration_seconds", &labels);
+    }
+    header(
+        &mut out,
+        "tsv_rate_limit_rejections_total",
+        "counter",
+        "Requests rejected by the rate limits",
+    );
+    for (route, count) in &registry.rate_limited {
+        let _ = writeln!(
+            out,
+            "tsv_rate_limit_rejections_total{{route=\"{}\"}} {count}",
+            escape(route)
+        );
+    }
+    header(
+        &mut out,
+        "tsv_load_shed_requests_total",
+        "counter",
+        "Requests turned away because too many Teable-heavy requests were in flight",
+    );
+    let _ = writeln!(
+        out,
+        "tsv_load_shed_requests_total {}",
+        registry.shed_requests
+    );
+    header(
+        &mut out,
+        "tsv_deprecated_requests_total",
+        "counter",
+        "Calls of deprecated routes",
+    );
+    for ((method, route), (count, _)) in &registry.deprecated_requests {
+        let _ = writeln!(
+            out,
+            "tsv_deprecated_requests_total{{method=\"{}\",route=\"{}\"}} {count}",
+            escape(method),
+            escape(route)
+        );
+    }
+    header(
+        &mut out,
+        "tsv_deprecated_request_last_seen_seconds",
+        "gauge",
+        "When a deprecated route was last called",
+    );
+    for ((method, route), (_, last_seen)) in &registry.deprecated_requests {
+        let _ = writeln!(
+            out,
+            "tsv_deprecated_request_last_seen_seconds{{method=\"{}\",route=\"{}\"}} {last_seen}",
+            escape(method),
+            escape(route)
+        );
+    }
+
+    header(
+        &mut out,
+        "tsv_teable_requests_total",
+        "counter",
+        "Requests to the Teable API, including retries",
+    );
+    for ((method, status), count) in &registry.teable_calls {
+        let _ = writeln!(
+            out,
+            "tsv_teable_requests_total{{method=\"{method}\",status=\"{status}\"}} {count}"
+        );
+    }
+    header(
+        &mut out,
+        "tsv_teable_request_duration_seconds",
+        "histogram",
+        "Latency of Teable API requests",
+    );
+    for (method, histogram) in &registry.teable_latency {
+        let labels = format!("method=\"{method}\"");
+        histogram.render(&mut out, "tsv_teable_request_duration_seconds", &labels);
+    }
+    let throttles = teable_throttle::all_stats();
+    header(
+        &mut out,
+        "tsv_teable_rate_limited_total",
+        "counter",
+        "429 responses of the Teable API",
+    );
+    for (host, stats) in &throttles {
+        let _ = writeln!(
+            out,
+            "tsv_teable_rate_limited_total{{host=\"{}\"}} {}",
+            escape(host),
+            stats.rate_limited
+        );
+    }
+    header(
+        &mut out,
+        "tsv_teable_queued_requests",
+        "gauge",
+        "Teable requests held back by the throttle",
+    );
+    for (host, stats) in &throttles {
+        let _ = writeln!(
+            out,
+            "tsv_teable_queued_requests{{host=\"{}\"}} {}",
+            escape(host),
+            stats.queued
+        );
+    }
+
+    header(&mut out, "tsv_emails_sent_total", "counter", "Emails sent");
+    let _ = writeln!(out, "tsv_emails_sent_total {}", registry.emails_sent);
+    header(
+        &mut out,
+        "tsv_email_send_failures_total",
+        "counter",
+        "Emails the SMTP server didn't accept",
+    );
+    let _ = writeln!(
+        out,
+        "tsv_email_send_failures_total {}",
+        registry.email_failures
+    );
+
+    if let Some((integrity_ok, finished_at)) = registry.maintenance {
+        header(
+            &mut out,
+            "tsv_database_integrity_ok",
+            "gauge",
+            "1 if the last integrity check of SQLite found no problems",
+        );
+        let _ = writeln!(out, "tsv_database_integrity_ok {}", integrity_ok as u8);
+        header(
+            &mut out,
+            "tsv_database_maintenance_timestamp_seconds",
+            "gauge",
+            "When the last database maintenance finished",
+        );
+        let _ = writeln!(
+            out,
+            "tsv_database_maintenance_timestamp_seconds {finished_at}"
+        );
+    }
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
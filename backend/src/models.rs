@@ -1,20 +1,37 @@
+use crate::attachment::{WorkHourAttachment, MAX_NOTE_CHARS};
+use crate::validation;
 use serde::{Deserialize, Serialize};
 use specta::Type;
+use tracing::warn;
+use validator::Validate;
 
 // Request/Response models
-#[derive(Debug, Deserialize, Type)]
+#[derive(Debug, Deserialize, Type, Validate)]
 pub struct LoginRequest {
+    #[validate(length(max = 254, message = "Die E-Mail-Adresse ist zu lang."))]
     pub email: String,
+    #[validate(length(max = 1024, message = "Das Passwort ist zu lang."))]
     pub password: String,
+    /// Current code of the member's authenticator app, if two-factor authentication is enabled
+    pub totp_code: Option<String>,
 }
 
 #[derive(Debug, Serialize, Type)]
 pub struct LoginResponse {
-    pub success: bool,
     pub token: String,
     pub user: UserResponse,
 }
 
+/// Response of `POST /admin/impersonate/{member_id}`
+#[derive(Debug, Serialize, Type)]
+pub struct ImpersonationResponse {
+    /// Bearer token acting as `user`; it isn't refreshed and can't change how they log in
+    pub token: String,
+    /// Seconds until `token` expires
+    pub expires_in: u64,
+    pub user: UserResponse,
+}
+
 #[derive(Debug, Deserialize, Type)]
 #[allow(dead_code)]
 pub struct RegisterRequest {
@@ -23,8 +40,9 @@ pub struct RegisterRequest {
     pub password: String,
 }
 
-#[derive(Debug, Deserialize, Type)]
+#[derive(Debug, Deserialize, Type, Validate)]
 pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Bitte gib eine gültige E-Mail-Adresse an."))]
     pub email: String,
 }
 
@@ -43,18 +61,89 @@ pub struct UserResponse {
     pub email: String,
 }
 
-#[derive(Debug, Deserialize, Type)]
+/// Data of `GET /user`: the logged-in member and their session
+#[derive(Debug, Serialize, Type)]
+pub struct SessionResponse {
+    pub user: SessionUser,
+    /// Seconds until the bearer token expires
+    pub token_expires_in: Option<u64>,
+    /// Board member seeing the app as this member, for a banner in the frontend
+    pub impersonator: Option<String>,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct SessionUser {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub profile: SessionProfile,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct SessionProfile {
+    pub nachname: String,
+    pub vorname: String,
+    #[serde(rename = "teableId")]
+    pub teable_id: String,
+}
+
+#[derive(Debug, Deserialize, Type, Validate)]
 pub struct CreateWorkHourRequest {
     #[serde(rename = "Datum")]
+    #[validate(custom(function = "validation::work_hour_date"))]
     pub date: String,
     #[serde(rename = "Tätigkeit")]
+    #[validate(custom(function = "validation::work_hour_description"))]
     pub description: String,
     #[serde(rename = "Stunden", deserialize_with = "string_or_f64")]
+    #[validate(range(exclusive_min = 0.0, message = "Stunden müssen größer als 0 sein."))]
     pub hours: f64, // Frontend sends hours as string, need to convert
 }
 
-// Custom deserializer to handle string or f64 for hours
-fn string_or_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+/// Body of `POST /admin/arbeitsstunden`, for hours the board enters on behalf of a member,
+/// e.g. from a paper list
+#[derive(Debug, Deserialize, Type, Validate)]
+pub struct AdminCreateWorkHourRequest {
+    /// Teable record ID of the member the hours belong to
+    #[validate(length(min = 1, max = 64, message = "Ungültige Mitglieds-ID."))]
+    pub member_id: String,
+    #[serde(rename = "Datum")]
+    #[validate(custom(function = "validation::work_hour_date"))]
+    pub date: String,
+    #[serde(rename = "Tätigkeit")]
+    #[validate(custom(function = "validation::work_hour_description"))]
+    pub description: String,
+    #[serde(rename = "Stunden", deserialize_with = "string_or_f64")]
+    #[validate(range(exclusive_min = 0.0, message = "Stunden müssen größer als 0 sein."))]
+    pub hours: f64,
+    /// Added to the entry's note after "Erfasst durch …", e.g. which list the hours are from
+    pub note: Option<String>,
+}
+
+impl AdminCreateWorkHourRequest {
+    /// The note stored on the entry, naming the board member who entered it, or the message
+    /// for the board member
+    pub fn note(&self, recorded_by: &str) -> Result<String, &'static str> {
+        let mut note = format!("Erfasst durch {recorded_by}");
+        if let Some(extra) = self
+            .note
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+        {
+            note.push_str(": ");
+            note.push_str(extra);
+        }
+        if note.chars().count() > MAX_NOTE_CHARS {
+            return Err("Die Notiz darf höchstens 1000 Zeichen lang sein.");
+        }
+        Ok(note)
+    }
+}
+
+// Custom deserializer to handle string or f64 for hours; strings follow `utils::parse_hours`
+// so German input like "2,5 Std" is accepted
+pub(crate) fn string_or_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -74,7 +163,7 @@ where
         where
             E: de::Error,
         {
-            value.parse().map_err(de::Error::custom)
+            crate::utils::parse_hours(value).map_err(de::Error::custom)
         }
 
         fn visit_f64<E>(self, value: f64) -> Result<f64, E>
@@ -102,12 +191,116 @@ where
     deserializer.deserialize_any(StringOrF64Visitor)
 }
 
+/// Entry of a bulk request; `Mitglied_id` defaults to the logged-in member
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct BulkWorkHourEntry {
+    #[serde(rename = "Mitglied_id")]
+    pub member_id: Option<String>,
+    #[serde(rename = "Datum")]
+    pub date: String,
+    #[serde(rename = "Tätigkeit")]
+    pub description: String,
+    #[serde(rename = "Stunden", deserialize_with = "string_or_f64")]
+    pub hours: f64,
+}
+
+/// Also the payload of work hour import jobs
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct BulkCreateWorkHoursRequest {
+    pub entries: Vec<BulkWorkHourEntry>,
+    /// From the response to the same request; only its failed entries are processed again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_token: Option<String>,
+}
+
+/// Item of a bulk work hour result
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct BulkWorkHourItem {
+    pub member_id: String,
+    /// ID of the created entry
+    pub id: Option<String>,
+}
+
+/// Query of the calendar feed; calendar apps can't send an Authorization header
+#[derive(Debug, Deserialize)]
+pub struct CalendarFeedQuery {
+    pub token: String,
+}
+
+/// Query of a signed file download URL
+#[derive(Debug, Deserialize)]
+pub struct FileDownloadQuery {
+    /// Unix timestamp
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Subscription link of a member's calendar feed
+#[derive(Debug, Serialize, Type)]
+pub struct CalendarFeedLink {
+    pub token: String,
+    pub url: String,
+}
+
+/// Signed, short-lived download URL of a stored file
+#[derive(Debug, Serialize, Type)]
+pub struct FileLink {
+    pub url: String,
+    /// RFC 3339
+    pub expires_at: String,
+}
+
+/// A file stored by `PUT /api/files/{key}`
+#[derive(Debug, Serialize, Type)]
+pub struct StoredFileInfo {
+    pub key: String,
+    /// Bytes
+    pub size: u64,
+}
+
+/// A work hour entry as created or updated by a member or the board
 #[derive(Debug, Serialize, Type)]
 pub struct WorkHourResponse {
     pub id: String,
+    /// Name of the member the entry belongs to
+    pub user: Option<String>,
     pub date: String,
     pub description: String,
+    /// Same as `duration_hours`, which older clients read
+    pub hours: f64,
     pub duration_hours: f64,
+    pub event_id: Option<String>,
+    pub event_title: Option<String>,
+    /// Accepted during a Teable outage, see `WorkHourEntry::pending_sync`
+    pub pending_sync: bool,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct WorkHourNote {
+    pub note: Option<String>,
+}
+
+/// All attachments of an entry after adding or removing one
+#[derive(Debug, Serialize, Type)]
+pub struct WorkHourAttachments {
+    pub attachments: Vec<WorkHourAttachment>,
+}
+
+/// Records of a Teable view configured in `ADMIN_VIEWS`
+#[derive(Debug, Serialize, Type)]
+pub struct AdminViewRecords {
+    pub view: String,
+    /// Only these fields are set on the records, in this order
+    pub fields: Vec<String>,
+    pub records: Vec<serde_json::Value>,
+}
+
+/// A work hour entry after the board approved or rejected it
+#[derive(Debug, Serialize, Type)]
+pub struct ReviewedWorkHour {
+    pub id: String,
+    pub status: WorkHourStatus,
+    pub comment: Option<String>,
 }
 
 // Teable API models
@@ -134,12 +327,51 @@ pub struct Member {
     pub birth_date: String,
     #[serde(rename = "Eintrittsdatum")]
     pub join_date: Option<String>,
+    /// Annual membership fee in euros
+    #[serde(rename = "Beitrag")]
+    pub contribution: Option<f64>,
+    #[serde(rename = "Beitrag bezahlt", default)]
+    pub contribution_paid: bool,
+    /// Date of the SEPA direct debit, YYYY-MM-DD
+    #[serde(rename = "SEPA-Einzug")]
+    pub sepa_date: Option<String>,
+    /// Hours the board set for the member, replacing the ones by age and entry date
+    #[serde(rename = "Sollstunden")]
+    pub required_hours: Option<f64>,
 }
 
 impl Member {
     pub fn name(&self) -> String {
         format!("{} {}", self.first_name, self.last_name)
     }
+
+    /// Members without a fee on record show up as unknown rather than open
+    pub fn dues_status(&self) -> DuesStatus {
+        match self.contribution {
+            _ if self.contribution_paid => DuesStatus::Paid,
+            Some(amount) if amount <= 0.0 => DuesStatus::Paid,
+            Some(_) => DuesStatus::Open,
+            None => DuesStatus::Unknown,
+        }
+    }
+}
+
+/// Payment state of the annual membership fee
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub enum DuesStatus {
+    #[serde(rename = "bezahlt")]
+    Paid,
+    #[serde(rename = "offen")]
+    Open,
+    #[serde(rename = "unbekannt")]
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct DuesResponse {
+    pub status: DuesStatus,
+    pub amount: Option<f64>,
+    pub sepa_date: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -163,6 +395,20 @@ pub struct WorkHour {
     pub description: Option<String>,
     #[serde(rename = "Stunden")] // This field stores hours as a floating point number
     pub duration_hours: Option<f64>,
+    #[serde(rename = "Status")]
+    pub status: Option<String>,
+    #[serde(rename = "Prüfkommentar")]
+    pub review_comment: Option<String>,
+    /// Set on correction entries: ID of the entry whose hours they reduce
+    #[serde(rename = "Korrektur_von")]
+    pub corrects: Option<String>,
+    #[serde(rename = "Notiz")]
+    pub note: Option<String>,
+    #[serde(rename = "Anhänge", default)]
+    pub attachments: Vec<WorkHourAttachment>,
+    /// Work event the hours were worked at
+    #[serde(rename = "Arbeitseinsatz_id")]
+    pub event_id: Option<String>,
 }
 
 impl WorkHour {
@@ -184,13 +430,73 @@ impl WorkHour {
     }
 }
 
+/// Approval state of a work hour entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub enum WorkHourStatus {
+    #[serde(rename = "eingereicht")]
+    Submitted,
+    #[serde(rename = "genehmigt")]
+    Approved,
+    #[serde(rename = "abgelehnt")]
+    Rejected,
+}
+
+impl WorkHourStatus {
+    /// Value stored in the Teable "Status" field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkHourStatus::Submitted => "eingereicht",
+            WorkHourStatus::Approved => "genehmigt",
+            WorkHourStatus::Rejected => "abgelehnt",
+        }
+    }
+
+    /// Parses the Teable "Status" field. Entries created before the approval
+    /// workflow existed have no status and count as approved. Anything else, e.g. a
+    /// mistyped status set in Teable, stays pending until the board reviews it.
+    pub fn from_teable(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("genehmigt") => WorkHourStatus::Approved,
+            Some("eingereicht") => WorkHourStatus::Submitted,
+            Some("abgelehnt") => WorkHourStatus::Rejected,
+            Some(_) => {
+                warn!(
+                    "Work Hours: Unknown status {:?}, treating the entry as submitted",
+                    value.unwrap_or_default()
+                );
+                WorkHourStatus::Submitted
+            }
+        }
+    }
+
+    /// Only approved hours count toward dashboard totals
+    pub fn counts_toward_total(&self) -> bool {
+        matches!(self, WorkHourStatus::Approved)
+    }
+}
+
+#[derive(Debug, Deserialize, Type)]
+pub struct ReviewWorkHourRequest {
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct ReviewQueueEntry {
+    pub member_id: Option<String>,
+    pub member_name: String,
+    pub entry: WorkHourEntry,
+    /// Title of the work event the entry is linked to
+    pub event_title: Option<String>,
+}
+
 // Dashboard models
 #[derive(Debug, Serialize, Type)]
 pub struct DashboardResponse {
-    pub success: bool,
     pub family: Option<FamilyData>,
     pub personal: Option<PersonalData>,
     pub year: i32,
+    /// Set when Teable is unreachable and the member's last dashboard is served instead
+    pub cached_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Type)]
@@ -209,6 +515,7 @@ pub struct FamilyData {
 pub struct PersonalData {
     pub name: String,
     pub hours: f64,
+    pub pending_hours: f64,
     pub required: f64,
     pub entries: Vec<WorkHourEntry>,
     pub exemption_reason: Option<String>,
@@ -226,6 +533,7 @@ pub struct MemberContribution {
     pub id: String,
     pub name: String,
     pub hours: f64,
+    pub pending_hours: f64,
     pub required: f64,
     pub entries: Vec<WorkHourEntry>,
     pub exemption_reason: Option<String>,
@@ -240,7 +548,22 @@ pub struct WorkHourEntry {
     pub description: String,
     #[serde(rename = "Stunden")]
     pub duration_hours: f64, // Now represents hours with German field name
+    pub status: WorkHourStatus,
+    pub review_comment: Option<String>,
+    /// Accepted during a Teable outage and not yet written to Teable
+    pub pending_sync: bool,
+    /// Why replaying a queued entry was stopped, e.g. a conflicting entry on the same date
+    pub sync_conflict: Option<String>,
+    /// Set on corrections made by the board: the entry whose hours this one (with negative
+    /// hours) reduces
+    pub corrects: Option<String>,
+    /// The member's note, e.g. which courts were swept
+    pub note: Option<String>,
+    /// Photos or documents as proof of the work
+    pub attachments: Vec<WorkHourAttachment>,
+    /// Work event the entry belongs to, linked when it was recorded
+    pub event_id: Option<String>,
 }
 
-#[allow(unused_imports)] // These are used in main.rs via re-export
+#[allow(unused_imports)] // These are used in server.rs via re-export
 pub use crate::member_selection::{MemberSelectionResponse, SelectMemberRequest};
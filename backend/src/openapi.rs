@@ -0,0 +1,1288 @@
+use crate::deprecation;
+use crate::error::ErrorCode;
+use crate::json_schema;
+use serde_json::{json, Map, Value};
+
+/// Who may call an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Public,
+    Member,
+    Board,
+}
+
+/// What a successful request answers with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Returns {
+    /// `ApiResponse` with only a `message`
+    Message,
+    /// `ApiResponse` with the named schema in `data`
+    Data(&'static str),
+    /// `ApiResponse` with a list of the named schema in `data`
+    List(&'static str),
+    /// Not wrapped in `ApiResponse`: a body of this content type, e.g. a CSV export
+    Raw(&'static str),
+}
+
+/// A route of the API as documented in the OpenAPI spec. `path` uses the router syntax
+/// (`:id`, `*key`) below `/api`, so entries can be compared with the routes in `server.rs`.
+#[derive(Debug, Clone, Copy)]
+pub struct Operation {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub tag: &'static str,
+    pub summary: &'static str,
+    pub access: Access,
+    /// Name of the JSON body schema in `components.schemas`
+    pub body: Option<&'static str>,
+    /// The body is `multipart/form-data` instead of JSON
+    pub multipart: bool,
+    /// The body is the file itself, a photo or PDF
+    pub file_body: bool,
+    /// Optional query parameters as name and description
+    pub query: &'static [(&'static str, &'static str)],
+    /// Accepts `?dry_run=true`, see `dry_run.rs`
+    pub dry_run: bool,
+    /// Path parameters are numbers (years, SQLite IDs) instead of Teable record IDs
+    pub numeric_params: bool,
+    pub returns: Returns,
+}
+
+impl Operation {
+    const fn new(
+        method: &'static str,
+        path: &'static str,
+        tag: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        Operation {
+            method,
+            path,
+            tag,
+            summary,
+            access: Access::Member,
+            body: None,
+            multipart: false,
+            file_body: false,
+            query: &[],
+            dry_run: false,
+            numeric_params: false,
+            returns: Returns::Message,
+        }
+    }
+
+    const fn get(path: &'static str, tag: &'static str, summary: &'static str) -> Self {
+        Operation::new("get", path, tag, summary)
+    }
+
+    const fn post(path: &'static str, tag: &'static str, summary: &'static str) -> Self {
+        Operation::new("post", path, tag, summary)
+    }
+
+    const fn put(path: &'static str, tag: &'static str, summary: &'static str) -> Self {
+        Operation::new("put", path, tag, summary)
+    }
+
+    const fn delete(path: &'static str, tag: &'static str, summary: &'static str) -> Self {
+        Operation::new("delete", path, tag, summary)
+    }
+
+    const fn public(mut self) -> Self {
+        self.access = Access::Public;
+        self
+    }
+
+    const fn board(mut self) -> Self {
+        self.access = Access::Board;
+        self
+    }
+
+    const fn body(mut self, schema: &'static str) -> Self {
+        self.body = Some(schema);
+        self
+    }
+
+    /// File upload; `schema` describes the form parts
+    const fn multipart(mut self, schema: &'static str) -> Self {
+        self.body = Some(schema);
+        self.multipart = true;
+        self
+    }
+
+    const fn file_body(mut self) -> Self {
+        self.file_body = true;
+        self
+    }
+
+    const fn query(mut self, query: &'static [(&'static str, &'static str)]) -> Self {
+        self.query = query;
+        self
+    }
+
+    const fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    const fn numeric_params(mut self) -> Self {
+        self.numeric_params = true;
+        self
+    }
+
+    /// `data` of the response is the schema `schema`
+    const fn data(mut self, schema: &'static str) -> Self {
+        self.returns = Returns::Data(schema);
+        self
+    }
+
+    /// `data` of the response is a list of `schema`
+    const fn list(mut self, schema: &'static str) -> Self {
+        self.returns = Returns::List(schema);
+        self
+    }
+
+    /// The response isn't JSON in `ApiResponse` but `content_type`
+    const fn raw(mut self, content_type: &'static str) -> Self {
+        self.returns = Returns::Raw(content_type);
+        self
+    }
+
+    /// Path in OpenAPI syntax, e.g. `/api/arbeitsstunden/{id}`
+    pub fn openapi_path(&self) -> String {
+        let segments: Vec<String> = self
+            .path
+            .split('/')
+            .map(|segment| match segment.strip_prefix([':', '*']) {
+                Some(name) => format!("{{{name}}}"),
+                None => segment.to_string(),
+            })
+            .collect();
+        format!("/api{}", segments.join("/"))
+    }
+
+    fn path_params(&self) -> impl Iterator<Item = &'static str> {
+        self.path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix([':', '*']))
+    }
+}
+
+const AUTH: &str = "Authentication";
+const WORK_HOURS: &str = "Work hours";
+const MEMBER: &str = "Member";
+const BOARD: &str = "Board";
+const CERTIFICATES: &str = "Certificates";
+const GUESTS: &str = "Guest fees";
+const EVENTS: &str = "Work events";
+const FILES: &str = "Files";
+const META: &str = "Meta";
+
+const SEASON: &[(&str, &str)] = &[("season", "Calendar year, defaults to the current one")];
+const LANG: (&str, &str) = ("lang", "`de` (default) or `en`");
+const SEASON_AND_LANG: &[(&str, &str)] = &[SEASON[0], LANG];
+const MESSAGE_LANG: &[(&str, &str)] = &[(
+    "lang",
+    "`de` (default) or `en` messages of rejected passwords",
+)];
+const SURVEY_TOKEN: &[(&str, &str)] = &[("token", "Token from the emailed survey link")];
+const PAGE: &[(&str, &str)] = &[
+    ("limit", "Maximum number of entries"),
+    (
+        "before",
+        "Only entries older than this ID, for further pages",
+    ),
+];
+
+/// Every route of the API. New routes need an entry here; the tests check that each entry
+/// is routed.
+pub const OPERATIONS: &[Operation] = &[
+    Operation::get("/health", META, "Health check").public().raw("application/json"),
+    Operation::get(
+        "/health/ready",
+        META,
+        "Readiness check probing SQLite, Teable and the mail server",
+    )
+    .public().raw("application/json"),
+    Operation::get("/docs", META, "Swagger UI").public().raw("text/html"),
+    Operation::get("/docs/openapi.json", META, "This OpenAPI document").public().raw("application/json"),
+    Operation::get(
+        "/events/stream",
+        META,
+        "Server-sent events of the member, or of everyone for board members",
+    ).raw("text/event-stream"),
+    Operation::post(
+        "/webhooks/teable",
+        META,
+        "Changes of Teable records, signed with TEABLE_WEBHOOK_SECRET",
+    )
+    .public().data("WebhookReceipt"),
+    // Authentication
+    Operation::post("/login", AUTH, "Log in with email and password")
+        .public()
+        .body("LoginRequest").data("LoginResponseVariant"),
+    Operation::post("/register", AUTH, "Register (not implemented)")
+        .public()
+        .body("RegisterRequest")
+        .query(MESSAGE_LANG),
+    Operation::post(
+        "/select-member",
+        AUTH,
+        "Pick one of several members sharing an email after login",
+    )
+    .public()
+    .body("SelectMemberRequest").data("LoginResponseVariant"),
+    Operation::post(
+        "/switch-member",
+        AUTH,
+        "Switch to another member sharing the email, without entering the password again",
+    )
+    .body("SwitchMemberRequest").data("LoginResponseVariant"),
+    Operation::post("/forgot-password", AUTH, "Send a password reset email")
+        .public()
+        .body("ForgotPasswordRequest"),
+    Operation::post(
+        "/reset-password",
+        AUTH,
+        "Set a new password with a reset token",
+    )
+    .public()
+    .body("ResetPasswordRequest")
+    .query(MESSAGE_LANG),
+    Operation::post("/forgotPassword", AUTH, "Old name of `/forgot-password`")
+        .public()
+        .body("ForgotPasswordRequest"),
+    Operation::post("/resetPassword", AUTH, "Old name of `/reset-password`")
+        .public()
+        .body("ResetPasswordRequest")
+        .query(MESSAGE_LANG),
+    Operation::get(
+        "/verify-token",
+        AUTH,
+        "Check the bearer token and return the member, like `/user`",
+    ).data("SessionResponse"),
+    // Member
+    Operation::get("/user", MEMBER, "The logged-in member").data("SessionResponse"),
+    Operation::get(
+        "/write-freeze",
+        MEMBER,
+        "The write freeze in effect today and the one scheduled by the board",
+    ).data("WriteFreezeStatus"),
+    Operation::put(
+        "/user/profile",
+        MEMBER,
+        "Update the own phone number and address in Teable",
+    )
+    .body("ProfileUpdateRequest").data("MemberProfile"),
+    Operation::get(
+        "/dashboard/:year",
+        MEMBER,
+        "Work hours of the member and their family in a year",
+    )
+    .numeric_params().data("DashboardResponse"),
+    Operation::get(
+        "/statistics",
+        MEMBER,
+        "Hours, required hours and fulfillment of the member and their family per year",
+    )
+    .query(&[
+        ("from", "First year; defaults to three years before `to`"),
+        ("to", "Last year; defaults to the current year"),
+    ]).data("MemberStatistics"),
+    Operation::get("/dues", MEMBER, "Membership dues of the member's family").data("DuesResponse"),
+    Operation::get("/me/activity", MEMBER, "Activity feed of the member").query(PAGE).list("ActivityEntry"),
+    // Work hours
+    Operation::get(
+        "/arbeitsstunden",
+        WORK_HOURS,
+        "The member's entries of a year or range of days, sorted and paginated",
+    )
+    .query(&[
+        ("year", "Defaults to the current year; not together with `from`/`to`"),
+        ("from", "First day (`YYYY-MM-DD`); defaults to January 1st of the year of `to`"),
+        ("to", "Last day; defaults to the end of the current year"),
+        (
+            "sort",
+            "`date`, `hours` or `description`, descending with a leading `-`; default `-date`",
+        ),
+        ("page", "Page, starting at 1"),
+        ("per_page", "Entries per page, 50 by default and at most 200"),
+    ]).data("WorkHourPage"),
+    Operation::post("/arbeitsstunden", WORK_HOURS, "Create a work hour entry")
+        .body("CreateWorkHourRequest").data("WorkHourResponse"),
+    Operation::post(
+        "/arbeitsstunden/bulk",
+        WORK_HOURS,
+        "Create several entries, e.g. for family members",
+    )
+    .body("BulkCreateWorkHoursRequest").data("BulkResult"),
+    Operation::get(
+        "/arbeitsstunden/:id",
+        WORK_HOURS,
+        "A work hour entry of the member",
+    ).data("WorkHourEntry"),
+    Operation::put(
+        "/arbeitsstunden/:id",
+        WORK_HOURS,
+        "Update a work hour entry",
+    )
+    .body("CreateWorkHourRequest").data("WorkHourResponse"),
+    Operation::delete(
+        "/arbeitsstunden/:id",
+        WORK_HOURS,
+        "Delete a work hour entry; it stays restorable for 30 days",
+    ).data("WorkHourDeletion"),
+    Operation::post(
+        "/arbeitsstunden/:id/restore",
+        WORK_HOURS,
+        "Restore a deleted work hour entry",
+    ).data("WorkHourEntry"),
+    Operation::put(
+        "/arbeitsstunden/:id/note",
+        WORK_HOURS,
+        "Set or remove the note on an entry",
+    )
+    .body("WorkHourNoteRequest").data("WorkHourNote"),
+    Operation::post(
+        "/arbeitsstunden/:id/attachments",
+        WORK_HOURS,
+        "Attach a photo or PDF (at most 10 MB) to an entry as proof of the work",
+    )
+    .multipart("WorkHourAttachmentUpload").data("WorkHourAttachments"),
+    Operation::delete(
+        "/arbeitsstunden/:id/attachments/:attachment_id",
+        WORK_HOURS,
+        "Remove an attachment from an entry",
+    ).data("WorkHourAttachments"),
+    Operation::get(
+        "/arbeitsstunden/trash",
+        WORK_HOURS,
+        "Deleted entries that can still be restored",
+    ).data("Trash"),
+    Operation::get(
+        "/arbeitsstunden/suggestions",
+        WORK_HOURS,
+        "The board's suggestions for the activity description, most used first",
+    ).list("DescriptionSuggestion"),
+    Operation::get(
+        "/arbeitsstunden/calendar-token",
+        WORK_HOURS,
+        "Subscription URL of the member's calendar feed",
+    ).data("CalendarFeedLink"),
+    Operation::get(
+        "/arbeitsstunden/calendar.ics",
+        WORK_HOURS,
+        "iCalendar feed of the member's entries",
+    )
+    .public()
+    .query(&[("token", "Feed token from the subscription URL")]).raw("text/calendar"),
+    Operation::get(
+        "/arbeitsstunden/timesheet/:year/:month",
+        WORK_HOURS,
+        "Printable timesheet of a month with signature lines; `:month` is e.g. `05.pdf`",
+    )
+    .query(&[LANG]).raw("application/pdf"),
+    // Certificates
+    Operation::get(
+        "/me/certificate/:year",
+        CERTIFICATES,
+        "State of a certificate request; `2025.pdf` returns the approved certificate",
+    )
+    .query(&[LANG]).data("CertificateRequest"),
+    Operation::post(
+        "/me/certificate/:year",
+        CERTIFICATES,
+        "Request a certificate of the year's work hours",
+    )
+    .numeric_params().data("CertificateRequest"),
+    Operation::get(
+        "/admin/certificates",
+        CERTIFICATES,
+        "Certificate requests, oldest first",
+    )
+    .board()
+    .query(&[("status", "`requested` or `approved`")]).list("CertificateRequest"),
+    Operation::post(
+        "/admin/certificates/:id/approve",
+        CERTIFICATES,
+        "Approve a certificate",
+    )
+    .board()
+    .dry_run()
+    .numeric_params().data("CertificateSummary"),
+    // Family invitations
+    Operation::get(
+        "/family-invitations",
+        MEMBER,
+        "The member's family invitations, newest first",
+    ).list("FamilyInvitation"),
+    Operation::post(
+        "/family-invitations",
+        MEMBER,
+        "Ask the board for a separate login of a family member",
+    )
+    .body("CreateFamilyInvitationRequest").data("FamilyInvitation"),
+    Operation::get(
+        "/admin/family-invitations",
+        BOARD,
+        "Family invitations, newest first",
+    )
+    .board()
+    .query(&[("status", "`pending`, `approved` or `rejected`")]).list("FamilyInvitation"),
+    Operation::post(
+        "/admin/family-invitations/:id/approve",
+        BOARD,
+        "Create the account of a family invitation and send the invitation email",
+    )
+    .board()
+    .dry_run()
+    .numeric_params(),
+    Operation::post(
+        "/admin/family-invitations/:id/reject",
+        BOARD,
+        "Reject a family invitation",
+    )
+    .board()
+    .numeric_params(),
+    // Two-factor authentication
+    Operation::get(
+        "/2fa",
+        AUTH,
+        "Whether the member has two-factor authentication enabled",
+    ).data("TwoFactorStatus"),
+    Operation::post(
+        "/2fa/setup",
+        AUTH,
+        "Generate a TOTP secret and its otpauth URI for the authenticator app",
+    ).data("TwoFactorSetupResponse"),
+    Operation::post(
+        "/2fa/confirm",
+        AUTH,
+        "Enable two-factor authentication with a code of the new secret",
+    )
+    .body("TwoFactorCodeRequest"),
+    Operation::post(
+        "/2fa/disable",
+        AUTH,
+        "Disable two-factor authentication with a current code",
+    )
+    .body("TwoFactorCodeRequest"),
+    Operation::post(
+        "/admin/2fa/:member_id/reset",
+        BOARD,
+        "Remove a member's two-factor authentication, e.g. after a lost phone",
+    )
+    .board()
+    .dry_run(),
+    Operation::post(
+        "/admin/impersonate/:member_id",
+        BOARD,
+        "Get a 30-minute token to see the app as a member; changes made with it are audit-logged",
+    )
+    .board().data("ImpersonationResponse"),
+    Operation::put(
+        "/admin/write-freeze",
+        BOARD,
+        "Schedule a period in which members can't change work hours",
+    )
+    .board()
+    .body("SetWriteFreezeRequest").data("WriteFreeze"),
+    Operation::delete(
+        "/admin/write-freeze",
+        BOARD,
+        "Lift the scheduled write freeze",
+    )
+    .board(),
+    // Guest fees
+    Operation::get("/guests", GUESTS, "Guest sessions of the member").query(SEASON).data("GuestSessions"),
+    Operation::post("/guests", GUESTS, "Register a guest session")
+        .body("CreateGuestSessionRequest").data("GuestSession"),
+    Operation::get("/admin/guests/report", GUESTS, "Guest fees per member")
+        .board()
+        .query(SEASON).data("GuestFeeReport"),
+    Operation::get(
+        "/admin/guests/report.csv",
+        GUESTS,
+        "Guest fees per member as CSV",
+    )
+    .board()
+    .query(SEASON_AND_LANG).raw("text/csv"),
+    Operation::post("/admin/guests/:id/paid", GUESTS, "Mark a guest fee as paid")
+        .board()
+        .dry_run()
+        .numeric_params(),
+    // Work events
+    Operation::get(
+        "/events",
+        EVENTS,
+        "Upcoming work events and whether the member signed up",
+    ).list("EventOverview"),
+    Operation::post("/events/:id/signup", EVENTS, "Sign up for a work event").data("EventSignup"),
+    Operation::delete("/events/:id/signup", EVENTS, "Cancel a sign-up"),
+    Operation::post("/admin/events", EVENTS, "Publish a work event")
+        .board()
+        .body("CreateEventRequest")
+        .dry_run().data("WorkEvent"),
+    Operation::get(
+        "/admin/events/:id/signups",
+        EVENTS,
+        "Sign-ups of a work event",
+    )
+    .board().list("EventSignup"),
+    Operation::post(
+        "/admin/events/:id/complete",
+        EVENTS,
+        "Complete a work event and credit the helpers' hours",
+    )
+    .board()
+    .body("CompleteEventRequest")
+    .dry_run().data("EventCompletion"),
+    Operation::get(
+        "/admin/events/:id/hours",
+        EVENTS,
+        "Approved and pending hours of the entries linked to a work event",
+    )
+    .board().data("EventHours"),
+    Operation::get(
+        "/admin/events/:id/survey",
+        EVENTS,
+        "Aggregated answers of the survey sent to a work event's helpers",
+    )
+    .board().data("SurveyResults"),
+    Operation::get("/survey", EVENTS, "The survey behind an emailed link")
+        .public()
+        .query(SURVEY_TOKEN).data("SurveyInvitation"),
+    Operation::post(
+        "/survey",
+        EVENTS,
+        "Rate the organization of a work event through an emailed link",
+    )
+    .public()
+    .body("SurveyAnswerRequest")
+    .query(SURVEY_TOKEN),
+    // Files
+    Operation::get(
+        "/file-url/*key",
+        FILES,
+        "Signed download URL of a file, valid for 15 minutes",
+    ).data("FileLink"),
+    Operation::get("/files/*key", FILES, "Download a file with a signed URL")
+        .public()
+        .query(&[
+            (
+                "expires",
+                "Expiry of the signature, seconds since the epoch",
+            ),
+            ("signature", "Signature from `/api/file-url`"),
+        ]).raw("application/octet-stream"),
+    Operation::put(
+        "/files/*key",
+        FILES,
+        "Upload a photo or PDF, replacing the file at the key",
+    )
+    .file_body()
+    .data("StoredFileInfo"),
+    Operation::delete("/files/*key", FILES, "Delete a file"),
+    // Board
+    Operation::get(
+        "/admin/arbeitsstunden/pending",
+        BOARD,
+        "Work hour entries awaiting review",
+    )
+    .board().list("ReviewQueueEntry"),
+    Operation::post(
+        "/admin/arbeitsstunden",
+        BOARD,
+        "Enter hours on behalf of a member, e.g. from a paper list",
+    )
+    .board()
+    .body("AdminCreateWorkHourRequest")
+    .dry_run().data("WorkHourEntry"),
+    Operation::put(
+        "/admin/arbeitsstunden/:id/approve",
+        BOARD,
+        "Approve a work hour entry",
+    )
+    .board()
+    .body("ReviewWorkHourRequest")
+    .dry_run().data("ReviewedWorkHour"),
+    Operation::put(
+        "/admin/arbeitsstunden/:id/reject",
+        BOARD,
+        "Reject a work hour entry",
+    )
+    .board()
+    .body("ReviewWorkHourRequest")
+    .dry_run().data("ReviewedWorkHour"),
+    Operation::post(
+        "/admin/arbeitsstunden/:id/correction",
+        BOARD,
+        "Book a correction with negative hours against an approved entry",
+    )
+    .board()
+    .body("CreateCorrectionRequest")
+    .dry_run().data("WorkHourEntry"),
+    Operation::post(
+        "/admin/arbeitsstunden/suggestions",
+        BOARD,
+        "Add a suggestion for the activity description",
+    )
+    .board()
+    .body("CreateSuggestionRequest").data("DescriptionSuggestion"),
+    Operation::delete(
+        "/admin/arbeitsstunden/suggestions/:id",
+        BOARD,
+        "Remove a suggestion; entries keep their description",
+    )
+    .numeric_params()
+    .board(),
+    Operation::get(
+        "/admin/consistency",
+        BOARD,
+        "Latest consistency report of login accounts and Teable members",
+    )
+    .board().data("ConsistencyReport"),
+    Operation::post("/admin/consistency/check", BOARD, "Run a consistency check").board().data("ConsistencyReport"),
+    Operation::post(
+        "/admin/consistency/accounts/:id/deactivate",
+        BOARD,
+        "Deactivate a login account without member",
+    )
+    .board()
+    .dry_run()
+    .numeric_params(),
+    Operation::post(
+        "/admin/consistency/members/:id/invite",
+        BOARD,
+        "Invite a member without login account",
+    )
+    .board()
+    .dry_run(),
+    Operation::post(
+        "/admin/password-rotations",
+        BOARD,
+        "Force all members to set a new password",
+    )
+    .board()
+    .dry_run().data("StartedRotation"),
+    Operation::get(
+        "/admin/password-resets",
+        BOARD,
+        "Logged password reset emails and whether the mail server took them, newest first",
+    )
+    .board()
+    .query(&[
+        ("member_id", "Only emails for this member"),
+        (
+            "status",
+            "Only this status: `sent`, `bounced`, `failed` or `queued`",
+        ),
+        ("limit", "Maximum number of entries"),
+    ]).list("ResetRequestEntry"),
+    Operation::get(
+        "/admin/emails",
+        BOARD,
+        "Emails waiting in the outbox for a retry or given up, newest first",
+    )
+    .board()
+    .query(&[
+        ("status", "Only this status: `pending`, `sent` or `failed`"),
+        (
+            "limit",
+            "Maximum number of emails, 50 by default, at most 200",
+        ),
+    ]).list("OutboxEmail"),
+    Operation::post(
+        "/admin/password-resets/:id/resend",
+        BOARD,
+        "Send a new reset link to the current address of the member or account",
+    )
+    .numeric_params()
+    .board().data("ResendResult"),
+    Operation::get(
+        "/admin/password-rotations/:id",
+        BOARD,
+        "Progress of a forced password rotation",
+    )
+    .board()
+    .numeric_params().data("PasswordRotationStats"),
+    Operation::post(
+        "/admin/jobs/work-hour-import",
+        BOARD,
+        "Import many work hours as a background job",
+    )
+    .board()
+    .body("BulkCreateWorkHoursRequest").data("Job"),
+    Operation::get(
+        "/admin/jobs/:id",
+        BOARD,
+        "Status, progress and failed items of a job",
+    )
+    .board()
+    .numeric_params().data("JobDetail"),
+    Operation::post("/admin/jobs/:id/cancel", BOARD, "Cancel a job")
+        .board()
+        .numeric_params().data("Job"),
+    Operation::get(
+        "/admin/stats/heatmap/:year",
+        BOARD,
+        "Hours by calendar week and weekday",
+    )
+    .numeric_params()
+    .board().data("WorkHourHeatmap"),
+    Operation::get(
+        "/admin/statistics/:year",
+        BOARD,
+        "Club-wide hours by activity and month, top contributors and fulfillment rate",
+    )
+    .numeric_params()
+    .board().data("ClubStatistics"),
+    Operation::get(
+        "/admin/analytics",
+        BOARD,
+        "Monthly use of dashboards, entry sources and event sign-ups",
+    )
+    .board()
+    .query(&[(
+        "months",
+        "Months up to the current one, 12 by default, at most 36",
+    )]).data("UsageAnalytics"),
+    Operation::get("/admin/rules", BOARD, "Work hour rules of a year")
+        .board()
+        .query(&[("year", "Defaults to the current year")]).data("YearRules"),
+    Operation::post(
+        "/admin/rules/simulate",
+        BOARD,
+        "What proposed rules would have changed last year",
+    )
+    .board()
+    .body("SimulateRulesRequest").data("RuleSimulation"),
+    Operation::get(
+        "/rules/changelog",
+        MEMBER,
+        "Changes of the club's rules, latest effective date first",
+    )
+    .query(&[(
+        "limit",
+        "Maximum number of changes, 50 by default, at most 200",
+    )]).list("RuleChange"),
+    Operation::post(
+        "/admin/rules/changelog",
+        BOARD,
+        "Add a change the work hour rules don't cover, e.g. a deadline or fee",
+    )
+    .board()
+    .body("CreateRuleChangeRequest").data("RuleChange"),
+    Operation::put(
+        "/admin/rules/changelog/:id",
+        BOARD,
+        "Set or remove the board's explanation of a change",
+    )
+    .numeric_params()
+    .board()
+    .body("RuleChangeNoteRequest").data("RuleChange"),
+    Operation::post(
+        "/admin/rules/changelog/:id/announce",
+        BOARD,
+        "Email a change to all members as a background job, once per change",
+    )
+    .numeric_params()
+    .board().data("Job"),
+    Operation::post(
+        "/admin/notify/year-summary/:year",
+        BOARD,
+        "Email every member their hours of a year and the charge for missing ones, as a throttled background job",
+    )
+    .numeric_params()
+    .board()
+    .dry_run()
+    .body("YearSummaryRequest").data("Job"),
+    Operation::get(
+        "/admin/views/:name",
+        BOARD,
+        "Records of a configured Teable view",
+    )
+    .board().data("AdminViewRecords"),
+    Operation::get("/admin/audit", BOARD, "Audit log, newest first")
+        .board()
+        .query(&[
+            ("actor_id", "Only changes by this member"),
+            ("action", "Only this action, e.g. `work_hour_created`"),
+            ("target_id", "Only changes of this record"),
+            ("from", "First day to include (`YYYY-MM-DD`)"),
+            ("to", "Last day to include (`YYYY-MM-DD`)"),
+            ("limit", "Maximum number of entries"),
+            (
+                "before",
+                "Only entries older than this ID, for further pages",
+            ),
+        ]).list("AuditEntry"),
+    Operation::get(
+        "/admin/teable/throttle",
+        BOARD,
+        "Rate limiting and back-pressure of the Teable client",
+    )
+    .board().data("ThrottleOverview"),
+    Operation::get(
+        "/admin/verify/:member_id/:year",
+        BOARD,
+        "Recount a member's approved hours from Teable and compare them with the dashboard",
+    )
+    .board().data("TotalsVerification"),
+    Operation::get(
+        "/admin/telemetry/preview",
+        BOARD,
+        "The anonymous usage report this instance sends when telemetry is enabled",
+    )
+    .board().data("TelemetryPreview"),
+    Operation::get(
+        "/admin/system/maintenance",
+        BOARD,
+        "Latest runs of the nightly database integrity check and VACUUM",
+    )
+    .board().data("MaintenanceOverview"),
+    Operation::get(
+        "/admin/member-aliases",
+        BOARD,
+        "Old record IDs of merged members and the records they point to",
+    )
+    .board().list("MemberAlias"),
+    Operation::post(
+        "/admin/member-aliases",
+        BOARD,
+        "Point the record ID of a merged member to the surviving record",
+    )
+    .board()
+    .body("CreateMemberAliasRequest").data("MemberAlias"),
+    Operation::put(
+        "/admin/members/:id",
+        BOARD,
+        "Change a member's family or required hours; the member is notified by email",
+    )
+    .board()
+    .body("MemberChangeRequest").data("MemberData"),
+];
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn date() -> Value {
+    json!({ "type": "string", "format": "date", "example": "2025-05-17" })
+}
+
+/// Hours are accepted as number or German text like "2,5"
+fn hours() -> Value {
+    json!({ "oneOf": [{ "type": "number" }, { "type": "string", "example": "2,5" }] })
+}
+
+fn object(properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required
+    })
+}
+
+/// Request bodies and the error response; the other types are converted from their Rust
+/// definitions, see `json_schema`
+fn written_schemas() -> Value {
+    let error_codes: Vec<Value> = ErrorCode::ALL
+        .iter()
+        .map(|code| serde_json::to_value(code).unwrap_or_default())
+        .collect();
+
+    json!({
+        "ErrorCode": { "type": "string", "enum": error_codes },
+        "ErrorResponse": object(
+            json!({
+                "success": { "type": "boolean", "enum": [false] },
+                "code": { "$ref": "#/components/schemas/ErrorCode" },
+                "error": { "type": "string", "description": "German message for display" },
+                "message": { "type": "string", "description": "Same as `error`" },
+                "request_id": { "type": "string", "description": "ID to quote when reporting the error" }
+            }),
+            &["success", "code", "error", "message"],
+        ),
+        "LoginRequest": object(
+            json!({
+                "email": string(),
+                "password": string(),
+                "totp_code": { "type": "string", "description": "Authenticator code, once the response asked for it" }
+            }),
+            &["email", "password"],
+        ),
+        "RegisterRequest": object(
+            json!({ "name": string(), "email": string(), "password": string() }),
+            &["name", "email", "password"],
+        ),
+        "SelectMemberRequest": object(
+            json!({
+                "member_id": string(),
+                "selection_token": { "type": "string", "description": "From the login response" },
+                "totp_code": { "type": "string", "description": "Authenticator code, once the response asked for it" }
+            }),
+            &["member_id", "selection_token"],
+        ),
+        "SwitchMemberRequest": object(
+            json!({
+                "member_id": string(),
+                "totp_code": { "type": "string", "description": "Authenticator code, once the response asked for it" }
+            }),
+            &["member_id"],
+        ),
+        "ForgotPasswordRequest": object(json!({ "email": string() }), &["email"]),
+        "ResetPasswordRequest": object(
+            json!({ "token": string(), "password": string() }),
+            &["token", "password"],
+        ),
+        "CreateWorkHourRequest": object(
+            json!({ "Datum": date(), "Tätigkeit": string(), "Stunden": hours() }),
+            &["Datum", "Tätigkeit", "Stunden"],
+        ),
+        "AdminCreateWorkHourRequest": object(
+            json!({
+                "member_id": string(),
+                "Datum": date(),
+                "Tätigkeit": string(),
+                "Stunden": hours(),
+                "note": { "type": ["string", "null"], "description": "Stored after \"Erfasst durch <board member>\" in the entry's note" }
+            }),
+            &["member_id", "Datum", "Tätigkeit", "Stunden"],
+        ),
+        "WorkHourNoteRequest": object(
+            json!({ "note": { "type": ["string", "null"], "description": "At most 1000 characters; empty or null removes the note" } }),
+            &[],
+        ),
+        "WorkHourAttachmentUpload": object(
+            json!({ "file": { "type": "string", "format": "binary", "description": "JPEG, PNG, WebP, HEIC or PDF" } }),
+            &["file"],
+        ),
+        "BulkWorkHourEntry": object(
+            json!({
+                "Mitglied_id": { "type": "string", "description": "Defaults to the logged-in member" },
+                "Datum": date(),
+                "Tätigkeit": string(),
+                "Stunden": hours()
+            }),
+            &["Datum", "Tätigkeit", "Stunden"],
+        ),
+        "BulkCreateWorkHoursRequest": object(
+            json!({
+                "entries": { "type": "array", "items": { "$ref": "#/components/schemas/BulkWorkHourEntry" } },
+                "retry_token": { "type": "string", "description": "From the response to the same request; only retryable failures are processed again" }
+            }),
+            &["entries"],
+        ),
+        "CreateCorrectionRequest": object(
+            json!({
+                "hours": { "type": "number", "description": "Negative, at most the hours still credited for the entry" },
+                "reason": { "type": "string", "description": "Stored as Tätigkeit of the correction" }
+            }),
+            &["hours", "reason"],
+        ),
+        "CreateSuggestionRequest": object(
+            json!({ "text": { "type": "string", "description": "At most 100 characters, unique ignoring case" } }),
+            &["text"],
+        ),
+        "ReviewWorkHourRequest": object(
+            json!({ "comment": { "type": "string", "description": "Shown to the member" } }),
+            &[],
+        ),
+        "WorkHourRules": object(
+            json!({
+                "required_hours": { "type": "number" },
+                "min_age": { "type": "integer" },
+                "max_age": { "type": "integer" },
+                "family_max_hours": { "type": ["number", "null"] }
+            }),
+            &["required_hours", "min_age", "max_age"],
+        ),
+        "SimulateRulesRequest": object(
+            json!({
+                "rules": { "$ref": "#/components/schemas/WorkHourRules" },
+                "fee_per_missing_hour": { "type": "number", "description": "In euros" }
+            }),
+            &["rules", "fee_per_missing_hour"],
+        ),
+        "CreateRuleChangeRequest": object(
+            json!({
+                "subject": { "type": "string", "description": "What changes, at most 100 characters" },
+                "old_value": string(),
+                "new_value": string(),
+                "effective_from": date(),
+                "note": { "type": "string", "description": "Explanation for the members" }
+            }),
+            &["subject", "effective_from"],
+        ),
+        "RuleChangeNoteRequest": object(
+            json!({ "note": { "type": "string", "description": "Empty to remove it" } }),
+            &[],
+        ),
+        "YearSummaryRequest": object(
+            json!({
+                "fee_per_missing_hour": { "type": "number", "description": "In euros, 0 for no charge" }
+            }),
+            &["fee_per_missing_hour"],
+        ),
+        "CreateGuestSessionRequest": object(
+            json!({ "Datum": date(), "Gast": string(), "Gebühr": hours() }),
+            &["Datum", "Gast", "Gebühr"],
+        ),
+        "CreateEventRequest": object(
+            json!({
+                "Titel": string(),
+                "Datum": date(),
+                "Beschreibung": string(),
+                "Helfer": { "type": "integer", "minimum": 1 },
+                "Stunden": hours()
+            }),
+            &["Titel", "Datum", "Helfer", "Stunden"],
+        ),
+        "EventAttendance": object(
+            json!({
+                "Mitglied_id": string(),
+                "Stunden": { "type": "number", "description": "Defaults to the event's hours" }
+            }),
+            &["Mitglied_id"],
+        ),
+        "CompleteEventRequest": object(
+            json!({
+                "attendance": { "type": "array", "items": { "$ref": "#/components/schemas/EventAttendance" } },
+                "retry_token": { "type": "string", "description": "From the response to the same request; only retryable failures are processed again" },
+                "send_survey": { "type": "boolean", "description": "Email the helpers a rating survey once the event is completed" }
+            }),
+            &["attendance"],
+        ),
+        "SurveyAnswerRequest": object(
+            json!({
+                "rating": { "type": "integer", "minimum": 1, "maximum": 5 },
+                "comment": { "type": "string", "description": "At most 500 characters" }
+            }),
+            &["rating"],
+        ),
+        "CreateMemberAliasRequest": object(
+            json!({
+                "old_id": { "type": "string", "description": "Record ID of the merged member" },
+                "new_id": { "type": "string", "description": "Record ID of the surviving member" }
+            }),
+            &["old_id", "new_id"],
+        ),
+        "CreateFamilyInvitationRequest": object(
+            json!({
+                "member_id": { "type": "string", "description": "Record ID of the family member who gets the login" },
+                "email": { "type": "string", "format": "email", "description": "Email address of the new login" }
+            }),
+            &["member_id", "email"],
+        ),
+        "SetWriteFreezeRequest": object(
+            json!({
+                "from": { "type": "string", "format": "date", "description": "First frozen day" },
+                "until": { "type": "string", "format": "date", "description": "Last frozen day" },
+                "reason": { "type": "string", "description": "Shown to members" }
+            }),
+            &["from", "until"],
+        ),
+        "ProfileUpdateRequest": object(
+            json!({
+                "phone": { "type": "string", "description": "Left out: unchanged, empty: cleared" },
+                "street": { "type": "string", "description": "Street and house number" },
+                "postal_code": { "type": "string" },
+                "city": { "type": "string" }
+            }),
+            &[],
+        ),
+        "TwoFactorCodeRequest": object(
+            json!({ "code": { "type": "string", "description": "6-digit code of the authenticator app" } }),
+            &["code"],
+        ),
+    })
+}
+
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": { "schema": { "$ref": "#/components/schemas/ErrorResponse" } }
+        }
+    })
+}
+
+/// `ApiResponse` with `data` of the operation, or its unwrapped body
+fn success_response(op: &Operation) -> Value {
+    let data = match op.returns {
+        Returns::Raw(content_type) => {
+            return json!({
+                "description": "Success",
+                "content": { content_type: {} }
+            });
+        }
+        Returns::Message => None,
+        Returns::Data(schema) => Some(json_schema::reference(schema)),
+        Returns::List(schema) => Some(json!({
+            "type": "array",
+            "items": json_schema::reference(schema)
+        })),
+    };
+    let data = match (data, op.dry_run) {
+        (data, false) => data,
+        // `data` holds the planned changes instead, next to what the handler reports
+        (data, true) => Some(json!({
+            "anyOf": [
+                data.unwrap_or_else(|| json!({ "type": "null" })),
+                object(
+                    json!({ "changes": json_schema::reference("PlannedChanges") }),
+                    &["changes"],
+                ),
+            ]
+        })),
+    };
+    let schema = match data {
+        Some(data) => json!({
+            "allOf": [
+                json_schema::reference("ApiResponse"),
+                object(json!({ "data": data }), &["data"]),
+            ]
+        }),
+        None => json_schema::reference("ApiResponse"),
+    };
+    json!({
+        "description": "Success",
+        "content": { "application/json": { "schema": schema } }
+    })
+}
+
+fn operation(op: &Operation) -> Value {
+    let mut parameters: Vec<Value> = op
+        .path_params()
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": if op.numeric_params { "integer" } else { "string" } }
+            })
+        })
+        .collect();
+    parameters.extend(op.query.iter().map(|(name, description)| {
+        json!({
+            "name": name,
+            "in": "query",
+            "required": false,
+            "description": description,
+            "schema": string()
+        })
+    }));
+    if op.dry_run {
+        parameters.push(json!({
+            "name": "dry_run",
+            "in": "query",
+            "required": false,
+            "description": "Return the planned changes without performing them",
+            "schema": { "type": "boolean" }
+        }));
+    }
+
+    let mut responses = Map::new();
+    responses.insert("200".to_string(), success_response(op));
+    if op.access != Access::Public {
+        responses.insert(
+            "401".to_string(),
+            error_response("Missing or invalid bearer token"),
+        );
+    }
+    if op.access == Access::Board {
+        responses.insert("403".to_string(), error_response("Not a board member"));
+    }
+    responses.insert("default".to_string(), error_response("Error"));
+
+    let mut operation = json!({
+        "tags": [op.tag],
+        "summary": op.summary,
+        "operationId": format!("{}{}", op.method, op.openapi_path().replace(['/', '{', '}', '.', '-'], "_")),
+        "parameters": parameters,
+        "responses": responses
+    });
+    if deprecation::find(op.method, op.path).is_some() {
+        operation["deprecated"] = json!(true);
+    }
+    if op.access != Access::Public {
+        operation["security"] = json!([{ "bearerAuth": [] }]);
+    }
+    if let Some(body) = op.body {
+        let content_type = if op.multipart {
+            "multipart/form-data"
+        } else {
+            "application/json"
+        };
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": {
+                content_type: { "schema": { "$ref": format!("#/components/schemas/{body}") } }
+            }
+        });
+    }
+    if op.file_body {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": { "image/*": {}, "application/pdf": {} }
+        });
+    }
+    operation
+}
+
+/// The OpenAPI 3.1 document served at `/api/docs/openapi.json`
+pub fn spec() -> Value {
+    // The written schemas of request bodies describe more than the types, e.g. hours given
+    // as German text, so they take precedence
+    let mut schemas = json_schema::component_schemas();
+    if let Value::Object(written) = written_schemas() {
+        schemas.extend(written);
+    }
+
+    let mut paths = Map::new();
+    for op in OPERATIONS {
+        let path = paths.entry(op.openapi_path()).or_insert_with(|| json!({}));
+        path[op.method] = operation(op);
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "TSV Tennis API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Work hours, dues and club administration. Errors carry a machine-readable `code`, see `ErrorCode`."
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" }
+            }
+        }
+    })
+}
+
+/// Swagger UI for the spec. Its assets are served with the frontend's, from
+/// `STATIC_DIR/assets/swagger-ui`, where the Docker build puts the pinned `swagger-ui-dist`;
+/// no CDN can change what runs next to the API's tokens.
+pub const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="de">
+<head>
+  <meta charset="utf-8">
+  <title>TSV Tennis API</title>
+  <link rel="stylesheet" href="/assets/swagger-ui/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="/assets/swagger-ui/swagger-ui-bundle.js"></script>
+  <script>
+    if (window.SwaggerUIBundle) {
+      window.ui = SwaggerUIBundle({ url: "/api/docs/openapi.json", dom_id: "#swagger-ui" });
+    } else {
+      document.getElementById("swagger-ui").innerHTML =
+        '<p>Swagger UI ist nicht installiert. Die Spezifikation liegt unter <a href="/api/docs/openapi.json">/api/docs/openapi.json</a>.</p>';
+    }
+  </script>
+</body>
+</html>
+"##;
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+/// Attempts in total, including the first one before the email was queued; with the backoff
+/// below the last retry is about an hour after the first attempt
+pub const MAX_ATTEMPTS: u32 = 6;
+
+/// Delay before the first retry, doubled for every further one
+const FIRST_RETRY_DELAY: Duration = Duration::minutes(2);
+
+/// Queued emails sent per run of the outbox worker
+pub const BATCH_SIZE: u32 = 20;
+
+/// How long finished emails stay in the outbox
+pub const RETENTION_DAYS: i64 = 30;
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+/// State of a queued email, stored in the `status` column of `email_outbox`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    /// Waiting for its next attempt
+    Pending,
+    /// Accepted by the mail server on a retry
+    Sent,
+    /// Refused permanently, or still failing after `MAX_ATTEMPTS` attempts
+    Failed,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Sent => "sent",
+            OutboxStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(value.to_string())).ok()
+    }
+}
+
+/// An email of the outbox as shown to the board. The bodies aren't shown, as they may
+/// contain reset links.
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct OutboxEmail {
+    pub id: i64,
+    pub recipient: String,
+    pub subject: String,
+    pub status: OutboxStatus,
+    pub attempts: u32,
+    /// What the mail server answered on the last failed attempt
+    pub last_error: Option<String>,
+    /// RFC 3339 timestamps; `next_attempt_at` is only set while pending
+    pub created_at: String,
+    pub next_attempt_at: Option<String>,
+    pub sent_at: Option<String>,
+}
+
+/// A pending email with everything needed to send it again
+#[derive(Debug, Clone)]
+pub struct QueuedEmail {
+    pub id: i64,
+    pub recipient: String,
+    /// Complete subject line, including the club name
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    pub attempts: u32,
+}
+
+/// Filters of `GET /admin/emails`
+#[derive(Debug, Default, Deserialize)]
+pub struct OutboxQuery {
+    pub status: Option<OutboxStatus>,
+    pub limit: Option<u32>,
+}
+
+impl OutboxQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+}
+
+/// When an email that failed `attempts` times is tried again; `None` once it has used up
+/// its attempts
+pub fn next_attempt_at(attempts: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    Some(now + FIRST_RETRY_DELAY * 2i32.pow(attempts.saturating_sub(1)))
+}
+
+/// Finished emails older than this are purged
+pub fn retention_cutoff(now: DateTime<Utc>) -> DateTime<Utc> {
+    now - Duration::days(RETENTION_DAYS)
+}
@@ -0,0 +1,284 @@
+use crate::config::Config;
+use crate::error::{AppError, ErrorCode};
+use crate::export::Locale;
+
+/// bcrypt ignores everything after the first 72 bytes
+const MAX_BYTES: usize = 72;
+/// Fewer different characters than this (`aaaaaaaaaa`, `1212121212`) count as guessable
+const MIN_DISTINCT_CHARS: usize = 4;
+
+/// Passwords found at the top of leaked password lists, plus obvious ones for a tennis club.
+/// Compared in lowercase, also with trailing digits and symbols removed (`Passwort2024!`).
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456",
+    "1234567",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "12345678910",
+    "0123456789",
+    "987654321",
+    "111111",
+    "000000",
+    "121212",
+    "123123",
+    "123321",
+    "654321",
+    "666666",
+    "696969",
+    "password",
+    "passwort",
+    "kennwort",
+    "geheim",
+    "passw0rd",
+    "p@ssw0rd",
+    "p@ssword",
+    "qwerty",
+    "qwertz",
+    "qwertyuiop",
+    "qwertzuiop",
+    "asdfgh",
+    "asdfghjkl",
+    "yxcvbnm",
+    "zxcvbnm",
+    "abc",
+    "abcdef",
+    "abcdefg",
+    "abcdefgh",
+    "iloveyou",
+    "ichliebedich",
+    "letmein",
+    "welcome",
+    "willkommen",
+    "admin",
+    "administrator",
+    "login",
+    "master",
+    "hallo",
+    "hallo123",
+    "hello",
+    "test",
+    "test123",
+    "schatz",
+    "schatzi",
+    "sonne",
+    "sommer",
+    "fussball",
+    "football",
+    "baseball",
+    "dragon",
+    "monkey",
+    "shadow",
+    "sunshine",
+    "princess",
+    "superman",
+    "batman",
+    "starwars",
+    "pokemon",
+    "michael",
+    "daniel",
+    "thomas",
+    "andreas",
+    "stefan",
+    "sabine",
+    "nicole",
+    "jennifer",
+    "arsenal",
+    "bayern",
+    "borussia",
+    "schalke",
+    "deutschland",
+    "berlin",
+    "hamburg",
+    "muenchen",
+    "tennis",
+    "tennisclub",
+    "tennisverein",
+    "tsv",
+    "tsvbue",
+    "tsvbuechenbach",
+    "verein",
+    "mitglied",
+    "arbeitsstunden",
+    "changeme",
+    "secret",
+    "trustno1",
+    "freedom",
+    "whatever",
+    "computer",
+    "internet",
+];
+
+/// Why a password was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordProblem {
+    TooShort {
+        min_length: usize,
+    },
+    TooLong,
+    /// On the list of common passwords, or made of very few different characters
+    Common,
+    /// Contains the part of the member's email address before the `@`
+    ContainsEmail,
+    /// Below `PASSWORD_MIN_SCORE`
+    TooWeak,
+}
+
+impl PasswordProblem {
+    pub fn message(&self, locale: Locale) -> String {
+        match (self, locale) {
+            (PasswordProblem::TooShort { min_length }, Locale::De) => {
+                format!("Das Passwort muss mindestens {min_length} Zeichen lang sein.")
+            }
+            (PasswordProblem::TooShort { min_length }, Locale::En) => {
+                format!("The password must be at least {min_length} characters long.")
+            }
+            (PasswordProblem::TooLong, Locale::De) => {
+                format!("Das Passwort darf höchstens {MAX_BYTES} Zeichen lang sein.")
+            }
+            (PasswordProblem::TooLong, Locale::En) => {
+                format!("The password must be at most {MAX_BYTES} characters long.")
+            }
+            (PasswordProblem::Common, Locale::De) => {
+                "Dieses Passwort ist zu verbreitet. Bitte wähle ein anderes.".to_string()
+            }
+            (PasswordProblem::Common, Locale::En) => {
+                "This password is too common. Please choose another one.".to_string()
+            }
+            (PasswordProblem::ContainsEmail, Locale::De) => {
+                "Das Passwort darf deine E-Mail-Adresse nicht enthalten.".to_string()
+            }
+            (PasswordProblem::ContainsEmail, Locale::En) => {
+                "The password must not contain your email address.".to_string()
+            }
+            (PasswordProblem::TooWeak, Locale::De) => {
+                "Das Passwort ist zu leicht zu erraten. Verwende mehr Zeichen oder mische Buchstaben, Ziffern und Sonderzeichen."
+                    .to_string()
+            }
+            (PasswordProblem::TooWeak, Locale::En) => {
+                "The password is too easy to guess. Use more characters or mix letters, digits and symbols."
+                    .to_string()
+            }
+        }
+    }
+
+    pub fn into_error(self, locale: Locale) -> AppError {
+        AppError::new(ErrorCode::WeakPassword, self.message(locale))
+    }
+}
+
+/// Rules for new passwords, applied when registering and resetting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    /// Minimum of `estimate_score` (0-4), off when `None`
+    pub min_score: Option<u8>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: 10,
+            min_score: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// `PASSWORD_MIN_LENGTH` and `PASSWORD_MIN_SCORE`
+    pub fn from_config(config: &Config) -> Self {
+        PasswordPolicy {
+            min_length: config.password_min_length,
+            min_score: config.password_min_score,
+        }
+    }
+
+    /// Checks a new password; `email` is the member's address if already known
+    pub fn check(&self, password: &str, email: Option<&str>) -> Result<(), PasswordProblem> {
+        if password.chars().count() < self.min_length {
+            return Err(PasswordProblem::TooShort {
+                min_length: self.min_length,
+            });
+        }
+        if password.len() > MAX_BYTES {
+            return Err(PasswordProblem::TooLong);
+        }
+        if is_common(password) {
+            return Err(PasswordProblem::Common);
+        }
+        let local_part = email
+            .and_then(|email| email.split('@').next())
+            .map(|local| local.trim().to_lowercase())
+            .filter(|local| local.chars().count() >= 3);
+        if local_part.is_some_and(|local| password.to_lowercase().contains(&local)) {
+            return Err(PasswordProblem::ContainsEmail);
+        }
+        if self
+            .min_score
+            .is_some_and(|min_score| estimate_score(password) < min_score)
+        {
+            return Err(PasswordProblem::TooWeak);
+        }
+        Ok(())
+    }
+}
+
+fn is_common(password: &str) -> bool {
+    let lower = password.to_lowercase();
+    let base = lower.trim_end_matches(|c: char| !c.is_alphabetic());
+    let mut distinct: Vec<char> = lower.chars().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+
+    COMMON_PASSWORDS.contains(&lower.as_str())
+        || (!base.is_empty() && COMMON_PASSWORDS.contains(&base))
+        || distinct.len() < MIN_DISTINCT_CHARS
+}
+
+/// Rough strength from 0 (trivial) to 4 (strong), on the scale of zxcvbn: the number of
+/// guesses is estimated from the character classes used and the length, where repeated
+/// characters and runs like `abc` or `123` don't count. Unlike zxcvbn it knows no
+/// dictionaries besides the list of common passwords.
+pub fn estimate_score(password: &str) -> u8 {
+    if is_common(password) {
+        return 0;
+    }
+
+    let chars: Vec<char> = password.chars().collect();
+    let classes = [
+        (chars.iter().any(|c| c.is_ascii_lowercase()), 26.0),
+        (chars.iter().any(|c| c.is_ascii_uppercase()), 26.0),
+        (chars.iter().any(|c| c.is_ascii_digit()), 10.0),
+        (
+            chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' '),
+            33.0,
+        ),
+        (chars.iter().any(|c| !c.is_ascii()), 100.0),
+    ];
+    let alphabet: f64 = classes
+        .iter()
+        .filter(|(used, _)| *used)
+        .map(|(_, size)| size)
+        .sum();
+
+    let effective_length = chars
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            let Some(previous) = i.checked_sub(1).map(|i| chars[i]) else {
+                return true;
+            };
+            let step = **c as i64 - previous as i64;
+            !(step == 0 || step.abs() == 1)
+        })
+        .count();
+
+    let log10_guesses = effective_length as f64 * alphabet.max(1.0).log10();
+    match log10_guesses {
+        g if g < 3.0 => 0,
+        g if g < 6.0 => 1,
+        g if g < 8.0 => 2,
+        g if g < 10.0 => 3,
+        _ => 4,
+    }
+}
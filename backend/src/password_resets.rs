@@ -0,0 +1,125 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+/// How long the board has to wait before resending the link to the same account
+pub const RESEND_COOLDOWN: Duration = Duration::minutes(5);
+
+/// How long reset emails stay in the log
+pub const RETENTION_DAYS: i64 = 90;
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+/// What became of a reset email, stored in the `status` column of `password_reset_requests`.
+/// Only what the mail server answers while sending is known; bounces it reports later by
+/// email are not seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Accepted by the mail server
+    Sent,
+    /// The address is malformed or was refused permanently, usually a typo in Teable
+    Bounced,
+    /// Not sent for another reason; a resend may work
+    Failed,
+    /// The mail server was unreachable; the email waits in the outbox for a retry, see
+    /// `GET /admin/emails`
+    Queued,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Sent => "sent",
+            DeliveryStatus::Bounced => "bounced",
+            DeliveryStatus::Failed => "failed",
+            DeliveryStatus::Queued => "queued",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(value.to_string())).ok()
+    }
+
+    /// The status of an error of `EmailService::send_email`: a recipient that can't be parsed
+    /// or a permanent (5xx) SMTP error means the address is wrong, everything else may pass
+    /// on the next try
+    pub fn of_error(error: &(dyn std::error::Error + 'static)) -> Self {
+        if error.is::<lettre::address::AddressError>() {
+            return DeliveryStatus::Bounced;
+        }
+        match error.downcast_ref::<lettre::transport::smtp::Error>() {
+            Some(e) if e.is_permanent() => DeliveryStatus::Bounced,
+            _ => DeliveryStatus::Failed,
+        }
+    }
+}
+
+/// A password reset email as shown to the board. The token isn't kept, only who got a link.
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct ResetRequestEntry {
+    pub id: i64,
+    /// Teable record of the member; for family accounts the member they belong to
+    pub member_id: String,
+    /// Address the email went to, as it was at the time
+    pub email: String,
+    /// Board member who resent the link, `None` if the member asked for it
+    pub resent_by: Option<String>,
+    pub status: DeliveryStatus,
+    /// What the mail server answered, if the email wasn't sent
+    pub error: Option<String>,
+    /// RFC 3339 timestamp
+    pub requested_at: String,
+}
+
+/// Answer to the board resending a reset link
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ResendResult {
+    /// Whether the mail server took the email; if not, the outbox retries it
+    pub sent: bool,
+    pub status: DeliveryStatus,
+    /// Address the link went to
+    pub email: String,
+}
+
+/// A reset email to log
+#[derive(Debug, Clone)]
+pub struct NewResetRequest<'a> {
+    /// Subject of the token, see `token_store::account_subject`
+    pub subject: &'a str,
+    pub member_id: &'a str,
+    pub email: &'a str,
+    pub resent_by: Option<&'a str>,
+    pub status: DeliveryStatus,
+    pub error: Option<String>,
+}
+
+/// Filters of `GET /admin/password-resets`; all of them are optional and combined with AND
+#[derive(Debug, Default, Deserialize)]
+pub struct ResetRequestQuery {
+    pub member_id: Option<String>,
+    pub status: Option<DeliveryStatus>,
+    pub limit: Option<u32>,
+}
+
+impl ResetRequestQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+}
+
+/// Entries logged before this point in time are purged
+pub fn retention_cutoff(now: DateTime<Utc>) -> DateTime<Utc> {
+    now - Duration::days(RETENTION_DAYS)
+}
+
+/// When the board may resend a link last sent at `last_sent_at`; `None` if it may right away
+pub fn resend_blocked_until(
+    last_sent_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let until = last_sent_at + RESEND_COOLDOWN;
+    (until > now).then_some(until)
+}
@@ -0,0 +1,127 @@
+use crate::database::Database;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Reset emails of a rotation that failed this often are given up on and counted as failed
+pub const MAX_EMAIL_ATTEMPTS: i32 = 3;
+
+/// Bearer tokens issued before the latest forced password rotation are rejected. Kept in
+/// memory so the auth middleware doesn't hit the database on every request.
+#[derive(Debug, Clone, Default)]
+pub struct SessionCutoff(Arc<AtomicI64>);
+
+impl SessionCutoff {
+    pub fn new(revoked_before: Option<DateTime<Utc>>) -> Self {
+        let cutoff = SessionCutoff::default();
+        if let Some(at) = revoked_before {
+            cutoff.revoke_before(at);
+        }
+        cutoff
+    }
+
+    pub fn revoke_before(&self, at: DateTime<Utc>) {
+        self.0.fetch_max(at.timestamp(), Ordering::Relaxed);
+    }
+
+    /// `issued_at` is the `iat` claim of a bearer token in seconds since the epoch. Tokens
+    /// of the second of the rotation itself are rejected, as they may have been issued before it.
+    pub fn allows(&self, issued_at: usize) -> bool {
+        issued_at as i64 > self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How long the revoked sessions are reused before they're read again; revocations of the
+/// admin CLI, which writes to the database directly, take effect within this time
+pub const REVOKED_SESSIONS_REFRESH: Duration = Duration::from_secs(30);
+
+/// Bearer tokens of single members issued before their sessions were revoked are rejected.
+/// Like `SessionCutoff` kept in memory, and re-read every `REVOKED_SESSIONS_REFRESH`.
+#[derive(Debug, Clone, Default)]
+pub struct RevokedSessions(Arc<RwLock<Option<RevokedSessionsSnapshot>>>);
+
+#[derive(Debug)]
+struct RevokedSessionsSnapshot {
+    loaded_at: Instant,
+    /// Member ID to the time before which its tokens are invalid, in seconds since the epoch
+    revoked_before: HashMap<String, i64>,
+}
+
+impl RevokedSessions {
+    /// Whether a token of the member issued at `issued_at` (seconds since the epoch) is still
+    /// valid. Fails if the revocations are due to be read and the database can't be reached.
+    pub async fn allows(
+        &self,
+        database: &Database,
+        member_id: &str,
+        issued_at: usize,
+    ) -> Result<bool, sqlx::Error> {
+        let cached = self
+            .0
+            .read()
+            .expect("Revoked sessions lock poisoned")
+            .as_ref()
+            .filter(|snapshot| snapshot.loaded_at.elapsed() < REVOKED_SESSIONS_REFRESH)
+            .map(|snapshot| snapshot.revoked_before.get(member_id).copied());
+        let revoked_before = match cached {
+            Some(revoked_before) => revoked_before,
+            None => {
+                let revoked_before: HashMap<String, i64> = database
+                    .get_revoked_sessions()
+                    .await?
+                    .into_iter()
+                    .map(|(member_id, at)| (member_id, at.timestamp()))
+                    .collect();
+                let found = revoked_before.get(member_id).copied();
+                *self.0.write().expect("Revoked sessions lock poisoned") =
+                    Some(RevokedSessionsSnapshot {
+                        loaded_at: Instant::now(),
+                        revoked_before,
+                    });
+                found
+            }
+        };
+        // As with `SessionCutoff`, the second of the revocation itself counts as before it
+        Ok(revoked_before.is_none_or(|before| issued_at as i64 > before))
+    }
+}
+
+/// A queued reset email of a forced password rotation
+#[derive(Debug, Clone)]
+pub struct RotationEmail {
+    pub id: i64,
+    pub rotation_id: i64,
+    pub email: String,
+    pub attempts: i32,
+}
+
+/// A forced password rotation that was just started
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct StartedRotation {
+    pub id: i64,
+    /// Active accounts flagged for a mandatory reset
+    pub accounts: u32,
+}
+
+/// Progress of a forced password rotation
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct PasswordRotationStats {
+    pub id: i64,
+    /// Board member who started the rotation
+    pub started_by: String,
+    /// RFC 3339 timestamps; `completed_at` is set once no reset email is pending anymore
+    pub started_at: String,
+    pub completed_at: Option<String>,
+    /// Active accounts flagged for a mandatory reset
+    pub accounts: u32,
+    pub emails_sent: u32,
+    /// Emails given up on after `MAX_EMAIL_ATTEMPTS` attempts
+    pub emails_failed: u32,
+    pub emails_pending: u32,
+    /// Flagged accounts that have set a new password since
+    pub passwords_reset: u32,
+}
@@ -0,0 +1,239 @@
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+/// A baseline JPEG, embedded as is since PDF viewers decode JPEG themselves
+#[derive(Debug, Clone)]
+pub struct JpegImage {
+    pub width: u16,
+    pub height: u16,
+    /// 1 for grayscale, 3 for RGB, 4 for CMYK
+    pub components: u8,
+    pub data: Vec<u8>,
+}
+
+impl JpegImage {
+    /// Reads the size from the first start-of-frame marker; `None` if `data` is no JPEG
+    pub fn parse(data: Vec<u8>) -> Option<Self> {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return None;
+        }
+        let mut pos = 2;
+        while pos + 4 <= data.len() {
+            if data[pos] != 0xFF {
+                return None;
+            }
+            let marker = data[pos + 1];
+            let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            // SOF0..SOF15 except DHT (C4), JPG (C8) and DAC (CC)
+            if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                let frame = data.get(pos + 4..pos + 10)?;
+                let height = u16::from_be_bytes([frame[1], frame[2]]);
+                let width = u16::from_be_bytes([frame[3], frame[4]]);
+                let components = frame[5];
+                if width == 0 || height == 0 || !matches!(components, 1 | 3 | 4) {
+                    return None;
+                }
+                return Some(JpegImage {
+                    width,
+                    height,
+                    components,
+                    data,
+                });
+            }
+            pos += 2 + length;
+        }
+        None
+    }
+
+    fn color_space(&self) -> &'static str {
+        match self.components {
+            1 => "/DeviceGray",
+            4 => "/DeviceCMYK",
+            _ => "/DeviceRGB",
+        }
+    }
+}
+
+/// Breaks text into lines of at most `max_chars`, at spaces
+pub fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// `#rrggbb` or `#rgb` as PDF color components; black if invalid
+pub fn parse_color(hex: &str) -> (f32, f32, f32) {
+    let hex = hex.trim_start_matches('#');
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).unwrap_or(0) as f32 / 255.0;
+    match hex.len() {
+        6 => (
+            channel(&hex[0..2]),
+            channel(&hex[2..4]),
+            channel(&hex[4..6]),
+        ),
+        3 => {
+            let double = |i: usize| channel(&hex[i..=i].repeat(2));
+            (double(0), double(1), double(2))
+        }
+        _ => (0.0, 0.0, 0.0),
+    }
+}
+
+/// The standard Helvetica fonts, which PDF viewers have built in
+#[derive(Clone, Copy)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+/// Drawing operators of the page's content stream
+#[derive(Default)]
+pub struct PageContent {
+    pub bytes: Vec<u8>,
+}
+
+impl PageContent {
+    pub fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        let font = match font {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        };
+        self.bytes
+            .extend_from_slice(format!("BT /{font} {size} Tf {x:.1} {y:.1} Td (").as_bytes());
+        self.bytes.extend(encode_text(text));
+        self.bytes.extend_from_slice(b") Tj ET\n");
+    }
+
+    pub fn fill_color(&mut self, r: f32, g: f32, b: f32) {
+        self.bytes
+            .extend_from_slice(format!("{r:.3} {g:.3} {b:.3} rg\n").as_bytes());
+    }
+
+    pub fn stroke_color(&mut self, r: f32, g: f32, b: f32) {
+        self.bytes
+            .extend_from_slice(format!("{r:.3} {g:.3} {b:.3} RG\n").as_bytes());
+    }
+
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32) {
+        self.bytes.extend_from_slice(
+            format!("{width} w {x1:.1} {y1:.1} m {x2:.1} {y2:.1} l S\n").as_bytes(),
+        );
+    }
+
+    pub fn image(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.bytes.extend_from_slice(
+            format!("q {width:.1} 0 0 {height:.1} {x:.1} {y:.1} cm /Im1 Do Q\n").as_bytes(),
+        );
+    }
+}
+
+/// Encodes text for a PDF string in WinAnsiEncoding, escaping delimiters; characters
+/// outside the encoding become `?`
+fn encode_text(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                bytes.push(b'\\');
+                c as u8
+            }
+            ' '..='~' => c as u8,
+            '\u{A0}'..='\u{FF}' => c as u32 as u8,
+            '€' => 0x80,
+            '„' => 0x84,
+            '…' => 0x85,
+            '–' => 0x96,
+            '—' => 0x97,
+            '“' => 0x93,
+            '”' => 0x94,
+            '‘' => 0x91,
+            '’' => 0x92,
+            _ => b'?',
+        };
+        bytes.push(byte);
+    }
+    bytes
+}
+
+/// Assembles a single A4 page PDF from its content stream; `image` is drawn as `/Im1`
+pub fn write_pdf(content: &[u8], image: Option<&JpegImage>) -> Vec<u8> {
+    let font = |name: &str| {
+        format!("<< /Type /Font /Subtype /Type1 /BaseFont /{name} /Encoding /WinAnsiEncoding >>")
+            .into_bytes()
+    };
+    let image_resource = if image.is_some() {
+        " /XObject << /Im1 7 0 R >>"
+    } else {
+        ""
+    };
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] /Resources << /Font << /F1 5 0 R /F2 6 0 R >>{image_resource} >> /Contents 4 0 R >>"
+        )
+        .into_bytes(),
+        stream(format!("<< /Length {} >>", content.len()), content),
+        font("Helvetica"),
+        font("Helvetica-Bold"),
+    ];
+    if let Some(image) = image {
+        objects.push(stream(
+            format!(
+                "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {} /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>",
+                image.width,
+                image.height,
+                image.color_space(),
+                image.data.len()
+            ),
+            &image.data,
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+fn stream(dictionary: String, data: &[u8]) -> Vec<u8> {
+    let mut object = dictionary.into_bytes();
+    object.extend_from_slice(b"\nstream\n");
+    object.extend_from_slice(data);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
@@ -0,0 +1,117 @@
+use crate::schema::{FieldNames, MemberField, TeableField};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use specta::Type;
+
+/// Teable fields of the members table a member may change themselves; everything else
+/// (name, email, fee) is kept by the board
+pub const PROFILE_FIELDS: [MemberField; 4] = [
+    MemberField::Phone,
+    MemberField::Street,
+    MemberField::PostalCode,
+    MemberField::City,
+];
+
+const MAX_PHONE_CHARS: usize = 30;
+/// Fewer digits can't be a reachable number
+const MIN_PHONE_DIGITS: usize = 6;
+const MAX_POSTAL_CODE_CHARS: usize = 10;
+const MAX_ADDRESS_CHARS: usize = 100;
+
+/// Body of `PUT /user/profile`. Fields left out stay as they are, empty strings clear them.
+#[derive(Debug, Default, Deserialize, Type)]
+pub struct ProfileUpdateRequest {
+    pub phone: Option<String>,
+    pub street: Option<String>,
+    pub postal_code: Option<String>,
+    pub city: Option<String>,
+}
+
+/// Contact details of a member as stored in Teable
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Type)]
+pub struct MemberProfile {
+    pub phone: Option<String>,
+    pub street: Option<String>,
+    pub postal_code: Option<String>,
+    pub city: Option<String>,
+}
+
+impl MemberProfile {
+    /// Reads the profile from the `fields` of a Teable member record
+    pub fn from_fields(fields: &Value, names: &FieldNames) -> Self {
+        let field = |field: MemberField| {
+            fields[field.name(names)]
+                .as_str()
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        MemberProfile {
+            phone: field(MemberField::Phone),
+            street: field(MemberField::Street),
+            postal_code: field(MemberField::PostalCode),
+            city: field(MemberField::City),
+        }
+    }
+}
+
+impl ProfileUpdateRequest {
+    /// The Teable fields to write, or the message for the member if a value is implausible
+    pub fn to_fields(&self, names: &FieldNames) -> Result<Map<String, Value>, &'static str> {
+        let mut fields = Map::new();
+        let changes = [
+            (MemberField::Phone, &self.phone),
+            (MemberField::Street, &self.street),
+            (MemberField::PostalCode, &self.postal_code),
+            (MemberField::City, &self.city),
+        ];
+        for (field, value) in changes {
+            let Some(value) = value else {
+                continue;
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                fields.insert(field.name(names).to_string(), Value::Null);
+                continue;
+            }
+            match field {
+                MemberField::Phone if !is_valid_phone(value) => {
+                    return Err("Bitte gib eine gültige Telefonnummer an.")
+                }
+                MemberField::PostalCode if !is_valid_postal_code(value) => {
+                    return Err("Bitte gib eine gültige Postleitzahl an.")
+                }
+                _ if value.chars().count() > MAX_ADDRESS_CHARS
+                    || value.chars().any(char::is_control) =>
+                {
+                    return Err("Bitte gib eine gültige Adresse an.")
+                }
+                _ => {}
+            }
+            fields.insert(
+                field.name(names).to_string(),
+                Value::String(value.to_string()),
+            );
+        }
+        if fields.is_empty() {
+            return Err("Bitte gib mindestens ein Feld an, das geändert werden soll.");
+        }
+        Ok(fields)
+    }
+}
+
+/// Digits with the usual separators, e.g. `+49 4651 12345` or `(04651) 123-45`
+fn is_valid_phone(phone: &str) -> bool {
+    phone.chars().count() <= MAX_PHONE_CHARS
+        && phone
+            .chars()
+            .all(|c| c.is_ascii_digit() || " +-/()".contains(c))
+        && phone.chars().filter(char::is_ascii_digit).count() >= MIN_PHONE_DIGITS
+}
+
+/// German postal codes have five digits, but members may live across the border
+fn is_valid_postal_code(postal_code: &str) -> bool {
+    postal_code.chars().count() <= MAX_POSTAL_CODE_CHARS
+        && postal_code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-')
+}
@@ -0,0 +1,217 @@
+use crate::config::{Config, RateLimitStoreConfig};
+use crate::error::{AppError, ErrorCode};
+use axum::extract::Request;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tower_governor::governor::GovernorConfigBuilder;
+use tower_governor::key_extractor::KeyExtractor;
+use tower_governor::GovernorLayer;
+use tracing::{info, warn};
+
+/// A quota in the terms of the governor crate: up to `burst` requests at once, and one more
+/// every `period` after that
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Keeps the counters of the quotas apart in Redis
+    pub name: &'static str,
+    pub period: Duration,
+    pub burst: u32,
+}
+
+/// Login, password reset and the other endpoints without a token, per client IP
+pub const AUTH_LIMIT: RateLimit = RateLimit {
+    name: "auth",
+    period: Duration::from_secs(1),
+    burst: 3,
+};
+
+/// Protected GET endpoints, per member; bursts cover loading a page
+pub const READ_LIMIT: RateLimit = RateLimit {
+    name: "read",
+    period: Duration::from_secs(5),
+    burst: 10,
+};
+
+/// Protected write endpoints, per member
+pub const WRITE_LIMIT: RateLimit = RateLimit {
+    name: "write",
+    period: Duration::from_secs(1),
+    burst: 3,
+};
+
+/// Redis must answer within this time, or the request is let through
+const REDIS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// GCRA as the governor crate implements it, on Redis: the key holds the theoretical arrival
+/// time (TAT) in milliseconds. Uses the Redis clock, so replicas with skewed clocks agree.
+/// Returns 0 if the request is allowed, else the milliseconds until it would be.
+const GCRA_SCRIPT: &str = r"
+local now = redis.call('TIME')
+local now_ms = tonumber(now[1]) * 1000 + math.floor(tonumber(now[2]) / 1000)
+local period = tonumber(ARGV[1])
+local burst = tonumber(ARGV[2])
+local tat = tonumber(redis.call('GET', KEYS[1]) or now_ms)
+if tat < now_ms then
+  tat = now_ms
+end
+local new_tat = tat + period
+local allowed_at = new_tat - period * burst
+if allowed_at > now_ms then
+  return allowed_at - now_ms
+end
+redis.call('SET', KEYS[1], new_tat, 'PX', new_tat - now_ms)
+return 0
+";
+
+/// Counts requests against the quotas of the routes, in memory or, for deployments with
+/// several replicas, in Redis. Selected by `RATE_LIMIT_STORE`.
+#[derive(Clone)]
+pub enum RateLimiter {
+    /// tower-governor in this process
+    Memory,
+    Redis(Arc<RedisRateLimiter>),
+    /// No limits at all, for apps built by tests
+    Unlimited,
+}
+
+impl RateLimiter {
+    pub fn from_config(config: &Config) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(match &config.rate_limit_store {
+            RateLimitStoreConfig::Memory => RateLimiter::Memory,
+            RateLimitStoreConfig::Redis { url, key_prefix } => {
+                info!("Rate Limit: Counting requests in Redis, shared by all instances");
+                RateLimiter::Redis(Arc::new(RedisRateLimiter::new(url, key_prefix.clone())?))
+            }
+        })
+    }
+
+    /// Puts the routes of `router` under `limit`, counted per key of `extractor`. Requests
+    /// over the limit get `429 RATE_LIMIT_EXCEEDED`.
+    pub fn apply<S, K>(&self, router: Router<S>, limit: RateLimit, extractor: K) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+        K: KeyExtractor<Key = String> + Send + Sync + 'static,
+    {
+        match self {
+            RateLimiter::Unlimited => router,
+            RateLimiter::Memory => {
+                let config = GovernorConfigBuilder::default()
+                    .period(limit.period)
+                    .burst_size(limit.burst)
+                    .key_extractor(extractor)
+                    .finish()
+                    .expect("rate limits have a period and burst size above zero");
+                router
+                    .layer(GovernorLayer {
+                        config: Arc::new(config),
+                    })
+                    .layer(middleware::from_fn(rewrite_429_to_json))
+            }
+            RateLimiter::Redis(store) => {
+                let store = store.clone();
+                router.layer(middleware::from_fn(move |request: Request, next: Next| {
+                    let store = store.clone();
+                    let key = extractor.extract(&request);
+                    async move {
+                        let Ok(key) = key else {
+                            // Like tower-governor, which answers 500 without a key
+                            return AppError::internal().into_response();
+                        };
+                        match store.check(limit, &key).await {
+                            Ok(None) => next.run(request).await,
+                            Ok(Some(wait)) => rate_limited_response(wait),
+                            Err(e) => {
+                                // An outage of Redis mustn't take the app down with it
+                                warn!("Rate Limit: Redis unavailable, not limiting: {}", e);
+                                next.run(request).await
+                            }
+                        }
+                    }
+                }))
+            }
+        }
+    }
+}
+
+/// Rate limits counted in Redis, so all replicas share one quota per client. The connection
+/// is opened with the first request and re-established after failures.
+pub struct RedisRateLimiter {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+    key_prefix: String,
+    script: redis::Script,
+}
+
+impl RedisRateLimiter {
+    pub fn new(url: &str, key_prefix: String) -> redis::RedisResult<Self> {
+        Ok(RedisRateLimiter {
+            client: redis::Client::open(url)?,
+            connection: OnceCell::new(),
+            key_prefix,
+            script: redis::Script::new(GCRA_SCRIPT),
+        })
+    }
+
+    async fn connection(&self) -> redis::RedisResult<ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| {
+                let config = ConnectionManagerConfig::new()
+                    .set_connection_timeout(REDIS_TIMEOUT)
+                    .set_response_timeout(REDIS_TIMEOUT)
+                    .set_number_of_retries(1);
+                ConnectionManager::new_with_config(self.client.clone(), config)
+            })
+            .await
+            .cloned()
+    }
+
+    /// `None` if the request is within the limit, else how long until it would be
+    pub async fn check(&self, limit: RateLimit, key: &str) -> redis::RedisResult<Option<Duration>> {
+        let mut connection = self.connection().await?;
+        let wait_ms: u64 = self
+            .script
+            .key(format!("{}:{}:{}", self.key_prefix, limit.name, key))
+            .arg(limit.period.as_millis() as u64)
+            .arg(limit.burst)
+            .invoke_async(&mut connection)
+            .await?;
+        Ok((wait_ms > 0).then(|| Duration::from_millis(wait_ms)))
+    }
+}
+
+fn rate_limited_response(wait: Duration) -> Response {
+    let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    rate_limited_response_after(retry_after.max(1))
+}
+
+fn rate_limited_response_after(seconds: u64) -> Response {
+    let mut response = AppError::code(ErrorCode::RateLimitExceeded).into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+/// tower-governor answers with plain text and its own `x-ratelimit-after` header; the SPA
+/// expects the usual JSON error, with `Retry-After` as from the Redis store
+pub async fn rewrite_429_to_json(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get("x-ratelimit-after")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        return match retry_after {
+            Some(seconds) => rate_limited_response_after(seconds.max(1)),
+            None => AppError::code(ErrorCode::RateLimitExceeded).into_response(),
+        };
+    }
+    response
+}
@@ -0,0 +1,58 @@
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+
+/// Correlation ID of a request, taken from the client or generated, and echoed in the response
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longer IDs from clients are replaced, so they can't flood the logs
+const MAX_LENGTH: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// ID of the request being handled, `None` in background jobs
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// Middleware giving every request an ID. Everything logged while handling the request,
+/// including Teable and SMTP calls, is inside a `request` span carrying the ID, and error
+/// responses contain it, so a member's report can be matched with the logs.
+pub async fn assign_request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        request.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path()
+    );
+    let mut response = REQUEST_ID
+        .scope(id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
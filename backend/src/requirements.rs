@@ -0,0 +1,157 @@
+use crate::activity::{ActivityKind, NewActivity};
+use crate::database::Database;
+use crate::email::EmailService;
+use crate::models::Member;
+use crate::rules::{self, WorkHourRules};
+use crate::schema::MemberField;
+use crate::teable;
+use crate::teable::TeableClient;
+use crate::utils::{get_member_work_hours_info, is_member_eligible_for_work_hours};
+use tracing::{error, info, warn};
+
+/// How a member's age-based obligation changed from one year to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeTransition {
+    /// Reached the minimum age in the previous year, so work hours are required from now on
+    NowRequired,
+    /// Reaches the maximum age this year, so work hours are no longer required
+    NowExempt,
+}
+
+impl AgeTransition {
+    /// German text for the notification email and the activity feed
+    pub fn message(&self, year: i32, rules: &WorkHourRules) -> String {
+        match self {
+            AgeTransition::NowRequired => format!(
+                "Ab {year} sind Sie zur Leistung von Arbeitsstunden verpflichtet, da Sie im Vorjahr {} Jahre alt geworden sind.",
+                rules.min_age
+            ),
+            AgeTransition::NowExempt => format!(
+                "Ab {year} sind Sie aus Altersgründen von den Arbeitsstunden befreit."
+            ),
+        }
+    }
+}
+
+/// A member's required hours of one year, as materialized in SQLite
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberRequirement {
+    pub member_id: String,
+    pub required_hours: f64,
+    pub exemption_reason: Option<String>,
+}
+
+impl MemberRequirement {
+    pub fn for_member(member: &Member, year: i32, rules: &WorkHourRules) -> Self {
+        let (required_hours, exemption_reason) = get_member_work_hours_info(member, year, rules);
+        MemberRequirement {
+            member_id: member.id.clone(),
+            required_hours,
+            exemption_reason,
+        }
+    }
+}
+
+/// Compares the member's age-based status in `year` with the previous year, both under the
+/// rules of `year` so a rule change isn't reported as a birthday. Members without a valid
+/// birth date are always treated as required, so they never cross a threshold.
+pub fn age_transition(member: &Member, year: i32, rules: &WorkHourRules) -> Option<AgeTransition> {
+    match (
+        is_member_eligible_for_work_hours(member, year - 1, rules),
+        is_member_eligible_for_work_hours(member, year, rules),
+    ) {
+        (false, true) => Some(AgeTransition::NowRequired),
+        (true, false) => Some(AgeTransition::NowExempt),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RecalculationSummary {
+    pub members: usize,
+    pub notified: usize,
+}
+
+/// Rebuilds the materialized requirements of a year from Teable and notifies members
+/// whose age-based status changed. Each member is notified at most once per year.
+pub async fn recalculate_year(
+    client: &TeableClient,
+    database: &Database,
+    email_service: &EmailService,
+    year: i32,
+) -> anyhow::Result<RecalculationSummary> {
+    let members = teable::get_all_members_with_projection(
+        client,
+        &[
+            MemberField::FirstName,
+            MemberField::LastName,
+            MemberField::Email,
+            MemberField::BirthDate,
+            MemberField::JoinDate,
+        ],
+    )
+    .await?;
+    let all_rules = rules::load(client).await;
+    let rules = all_rules.for_year(year);
+    let requirements: Vec<MemberRequirement> = members
+        .iter()
+        .map(|member| MemberRequirement::for_member(member, year, rules))
+        .collect();
+    database
+        .upsert_member_requirements(year, &requirements)
+        .await?;
+
+    let mut summary = RecalculationSummary {
+        members: members.len(),
+        notified: 0,
+    };
+    for member in &members {
+        let Some(transition) = age_transition(member, year, rules) else {
+            continue;
+        };
+        if !database
+            .claim_requirement_notification(&member.id, year)
+            .await?
+        {
+            continue;
+        }
+
+        info!(
+            "Requirements: Member {} crossed an age threshold for {}: {:?}",
+            member.id, year, transition
+        );
+        let message = transition.message(year, rules);
+        if let Err(e) = database
+            .record_activity(&NewActivity::new(
+                &member.id,
+                ActivityKind::RequirementChanged,
+                message.clone(),
+            ))
+            .await
+        {
+            error!(
+                "Requirements: Failed to record activity for member {}: {}",
+                member.id, e
+            );
+        }
+
+        if member.email.trim().is_empty() {
+            warn!(
+                "Requirements: Member {} has no email address, not notifying",
+                member.id
+            );
+        } else if let Err(e) = email_service
+            .send_requirement_change_email(&member.email, &member.name(), year, &message)
+            .await
+        {
+            // Not retried; the change is still visible in the activity feed
+            error!(
+                "Requirements: Failed to notify member {} about {}: {}",
+                member.id, year, e
+            );
+        }
+        summary.notified += 1;
+    }
+
+    Ok(summary)
+}
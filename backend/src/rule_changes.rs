@@ -0,0 +1,253 @@
+use crate::database::Database;
+use crate::export::Locale;
+use crate::rules::{
+    RequirementRules, WorkHourRules, FAMILY_MAX_HOURS_SETTING, MAX_AGE_SETTING, MIN_AGE_SETTING,
+    REQUIRED_HOURS_SETTING,
+};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use std::collections::BTreeSet;
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+const MAX_SUBJECT_LENGTH: usize = 100;
+const MAX_VALUE_LENGTH: usize = 200;
+const MAX_NOTE_LENGTH: usize = 2000;
+
+/// How an entry got into the changelog, stored in the `source` column of `rule_changes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleChangeSource {
+    /// Found by comparing the work hour rules with the ones of the last check
+    Detected,
+    /// Entered by the board, e.g. a new deadline or fee the rules don't know about
+    Board,
+}
+
+impl RuleChangeSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleChangeSource::Detected => "detected",
+            RuleChangeSource::Board => "board",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(value.to_string())).ok()
+    }
+}
+
+/// A change of the club's rules, as shown to members
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct RuleChange {
+    pub id: i64,
+    /// What changed, e.g. the settings key `Arbeitsstunden` or "Abgabefrist"
+    pub subject: String,
+    /// The values before and after, as displayed; `None` if there was none
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    /// First day the change applies to
+    pub effective_from: NaiveDate,
+    /// Explanation of the board
+    pub note: Option<String>,
+    pub source: RuleChangeSource,
+    /// RFC 3339 timestamps
+    pub recorded_at: String,
+    /// When the announcement email was started, `None` if it wasn't
+    pub announced_at: Option<String>,
+}
+
+/// A change to add to the changelog
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewRuleChange {
+    pub subject: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub effective_from: NaiveDate,
+    pub note: Option<String>,
+    pub source: RuleChangeSource,
+}
+
+/// Body of `POST /admin/rules/changelog`, for changes outside the work hour rules
+#[derive(Debug, Deserialize, Type)]
+pub struct CreateRuleChangeRequest {
+    pub subject: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub effective_from: NaiveDate,
+    pub note: Option<String>,
+}
+
+impl CreateRuleChangeRequest {
+    pub fn validate(self) -> Result<NewRuleChange, String> {
+        let subject = self.subject.trim();
+        if subject.is_empty() || subject.chars().count() > MAX_SUBJECT_LENGTH {
+            return Err(format!(
+                "Bitte gib an, was sich ändert (höchstens {} Zeichen).",
+                MAX_SUBJECT_LENGTH
+            ));
+        }
+        let old_value = trimmed(self.old_value);
+        let new_value = trimmed(self.new_value);
+        if [&old_value, &new_value].iter().any(|value| {
+            value
+                .as_ref()
+                .is_some_and(|v| v.chars().count() > MAX_VALUE_LENGTH)
+        }) {
+            return Err(format!(
+                "Die Werte dürfen höchstens {} Zeichen lang sein.",
+                MAX_VALUE_LENGTH
+            ));
+        }
+        if old_value.is_some() && old_value == new_value {
+            return Err("Der neue Wert ist derselbe wie der alte.".to_string());
+        }
+        Ok(NewRuleChange {
+            subject: subject.to_string(),
+            old_value,
+            new_value,
+            effective_from: self.effective_from,
+            note: validate_note(self.note)?,
+            source: RuleChangeSource::Board,
+        })
+    }
+}
+
+/// Body of `PUT /admin/rules/changelog/:id`; an empty note removes it
+#[derive(Debug, Deserialize, Type)]
+pub struct RuleChangeNoteRequest {
+    pub note: Option<String>,
+}
+
+/// The note of the board without surrounding whitespace, `None` if empty
+pub fn validate_note(note: Option<String>) -> Result<Option<String>, String> {
+    let note = trimmed(note);
+    if note
+        .as_ref()
+        .is_some_and(|note| note.chars().count() > MAX_NOTE_LENGTH)
+    {
+        return Err(format!(
+            "Die Erläuterung darf höchstens {} Zeichen lang sein.",
+            MAX_NOTE_LENGTH
+        ));
+    }
+    Ok(note)
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// `?limit=` of `GET /rules/changelog`
+#[derive(Debug, Default, Deserialize)]
+pub struct RuleChangeQuery {
+    pub limit: Option<u32>,
+}
+
+impl RuleChangeQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+}
+
+/// A member an announcement goes to, as it was when the board started it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnouncementRecipient {
+    pub member_id: String,
+    pub name: String,
+    pub email: String,
+}
+
+/// Payload of a `JobKind::RuleAnnouncement`; each item is one recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleAnnouncement {
+    pub change_id: i64,
+    pub recipients: Vec<AnnouncementRecipient>,
+}
+
+/// The values of a rule set as members read them, labelled with their settings keys
+fn displayed_values(rules: &WorkHourRules) -> [(&'static str, Option<String>); 4] {
+    let hours = |hours: f64| Locale::De.format_number(hours);
+    [
+        (REQUIRED_HOURS_SETTING, Some(hours(rules.required_hours))),
+        (MIN_AGE_SETTING, Some(rules.min_age.to_string())),
+        (MAX_AGE_SETTING, Some(rules.max_age.to_string())),
+        (FAMILY_MAX_HOURS_SETTING, rules.family_max_hours.map(hours)),
+    ]
+}
+
+/// Changes from `previous` to `current` that apply in the year of `today` or later; past
+/// years are settled. Every year a rule set takes effect is compared on its own, but a change
+/// carried over from the year before isn't listed again. Changes of the current year apply
+/// from `today`, those of later years from January 1st.
+pub fn detect_changes(
+    previous: &RequirementRules,
+    current: &RequirementRules,
+    today: NaiveDate,
+) -> Vec<NewRuleChange> {
+    let this_year = today.year();
+    let mut years: BTreeSet<i32> = previous
+        .by_year
+        .keys()
+        .chain(current.by_year.keys())
+        .copied()
+        .filter(|year| *year > this_year)
+        .collect();
+    years.insert(this_year);
+
+    let mut changes = Vec::new();
+    let mut carried_over = Vec::new();
+    for year in years {
+        let effective_from = if year == this_year {
+            today
+        } else {
+            NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or(today)
+        };
+        let pairs: Vec<_> = displayed_values(previous.for_year(year))
+            .into_iter()
+            .zip(displayed_values(current.for_year(year)))
+            .map(|((subject, old_value), (_, new_value))| (subject, old_value, new_value))
+            .collect();
+        for (index, (subject, old_value, new_value)) in pairs.iter().enumerate() {
+            if old_value != new_value && carried_over.get(index) != Some(&pairs[index]) {
+                changes.push(NewRuleChange {
+                    subject: subject.to_string(),
+                    old_value: old_value.clone(),
+                    new_value: new_value.clone(),
+                    effective_from,
+                    note: None,
+                    source: RuleChangeSource::Detected,
+                });
+            }
+        }
+        carried_over = pairs;
+    }
+    changes
+}
+
+/// Compares the rules with the ones stored by the last call and records what changed. The
+/// first call only stores the rules, so the rules in effect at the time aren't listed as
+/// changes. Returns the number of recorded changes.
+pub async fn track(
+    database: &Database,
+    rules: &RequirementRules,
+    today: NaiveDate,
+) -> anyhow::Result<usize> {
+    let previous = database
+        .get_rules_snapshot()
+        .await?
+        .and_then(|snapshot| serde_json::from_str::<RequirementRules>(&snapshot).ok());
+    if previous.as_ref() == Some(rules) {
+        return Ok(0);
+    }
+    let changes = previous
+        .map(|previous| detect_changes(&previous, rules, today))
+        .unwrap_or_default();
+    let snapshot = serde_json::to_string(rules)?;
+    database.record_rule_changes(&snapshot, &changes).await?;
+    Ok(changes.len())
+}
@@ -0,0 +1,197 @@
+use crate::config::Config;
+use crate::models::Member;
+use crate::teable;
+use crate::teable::TeableClient;
+use crate::utils::get_member_work_hours_info;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{BTreeMap, HashMap};
+use tracing::warn;
+
+/// Settings table keys the board can use to override the configured rules
+pub const REQUIRED_HOURS_SETTING: &str = "Arbeitsstunden";
+pub const MIN_AGE_SETTING: &str = "Mindestalter";
+pub const MAX_AGE_SETTING: &str = "Höchstalter";
+pub const FAMILY_MAX_HOURS_SETTING: &str = "Familienstunden";
+
+/// Who has to do work hours, and how many
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct WorkHourRules {
+    pub required_hours: f64,
+    /// Work hours are required from the year after turning this age
+    pub min_age: i32,
+    /// Members are exempt from the year they turn this age
+    pub max_age: i32,
+    /// Families owe at most this many hours combined; without a cap the requirements of
+    /// their members add up
+    pub family_max_hours: Option<f64>,
+}
+
+impl Default for WorkHourRules {
+    fn default() -> Self {
+        WorkHourRules {
+            required_hours: 8.0,
+            min_age: 16,
+            max_age: 70,
+            family_max_hours: None,
+        }
+    }
+}
+
+impl WorkHourRules {
+    /// Rules from `REQUIRED_WORK_HOURS`, `WORK_HOURS_MIN_AGE`, `WORK_HOURS_MAX_AGE` and
+    /// `FAMILY_MAX_WORK_HOURS`
+    pub fn from_config(config: &Config) -> Self {
+        WorkHourRules {
+            required_hours: config.required_work_hours,
+            min_age: config.work_hours_min_age,
+            max_age: config.work_hours_max_age,
+            family_max_hours: config.family_max_work_hours,
+        }
+    }
+
+    /// Applies values from the Teable settings table; invalid ones are logged and ignored
+    pub fn with_settings(mut self, settings: &HashMap<String, String>) -> Self {
+        if let Some(hours) = parse_setting::<f64>(settings, REQUIRED_HOURS_SETTING) {
+            if hours.is_finite() && hours >= 0.0 {
+                self.required_hours = hours;
+            } else {
+                warn!(
+                    "Rules: Ignoring negative {}: {}",
+                    REQUIRED_HOURS_SETTING, hours
+                );
+            }
+        }
+        if let Some(hours) = parse_setting::<f64>(settings, FAMILY_MAX_HOURS_SETTING) {
+            if hours.is_finite() && hours >= 0.0 {
+                self.family_max_hours = Some(hours);
+            } else {
+                warn!(
+                    "Rules: Ignoring negative {}: {}",
+                    FAMILY_MAX_HOURS_SETTING, hours
+                );
+            }
+        }
+        if let Some(min_age) = parse_setting(settings, MIN_AGE_SETTING) {
+            self.min_age = min_age;
+        }
+        if let Some(max_age) = parse_setting(settings, MAX_AGE_SETTING) {
+            self.max_age = max_age;
+        }
+        self
+    }
+
+    /// `age_in_year` is the age a member reaches during the year in question
+    pub fn is_required_at_age(&self, age_in_year: i32) -> bool {
+        age_in_year > self.min_age && age_in_year < self.max_age
+    }
+}
+
+fn parse_setting<T: std::str::FromStr>(settings: &HashMap<String, String>, key: &str) -> Option<T> {
+    let value = settings.get(key)?;
+    match value.trim().replace(',', ".").parse() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            warn!("Rules: Ignoring invalid setting {}: '{}'", key, value);
+            None
+        }
+    }
+}
+
+/// `?year=YYYY` of the rules endpoint
+#[derive(Debug, Deserialize)]
+pub struct RulesQuery {
+    pub year: Option<i32>,
+}
+
+/// The rules in effect for a year
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct YearRules {
+    pub year: i32,
+    pub rules: WorkHourRules,
+}
+
+/// One row of the Teable settings table
+#[derive(Debug, Clone)]
+pub struct Setting {
+    /// First year the value applies to; rows without a year apply to all years
+    pub year: Option<i32>,
+    pub key: String,
+    pub value: String,
+}
+
+/// Rule sets keyed by the year they take effect. A change stays in effect until a later
+/// year changes it again, so past years keep the rules they were evaluated with.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RequirementRules {
+    /// Configured rules plus settings without a year
+    pub base: WorkHourRules,
+    pub by_year: BTreeMap<i32, WorkHourRules>,
+}
+
+impl RequirementRules {
+    pub fn from_settings(configured: WorkHourRules, settings: &[Setting]) -> Self {
+        let mut undated = HashMap::new();
+        let mut dated: BTreeMap<i32, HashMap<String, String>> = BTreeMap::new();
+        for setting in settings {
+            let values = match setting.year {
+                Some(year) => dated.entry(year).or_default(),
+                None => &mut undated,
+            };
+            values.insert(setting.key.clone(), setting.value.clone());
+        }
+
+        let base = configured.with_settings(&undated);
+        let mut by_year = BTreeMap::new();
+        let mut current = base.clone();
+        for (year, values) in dated {
+            current = current.with_settings(&values);
+            by_year.insert(year, current.clone());
+        }
+        RequirementRules { base, by_year }
+    }
+
+    /// The rule set of the latest change up to `year`
+    pub fn for_year(&self, year: i32) -> &WorkHourRules {
+        self.by_year
+            .range(..=year)
+            .next_back()
+            .map(|(_, rules)| rules)
+            .unwrap_or(&self.base)
+    }
+
+    /// Required hours and exemption reason of a member under the rules of `year`
+    pub fn get_required_hours_for_member(
+        &self,
+        member: &Member,
+        year: i32,
+    ) -> (f64, Option<String>) {
+        get_member_work_hours_info(member, year, self.for_year(year))
+    }
+}
+
+/// The rules of all years: the configured ones, overridden by the Teable settings table if
+/// `SETTINGS_TABLE_ID` is set. Falls back to the configured rules when Teable fails, so a
+/// settings outage never blocks the dashboard.
+pub async fn load(client: &TeableClient) -> RequirementRules {
+    match try_load(client).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            warn!(
+                "Rules: Failed to load settings from Teable, using configured rules: {}",
+                e
+            );
+            RequirementRules::from_settings(client.configured_rules().clone(), &[])
+        }
+    }
+}
+
+/// Like `load`, but fails when Teable does, for callers that must not mistake the fallback
+/// for the board's rules
+pub async fn try_load(client: &TeableClient) -> anyhow::Result<RequirementRules> {
+    let settings = teable::get_settings(client).await?.unwrap_or_default();
+    Ok(RequirementRules::from_settings(
+        client.configured_rules().clone(),
+        &settings,
+    ))
+}
@@ -0,0 +1,876 @@
+use crate::app_events::AppEvent;
+use crate::config::TlsConfig;
+use crate::consistency::{self, ConsistencyReportCache};
+use crate::database::Database;
+use crate::email::EmailService;
+use crate::event_bus::EventBus;
+use crate::export::Locale;
+use crate::hours_reminder::{self, ReminderSchedule};
+use crate::jobs::{self, ChunkProcessor};
+use crate::maintenance;
+use crate::member_change::{self, NotificationSettings};
+use crate::outbox;
+use crate::password_resets::{self, DeliveryStatus};
+use crate::requirements;
+use crate::rule_changes;
+use crate::rules;
+use crate::stats;
+use crate::teable;
+use crate::teable::TeableClient;
+use crate::telemetry::{self, TelemetryFeatures};
+use crate::token_store::{account_subject, ResetTokenStore, SharedResetTokenStore};
+use crate::trash;
+use crate::utils::{day_activities, logged_hours, EntryPolicy, HourLimits};
+use axum_server::tls_rustls::RustlsConfig;
+use chrono::Datelike;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Spawns a background task that periodically purges expired reset tokens from the
+/// configured `ResetTokenStore`, along with deleted work hours past their retention period
+pub fn spawn_token_cleanup(
+    token_store: SharedResetTokenStore,
+    database: Database,
+    interval_secs: u64,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Token cleanup job running every {} seconds",
+        period.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        // The first tick completes immediately; skip it so startup isn't slowed down
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            run_token_cleanup(token_store.as_ref(), &database).await;
+        }
+    })
+}
+
+/// Runs a single cleanup pass over the reset tokens, the work hour trash, the log of reset
+/// emails and the email outbox
+pub async fn run_token_cleanup(token_store: &dyn ResetTokenStore, database: &Database) {
+    match token_store.cleanup_expired_tokens().await {
+        Ok(0) => debug!("Scheduler: No expired reset tokens to purge"),
+        Ok(purged) => info!("Scheduler: Purged {} expired reset tokens", purged),
+        Err(e) => error!("Scheduler: Failed to purge expired reset tokens: {}", e),
+    }
+
+    match database
+        .purge_deleted_work_hours(trash::retention_cutoff(chrono::Utc::now()))
+        .await
+    {
+        Ok(0) => debug!("Scheduler: No deleted work hours past retention"),
+        Ok(purged) => info!(
+            "Scheduler: Permanently removed {} deleted work hours past retention",
+            purged
+        ),
+        Err(e) => error!("Scheduler: Failed to purge deleted work hours: {}", e),
+    }
+
+    match database
+        .purge_reset_requests(password_resets::retention_cutoff(chrono::Utc::now()))
+        .await
+    {
+        Ok(0) => debug!("Scheduler: No logged reset emails past retention"),
+        Ok(purged) => info!("Scheduler: Removed {} logged reset emails", purged),
+        Err(e) => error!("Scheduler: Failed to purge logged reset emails: {}", e),
+    }
+
+    match database
+        .purge_outbox_emails(outbox::retention_cutoff(chrono::Utc::now()))
+        .await
+    {
+        Ok(0) => debug!("Scheduler: No finished outbox emails past retention"),
+        Ok(purged) => info!("Scheduler: Removed {} finished outbox emails", purged),
+        Err(e) => error!("Scheduler: Failed to purge outbox emails: {}", e),
+    }
+}
+
+/// Spawns a background task that replays work hour entries queued during a Teable outage
+pub fn spawn_work_hour_replay(
+    client: TeableClient,
+    database: Database,
+    policy: EntryPolicy,
+    limits: HourLimits,
+    interval_secs: u64,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Work hour replay job running every {} seconds",
+        period.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            replay_queued_work_hours(&client, &database, policy, limits).await;
+        }
+    })
+}
+
+/// Writes queued entries to Teable, oldest first. An entry is flagged as a conflict
+/// instead of being written when the member's entries of that date in Teable don't allow it
+/// under the `EntryPolicy` or the daily hour limit.
+/// Stops at the first failed request so a still unavailable Teable isn't hammered.
+pub async fn replay_queued_work_hours(
+    client: &TeableClient,
+    database: &Database,
+    policy: EntryPolicy,
+    limits: HourLimits,
+) {
+    let queued = match database.get_replayable_work_hours().await {
+        Ok(queued) => queued,
+        Err(e) => {
+            error!("Scheduler: Failed to load queued work hours: {}", e);
+            return;
+        }
+    };
+
+    if queued.is_empty() {
+        debug!("Scheduler: No queued work hours to replay");
+        return;
+    }
+
+    // Only the daily limit is checked, the yearly one would take another Teable request per
+    // entry; without it the year passed to `check` doesn't matter
+    let limits = HourLimits {
+        per_year: None,
+        ..limits
+    };
+    let mut synced = 0;
+    for entry in &queued {
+        let existing =
+            match teable::get_work_hours_for_member_at_date(client, &entry.member_id, &entry.date)
+                .await
+            {
+                Ok(existing) => existing,
+                Err(e) => {
+                    warn!(
+                        "Scheduler: Teable still unavailable, keeping {} queued work hours: {}",
+                        queued.len() - synced,
+                        e
+                    );
+                    record_failure(database, entry.id, &e.to_string()).await;
+                    break;
+                }
+            };
+
+        let conflict = policy
+            .check(&entry.description, day_activities(&existing, None))
+            .err()
+            .map(str::to_string)
+            .or_else(|| {
+                limits
+                    .check(entry.hours, logged_hours(&existing, None), 0, 0.0)
+                    .err()
+                    .map(|exceeded| exceeded.message(Locale::De))
+            });
+        if let Some(conflict) = conflict {
+            warn!(
+                "Scheduler: Queued work hour {} conflicts with the entries of member {} on {}",
+                entry.id, entry.member_id, entry.date
+            );
+            if let Err(e) = database
+                .mark_queued_work_hour_conflict(entry.id, &conflict)
+                .await
+            {
+                error!(
+                    "Scheduler: Failed to flag queued work hour {}: {}",
+                    entry.id, e
+                );
+            }
+            continue;
+        }
+
+        match teable::create_work_hour(
+            client,
+            &entry.date,
+            &entry.description,
+            entry.hours,
+            entry.member_id.clone(),
+            None,
+            None,
+        )
+        .await
+        {
+            Ok(work_hour) => {
+                info!(
+                    "Scheduler: Replayed queued work hour {} as Teable record {}",
+                    entry.id, work_hour.id
+                );
+                if let Err(e) = database.delete_queued_work_hour(entry.id).await {
+                    // The next run would see the Teable record and flag a conflict, not a duplicate
+                    error!(
+                        "Scheduler: Failed to remove replayed work hour {} from queue: {}",
+                        entry.id, e
+                    );
+                }
+                synced += 1;
+            }
+            Err(e) => {
+                warn!(
+                    "Scheduler: Failed to replay queued work hour {}: {}",
+                    entry.id, e
+                );
+                record_failure(database, entry.id, &e.to_string()).await;
+                break;
+            }
+        }
+    }
+
+    if synced > 0 {
+        info!("Scheduler: Replayed {} queued work hours to Teable", synced);
+    }
+}
+
+async fn record_failure(database: &Database, id: i64, error: &str) {
+    if let Err(e) = database.record_work_hour_sync_failure(id, error).await {
+        error!(
+            "Scheduler: Failed to record sync failure for queued work hour {}: {}",
+            id, e
+        );
+    }
+}
+
+/// Spawns a background task that compares login accounts with Teable members and
+/// keeps the latest report for the admin endpoints. Unlike the other jobs the first
+/// check runs right away so the report is available soon after startup.
+pub fn spawn_consistency_check(
+    client: TeableClient,
+    database: Database,
+    cache: ConsistencyReportCache,
+    interval_secs: u64,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Consistency check running every {} seconds",
+        period.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+            run_consistency_check(&client, &database, &cache).await;
+        }
+    })
+}
+
+/// Runs a single consistency check and stores the report in the cache
+pub async fn run_consistency_check(
+    client: &TeableClient,
+    database: &Database,
+    cache: &ConsistencyReportCache,
+) {
+    match consistency::check_consistency(client, database).await {
+        Ok(report) => {
+            if report.orphaned_accounts.is_empty() && report.members_without_account.is_empty() {
+                debug!("Scheduler: Login accounts and Teable members are consistent");
+            } else {
+                warn!(
+                    "Scheduler: Found {} orphaned login accounts and {} members without account",
+                    report.orphaned_accounts.len(),
+                    report.members_without_account.len()
+                );
+            }
+            *cache.write().await = Some(report);
+        }
+        Err(e) => {
+            error!("Scheduler: Consistency check failed: {}", e);
+        }
+    }
+}
+
+/// Spawns a background task that rebuilds the materialized work hour stats of the current
+/// year. Other years are rebuilt on demand when first requested.
+pub fn spawn_stats_refresh(
+    client: TeableClient,
+    database: Database,
+    interval_secs: u64,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Stats refresh running every {} seconds",
+        period.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            run_stats_refresh(&client, &database, chrono::Utc::now().year()).await;
+        }
+    })
+}
+
+/// Rebuilds the stats of one year, returning whether it succeeded
+pub async fn run_stats_refresh(client: &TeableClient, database: &Database, year: i32) -> bool {
+    match stats::refresh_year(client, database, year).await {
+        Ok(days) => {
+            debug!(
+                "Scheduler: Refreshed stats of {} ({} days with work)",
+                year, days
+            );
+            true
+        }
+        Err(e) => {
+            error!("Scheduler: Stats refresh for {} failed: {}", year, e);
+            false
+        }
+    }
+}
+
+/// Spawns a background task that materializes the required hours of the current year and
+/// notifies members who turned 16 or 70. Checking daily makes the job pick up the new year
+/// within a day of New Year's Eve.
+pub fn spawn_requirement_recalculation(
+    client: TeableClient,
+    database: Database,
+    email_service: Arc<EmailService>,
+    interval_secs: u64,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Requirement recalculation running every {} seconds",
+        period.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            run_requirement_recalculation(
+                &client,
+                &database,
+                &email_service,
+                chrono::Utc::now().year(),
+            )
+            .await;
+        }
+    })
+}
+
+/// Recalculates the requirements of one year
+pub async fn run_requirement_recalculation(
+    client: &TeableClient,
+    database: &Database,
+    email_service: &EmailService,
+    year: i32,
+) {
+    match requirements::recalculate_year(client, database, email_service, year).await {
+        Ok(summary) => {
+            if summary.notified > 0 {
+                info!(
+                    "Scheduler: Recalculated requirements of {} for {} members, notified {} about a changed status",
+                    year, summary.members, summary.notified
+                );
+            } else {
+                debug!(
+                    "Scheduler: Recalculated requirements of {} for {} members",
+                    year, summary.members
+                );
+            }
+        }
+        Err(e) => {
+            error!(
+                "Scheduler: Requirement recalculation for {} failed: {}",
+                year, e
+            );
+        }
+    }
+}
+
+/// Spawns a background task that retries the emails in the outbox
+pub fn spawn_email_outbox(
+    database: Database,
+    email_service: Arc<EmailService>,
+    interval_secs: u64,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Email outbox retried every {} seconds",
+        period.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            send_outbox_emails(&database, &email_service).await;
+        }
+    })
+}
+
+/// Sends one batch of due emails from the outbox. Failed ones are tried again later with a
+/// growing delay until they run out of attempts; refused addresses are given up right away.
+pub async fn send_outbox_emails(database: &Database, email_service: &EmailService) {
+    let due = match database
+        .get_due_emails(chrono::Utc::now(), outbox::BATCH_SIZE)
+        .await
+    {
+        Ok(due) => due,
+        Err(e) => {
+            error!("Scheduler: Failed to load due outbox emails: {}", e);
+            return;
+        }
+    };
+    if due.is_empty() {
+        debug!("Scheduler: No outbox emails due");
+        return;
+    }
+
+    let mut sent = 0;
+    for email in &due {
+        let recorded = match email_service
+            .send_email(&email.recipient, &email.subject, &email.html, &email.text)
+            .await
+        {
+            Ok(()) => {
+                sent += 1;
+                database.mark_email_sent(email.id).await
+            }
+            Err(e) => {
+                let attempts = email.attempts + 1;
+                let next_attempt_at = match DeliveryStatus::of_error(&*e) {
+                    DeliveryStatus::Failed => outbox::next_attempt_at(attempts, chrono::Utc::now()),
+                    _ => None,
+                };
+                match next_attempt_at {
+                    Some(at) => warn!(
+                        "Scheduler: Outbox email {} failed (attempt {}), retrying at {}: {}",
+                        email.id, attempts, at, e
+                    ),
+                    None => error!(
+                        "Scheduler: Giving up outbox email {} to {} after {} attempts: {}",
+                        email.id, email.recipient, attempts, e
+                    ),
+                }
+                database
+                    .record_email_failure(email.id, &e.to_string(), next_attempt_at)
+                    .await
+            }
+        };
+        if let Err(e) = recorded {
+            error!(
+                "Scheduler: Failed to record result of outbox email {}: {}",
+                email.id, e
+            );
+        }
+    }
+
+    if sent > 0 {
+        info!(
+            "Scheduler: Sent {} of {} due outbox emails",
+            sent,
+            due.len()
+        );
+    }
+}
+
+/// Spawns a background task that records changes of the work hour rules in the changelog.
+/// The first check runs right away, so the rules in effect are stored soon after the
+/// feature is deployed.
+pub fn spawn_rule_change_tracking(
+    client: TeableClient,
+    database: Database,
+    interval_secs: u64,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Rule change tracking running every {} seconds",
+        period.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+            run_rule_change_tracking(&client, &database).await;
+        }
+    })
+}
+
+/// Compares the rules with the ones of the last check. Skipped while Teable is unreachable,
+/// as the configured fallback rules would show up as changes.
+pub async fn run_rule_change_tracking(client: &TeableClient, database: &Database) {
+    let rules = match rules::try_load(client).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            warn!("Scheduler: Skipping rule change tracking: {}", e);
+            return;
+        }
+    };
+    match rule_changes::track(database, &rules, chrono::Utc::now().date_naive()).await {
+        Ok(0) => debug!("Scheduler: Work hour rules unchanged"),
+        Ok(changes) => info!("Scheduler: Recorded {} rule changes", changes),
+        Err(e) => error!("Scheduler: Rule change tracking failed: {}", e),
+    }
+}
+
+/// Spawns a background task that sends the queued reset emails of forced password rotations
+/// in batches, so a rotation doesn't flood the SMTP server
+pub fn spawn_password_rotation_emails(
+    client: TeableClient,
+    database: Database,
+    email_service: Arc<EmailService>,
+    token_store: SharedResetTokenStore,
+    interval_secs: u64,
+    batch_size: u32,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Password rotation emails sent every {} seconds in batches of {}",
+        period.as_secs(),
+        batch_size
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            send_password_rotation_emails(
+                &client,
+                &database,
+                &email_service,
+                token_store.as_ref(),
+                batch_size,
+            )
+            .await;
+        }
+    })
+}
+
+/// Sends one batch of reset emails. The link is issued for the Teable member with the
+/// account's email address, or for the account itself if it was created by a family
+/// invitation; failed emails are retried by later runs.
+pub async fn send_password_rotation_emails(
+    client: &TeableClient,
+    database: &Database,
+    email_service: &EmailService,
+    token_store: &dyn ResetTokenStore,
+    batch_size: u32,
+) {
+    let pending = match database
+        .get_pending_rotation_emails(batch_size.max(1))
+        .await
+    {
+        Ok(pending) => pending,
+        Err(e) => {
+            error!(
+                "Scheduler: Failed to load pending password rotation emails: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    let mut sent = 0;
+    for queued in &pending {
+        let linked_account = match database.get_user_by_email(&queued.email).await {
+            Ok(account) => account.and_then(|account| Some((account.id, account.member_id?))),
+            Err(e) => {
+                error!(
+                    "Scheduler: Failed to look up account {}: {}",
+                    queued.email, e
+                );
+                None
+            }
+        };
+        let subject = match linked_account {
+            Some((account_id, member_id)) => Ok((account_subject(account_id), member_id)),
+            None => match teable::get_member_by_email(client, &queued.email).await {
+                Ok(Some(member)) => Ok((member.id.clone(), member.id)),
+                Ok(None) => Err("Kein Mitglied mit dieser E-Mail-Adresse".to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+        };
+        let result = match subject {
+            Ok((subject, member_id)) => match token_store.create_reset_token(&subject).await {
+                Ok(token) => email_service
+                    .send_forced_password_reset_email(&queued.email, &token, &member_id)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+            Err(e) => Err(e),
+        };
+
+        let recorded = match &result {
+            Ok(()) => {
+                sent += 1;
+                database.mark_rotation_email_sent(queued.id).await
+            }
+            Err(message) => {
+                warn!(
+                    "Scheduler: Failed to send reset email {} of password rotation {} (attempt {}): {}",
+                    queued.id,
+                    queued.rotation_id,
+                    queued.attempts + 1,
+                    message
+                );
+                database
+                    .record_rotation_email_failure(queued.id, message)
+                    .await
+            }
+        };
+        if let Err(e) = recorded {
+            error!(
+                "Scheduler: Failed to record state of reset email {}: {}",
+                queued.id, e
+            );
+        }
+    }
+
+    if sent > 0 {
+        info!(
+            "Scheduler: Sent {} of {} password rotation emails",
+            sent,
+            pending.len()
+        );
+    }
+
+    match database.complete_password_rotations().await {
+        Ok(completed) if completed > 0 => {
+            info!("Scheduler: Completed {} password rotations", completed);
+        }
+        Ok(_) => {}
+        Err(e) => error!("Scheduler: Failed to complete password rotations: {}", e),
+    }
+}
+
+/// Spawns the worker of the board's background jobs. Each run works off all unfinished
+/// jobs, so the interval is only the delay until a new job is picked up.
+pub fn spawn_job_worker(
+    database: Database,
+    processor: Arc<dyn ChunkProcessor>,
+    interval_secs: u64,
+    chunk_size: u32,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(interval_secs.max(1));
+    info!(
+        "Scheduler: Job worker running every {} seconds in chunks of {}",
+        period.as_secs(),
+        chunk_size
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        // Jobs interrupted by a restart continue right away
+        loop {
+            interval.tick().await;
+            jobs::run_jobs(&database, processor.as_ref(), chunk_size).await;
+        }
+    })
+}
+
+/// Spawns the opt-in telemetry. It checks hourly and sends a report once the last one is
+/// older than `interval_secs`, so restarts don't cause extra reports.
+pub fn spawn_telemetry(
+    client: TeableClient,
+    database: Database,
+    url: String,
+    interval_secs: u64,
+    features: TelemetryFeatures,
+) -> JoinHandle<()> {
+    let report_interval = chrono::Duration::seconds(interval_secs.max(1) as i64);
+    info!(
+        "Scheduler: Telemetry enabled, reporting every {} seconds to {}",
+        interval_secs, url
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            if let Err(e) =
+                telemetry::send_if_due(&client, &database, &url, report_interval, &features).await
+            {
+                warn!("Scheduler: Failed to send telemetry: {}", e);
+            }
+        }
+    })
+}
+
+/// Spawns the nightly database maintenance at `hour` o'clock club time, when hardly anyone
+/// enters hours. `VACUUM` blocks writes while it runs. A failed integrity check is emailed
+/// to the members of `board`.
+pub fn spawn_database_maintenance(
+    client: TeableClient,
+    database: Database,
+    email_service: Arc<EmailService>,
+    board: Vec<String>,
+    hour: u32,
+) -> JoinHandle<()> {
+    info!(
+        "Scheduler: Database maintenance running daily at {}:00",
+        hour
+    );
+
+    tokio::spawn(async move {
+        loop {
+            let now = chrono::Utc::now();
+            let wait = (maintenance::next_run(now, hour) - now)
+                .to_std()
+                .unwrap_or_default();
+            tokio::time::sleep(wait).await;
+            run_database_maintenance(&client, &database, &email_service, &board).await;
+        }
+    })
+}
+
+/// Runs the maintenance once and alerts the board if the integrity check failed
+pub async fn run_database_maintenance(
+    client: &TeableClient,
+    database: &Database,
+    email_service: &EmailService,
+    board: &[String],
+) {
+    match maintenance::run(database).await {
+        Ok(run) if !run.integrity_ok => {
+            error!(
+                "Scheduler: Database integrity check found {} problems: {}",
+                run.integrity_errors.len(),
+                run.integrity_errors.join("; ")
+            );
+            maintenance::alert_board(client, email_service, board, &run).await;
+        }
+        Ok(run) => match &run.error {
+            Some(e) => warn!("Scheduler: Database maintenance incomplete: {}", e),
+            None => info!(
+                "Scheduler: Database maintenance done, {} -> {} bytes",
+                run.size_before_bytes, run.size_after_bytes
+            ),
+        },
+        Err(e) => error!("Scheduler: Database maintenance failed: {}", e),
+    }
+}
+
+/// Spawns a task that reminds members behind on their hours, checking daily at `hour` o'clock
+/// club time whether one of the reminder days is due
+pub fn spawn_hours_reminders(
+    client: TeableClient,
+    database: Database,
+    email_service: Arc<EmailService>,
+    schedule: ReminderSchedule,
+    hour: u32,
+) -> JoinHandle<()> {
+    info!("Scheduler: Hours reminders checked daily at {}:00", hour);
+
+    tokio::spawn(async move {
+        loop {
+            let now = chrono::Utc::now();
+            let wait = (maintenance::next_run(now, hour) - now)
+                .to_std()
+                .unwrap_or_default();
+            tokio::time::sleep(wait).await;
+            run_hours_reminders(&client, &database, &email_service, &schedule).await;
+        }
+    })
+}
+
+/// Sends the reminders of the reminder day due today, if any
+pub async fn run_hours_reminders(
+    client: &TeableClient,
+    database: &Database,
+    email_service: &EmailService,
+    schedule: &ReminderSchedule,
+) {
+    let today = chrono::Utc::now()
+        .with_timezone(&chrono_tz::Europe::Berlin)
+        .date_naive();
+    let Some(day) = schedule.due_day(today) else {
+        return;
+    };
+    if let Err(e) =
+        hours_reminder::send_reminders(client, database, email_service, today, day).await
+    {
+        // Retried on the next day until the catch-up window has passed
+        error!("Scheduler: Hours reminders of {} failed: {}", day, e);
+    }
+}
+
+/// Spawns a task that emails members about data the board changed, as announced on the
+/// event bus. Ends when the bus is closed on shutdown.
+pub fn spawn_member_change_notifications(
+    client: TeableClient,
+    database: Database,
+    email_service: Arc<EmailService>,
+    event_bus: EventBus,
+    settings: NotificationSettings,
+) -> JoinHandle<()> {
+    let mut receiver = event_bus.subscribe();
+    info!("Scheduler: Member change notifications listening on the event bus");
+
+    tokio::spawn(async move {
+        loop {
+            let envelope = tokio::select! {
+                received = receiver.recv() => received,
+                _ = event_bus.closed() => break,
+            };
+            match envelope {
+                Ok(envelope) => {
+                    if let AppEvent::MemberUpdated(data) = envelope.event {
+                        member_change::notify(&client, &database, &email_service, &settings, &data)
+                            .await;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => warn!(
+                    "Scheduler: Member change notifications missed {} events",
+                    missed
+                ),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+/// Spawns a task that re-reads the TLS certificate twice a day, so renewals (e.g. by certbot)
+/// are picked up without a restart. A failed reload keeps the current certificate.
+pub fn spawn_tls_reload(rustls_config: RustlsConfig, tls: TlsConfig) -> JoinHandle<()> {
+    let period = Duration::from_secs(12 * 60 * 60);
+    info!(
+        "Scheduler: TLS certificate reloaded every {} seconds from {}",
+        period.as_secs(),
+        tls.cert_path.display()
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            match rustls_config
+                .reload_from_pem_file(&tls.cert_path, &tls.key_path)
+                .await
+            {
+                Ok(()) => debug!("Scheduler: TLS certificate reloaded"),
+                Err(e) => warn!("Scheduler: Failed to reload the TLS certificate: {}", e),
+            }
+        }
+    })
+}
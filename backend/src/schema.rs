@@ -0,0 +1,214 @@
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// A field of one Teable table
+pub trait TeableField: Copy + std::fmt::Debug + 'static {
+    /// Every field of the table, in the order of the table
+    const ALL: &'static [Self];
+
+    /// Name of the field in the Teable base the app was built against
+    fn default_name(self) -> &'static str;
+
+    /// Environment variable overriding the name, e.g. `TEABLE_FIELD_MEMBER_EMAIL`
+    fn env_var(self) -> &'static str;
+
+    /// Name of the field in the Teable base of `names`
+    fn name(self, names: &FieldNames) -> &str {
+        names
+            .overrides
+            .get(self.env_var())
+            .map(String::as_str)
+            .unwrap_or_else(|| self.default_name())
+    }
+}
+
+/// The field names of a Teable base. When the board renames a field in Teable,
+/// `TEABLE_FIELD_<TABLE>_<FIELD>` points the backend at the new name without a release, e.g.
+/// `TEABLE_FIELD_WORK_HOUR_HOURS=Dauer` for `Stunden` of the work hours table. Part of
+/// `Config`; `TeableClient::fields` hands it to the code building and parsing records.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldNames {
+    /// Names by the variable that set them, e.g. `TEABLE_FIELD_WORK_HOUR_HOURS`
+    overrides: HashMap<&'static str, String>,
+}
+
+impl FieldNames {
+    /// Looks up the override of every field of every table
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut names = FieldNames::default();
+        names.read::<MemberField>(&lookup);
+        names.read::<WorkHourField>(&lookup);
+        names.read::<EventField>(&lookup);
+        names.read::<SignupField>(&lookup);
+        names.read::<SettingField>(&lookup);
+        names
+    }
+
+    fn read<F: TeableField>(&mut self, lookup: &impl Fn(&str) -> Option<String>) {
+        for field in F::ALL {
+            if let Some(name) = lookup(field.env_var()).filter(|v| !v.trim().is_empty()) {
+                self.overrides
+                    .insert(field.env_var(), name.trim().to_string());
+            }
+        }
+    }
+}
+
+macro_rules! teable_fields {
+    (
+        $(#[$meta:meta])*
+        $name:ident, $table:literal {
+            $($(#[$variant_meta:meta])* $variant:ident = $default:literal, $key:literal;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+        }
+
+        impl TeableField for $name {
+            const ALL: &'static [Self] = &[$($name::$variant,)*];
+
+            fn default_name(self) -> &'static str {
+                match self {
+                    $($name::$variant => $default,)*
+                }
+            }
+
+            fn env_var(self) -> &'static str {
+                match self {
+                    $($name::$variant => concat!("TEABLE_FIELD_", $table, "_", $key),)*
+                }
+            }
+        }
+    };
+}
+
+teable_fields! {
+    /// Fields of the members table
+    MemberField, "MEMBER" {
+        FirstName = "Vorname", "FIRST_NAME";
+        LastName = "Nachname", "LAST_NAME";
+        Email = "Email", "EMAIL";
+        /// Name or number shared by the members of a family
+        Family = "Familie", "FAMILY";
+        BirthDate = "Geburtsdatum", "BIRTH_DATE";
+        JoinDate = "Eintrittsdatum", "JOIN_DATE";
+        Contribution = "Beitrag", "CONTRIBUTION";
+        ContributionPaid = "Beitrag bezahlt", "CONTRIBUTION_PAID";
+        SepaDate = "SEPA-Einzug", "SEPA_DATE";
+        Phone = "Telefon", "PHONE";
+        Street = "Straße", "STREET";
+        PostalCode = "PLZ", "POSTAL_CODE";
+        City = "Ort", "CITY";
+        /// Hours the board set for the member, replacing the ones by age and entry date
+        RequiredHours = "Sollstunden", "REQUIRED_HOURS";
+    }
+}
+
+teable_fields! {
+    /// Fields of the work hours table
+    WorkHourField, "WORK_HOUR" {
+        /// Link to the member record
+        Member = "Mitglied_id", "MEMBER";
+        LastName = "Nachname", "LAST_NAME";
+        FirstName = "Vorname", "FIRST_NAME";
+        CreatedOn = "Created on", "CREATED_ON";
+        Date = "Datum", "DATE";
+        Description = "Tätigkeit", "DESCRIPTION";
+        Hours = "Stunden", "HOURS";
+        Status = "Status", "STATUS";
+        ReviewComment = "Prüfkommentar", "REVIEW_COMMENT";
+        /// ID of the entry a correction entry corrects
+        Correction = "Korrektur_von", "CORRECTION";
+        /// The member's note on an entry, e.g. which courts were swept
+        Note = "Notiz", "NOTE";
+        /// Photos or documents of an entry
+        Attachments = "Anhänge", "ATTACHMENTS";
+        /// ID of the event an entry was worked at
+        Event = "Arbeitseinsatz_id", "EVENT";
+    }
+}
+
+teable_fields! {
+    /// Fields of the work events table
+    EventField, "EVENT" {
+        Title = "Titel", "TITLE";
+        Date = "Datum", "DATE";
+        Description = "Beschreibung", "DESCRIPTION";
+        NeededHelpers = "Benötigte Helfer", "NEEDED_HELPERS";
+        Hours = "Stunden", "HOURS";
+        Status = "Status", "STATUS";
+    }
+}
+
+teable_fields! {
+    /// Fields of the event sign-ups table
+    SignupField, "SIGNUP" {
+        /// Link to the event record
+        Event = "Einsatz_id", "EVENT";
+        /// Link to the member record
+        Member = "Mitglied_id", "MEMBER";
+        FirstName = "Vorname", "FIRST_NAME";
+        LastName = "Nachname", "LAST_NAME";
+    }
+}
+
+teable_fields! {
+    /// Fields of the key/value settings table
+    SettingField, "SETTING" {
+        /// Year a setting applies to; settings without one apply to every year
+        Year = "Jahr", "YEAR";
+        Key = "Schlüssel", "KEY";
+        Value = "Wert", "VALUE";
+    }
+}
+
+/// Member fields most lookups need: name, email and what the required hours depend on
+pub const MEMBER_DEFAULT_PROJECTION: [MemberField; 7] = [
+    MemberField::FirstName,
+    MemberField::LastName,
+    MemberField::Email,
+    MemberField::Family,
+    MemberField::BirthDate,
+    MemberField::JoinDate,
+    MemberField::RequiredHours,
+];
+
+/// `record` with its overridden field names replaced by the default names, which the typed
+/// field structs of `teable_record` deserialize. Borrowed when nothing is overridden.
+pub fn with_default_names<'a, F: TeableField>(
+    record: &'a Value,
+    names: &FieldNames,
+) -> Cow<'a, Value> {
+    let renames: Vec<(&str, &str)> = F::ALL
+        .iter()
+        .map(|field| (field.name(names), field.default_name()))
+        .filter(|(name, default)| name != default)
+        .collect();
+    if renames.is_empty() || !record["fields"].is_object() {
+        return Cow::Borrowed(record);
+    }
+    let mut record = record.clone();
+    if let Some(fields) = record["fields"].as_object_mut() {
+        rename_fields(fields, &renames);
+    }
+    Cow::Owned(record)
+}
+
+/// Moves each `(name, default)` field to its default name. All values are taken out before
+/// any is put back, so two fields swapping their names don't overwrite each other.
+pub fn rename_fields(fields: &mut Map<String, Value>, renames: &[(&str, &str)]) {
+    let values: Vec<Option<Value>> = renames
+        .iter()
+        .map(|(name, _)| fields.remove(*name))
+        .collect();
+    for ((_, default), value) in renames.iter().zip(values) {
+        fields.remove(*default);
+        if let Some(value) = value {
+            fields.insert(default.to_string(), value);
+        }
+    }
+}
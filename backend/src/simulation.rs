@@ -0,0 +1,168 @@
+use crate::models::{Member, WorkHour, WorkHourStatus};
+use crate::rules::WorkHourRules;
+use crate::utils::get_member_work_hours_info;
+use chrono::{DateTime, Datelike};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+
+/// Body of `POST /admin/rules/simulate`
+#[derive(Debug, Deserialize, Type)]
+pub struct SimulateRulesRequest {
+    pub rules: WorkHourRules,
+    /// Fee members pay per missing hour, in euros
+    pub fee_per_missing_hour: f64,
+}
+
+impl SimulateRulesRequest {
+    /// Returns the German error message for invalid proposals
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_hours = |hours: f64| hours.is_finite() && hours >= 0.0;
+        if !valid_hours(self.rules.required_hours) {
+            return Err("Die Arbeitsstunden dürfen nicht negativ sein.".to_string());
+        }
+        if !self.rules.family_max_hours.is_none_or(valid_hours) {
+            return Err("Die Familienstunden dürfen nicht negativ sein.".to_string());
+        }
+        if self.rules.min_age >= self.rules.max_age {
+            return Err("Das Mindestalter muss unter dem Höchstalter liegen.".to_string());
+        }
+        if !valid_hours(self.fee_per_missing_hour) {
+            return Err("Die Gebühr pro fehlender Stunde darf nicht negativ sein.".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// How a proposed rule set would have affected one group of members
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Type)]
+pub struct SegmentImpact {
+    /// Age band in the evaluated year, e.g. `18-29`, or `gesamt` for all members
+    pub segment: String,
+    pub members: u32,
+    /// Members whose required hours differ under the proposal
+    pub requirement_changed: u32,
+    pub newly_required: u32,
+    pub newly_exempt: u32,
+    pub required_hours_before: f64,
+    pub required_hours_after: f64,
+    /// Fees for missing hours under the current and the proposed rules, in euros
+    pub fee_income_before: f64,
+    pub fee_income_after: f64,
+    pub fee_income_delta: f64,
+}
+
+/// Impact of a proposed rule set, evaluated against the members and hours of `year`
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct RuleSimulation {
+    pub year: i32,
+    pub current: WorkHourRules,
+    pub proposed: WorkHourRules,
+    pub fee_per_missing_hour: f64,
+    /// Age bands from young to old, then members without a valid birth date
+    pub segments: Vec<SegmentImpact>,
+    pub total: SegmentImpact,
+}
+
+/// Age bands by the age reached during the evaluated year
+const AGE_SEGMENTS: [(i32, i32, &str); 5] = [
+    (0, 17, "bis 17"),
+    (18, 29, "18-29"),
+    (30, 49, "30-49"),
+    (50, 69, "50-69"),
+    (70, i32::MAX, "ab 70"),
+];
+const UNKNOWN_AGE_SEGMENT: &str = "ohne Geburtsdatum";
+const TOTAL_SEGMENT: &str = "gesamt";
+
+fn age_segment(member: &Member, year: i32) -> &'static str {
+    let Ok(birth_date) = DateTime::parse_from_rfc3339(&member.birth_date) else {
+        return UNKNOWN_AGE_SEGMENT;
+    };
+    let age = year - birth_date.naive_utc().date().year();
+    AGE_SEGMENTS
+        .iter()
+        .find(|(from, to, _)| (*from..=*to).contains(&age))
+        .map(|(_, _, label)| *label)
+        .unwrap_or(UNKNOWN_AGE_SEGMENT)
+}
+
+/// Approved hours per member ID; like on the dashboard, only approved hours count
+pub fn approved_hours_by_member(work_hours: &[WorkHour]) -> HashMap<String, f64> {
+    let mut hours: HashMap<String, f64> = HashMap::new();
+    for work_hour in work_hours {
+        if !WorkHourStatus::from_teable(work_hour.status.as_deref()).counts_toward_total() {
+            continue;
+        }
+        if let (Some(member_id), Some(duration)) =
+            (work_hour.get_member_id(), work_hour.duration_hours)
+        {
+            *hours.entry(member_id).or_default() += duration;
+        }
+    }
+    hours
+}
+
+/// Evaluates both rule sets for every member with the hours they actually did in `year`.
+/// Fees are estimated per member; family quotas are not taken into account.
+pub fn simulate(
+    members: &[Member],
+    hours_by_member: &HashMap<String, f64>,
+    year: i32,
+    current: &WorkHourRules,
+    proposed: &WorkHourRules,
+    fee_per_missing_hour: f64,
+) -> RuleSimulation {
+    let mut segments: Vec<SegmentImpact> = AGE_SEGMENTS
+        .iter()
+        .map(|(_, _, label)| *label)
+        .chain([UNKNOWN_AGE_SEGMENT])
+        .map(|label| SegmentImpact {
+            segment: label.to_string(),
+            ..SegmentImpact::default()
+        })
+        .collect();
+    let mut total = SegmentImpact {
+        segment: TOTAL_SEGMENT.to_string(),
+        ..SegmentImpact::default()
+    };
+
+    for member in members {
+        let done = hours_by_member.get(&member.id).copied().unwrap_or(0.0);
+        let (before, _) = get_member_work_hours_info(member, year, current);
+        let (after, _) = get_member_work_hours_info(member, year, proposed);
+        let fee = |required: f64| (required - done).max(0.0) * fee_per_missing_hour;
+
+        let label = age_segment(member, year);
+        let segment = segments
+            .iter_mut()
+            .find(|segment| segment.segment == label)
+            .expect("every age segment is listed");
+        for impact in [segment, &mut total] {
+            impact.members += 1;
+            if before != after {
+                impact.requirement_changed += 1;
+            }
+            if before == 0.0 && after > 0.0 {
+                impact.newly_required += 1;
+            }
+            if before > 0.0 && after == 0.0 {
+                impact.newly_exempt += 1;
+            }
+            impact.required_hours_before += before;
+            impact.required_hours_after += after;
+            impact.fee_income_before += fee(before);
+            impact.fee_income_after += fee(after);
+            impact.fee_income_delta = impact.fee_income_after - impact.fee_income_before;
+        }
+    }
+
+    RuleSimulation {
+        year,
+        current: current.clone(),
+        proposed: proposed.clone(),
+        fee_per_missing_hour,
+        segments,
+        total,
+    }
+}
@@ -0,0 +1,66 @@
+use crate::error::AppError;
+use axum::body::Bytes;
+use axum::http::{StatusCode, Uri};
+use axum::response::{Html, IntoResponse, Response};
+use axum::Router;
+use std::path::PathBuf;
+use tower_http::services::{ServeDir, ServeFile};
+use tracing::{info, warn};
+
+/// Shown for SPA routes when there is no frontend build, e.g. when only the API runs locally
+const MISSING_FRONTEND_HTML: &str = "<!doctype html><html lang=\"de\"><head><meta charset=\"utf-8\"><title>TSV Tennis</title></head><body><p>Das Frontend ist nicht installiert. Die API ist unter <a href=\"/api/docs\">/api/docs</a> erreichbar.</p></body></html>";
+
+/// The build of the SPA in `STATIC_DIR`. `index.html` is read once at startup, as every page
+/// of the SPA is served from it; assets are read from disk per request.
+#[derive(Clone)]
+pub struct StaticFiles {
+    dir: PathBuf,
+    index_html: Option<Bytes>,
+}
+
+impl StaticFiles {
+    /// Reads `index.html` of `dir`. A missing build is logged, not fatal: the API works
+    /// without the frontend.
+    pub async fn load(dir: PathBuf) -> Self {
+        let index_html = match tokio::fs::read(dir.join("index.html")).await {
+            Ok(content) => {
+                info!("Static Files: Serving the frontend from {}", dir.display());
+                Some(Bytes::from(content))
+            }
+            Err(e) => {
+                warn!(
+                    "Static Files: No index.html in {} ({}); serving the API only",
+                    dir.display(),
+                    e
+                );
+                None
+            }
+        };
+        StaticFiles { dir, index_html }
+    }
+
+    /// Assets, icons and the SPA fallback, which answers every other path outside `/api`
+    /// with `index.html` for the client-side router
+    pub fn router<S>(&self) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let index_html = self.index_html.clone();
+        Router::new()
+            .nest_service("/assets", ServeDir::new(self.dir.join("assets")))
+            .route_service("/favicon.ico", ServeFile::new(self.dir.join("favicon.ico")))
+            .route_service("/vite.svg", ServeFile::new(self.dir.join("vite.svg")))
+            .fallback(move |uri: Uri| spa_fallback(uri, index_html.clone()))
+    }
+}
+
+async fn spa_fallback(uri: Uri, index_html: Option<Bytes>) -> Response {
+    // Unknown API routes get the usual JSON error, not the SPA
+    if uri.path().starts_with("/api") {
+        return AppError::not_found("API endpoint not found").into_response();
+    }
+    match index_html {
+        Some(content) => Html(content).into_response(),
+        None => (StatusCode::NOT_FOUND, Html(MISSING_FRONTEND_HTML)).into_response(),
+    }
+}
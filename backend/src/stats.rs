@@ -0,0 +1,302 @@
+use crate::certificate::{self, CertificateCategory};
+use crate::database::Database;
+use crate::models::{Member, WorkHour, WorkHourStatus};
+use crate::rules::WorkHourRules;
+use crate::simulation::approved_hours_by_member;
+use crate::suggestions;
+use crate::teable;
+use crate::teable::TeableClient;
+use crate::utils::{family_quota, get_member_work_hours_info};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use specta::Type;
+use std::collections::BTreeMap;
+
+/// Members listed as top contributors in the club statistics
+const TOP_CONTRIBUTORS: usize = 10;
+
+/// Hours logged on one day across all members, as materialized in SQLite
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyHours {
+    pub date: NaiveDate,
+    pub hours: f64,
+    pub entries: u32,
+}
+
+/// One calendar week (KW, ISO 8601) of the heatmap
+#[derive(Debug, Serialize, Type)]
+pub struct HeatmapWeek {
+    /// Differs from the requested year for the first days of January or the last of December
+    pub iso_year: i32,
+    pub week: u32,
+    /// Monday to Sunday
+    pub hours_by_weekday: Vec<f64>,
+    pub total: f64,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct WorkHourHeatmap {
+    pub year: i32,
+    /// Every calendar week touching the year, in order; days outside the year are 0
+    pub weeks: Vec<HeatmapWeek>,
+    /// Monday to Sunday, summed over the year
+    pub hours_by_weekday: Vec<f64>,
+    pub total_hours: f64,
+    pub entries: u32,
+    /// When the materialized stats were last rebuilt from Teable
+    pub refreshed_at: Option<String>,
+}
+
+/// Rebuilds the materialized daily totals and description counts of a year from Teable,
+/// returning the number of days
+pub async fn refresh_year(
+    client: &TeableClient,
+    database: &Database,
+    year: i32,
+) -> anyhow::Result<usize> {
+    let work_hours = teable::get_work_hours_by_year(client, year).await?;
+    let days = daily_totals(year, &work_hours);
+    database
+        .replace_description_usage(year, &suggestions::usage_counts(&work_hours))
+        .await?;
+    database.replace_daily_stats(year, &days).await?;
+    Ok(days.len())
+}
+
+/// Sums hours per day. Rejected entries are left out since that work didn't happen as logged;
+/// submitted ones count, the heatmap is about when people work rather than what is credited.
+pub fn daily_totals(year: i32, work_hours: &[WorkHour]) -> Vec<DailyHours> {
+    let mut days: BTreeMap<NaiveDate, DailyHours> = BTreeMap::new();
+    for work_hour in work_hours {
+        if WorkHourStatus::from_teable(work_hour.status.as_deref()) == WorkHourStatus::Rejected {
+            continue;
+        }
+        let Some(date) = work_hour
+            .date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .filter(|d| d.year() == year)
+        else {
+            continue;
+        };
+        let day = days.entry(date).or_insert(DailyHours {
+            date,
+            hours: 0.0,
+            entries: 0,
+        });
+        day.hours += work_hour.duration_hours.unwrap_or(0.0);
+        // A correction reduces the hours of its entry but isn't work of its own
+        day.entries += u32::from(work_hour.corrects.is_none());
+    }
+    days.into_values().collect()
+}
+
+pub fn build_heatmap(
+    year: i32,
+    days: &[DailyHours],
+    refreshed_at: Option<DateTime<Utc>>,
+) -> WorkHourHeatmap {
+    let hours_by_date: BTreeMap<NaiveDate, f64> =
+        days.iter().map(|day| (day.date, day.hours)).collect();
+
+    let mut weeks: Vec<HeatmapWeek> = Vec::new();
+    let mut hours_by_weekday = vec![0.0; 7];
+    let dates = NaiveDate::from_ymd_opt(year, 1, 1)
+        .into_iter()
+        .flat_map(|first| first.iter_days())
+        .take_while(|date| date.year() == year);
+
+    for date in dates {
+        let iso_week = date.iso_week();
+        if weeks
+            .last()
+            .is_none_or(|w| w.iso_year != iso_week.year() || w.week != iso_week.week())
+        {
+            weeks.push(HeatmapWeek {
+                iso_year: iso_week.year(),
+                week: iso_week.week(),
+                hours_by_weekday: vec![0.0; 7],
+                total: 0.0,
+            });
+        }
+
+        let Some(&hours) = hours_by_date.get(&date) else {
+            continue;
+        };
+        let weekday = date.weekday().num_days_from_monday() as usize;
+        if let Some(week) = weeks.last_mut() {
+            week.hours_by_weekday[weekday] += hours;
+            week.total += hours;
+        }
+        hours_by_weekday[weekday] += hours;
+    }
+
+    for week in &mut weeks {
+        week.hours_by_weekday
+            .iter_mut()
+            .for_each(|h| *h = round_hours(*h));
+        week.total = round_hours(week.total);
+    }
+
+    WorkHourHeatmap {
+        year,
+        weeks,
+        hours_by_weekday: hours_by_weekday.into_iter().map(round_hours).collect(),
+        total_hours: round_hours(days.iter().map(|day| day.hours).sum()),
+        entries: days.iter().map(|day| day.entries).sum(),
+        refreshed_at: refreshed_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+/// Avoids floating point noise such as 7.499999999 in the JSON output
+fn round_hours(hours: f64) -> f64 {
+    (hours * 100.0).round() / 100.0
+}
+
+/// Approved hours of one member, for the top contributors
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct Contributor {
+    pub member_id: String,
+    pub name: String,
+    pub hours: f64,
+}
+
+/// Work logged in one month of the year
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct MonthHours {
+    /// 1 to 12
+    pub month: u32,
+    /// Approved hours
+    pub hours: f64,
+    /// Entries that aren't rejected, approved or not
+    pub entries: u32,
+}
+
+/// The year of the whole club, for the report at the annual general meeting
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct ClubStatistics {
+    pub year: i32,
+    /// Approved hours of all members
+    pub total_hours: f64,
+    /// Hours still waiting for review
+    pub pending_hours: f64,
+    /// Approved hours by activity (`Tätigkeit`), the most hours first
+    pub categories: Vec<CertificateCategory>,
+    /// Members with the most approved hours, the most first
+    pub top_contributors: Vec<Contributor>,
+    /// Families and members without a family that owed hours; families owe their quota
+    /// together, as on the dashboard
+    pub obligated: u32,
+    /// Those of `obligated` that did all their hours
+    pub fulfilled: u32,
+    /// `fulfilled` in percent of `obligated`; 100 if nobody owed hours
+    pub fulfillment_rate: f64,
+    /// January to December
+    pub months: Vec<MonthHours>,
+}
+
+/// Sums the work hours of `year` across the club. Only approved hours are credited;
+/// `members` are needed for names and for who owed hours.
+pub fn club_statistics(
+    members: &[Member],
+    work_hours: &[WorkHour],
+    year: i32,
+    rules: &WorkHourRules,
+) -> ClubStatistics {
+    let hours_by_member = approved_hours_by_member(work_hours);
+
+    let mut months: Vec<MonthHours> = (1..=12)
+        .map(|month| MonthHours {
+            month,
+            hours: 0.0,
+            entries: 0,
+        })
+        .collect();
+    let mut pending_hours = 0.0;
+    for work_hour in work_hours {
+        let status = WorkHourStatus::from_teable(work_hour.status.as_deref());
+        if status == WorkHourStatus::Rejected {
+            continue;
+        }
+        if !status.counts_toward_total() {
+            pending_hours += work_hour.duration_hours.unwrap_or(0.0);
+        }
+        let Some(date) = work_hour
+            .date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d.get(..10).unwrap_or(d), "%Y-%m-%d").ok())
+            .filter(|d| d.year() == year)
+        else {
+            continue;
+        };
+        let month = &mut months[date.month0() as usize];
+        if status.counts_toward_total() {
+            month.hours += work_hour.duration_hours.unwrap_or(0.0);
+        }
+        // A correction reduces the hours of its entry but isn't work of its own
+        month.entries += u32::from(work_hour.corrects.is_none());
+    }
+    for month in &mut months {
+        month.hours = round_hours(month.hours);
+    }
+
+    let mut contributors: Vec<Contributor> = members
+        .iter()
+        .filter_map(|member| {
+            let hours = hours_by_member.get(&member.id).copied().unwrap_or(0.0);
+            (hours > 0.0).then(|| Contributor {
+                member_id: member.id.clone(),
+                name: member.name(),
+                hours: round_hours(hours),
+            })
+        })
+        .collect();
+    contributors.sort_by(|a, b| b.hours.total_cmp(&a.hours).then(a.name.cmp(&b.name)));
+    contributors.truncate(TOP_CONTRIBUTORS);
+
+    let mut groups: BTreeMap<String, Vec<&Member>> = BTreeMap::new();
+    for member in members {
+        let key = match member.family_id.as_deref().map(str::trim) {
+            Some(family) if !family.is_empty() => format!("family:{family}"),
+            _ => format!("member:{}", member.id),
+        };
+        groups.entry(key).or_default().push(member);
+    }
+    let (mut obligated, mut fulfilled) = (0, 0);
+    for group in groups.values() {
+        let member_hours: Vec<f64> = group
+            .iter()
+            .map(|member| hours_by_member.get(&member.id).copied().unwrap_or(0.0))
+            .collect();
+        let member_required: Vec<f64> = group
+            .iter()
+            .map(|member| get_member_work_hours_info(member, year, rules).0)
+            .collect();
+        let quota = family_quota(&member_hours, &member_required, rules);
+        if quota.required > 0.0 {
+            obligated += 1;
+            fulfilled += u32::from(quota.remaining <= 0.0);
+        }
+    }
+
+    let mut categories = certificate::summarize_work_hours(work_hours).categories;
+    for category in &mut categories {
+        category.hours = round_hours(category.hours);
+    }
+
+    ClubStatistics {
+        year,
+        total_hours: round_hours(hours_by_member.values().sum()),
+        pending_hours: round_hours(pending_hours),
+        categories,
+        top_contributors: contributors,
+        obligated,
+        fulfilled,
+        fulfillment_rate: if obligated > 0 {
+            (f64::from(fulfilled) / f64::from(obligated) * 1000.0).round() / 10.0
+        } else {
+            100.0
+        },
+        months,
+    }
+}
@@ -0,0 +1,510 @@
+use crate::config::{Config, FileStoreConfig, S3Config};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
+use tracing::{debug, info};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// File contents as a stream of chunks, so uploads and downloads never sit in memory whole
+pub type ByteStream = Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send>>;
+
+pub struct StoredFile {
+    pub content_type: String,
+    pub size: Option<u64>,
+    pub body: ByteStream,
+}
+
+/// Storage for uploaded files (attachments, documents, avatars).
+/// Keys are relative paths like `attachments/2025/rec123.pdf`, see `validate_key`.
+#[async_trait]
+pub trait FileStore: Send + Sync {
+    /// Stores the file under `key`, replacing an existing one; returns the size in bytes
+    async fn put(&self, key: &str, content_type: &str, body: ByteStream) -> Result<u64>;
+
+    async fn get(&self, key: &str) -> Result<Option<StoredFile>>;
+
+    /// Deleting a missing file is not an error
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// URL a client can download the file from without a bearer token until it expires
+    fn signed_url(&self, key: &str, expires_in: Duration) -> Result<String>;
+}
+
+pub type SharedFileStore = Arc<dyn FileStore>;
+
+/// Builds the store selected by `FILE_STORE`
+pub fn from_config(client: Client, config: &Config) -> Result<SharedFileStore> {
+    Ok(match config.file_store.clone() {
+        FileStoreConfig::Local { root } => {
+            info!("Storage: Storing files on local disk in {}", root.display());
+            Arc::new(LocalFileStore::new(root, config))
+        }
+        FileStoreConfig::S3(s3) => {
+            info!(
+                "Storage: Storing files in bucket {} at {}",
+                s3.bucket, s3.endpoint
+            );
+            Arc::new(S3FileStore::new(client, s3)?)
+        }
+    })
+}
+
+/// Keys are slash-separated segments of letters, digits, `-`, `_` and `.`; no segment may be
+/// empty or start with a dot, which rules out `..` and hidden files
+pub fn validate_key(key: &str) -> Result<()> {
+    let valid = !key.is_empty()
+        && key.len() <= 512
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && !segment.starts_with('.')
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("Invalid file key: {key:?}"))
+    }
+}
+
+/// How long URLs handed out by `GET /api/file-url/{key}` stay valid
+pub const FILE_URL_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Who may get a download URL for a file, derived from the first segments of its key
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileAccess {
+    /// `avatars/...`: member photos are visible to everyone logged in
+    Members,
+    /// `members/{member_id}/...`: the member's own files
+    Owner(String),
+    /// Everything else (documents, branding, ...)
+    Board,
+}
+
+impl FileAccess {
+    pub fn for_key(key: &str) -> Self {
+        let mut segments = key.split('/');
+        match (segments.next(), segments.next(), segments.next()) {
+            (Some("avatars"), Some(_), _) => FileAccess::Members,
+            (Some("members"), Some(member_id), Some(_)) => FileAccess::Owner(member_id.to_string()),
+            _ => FileAccess::Board,
+        }
+    }
+
+    /// Board members may access every file
+    pub fn allows(&self, member_id: &str, is_admin: bool) -> bool {
+        is_admin
+            || match self {
+                FileAccess::Members => true,
+                FileAccess::Owner(owner_id) => owner_id == member_id,
+                FileAccess::Board => false,
+            }
+    }
+
+    /// Who may upload or delete the file at `key`: members their own files and their avatar
+    /// (`avatars/{member_id}.{extension}`), board members every file
+    pub fn allows_change(key: &str, member_id: &str, is_admin: bool) -> bool {
+        is_admin
+            || match FileAccess::for_key(key) {
+                FileAccess::Members => key
+                    .strip_prefix("avatars/")
+                    .and_then(|file_name| file_name.split_once('.'))
+                    .is_some_and(|(stem, _)| stem == member_id),
+                FileAccess::Owner(owner_id) => owner_id == member_id,
+                FileAccess::Board => false,
+            }
+    }
+}
+
+/// Origin (`scheme://host:port`) of the SPA, the only one allowed to read files cross-origin
+pub fn frontend_origin(frontend_url: &str) -> Option<String> {
+    Url::parse(frontend_url)
+        .ok()
+        .map(|url| url.origin().ascii_serialization())
+        .filter(|origin| origin != "null")
+}
+
+/// Content types browsers may render inline; anything else is sent as an attachment so
+/// uploaded HTML or SVG can't run scripts on the API's origin
+pub fn is_inline_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    matches!(
+        mime.as_str(),
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp" | "application/pdf"
+    )
+}
+
+/// Files below a root directory on the server. Signed URLs point at `GET /api/files/{key}`,
+/// which checks the signature and streams the file.
+pub struct LocalFileStore {
+    root: PathBuf,
+    frontend_url: String,
+    signing_key: Vec<u8>,
+}
+
+impl LocalFileStore {
+    pub fn new(root: impl Into<PathBuf>, config: &Config) -> Self {
+        LocalFileStore {
+            root: root.into(),
+            frontend_url: config.frontend_url.clone(),
+            signing_key: local_url_signing_key(config),
+        }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    /// The content type is kept next to the file; keys can't start with a dot, so it never collides
+    fn content_type_path(path: &Path) -> PathBuf {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        path.with_file_name(format!(".{file_name}.content-type"))
+    }
+}
+
+#[async_trait]
+impl FileStore for LocalFileStore {
+    async fn put(&self, key: &str, content_type: &str, mut body: ByteStream) -> Result<u64> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Write to a temporary file first so readers never see a partial upload
+        let partial = path.with_file_name(format!(".{}.part", uuid::Uuid::new_v4()));
+        let mut file = tokio::fs::File::create(&partial).await?;
+        let mut size = 0u64;
+        let written: Result<()> = async {
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk?;
+                size += chunk.len() as u64;
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+            Ok(())
+        }
+        .await;
+        if let Err(e) = written {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e);
+        }
+
+        tokio::fs::write(Self::content_type_path(&path), content_type).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        debug!("Storage: Stored {} ({} bytes) on disk", key, size);
+        Ok(size)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredFile>> {
+        let path = self.path(key)?;
+        let file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let size = file.metadata().await?.len();
+        let content_type = tokio::fs::read_to_string(Self::content_type_path(&path))
+            .await
+            .unwrap_or_else(|_| "application/octet-stream".to_string());
+
+        Ok(Some(StoredFile {
+            content_type,
+            size: Some(size),
+            body: Box::pin(ReaderStream::new(file)),
+        }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path(key)?;
+        for file in [Self::content_type_path(&path), path] {
+            match tokio::fs::remove_file(&file).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn signed_url(&self, key: &str, expires_in: Duration) -> Result<String> {
+        validate_key(key)?;
+        let expires = Utc::now().timestamp() + expires_in.as_secs() as i64;
+        let signature = sign_local_url(&self.signing_key, key, expires)?;
+        Ok(format!(
+            "{}/api/files/{}?expires={}&signature={}",
+            self.frontend_url, key, expires, signature
+        ))
+    }
+}
+
+/// Key for signing local download URLs, derived so these signatures can't be used as JWTs
+fn local_url_signing_key(config: &Config) -> Vec<u8> {
+    format!("{}:files", config.jwt_secret).into_bytes()
+}
+
+fn sign_local_url(signing_key: &[u8], key: &str, expires: i64) -> Result<String> {
+    let mut mac = HmacSha256::new_from_slice(signing_key)?;
+    mac.update(format!("{key}\n{expires}").as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Checks a signature created by `LocalFileStore::signed_url`
+pub fn verify_local_url(config: &Config, key: &str, expires: i64, signature: &str) -> bool {
+    if expires < Utc::now().timestamp() {
+        return false;
+    }
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let Ok(mut mac) = HmacSha256::new_from_slice(&local_url_signing_key(config)) else {
+        return false;
+    };
+    mac.update(format!("{key}\n{expires}").as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// S3-compatible object storage (AWS S3, MinIO, Hetzner, Cloudflare R2, ...).
+/// All requests, including the server's own, use presigned URLs (AWS Signature V4).
+pub struct S3FileStore {
+    client: Client,
+    config: S3Config,
+    endpoint: Url,
+}
+
+impl S3FileStore {
+    pub fn new(client: Client, config: S3Config) -> Result<Self> {
+        let endpoint = Url::parse(&config.endpoint)?;
+        if endpoint.host_str().is_none() {
+            return Err(anyhow!("S3 endpoint without host: {}", config.endpoint));
+        }
+        Ok(S3FileStore {
+            client,
+            config,
+            endpoint,
+        })
+    }
+
+    /// Presigns a request the way the AWS "query string authentication" docs describe
+    pub fn presign(
+        &self,
+        method: &str,
+        key: &str,
+        expires_in: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<String> {
+        validate_key(key)?;
+
+        let endpoint_host = self.endpoint.host_str().unwrap_or_default();
+        let port = self
+            .endpoint
+            .port()
+            .map(|port| format!(":{port}"))
+            .unwrap_or_default();
+        let base_path = self.endpoint.path().trim_end_matches('/');
+        let (host, path) = if self.config.path_style {
+            (
+                format!("{endpoint_host}{port}"),
+                format!("{base_path}/{}/{}", self.config.bucket, key),
+            )
+        } else {
+            (
+                format!("{}.{endpoint_host}{port}", self.config.bucket),
+                format!("{base_path}/{key}"),
+            )
+        };
+        let canonical_uri = aws_uri_encode(&path, false);
+
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{date}/{}/s3/aws4_request", self.config.region);
+        let mut query = [
+            ("X-Amz-Algorithm", "AWS4-HMAC-SHA256".to_string()),
+            (
+                "X-Amz-Credential",
+                format!("{}/{scope}", self.config.access_key_id),
+            ),
+            ("X-Amz-Date", amz_date.clone()),
+            // S3 accepts at most seven days
+            (
+                "X-Amz-Expires",
+                expires_in.as_secs().clamp(1, 604_800).to_string(),
+            ),
+            ("X-Amz-SignedHeaders", "host".to_string()),
+        ];
+        query.sort_by(|a, b| a.0.cmp(b.0));
+        let canonical_query = query
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "{}={}",
+                    aws_uri_encode(name, true),
+                    aws_uri_encode(value, true)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let canonical_request = format!(
+            "{method}\n{canonical_uri}\n{canonical_query}\nhost:{host}\n\nhost\nUNSIGNED-PAYLOAD"
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut signing_key = format!("AWS4{}", self.config.secret_access_key).into_bytes();
+        for part in [
+            date.as_str(),
+            self.config.region.as_str(),
+            "s3",
+            "aws4_request",
+        ] {
+            signing_key = hmac_sha256(&signing_key, part.as_bytes())?;
+        }
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes())?);
+
+        Ok(format!(
+            "{}://{host}{canonical_uri}?{canonical_query}&X-Amz-Signature={signature}",
+            self.endpoint.scheme()
+        ))
+    }
+}
+
+/// How long the server's own presigned requests stay valid
+const S3_REQUEST_TTL: Duration = Duration::from_secs(300);
+
+#[async_trait]
+impl FileStore for S3FileStore {
+    async fn put(&self, key: &str, content_type: &str, mut body: ByteStream) -> Result<u64> {
+        let url = self.presign("PUT", key, S3_REQUEST_TTL, Utc::now())?;
+
+        // S3 rejects uploads without Content-Length, so spool to a temporary file to learn it
+        let spool_path = std::env::temp_dir().join(format!("tsv-upload-{}", uuid::Uuid::new_v4()));
+        let mut spool = tokio::fs::File::create(&spool_path).await?;
+        let mut size = 0u64;
+        let spooled: Result<()> = async {
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk?;
+                size += chunk.len() as u64;
+                spool.write_all(&chunk).await?;
+            }
+            spool.flush().await?;
+            Ok(())
+        }
+        .await;
+
+        let uploaded: Result<()> = async {
+            spooled?;
+            let file = tokio::fs::File::open(&spool_path).await?;
+            let response = self
+                .client
+                .put(url)
+                .header("Content-Type", content_type)
+                .header("Content-Length", size)
+                .body(reqwest::Body::wrap_stream(ReaderStream::new(file)))
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                return Err(anyhow!("S3 upload of {key} failed with {status}: {text}"));
+            }
+            Ok(())
+        }
+        .await;
+        let _ = tokio::fs::remove_file(&spool_path).await;
+        uploaded?;
+
+        debug!("Storage: Uploaded {} ({} bytes) to S3", key, size);
+        Ok(size)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredFile>> {
+        let url = self.presign("GET", key, S3_REQUEST_TTL, Utc::now())?;
+        let response = self.client.get(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "S3 download of {key} failed with {}",
+                response.status()
+            ));
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let size = response.content_length();
+        let body = response.bytes_stream().map_err(std::io::Error::other);
+
+        Ok(Some(StoredFile {
+            content_type,
+            size,
+            body: Box::pin(body),
+        }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let url = self.presign("DELETE", key, S3_REQUEST_TTL, Utc::now())?;
+        let response = self.client.delete(url).send().await?;
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(anyhow!(
+                "S3 delete of {key} failed with {}",
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+
+    fn signed_url(&self, key: &str, expires_in: Duration) -> Result<String> {
+        self.presign("GET", key, expires_in, Utc::now())
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = HmacSha256::new_from_slice(key)?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// URI encoding as required by Signature V4: everything but `A-Za-z0-9-_.~` is
+/// percent-encoded, slashes only when `encode_slash` is set
+fn aws_uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
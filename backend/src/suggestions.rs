@@ -0,0 +1,89 @@
+use crate::models::{WorkHour, WorkHourStatus};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{BTreeMap, HashMap};
+
+const MAX_SUGGESTION_CHARS: usize = 100;
+
+/// A description the board suggests for the "Tätigkeit" field, e.g. `Plätze abziehen`. Members
+/// can still type anything else.
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct DescriptionSuggestion {
+    pub id: i64,
+    pub text: String,
+    /// Entries of this and last year with this description, ignoring case and spacing
+    pub uses: u32,
+}
+
+/// Body of `POST /admin/arbeitsstunden/suggestions`
+#[derive(Debug, Deserialize, Type)]
+pub struct CreateSuggestionRequest {
+    pub text: String,
+}
+
+impl CreateSuggestionRequest {
+    /// The text to store, with whitespace tidied up, or the message for the board member
+    pub fn validate(&self) -> Result<String, &'static str> {
+        let text = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return Err("Bitte gib einen Vorschlag ein.");
+        }
+        if text.chars().count() > MAX_SUGGESTION_CHARS {
+            return Err("Vorschläge dürfen höchstens 100 Zeichen lang sein.");
+        }
+        Ok(text)
+    }
+}
+
+/// Key under which descriptions are counted, so `Hecke schneiden ` and `hecke schneiden` match
+pub fn normalize(description: &str) -> String {
+    description
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// How often each description was used in `work_hours`. Rejected entries and corrections don't
+/// count, as they don't describe work done.
+pub fn usage_counts(work_hours: &[WorkHour]) -> BTreeMap<String, u32> {
+    let mut counts = BTreeMap::new();
+    for work_hour in work_hours {
+        if work_hour.corrects.is_some()
+            || WorkHourStatus::from_teable(work_hour.status.as_deref()) == WorkHourStatus::Rejected
+        {
+            continue;
+        }
+        let Some(key) = work_hour
+            .description
+            .as_deref()
+            .map(normalize)
+            .filter(|key| !key.is_empty())
+        else {
+            continue;
+        };
+        *counts.entry(key).or_default() += 1;
+    }
+    counts
+}
+
+/// The suggestions, given as ID and text, most used first and alphabetically among equals
+pub fn rank(
+    suggestions: Vec<(i64, String)>,
+    usage: &HashMap<String, u32>,
+) -> Vec<DescriptionSuggestion> {
+    let mut ranked: Vec<DescriptionSuggestion> = suggestions
+        .into_iter()
+        .map(|(id, text)| DescriptionSuggestion {
+            id,
+            uses: usage.get(&normalize(&text)).copied().unwrap_or(0),
+            text,
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.uses
+            .cmp(&a.uses)
+            .then_with(|| a.text.to_lowercase().cmp(&b.text.to_lowercase()))
+    });
+    ranked
+}
@@ -0,0 +1,100 @@
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// The one question of the survey after a work event
+pub const SURVEY_QUESTION: &str =
+    "Wie zufrieden warst du mit der Organisation des Arbeitseinsatzes?";
+
+/// How long the link in the survey email can be used
+pub const SURVEY_TTL: Duration = Duration::days(14);
+
+/// Longer comments are refused rather than cut off
+const MAX_COMMENT_CHARS: usize = 500;
+
+/// Query of `/survey`, with the token from the emailed link
+#[derive(Debug, Deserialize)]
+pub struct SurveyQuery {
+    pub token: String,
+}
+
+/// What the survey page shows, from `GET /survey`
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SurveyInvitation {
+    pub event_title: String,
+    /// YYYY-MM-DD
+    pub event_date: String,
+    pub question: String,
+    /// The member answered already; each link takes one answer
+    pub answered: bool,
+    /// RFC 3339
+    pub expires_at: String,
+}
+
+/// Body of `POST /survey`
+#[derive(Debug, Deserialize, Type)]
+pub struct SurveyAnswerRequest {
+    /// 1 (not satisfied) to 5 (very satisfied)
+    pub rating: u8,
+    /// What could be organized better
+    pub comment: Option<String>,
+}
+
+impl SurveyAnswerRequest {
+    /// The comment to store, trimmed and `None` if empty, or the message for the member
+    pub fn validate(&self) -> Result<Option<String>, &'static str> {
+        if !(1..=5).contains(&self.rating) {
+            return Err("Bitte gib eine Bewertung von 1 bis 5 an.");
+        }
+        let comment = self
+            .comment
+            .as_deref()
+            .map(str::trim)
+            .filter(|comment| !comment.is_empty());
+        if comment.is_some_and(|comment| comment.chars().count() > MAX_COMMENT_CHARS) {
+            return Err("Der Kommentar darf höchstens 500 Zeichen lang sein.");
+        }
+        Ok(comment.map(str::to_string))
+    }
+}
+
+/// Answers of the survey of one event, for the board. Answers aren't linked to members, so
+/// helpers can be honest.
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct SurveyResults {
+    pub event_id: String,
+    /// Helpers the survey was sent to
+    pub invited: u32,
+    pub responses: u32,
+    /// `None` without answers
+    pub average_rating: Option<f64>,
+    /// Number of answers per rating, index 0 for a rating of 1
+    pub ratings: Vec<u32>,
+    /// Newest first
+    pub comments: Vec<String>,
+}
+
+/// Aggregates the answers, given as rating and comment in the order they came in
+pub fn summarize(event_id: &str, invited: u32, answers: &[(u8, Option<String>)]) -> SurveyResults {
+    let mut ratings = vec![0; 5];
+    for (rating, _) in answers {
+        if let Some(count) = ratings.get_mut(usize::from(*rating).wrapping_sub(1)) {
+            *count += 1;
+        }
+    }
+    let responses = answers.len() as u32;
+    let total: u32 = answers.iter().map(|(rating, _)| u32::from(*rating)).sum();
+    SurveyResults {
+        event_id: event_id.to_string(),
+        invited,
+        responses,
+        average_rating: (responses > 0)
+            .then(|| (f64::from(total) / f64::from(responses) * 100.0).round() / 100.0),
+        ratings,
+        comments: answers
+            .iter()
+            .rev()
+            .filter_map(|(_, comment)| comment.clone())
+            .collect(),
+    }
+}
@@ -1,8 +1,27 @@
-use crate::config::Config;
-use crate::models::{Member, TeableResponse, WorkHour};
+use crate::config::{AdminView, Config};
+use crate::error::{AppError, ErrorCode, TeableApiError};
+use crate::events::{EventSignup, EventStatus, WorkEvent};
+use crate::member_change::BOARD_FIELDS;
+use crate::models::{Member, TeableResponse, WorkHour, WorkHourStatus};
+use crate::profile::{MemberProfile, PROFILE_FIELDS};
+use crate::rules::{Setting, WorkHourRules};
+use crate::schema::{
+    EventField, FieldNames, MemberField, SignupField, TeableField, WorkHourField,
+    MEMBER_DEFAULT_PROJECTION,
+};
+use crate::teable_filter::FilterBuilder;
+use crate::teable_record::{
+    parse_created_records, parse_record, parse_records, records_of, EventFields, EventSignupFields,
+    MemberFields, SettingFields, WorkHourFields,
+};
+use crate::teable_throttle::{SendPolicy, SendThrottled};
+use crate::utils::group_work_hours_by_member;
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
 struct TeableConfig {
@@ -10,32 +29,121 @@ struct TeableConfig {
     token: String,
     members_table_id: String,
     work_hours_table_id: String,
+    events_table_id: Option<String>,
+    event_signups_table_id: Option<String>,
+    settings_table_id: Option<String>,
+    attachment_field_id: Option<String>,
+    fields: FieldNames,
+    /// Rules of the config, which the settings table overrides, see `rules::load`
+    rules: WorkHourRules,
+    send_policy: SendPolicy,
 }
 
-fn get_teable_config() -> Result<TeableConfig, Box<dyn std::error::Error + Send + Sync>> {
-    let config = Config::from_env()?;
-    Ok(TeableConfig {
-        api_url: config.teable_api_url,
-        token: config.teable_token,
-        members_table_id: config.members_table_id,
-        work_hours_table_id: config.work_hours_table_id,
-    })
+impl TeableConfig {
+    /// Table IDs of the events and sign-ups tables, or an error while the feature is off
+    fn event_tables(&self) -> Result<(&str, &str)> {
+        match (&self.events_table_id, &self.event_signups_table_id) {
+            (Some(events), Some(signups)) => Ok((events, signups)),
+            _ => Err(AppError::code(ErrorCode::FeatureDisabled).into()),
+        }
+    }
+}
+
+/// The HTTP client together with the Teable base URL, token and tables it talks to, taken
+/// from `Config` once instead of the environment on every request. Derefs to the plain
+/// `reqwest::Client`, which S3 and telemetry requests share; cheap to clone.
+#[derive(Clone)]
+pub struct TeableClient {
+    http: Client,
+    config: Arc<TeableConfig>,
+}
+
+impl TeableClient {
+    /// With the timeouts, pool and proxy of `config`, see `build_client`
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Self::with_http(build_client(config)?, config))
+    }
+
+    pub fn with_http(http: Client, config: &Config) -> Self {
+        TeableClient {
+            http,
+            config: Arc::new(TeableConfig {
+                api_url: config.teable_api_url.clone(),
+                token: config.teable_token.clone(),
+                members_table_id: config.members_table_id.clone(),
+                work_hours_table_id: config.work_hours_table_id.clone(),
+                events_table_id: config.events_table_id.clone(),
+                event_signups_table_id: config.event_signups_table_id.clone(),
+                settings_table_id: config.settings_table_id.clone(),
+                attachment_field_id: config.attachment_field_id.clone(),
+                fields: config.teable_fields.clone(),
+                rules: WorkHourRules::from_config(config),
+                send_policy: SendPolicy::from_config(config),
+            }),
+        }
+    }
+
+    pub fn http(&self) -> &Client {
+        &self.http
+    }
+
+    pub fn api_url(&self) -> &str {
+        &self.config.api_url
+    }
+
+    /// Field names of the Teable base, for building and parsing its records
+    pub fn fields(&self) -> &FieldNames {
+        &self.config.fields
+    }
+
+    pub fn configured_rules(&self) -> &WorkHourRules {
+        &self.config.rules
+    }
+
+    pub fn send_policy(&self) -> &SendPolicy {
+        &self.config.send_policy
+    }
+}
+
+impl Deref for TeableClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.http
+    }
+}
+
+/// The HTTP client for Teable, also used for S3 and telemetry. Without timeouts a hung
+/// request would hold its worker forever.
+pub fn build_client(config: &Config) -> Result<Client> {
+    let mut builder = Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(
+            config.teable_connect_timeout_secs,
+        ))
+        .timeout(std::time::Duration::from_secs(config.teable_timeout_secs))
+        .pool_max_idle_per_host(config.teable_pool_max_idle)
+        .pool_idle_timeout(std::time::Duration::from_secs(
+            config.teable_pool_idle_timeout_secs,
+        ));
+    if let Some(proxy) = &config.teable_proxy {
+        builder = builder.proxy(
+            reqwest::Proxy::all(proxy).map_err(|e| anyhow::anyhow!("Invalid TEABLE_PROXY: {e}"))?,
+        );
+    }
+    Ok(builder.build()?)
 }
 
 /// Fetches all work hour records for a member at a specific date (exact date, Europe/Berlin timezone)
 pub async fn get_work_hours_for_member_at_date(
-    http_client: &Client,
+    http_client: &TeableClient,
     member_id: &str,
     date: &str,
-) -> Result<Vec<serde_json::Value>, anyhow::Error> {
-    let filter = serde_json::json!({
-        "conjunction": "and",
-        "filterSet": [
-            { "fieldId": "Mitglied_id", "operator": "is", "value": member_id },
-            { "fieldId": "Datum", "operator": "is", "value": { "mode": "exactDate", "exactDate": format!("{}T00:00:00.000Z", date), "timeZone": "Europe/Berlin" } }
-        ]
-    });
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+) -> Result<Vec<WorkHour>, anyhow::Error> {
+    let filter = FilterBuilder::and(http_client.fields())
+        .is(WorkHourField::Member, member_id)
+        .on_day(WorkHourField::Date, date)
+        .build();
+    let cfg = &http_client.config;
     let url = format!(
         "{}/table/{}/record?filter={}",
         cfg.api_url,
@@ -46,16 +154,15 @@ pub async fn get_work_hours_for_member_at_date(
         make_teable_request(http_client, &url, &cfg.token, "work_hours_for_date").await?;
     let response_text = handle_teable_response(response, "work_hours_for_date").await?;
     let teable_response: serde_json::Value = serde_json::from_str(&response_text)?;
-    let records = teable_response["records"]
-        .as_array()
-        .cloned()
-        .unwrap_or_default();
-    Ok(records)
+    Ok(parse_records::<WorkHourFields, _>(
+        &teable_response,
+        http_client.fields(),
+    )?)
 }
 
 /// Makes an authenticated GET request to Teable API
 async fn make_teable_request(
-    client: &Client,
+    client: &TeableClient,
     url: &str,
     token: &str,
     operation: &str,
@@ -66,7 +173,7 @@ async fn make_teable_request(
         .get(url)
         .header("Authorization", format!("Bearer {token}"))
         .header("Accept", "application/json")
-        .send()
+        .send_throttled(&client.config.send_policy)
         .await?;
 
     Ok(response)
@@ -82,11 +189,11 @@ async fn handle_teable_response(response: reqwest::Response, operation: &str) ->
             "Teable {} API error {}: {}",
             operation, status, response_text
         );
-        return Err(anyhow::anyhow!(
-            "Teable API error {}: {}",
-            status,
-            response_text
-        ));
+        return Err(TeableApiError {
+            status: status.as_u16(),
+            body: response_text,
+        }
+        .into());
     }
 
     info!(
@@ -97,30 +204,16 @@ async fn handle_teable_response(response: reqwest::Response, operation: &str) ->
     Ok(response_text)
 }
 
-pub async fn get_member_by_id(client: &Client, id: &str) -> Result<Option<Member>> {
-    get_member_by_id_with_projection(
-        client,
-        id,
-        Some(
-            &[
-                "Vorname",
-                "Nachname",
-                "Email",
-                "Familie",
-                "Geburtsdatum",
-                "Eintrittsdatum",
-            ][..],
-        ),
-    )
-    .await
+pub async fn get_member_by_id(client: &TeableClient, id: &str) -> Result<Option<Member>> {
+    get_member_by_id_with_projection(client, id, Some(&MEMBER_DEFAULT_PROJECTION[..])).await
 }
 
 pub async fn get_member_by_id_with_projection(
-    client: &Client,
+    client: &TeableClient,
     id: &str,
-    projection: Option<&[&str]>,
+    projection: Option<&[MemberField]>,
 ) -> Result<Option<Member>> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+    let cfg = &client.config;
     let url = format!(
         "{}/table/{}/record/{}",
         cfg.api_url, cfg.members_table_id, id
@@ -132,7 +225,7 @@ pub async fn get_member_by_id_with_projection(
             .header("Authorization", format!("Bearer {}", cfg.token))
             .header("Accept", "application/json");
         for field in proj {
-            req = req.query(&[("projection[]", *field)]);
+            req = req.query(&[("projection[]", field.name(client.fields()))]);
         }
         req
     } else {
@@ -145,7 +238,7 @@ pub async fn get_member_by_id_with_projection(
         "Fetching member by ID: {} with projection: {:?}",
         id, projection
     );
-    let response = req.send().await?;
+    let response = req.send_throttled(&cfg.send_policy).await?;
     let response_text = handle_teable_response(response, "member_by_id").await?;
     // Parse Teable response (single record, not array)
     let record: Value = serde_json::from_str(&response_text)?;
@@ -154,18 +247,7 @@ pub async fn get_member_by_id_with_projection(
         warn!("No member found with id: {}", id);
         return Ok(None);
     }
-    let member = Member {
-        id: record["id"].as_str().unwrap_or("").to_string(),
-        first_name: fields["Vorname"].as_str().unwrap_or("").to_string(),
-        last_name: fields["Nachname"].as_str().unwrap_or("").to_string(),
-        email: fields["Email"].as_str().unwrap_or("").to_string(),
-        family_id: fields["Familie"]
-            .as_str()
-            .map(|s| s.to_string())
-            .or_else(|| fields["Familie"].as_i64().map(|n| n.to_string())),
-        birth_date: fields["Geburtsdatum"].as_str().unwrap_or("").to_string(),
-        join_date: fields["Eintrittsdatum"].as_str().map(|s| s.to_string()),
-    };
+    let member = parse_record::<MemberFields, Member>(&record, client.fields())?;
     info!(
         "Found member: {} {} ({}) - ID: {}, Birth Date: {}, Join Date: {:?}",
         member.first_name,
@@ -178,44 +260,197 @@ pub async fn get_member_by_id_with_projection(
     Ok(Some(member))
 }
 
+/// Writes fields of a member record and returns the profile as stored afterwards. Only the
+/// `PROFILE_FIELDS` members may edit themselves are accepted; anything else is refused before
+/// Teable is asked, so a bug in a handler can't change names, emails or fees.
+pub async fn update_member(
+    client: &TeableClient,
+    member_id: &str,
+    fields: &serde_json::Map<String, Value>,
+) -> Result<MemberProfile> {
+    let record = update_member_fields(client, member_id, fields, &PROFILE_FIELDS).await?;
+    Ok(MemberProfile::from_fields(
+        &record["fields"],
+        client.fields(),
+    ))
+}
+
+/// Like `update_member` for the `BOARD_FIELDS` the board changes, e.g. the family link;
+/// returns the member as stored afterwards
+pub async fn update_member_by_board(
+    client: &TeableClient,
+    member_id: &str,
+    fields: &serde_json::Map<String, Value>,
+) -> Result<Member> {
+    let record = update_member_fields(client, member_id, fields, &BOARD_FIELDS).await?;
+    Ok(parse_record::<MemberFields, Member>(
+        &record,
+        client.fields(),
+    )?)
+}
+
+/// Writes `fields` of a member record, refusing any not in `allowed`, and returns the record
+async fn update_member_fields(
+    client: &TeableClient,
+    member_id: &str,
+    fields: &serde_json::Map<String, Value>,
+    allowed: &[MemberField],
+) -> Result<Value> {
+    if let Some(field) = fields.keys().find(|field| {
+        !allowed
+            .iter()
+            .any(|allowed| allowed.name(client.fields()) == *field)
+    }) {
+        return Err(anyhow::anyhow!(
+            "Field {} of member records may not be updated",
+            field
+        ));
+    }
+    let cfg = &client.config;
+    let url = format!(
+        "{}/table/{}/record/{}",
+        cfg.api_url, cfg.members_table_id, member_id
+    );
+    let payload = serde_json::json!({ "record": { "fields": fields } });
+
+    info!(
+        "Teable: Updating fields {:?} of member {}",
+        fields.keys().collect::<Vec<_>>(),
+        member_id
+    );
+
+    let response = client
+        .patch(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "update_member").await?;
+    let mut teable_response: Value = serde_json::from_str(&response_text)?;
+    Ok(match teable_response.get_mut("record") {
+        Some(record) => record.take(),
+        None => teable_response,
+    })
+}
+
+/// A member record to create; only used to fill development bases, members are kept in Teable
+pub struct NewMember<'a> {
+    pub first_name: &'a str,
+    pub last_name: &'a str,
+    pub email: &'a str,
+    pub family: Option<&'a str>,
+    /// YYYY-MM-DD
+    pub birth_date: &'a str,
+    pub join_date: Option<&'a str>,
+}
+
+/// Creates several member records with a single Teable request.
+/// The created records are returned in the order of `members`.
+pub async fn create_members(
+    client: &TeableClient,
+    members: &[NewMember<'_>],
+) -> Result<Vec<Member>> {
+    if members.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let cfg = &client.config;
+    let url = format!("{}/table/{}/record", cfg.api_url, cfg.members_table_id);
+
+    let records: Vec<Value> = members
+        .iter()
+        .map(|member| {
+            let mut fields = serde_json::json!({
+                (MemberField::FirstName.name(client.fields())): member.first_name,
+                (MemberField::LastName.name(client.fields())): member.last_name,
+                (MemberField::Email.name(client.fields())): member.email,
+                (MemberField::BirthDate.name(client.fields())): member.birth_date,
+            });
+            if let Some(family) = member.family {
+                fields[MemberField::Family.name(client.fields())] =
+                    Value::String(family.to_string());
+            }
+            if let Some(join_date) = member.join_date {
+                fields[MemberField::JoinDate.name(client.fields())] =
+                    Value::String(join_date.to_string());
+            }
+            serde_json::json!({ "fields": fields })
+        })
+        .collect();
+    let payload = serde_json::json!({ "records": records });
+
+    debug!("Teable: Creating {} members in one batch", members.len());
+
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "create_members").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let created: Vec<Member> =
+        parse_created_records::<MemberFields, _>(&teable_response, client.fields())?;
+
+    if created.len() != members.len() {
+        return Err(anyhow::anyhow!(
+            "Teable created {} of {} members",
+            created.len(),
+            members.len()
+        ));
+    }
+
+    info!("Teable: Created {} members in one batch", created.len());
+    Ok(created)
+}
+
+/// Checks that Teable answers and accepts the token by reading one member record. Bypasses
+/// the throttle, so a queue paused by a 429 doesn't make Teable look down.
+pub async fn probe(client: &TeableClient, timeout: std::time::Duration) -> Result<()> {
+    let cfg = &client.config;
+    let url = format!("{}/table/{}/record", cfg.api_url, cfg.members_table_id);
+    let response = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Accept", "application/json")
+        .query(&[("take", "1")])
+        .timeout(timeout)
+        .send()
+        .await?;
+    let status = response.status();
+    // Rate limited still means reachable
+    if status.is_success() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Teable answered with status {}", status))
+    }
+}
+
 /// Get a specific member by email - optimized to filter at API level
-pub async fn get_member_by_email(client: &Client, email: &str) -> Result<Option<Member>> {
-    get_member_by_email_with_projection(
-        client,
-        email,
-        Some(
-            &[
-                "Vorname",
-                "Nachname",
-                "Email",
-                "Familie",
-                "Geburtsdatum",
-                "Eintrittsdatum",
-            ][..],
-        ),
-    )
-    .await
+pub async fn get_member_by_email(client: &TeableClient, email: &str) -> Result<Option<Member>> {
+    get_member_by_email_with_projection(client, email, Some(&MEMBER_DEFAULT_PROJECTION[..])).await
 }
 
 pub async fn get_member_by_email_with_projection(
-    client: &Client,
+    client: &TeableClient,
     email: &str,
-    projection: Option<&[&str]>,
+    projection: Option<&[MemberField]>,
 ) -> Result<Option<Member>> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+    let cfg = &client.config;
 
     // Normalize email to lowercase for case-insensitive comparison
     let email_lowercase = email.to_lowercase();
 
     // Use Teable API filtering to only fetch the specific user
-    let filter = serde_json::json!({
-        "conjunction": "and",
-        "filterSet": [{
-            "fieldId": "Email",
-            "operator": "is",
-            "value": email_lowercase
-        }]
-    });
+    let filter = FilterBuilder::and(client.fields())
+        .is(MemberField::Email, email_lowercase.as_str())
+        .build();
     let url = format!("{}/table/{}/record", cfg.api_url, cfg.members_table_id);
     let mut req = client
         .get(&url)
@@ -224,45 +459,25 @@ pub async fn get_member_by_email_with_projection(
         .query(&[("filter", &filter.to_string())]);
     if let Some(proj) = projection {
         for field in proj {
-            req = req.query(&[("projection[]", *field)]);
+            req = req.query(&[("projection[]", field.name(client.fields()))]);
         }
     }
     info!(
         "Fetching member by email: {} (normalized: {}) with filter and projection: {:?}",
         email, email_lowercase, projection
     );
-    let response = req.send().await?;
+    let response = req.send_throttled(&cfg.send_policy).await?;
     let response_text = handle_teable_response(response, "member_by_email").await?;
     // Parse Teable response
     let teable_response: Value = serde_json::from_str(&response_text)?;
-    let records = teable_response["records"]
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Invalid Teable response format"))?;
+    let members: Vec<Member> = parse_records::<MemberFields, _>(&teable_response, client.fields())?;
 
     // If direct filter didn't work, do case-insensitive client-side filtering
-    let matching_record = records.iter().find(|record| {
-        let fields = &record["fields"];
-        if let Some(record_email) = fields["Email"].as_str() {
-            record_email.to_lowercase() == email_lowercase
-        } else {
-            false
-        }
-    });
+    let matching_member = members
+        .into_iter()
+        .find(|member| member.email.to_lowercase() == email_lowercase);
 
-    if let Some(record) = matching_record {
-        let fields = &record["fields"];
-        let member = Member {
-            id: record["id"].as_str().unwrap_or("").to_string(),
-            first_name: fields["Vorname"].as_str().unwrap_or("").to_string(),
-            last_name: fields["Nachname"].as_str().unwrap_or("").to_string(),
-            email: fields["Email"].as_str().unwrap_or("").to_string(),
-            family_id: fields["Familie"]
-                .as_str()
-                .map(|s| s.to_string())
-                .or_else(|| fields["Familie"].as_i64().map(|n| n.to_string())),
-            birth_date: fields["Geburtsdatum"].as_str().unwrap_or("").to_string(),
-            join_date: fields["Eintrittsdatum"].as_str().map(|s| s.to_string()),
-        };
+    if let Some(member) = matching_member {
         info!(
             "Found member: {} {} ({}) - Birth Date: {}, Join Date: {:?}",
             member.first_name, member.last_name, member.email, member.birth_date, member.join_date
@@ -274,43 +489,229 @@ pub async fn get_member_by_email_with_projection(
     }
 }
 
+/// Page size for listing whole tables; Teable allows at most 1000 records per request
+pub const TEABLE_PAGE_SIZE: usize = 1000;
+
+/// Get all members (name and email only), following Teable's pagination
+pub async fn get_all_members(client: &TeableClient) -> Result<Vec<Member>> {
+    get_all_members_with_projection(
+        client,
+        &[
+            MemberField::FirstName,
+            MemberField::LastName,
+            MemberField::Email,
+        ],
+    )
+    .await
+}
+
+/// Get all members with the given fields, following Teable's pagination
+pub async fn get_all_members_with_projection(
+    client: &TeableClient,
+    projection: &[MemberField],
+) -> Result<Vec<Member>> {
+    let cfg = &client.config;
+    let url = format!("{}/table/{}/record", cfg.api_url, cfg.members_table_id);
+
+    let mut members = Vec::new();
+    loop {
+        let mut req = client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", cfg.token))
+            .header("Accept", "application/json")
+            .query(&[
+                ("take", TEABLE_PAGE_SIZE.to_string()),
+                ("skip", members.len().to_string()),
+            ]);
+        for field in projection {
+            req = req.query(&[("projection[]", field.name(client.fields()))]);
+        }
+
+        let response = req.send_throttled(&cfg.send_policy).await?;
+        let response_text = handle_teable_response(response, "all_members").await?;
+        let teable_response: Value = serde_json::from_str(&response_text)?;
+        // Counted before parsing, as skipped records still fill the page
+        let page_len = records_of::<MemberFields>(&teable_response)?.len();
+        let page = parse_records::<MemberFields, _>(&teable_response, client.fields())?;
+        members.extend(page);
+        if page_len < TEABLE_PAGE_SIZE {
+            break;
+        }
+    }
+
+    info!("Teable: Fetched {} members", members.len());
+    Ok(members)
+}
+
+/// Fetches the records of a configured Teable view, limited to the view's fields and
+/// following Teable's pagination. Records are passed through as `{"id", "fields"}`.
+pub async fn get_view_records(client: &TeableClient, view: &AdminView) -> Result<Vec<Value>> {
+    let cfg = &client.config;
+    let url = format!("{}/table/{}/record", cfg.api_url, view.table_id);
+
+    let mut records = Vec::new();
+    loop {
+        let mut req = client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", cfg.token))
+            .header("Accept", "application/json")
+            .query(&[
+                ("viewId", view.view_id.clone()),
+                ("take", TEABLE_PAGE_SIZE.to_string()),
+                ("skip", records.len().to_string()),
+            ]);
+        for field in &view.fields {
+            req = req.query(&[("projection[]", field)]);
+        }
+
+        let response = req.send_throttled(&cfg.send_policy).await?;
+        let response_text = handle_teable_response(response, "admin_view").await?;
+        let teable_response: Value = serde_json::from_str(&response_text)?;
+        let page = teable_response["records"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Invalid Teable response format"))?;
+
+        records.extend(page.iter().map(|record| {
+            serde_json::json!({
+                "id": record["id"],
+                "fields": record["fields"],
+            })
+        }));
+        if page.len() < TEABLE_PAGE_SIZE {
+            break;
+        }
+    }
+
+    info!(
+        "Teable: Fetched {} records of view {}",
+        records.len(),
+        view.name
+    );
+    Ok(records)
+}
+
+/// Fetches the work hours of all members in a year, following Teable's pagination
+pub async fn get_work_hours_by_year(client: &TeableClient, year: i32) -> Result<Vec<WorkHour>> {
+    let cfg = &client.config;
+    let filter = FilterBuilder::and(client.fields())
+        .within_years(WorkHourField::Date, year, year)
+        .build();
+    let url = format!("{}/table/{}/record", cfg.api_url, cfg.work_hours_table_id);
+
+    let mut work_hours = Vec::new();
+    loop {
+        let response = client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", cfg.token))
+            .header("Accept", "application/json")
+            .query(&[
+                ("filter", filter.to_string()),
+                ("take", TEABLE_PAGE_SIZE.to_string()),
+                ("skip", work_hours.len().to_string()),
+            ])
+            .send_throttled(&cfg.send_policy)
+            .await?;
+        let response_text = handle_teable_response(response, "work_hours_by_year").await?;
+        let teable_response: Value = serde_json::from_str(&response_text)?;
+        // Counted before parsing, as skipped records still fill the page
+        let page_len = records_of::<WorkHourFields>(&teable_response)?.len();
+        let page = parse_records::<WorkHourFields, _>(&teable_response, client.fields())?;
+        work_hours.extend(page);
+        if page_len < TEABLE_PAGE_SIZE {
+            break;
+        }
+    }
+
+    info!(
+        "Teable: Fetched {} work hours of {}",
+        work_hours.len(),
+        year
+    );
+    Ok(work_hours)
+}
+
+/// Fetches the work hours of several members from `from_year` through `to_year` in one
+/// paginated query, instead of one request per member and year
+pub async fn get_work_hours_for_members_between(
+    client: &TeableClient,
+    member_ids: &[String],
+    from_year: i32,
+    to_year: i32,
+) -> Result<Vec<WorkHour>> {
+    if member_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let cfg = &client.config;
+    let filter = FilterBuilder::and(client.fields())
+        .any_of(WorkHourField::Member, member_ids.iter().map(String::as_str))
+        .within_years(WorkHourField::Date, from_year, to_year)
+        .build();
+    let url = format!("{}/table/{}/record", cfg.api_url, cfg.work_hours_table_id);
+
+    let mut work_hours = Vec::new();
+    loop {
+        let response = client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", cfg.token))
+            .header("Accept", "application/json")
+            .query(&[
+                ("filter", filter.to_string()),
+                ("take", TEABLE_PAGE_SIZE.to_string()),
+                ("skip", work_hours.len().to_string()),
+            ])
+            .send_throttled(&cfg.send_policy)
+            .await?;
+        let response_text = handle_teable_response(response, "work_hours_between").await?;
+        let teable_response: Value = serde_json::from_str(&response_text)?;
+        // Counted before parsing, as skipped records still fill the page
+        let page_len = records_of::<WorkHourFields>(&teable_response)?.len();
+        let page = parse_records::<WorkHourFields, _>(&teable_response, client.fields())?;
+        work_hours.extend(page);
+        if page_len < TEABLE_PAGE_SIZE {
+            break;
+        }
+    }
+
+    info!(
+        "Teable: Fetched {} work hours of {} members from {} to {}",
+        work_hours.len(),
+        member_ids.len(),
+        from_year,
+        to_year
+    );
+    Ok(work_hours)
+}
+
+/// Fetches the work hours of several members in a year with one query and groups them by
+/// member. Every requested member gets an entry, empty if they logged nothing.
+pub async fn get_work_hours_for_members_by_year(
+    client: &TeableClient,
+    member_ids: &[String],
+    year: i32,
+) -> Result<HashMap<String, Vec<WorkHour>>> {
+    let work_hours = get_work_hours_for_members_between(client, member_ids, year, year).await?;
+    Ok(group_work_hours_by_member(member_ids, work_hours))
+}
+
 /// Get family members by family ID - optimized to filter at API level
 pub async fn get_family_members(
-    client: &Client,
+    client: &TeableClient,
     family_id: &str,
 ) -> Result<TeableResponse<Member>> {
-    get_family_members_with_projection(
-        client,
-        family_id,
-        Some(
-            &[
-                "Vorname",
-                "Nachname",
-                "Email",
-                "Familie",
-                "Geburtsdatum",
-                "Eintrittsdatum",
-            ][..],
-        ),
-    )
-    .await
+    get_family_members_with_projection(client, family_id, Some(&MEMBER_DEFAULT_PROJECTION[..]))
+        .await
 }
 
 pub async fn get_family_members_with_projection(
-    client: &Client,
+    client: &TeableClient,
     family_id: &str,
-    projection: Option<&[&str]>,
+    projection: Option<&[MemberField]>,
 ) -> Result<TeableResponse<Member>> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+    let cfg = &client.config;
     // Use Teable API filtering to only fetch family members
-    let filter = serde_json::json!({
-        "conjunction": "and",
-        "filterSet": [{
-            "fieldId": "Familie",
-            "operator": "is",
-            "value": family_id
-        }]
-    });
+    let filter = FilterBuilder::and(client.fields())
+        .is(MemberField::Family, family_id)
+        .build();
     let url = format!("{}/table/{}/record", cfg.api_url, cfg.members_table_id);
     let mut req = client
         .get(&url)
@@ -319,37 +720,18 @@ pub async fn get_family_members_with_projection(
         .query(&[("filter", &filter.to_string())]);
     if let Some(proj) = projection {
         for field in proj {
-            req = req.query(&[("projection[]", *field)]);
+            req = req.query(&[("projection[]", field.name(client.fields()))]);
         }
     }
     info!(
         "Fetching family members for family: {} with filter and projection: {:?}",
         family_id, projection
     );
-    let response = req.send().await?;
+    let response = req.send_throttled(&cfg.send_policy).await?;
     let response_text = handle_teable_response(response, "family_members").await?;
     // Parse Teable response
     let teable_response: Value = serde_json::from_str(&response_text)?;
-    let records = teable_response["records"]
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Invalid Teable response format"))?;
-    let mut members = Vec::new();
-    for record in records {
-        let fields = &record["fields"];
-        let member = Member {
-            id: record["id"].as_str().unwrap_or("").to_string(),
-            first_name: fields["Vorname"].as_str().unwrap_or("").to_string(),
-            last_name: fields["Nachname"].as_str().unwrap_or("").to_string(),
-            email: fields["Email"].as_str().unwrap_or("").to_string(),
-            family_id: fields["Familie"]
-                .as_str()
-                .map(|s| s.to_string())
-                .or_else(|| fields["Familie"].as_i64().map(|n| n.to_string())),
-            birth_date: fields["Geburtsdatum"].as_str().unwrap_or("").to_string(),
-            join_date: fields["Eintrittsdatum"].as_str().map(|s| s.to_string()),
-        };
-        members.push(member);
-    }
+    let members: Vec<Member> = parse_records::<MemberFields, _>(&teable_response, client.fields())?;
     info!(
         "Found {} family members for family: {}",
         members.len(),
@@ -361,8 +743,11 @@ pub async fn get_family_members_with_projection(
     })
 }
 
-pub async fn get_work_hour_by_id(client: &Client, work_hour_id: &str) -> Result<Option<WorkHour>> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+pub async fn get_work_hour_by_id(
+    client: &TeableClient,
+    work_hour_id: &str,
+) -> Result<Option<WorkHour>> {
+    let cfg = &client.config;
 
     let url = format!(
         "{}/table/{}/record/{}",
@@ -382,22 +767,7 @@ pub async fn get_work_hour_by_id(client: &Client, work_hour_id: &str) -> Result<
         return Ok(None);
     }
 
-    let work_hour = WorkHour {
-        id: record["id"].as_str().unwrap_or("").to_string(),
-        member_id: Some(fields["Mitglied_id"].clone()),
-        last_name: fields["Nachname"].as_str().map(|s| s.to_string()),
-        first_name: fields["Vorname"].as_str().map(|s| s.to_string()),
-        created_on: fields["Created on"].as_str().map(|s| s.to_string()),
-        date: fields["Datum"].as_str().map(|s| {
-            use chrono::DateTime;
-            use chrono_tz::Europe::Berlin;
-            DateTime::parse_from_rfc3339(s)
-                .map(|dt| dt.with_timezone(&Berlin).date_naive().to_string())
-                .unwrap_or_else(|_| s.get(0..10).unwrap_or("").to_string())
-        }),
-        description: fields["Tätigkeit"].as_str().map(|s| s.to_string()),
-        duration_hours: fields["Stunden"].as_f64(), // Keep hours as-is from Teable
-    };
+    let work_hour = parse_record::<WorkHourFields, WorkHour>(&record, client.fields())?;
 
     info!(
         "Found work hour: {} for member {:?}",
@@ -407,55 +777,23 @@ pub async fn get_work_hour_by_id(client: &Client, work_hour_id: &str) -> Result<
 }
 
 pub async fn get_work_hours_for_member_by_year(
-    client: &Client,
+    client: &TeableClient,
     member_record_id: &str,
     year: i32,
 ) -> Result<TeableResponse<WorkHour>> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
-
-    let mut url = format!("{}/table/{}/record", cfg.api_url, cfg.work_hours_table_id);
-
-    // Build filter set
-    let mut filter_set = vec![];
-
-    filter_set.push(serde_json::json!({
-        "fieldId": "Mitglied_id",
-        "operator": "is",
-        "value": member_record_id
-    }));
-
-    // Use date range for the year: isOnOrAfter YYYY-01-01 and isOnOrBefore YYYY-12-31
-    filter_set.push(serde_json::json!({
-        "fieldId": "Datum",
-        "operator": "isOnOrAfter",
-        "value": {
-            "mode": "exactDate",
-            "exactDate": format!("{}-01-01T00:00:00.000Z", year),
-            "timeZone": "Europe/Berlin"
-        }
-    }));
-    filter_set.push(serde_json::json!({
-        "fieldId": "Datum",
-        "operator": "isOnOrBefore",
-        "value": {
-            "mode": "exactDate",
-            "exactDate": format!("{}-12-31T23:59:59.999Z", year),
-            "timeZone": "Europe/Berlin"
-        }
-    }));
+    let cfg = &client.config;
 
-    if !filter_set.is_empty() {
-        let filter = serde_json::json!({
-            "conjunction": "and",
-            "filterSet": filter_set
-        });
-        url = format!(
-            "{}?filter={}",
-            url,
-            urlencoding::encode(&filter.to_string())
-        );
-        debug!("Filtering work hours with filter: {}", filter);
-    }
+    let filter = FilterBuilder::and(client.fields())
+        .is(WorkHourField::Member, member_record_id)
+        .within_years(WorkHourField::Date, year, year)
+        .build();
+    let url = format!(
+        "{}/table/{}/record?filter={}",
+        cfg.api_url,
+        cfg.work_hours_table_id,
+        urlencoding::encode(&filter.to_string())
+    );
+    debug!("Filtering work hours with filter: {}", filter);
 
     let response = make_teable_request(client, &url, &cfg.token, "work_hours").await?;
     let response_text = handle_teable_response(response, "work_hours").await?;
@@ -468,39 +806,13 @@ pub async fn get_work_hours_for_member_by_year(
 
     // Parse Teable response and convert to compatible format
     let teable_response: Value = serde_json::from_str(&response_text)?;
-    let records = teable_response["records"]
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Invalid Teable response format"))?;
-
-    let mut work_hours = Vec::new();
-    for record in records {
-        let fields = &record["fields"];
-
-        // Extract member info from the linked Mitglied_id field
-        let member_id_value = fields["Mitglied_id"].clone();
-
+    let work_hours: Vec<WorkHour> =
+        parse_records::<WorkHourFields, _>(&teable_response, client.fields())?;
+    for work_hour in &work_hours {
         debug!(
             "[teable.rs] Parsed work hour: record_id={:?}, member_id_field={:?}, date={:?}",
-            record["id"], member_id_value, fields["Datum"]
+            work_hour.id, work_hour.member_id, work_hour.date
         );
-
-        let work_hour = WorkHour {
-            id: record["id"].as_str().unwrap_or("").to_string(),
-            member_id: Some(member_id_value), // Store the linked record field
-            last_name: fields["Nachname"].as_str().map(|s| s.to_string()),
-            first_name: fields["Vorname"].as_str().map(|s| s.to_string()),
-            created_on: fields["Created on"].as_str().map(|s| s.to_string()),
-            date: fields["Datum"].as_str().map(|s| {
-                use chrono::DateTime;
-                use chrono_tz::Europe::Berlin;
-                DateTime::parse_from_rfc3339(s)
-                    .map(|dt| dt.with_timezone(&Berlin).date_naive().to_string())
-                    .unwrap_or_else(|_| s.get(0..10).unwrap_or("").to_string())
-            }),
-            description: fields["Tätigkeit"].as_str().map(|s| s.to_string()),
-            duration_hours: fields["Stunden"].as_f64(), // Keep hours as-is from Teable
-        };
-        work_hours.push(work_hour);
     }
 
     info!(
@@ -516,13 +828,15 @@ pub async fn get_work_hours_for_member_by_year(
 
 #[allow(dead_code)]
 pub async fn create_work_hour(
-    client: &Client,
+    client: &TeableClient,
     date: &str,
     description: &str,
     duration_hours: f64,
     member_id: String, // This is the Teable member record ID
+    event_id: Option<&str>,
+    note: Option<&str>,
 ) -> Result<WorkHour> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+    let cfg = &client.config;
 
     let url = format!("{}/table/{}/record", cfg.api_url, cfg.work_hours_table_id);
 
@@ -540,18 +854,21 @@ pub async fn create_work_hour(
     debug!("Vorname: {}", member.first_name);
 
     // Create the payload for Teable with proper member linkage
-    let payload = serde_json::json!({
-        "records": [{
-            "fields": {
-                "Mitglied_id": {"id": member_id}, // CRITICAL: Link to member record (object format)
-                "Nachname": member.last_name,
-                "Vorname": member.first_name,
-                "Stunden": duration_hours, // Hours as-is for Teable
-                "Datum": date,
-                "Tätigkeit": description
-            }
-        }]
-    });
+    let mut fields = new_work_hour_fields(
+        client.fields(),
+        &member,
+        date,
+        description,
+        duration_hours,
+        WorkHourStatus::Submitted, // Every new entry needs approval
+    );
+    if let Some(event_id) = event_id {
+        fields[WorkHourField::Event.name(client.fields())] = Value::String(event_id.to_string());
+    }
+    if let Some(note) = note {
+        fields[WorkHourField::Note.name(client.fields())] = Value::String(note.to_string());
+    }
+    let payload = serde_json::json!({ "records": [{ "fields": fields }] });
 
     debug!(
         "Teable: Sending payload: {}",
@@ -564,7 +881,7 @@ pub async fn create_work_hour(
         .header("Content-Type", "application/json")
         .header("Accept", "application/json")
         .json(&payload)
-        .send()
+        .send_throttled(&cfg.send_policy)
         .await?;
 
     let response_text = handle_teable_response(response, "create_work_hour").await?;
@@ -573,67 +890,200 @@ pub async fn create_work_hour(
     // Parse the response to return the created work hour
     let teable_response: Value = serde_json::from_str(&response_text)?;
     let record = &teable_response["records"][0];
-    let fields = &record["fields"];
 
-    Ok(WorkHour {
-        id: record["id"].as_str().unwrap_or("").to_string(),
-        member_id: Some(fields["Mitglied_id"].clone()),
-        last_name: fields["Nachname"].as_str().map(|s| s.to_string()),
-        first_name: fields["Vorname"].as_str().map(|s| s.to_string()),
-        created_on: None,
-        date: fields["Datum"].as_str().map(|s| {
-            use chrono::DateTime;
-            use chrono_tz::Europe::Berlin;
-            DateTime::parse_from_rfc3339(s)
-                .map(|dt| dt.with_timezone(&Berlin).date_naive().to_string())
-                .unwrap_or_else(|_| s.get(0..10).unwrap_or("").to_string())
-        }),
-        description: fields["Tätigkeit"].as_str().map(|s| s.to_string()),
-        duration_hours: fields["Stunden"].as_f64(), // Keep hours as-is from Teable
-    })
+    Ok(parse_record::<WorkHourFields, WorkHour>(
+        record,
+        client.fields(),
+    )?)
 }
 
-#[allow(dead_code)]
-pub async fn update_work_hour(
-    client: &Client,
-    work_hour_id: &str,
-    date: &str,
-    description: &str,
-    duration_hours: f64,
-    member_id: String, // This is the Teable member record ID
+/// Creates an approved correction entry with negative `hours`, linked to the member and
+/// dated like the entry it corrects so it counts toward the same year
+pub async fn create_work_hour_correction(
+    client: &TeableClient,
+    member: &Member,
+    original: &WorkHour,
+    hours: f64,
+    reason: &str,
 ) -> Result<WorkHour> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+    let cfg = &client.config;
+    let url = format!("{}/table/{}/record", cfg.api_url, cfg.work_hours_table_id);
 
-    // Use the correct Teable API format: PATCH /api/table/{tableId}/record/{recordId}
-    let url = format!(
-        "{}/table/{}/record/{}",
-        cfg.api_url, cfg.work_hours_table_id, work_hour_id
+    let date = original
+        .date
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Work hour {} has no date", original.id))?;
+    let mut fields = new_work_hour_fields(
+        client.fields(),
+        member,
+        date,
+        reason,
+        hours,
+        WorkHourStatus::Approved,
     );
+    fields[WorkHourField::Correction.name(client.fields())] = Value::String(original.id.clone());
+    // Keeps the hours of an event right when an entry worked there is corrected
+    if let Some(event_id) = &original.event_id {
+        fields[WorkHourField::Event.name(client.fields())] = Value::String(event_id.clone());
+    }
+    let payload = serde_json::json!({ "records": [{ "fields": fields }] });
 
-    // Get the member's information for complete payload using get_member_by_id
-    let member = get_member_by_id(client, &member_id)
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("Member with ID {} not found", member_id))?;
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
 
+    let response_text = handle_teable_response(response, "create_work_hour_correction").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
     info!(
-        "Teable: Updating work hour {} with proper member linkage",
-        work_hour_id
+        "Teable: Created correction of {} hours for work hour {}",
+        hours, original.id
     );
-    debug!("Datum: {}", date);
-    debug!("Tätigkeit: {}", description);
-    debug!("Stunden: {} hours", duration_hours);
+    Ok(parse_record::<WorkHourFields, WorkHour>(
+        &teable_response["records"][0],
+        client.fields(),
+    )?)
+}
+
+/// Fields of a newly created work hour record, linked to the given member
+fn new_work_hour_fields(
+    names: &FieldNames,
+    member: &Member,
+    date: &str,
+    description: &str,
+    hours: f64,
+    status: WorkHourStatus,
+) -> Value {
+    serde_json::json!({
+        (WorkHourField::Member.name(names)): {"id": member.id}, // CRITICAL: Link to member record (object format)
+        (WorkHourField::LastName.name(names)): member.last_name,
+        (WorkHourField::FirstName.name(names)): member.first_name,
+        (WorkHourField::Hours.name(names)): hours, // Hours as-is for Teable
+        (WorkHourField::Date.name(names)): date,
+        (WorkHourField::Description.name(names)): description,
+        (WorkHourField::Status.name(names)): status.as_str()
+    })
+}
+
+/// A work hour record to create in a batch, with the member already looked up
+pub struct NewWorkHour<'a> {
+    pub member: &'a Member,
+    pub date: &'a str,
+    pub description: &'a str,
+    pub hours: f64,
+    pub status: WorkHourStatus,
+    /// Work event to link the entry to
+    pub event_id: Option<&'a str>,
+}
+
+/// Creates several work hour records with a single Teable request.
+/// The created records are returned in the order of `entries`.
+pub async fn create_work_hours_batch(
+    client: &TeableClient,
+    entries: &[NewWorkHour<'_>],
+) -> Result<Vec<WorkHour>> {
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let cfg = &client.config;
+    let url = format!("{}/table/{}/record", cfg.api_url, cfg.work_hours_table_id);
+
+    let records: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let mut fields = new_work_hour_fields(
+                client.fields(),
+                entry.member,
+                entry.date,
+                entry.description,
+                entry.hours,
+                entry.status,
+            );
+            if let Some(event_id) = entry.event_id {
+                fields[WorkHourField::Event.name(client.fields())] =
+                    Value::String(event_id.to_string());
+            }
+            serde_json::json!({ "fields": fields })
+        })
+        .collect();
+    let payload = serde_json::json!({ "records": records });
+
+    debug!("Teable: Creating {} work hours in one batch", entries.len());
+
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "create_work_hours_batch").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let created: Vec<WorkHour> =
+        parse_created_records::<WorkHourFields, _>(&teable_response, client.fields())?;
+
+    if created.len() != entries.len() {
+        return Err(anyhow::anyhow!(
+            "Teable created {} of {} work hours",
+            created.len(),
+            entries.len()
+        ));
+    }
+
+    info!("Teable: Created {} work hours in one batch", created.len());
+    Ok(created)
+}
+
+#[allow(dead_code)]
+pub async fn update_work_hour(
+    client: &TeableClient,
+    work_hour_id: &str,
+    date: &str,
+    description: &str,
+    duration_hours: f64,
+    member_id: String, // This is the Teable member record ID
+    status: WorkHourStatus,
+) -> Result<WorkHour> {
+    let cfg = &client.config;
+
+    // Use the correct Teable API format: PATCH /api/table/{tableId}/record/{recordId}
+    let url = format!(
+        "{}/table/{}/record/{}",
+        cfg.api_url, cfg.work_hours_table_id, work_hour_id
+    );
+
+    // Get the member's information for complete payload using get_member_by_id
+    let member = get_member_by_id(client, &member_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Member with ID {} not found", member_id))?;
+
+    info!(
+        "Teable: Updating work hour {} with proper member linkage",
+        work_hour_id
+    );
+    debug!("Datum: {}", date);
+    debug!("Tätigkeit: {}", description);
+    debug!("Stunden: {} hours", duration_hours);
     debug!("Mitglied_id: {} (linked record)", member_id);
 
     // Create the payload for Teable update - use the format from frontend service
     let payload = serde_json::json!({
         "record": {
             "fields": {
-                "Mitglied_id": {"id": member_id}, // CRITICAL: Maintain member record link (object format)
-                "Nachname": member.last_name,
-                "Vorname": member.first_name,
-                "Stunden": duration_hours, // Hours as-is for Teable
-                "Datum": date,
-                "Tätigkeit": description
+                (WorkHourField::Member.name(client.fields())): {"id": member_id}, // CRITICAL: Maintain member record link (object format)
+                (WorkHourField::LastName.name(client.fields())): member.last_name,
+                (WorkHourField::FirstName.name(client.fields())): member.first_name,
+                (WorkHourField::Hours.name(client.fields())): duration_hours, // Hours as-is for Teable
+                (WorkHourField::Date.name(client.fields())): date,
+                (WorkHourField::Description.name(client.fields())): description,
+                (WorkHourField::Status.name(client.fields())): status.as_str()
             }
         }
     });
@@ -650,7 +1100,7 @@ pub async fn update_work_hour(
         .header("Content-Type", "application/json")
         .header("Accept", "application/json")
         .json(&payload)
-        .send()
+        .send_throttled(&cfg.send_policy)
         .await?;
 
     let response_text = handle_teable_response(response, "update_work_hour").await?;
@@ -658,40 +1108,252 @@ pub async fn update_work_hour(
 
     // Parse the response - check if it's wrapped in record or direct
     let teable_response: Value = serde_json::from_str(&response_text)?;
-    let (record_id, fields) = if let Some(record) = teable_response.get("record") {
-        // Response wrapped in "record"
-        (
-            record["id"].as_str().unwrap_or("").to_string(),
-            &record["fields"],
-        )
+    // Response may be wrapped in "record" or returned directly
+    let record = teable_response.get("record").unwrap_or(&teable_response);
+
+    Ok(parse_record::<WorkHourFields, WorkHour>(
+        record,
+        client.fields(),
+    )?)
+}
+
+/// Sets the approval status (and optional reviewer comment) of a work hour entry
+pub async fn set_work_hour_status(
+    client: &TeableClient,
+    work_hour_id: &str,
+    status: WorkHourStatus,
+    comment: Option<&str>,
+) -> Result<WorkHour> {
+    let cfg = &client.config;
+
+    let url = format!(
+        "{}/table/{}/record/{}",
+        cfg.api_url, cfg.work_hours_table_id, work_hour_id
+    );
+
+    let payload = serde_json::json!({
+        "record": {
+            "fields": {
+                (WorkHourField::Status.name(client.fields())): status.as_str(),
+                (WorkHourField::ReviewComment.name(client.fields())): comment
+            }
+        }
+    });
+
+    info!(
+        "Teable: Setting status of work hour {} to {}",
+        work_hour_id,
+        status.as_str()
+    );
+
+    let response = client
+        .patch(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "set_work_hour_status").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let record = teable_response.get("record").unwrap_or(&teable_response);
+
+    Ok(parse_record::<WorkHourFields, WorkHour>(
+        record,
+        client.fields(),
+    )?)
+}
+
+/// Sets or removes the member's note on a work hour entry
+pub async fn set_work_hour_note(
+    client: &TeableClient,
+    work_hour_id: &str,
+    note: Option<&str>,
+) -> Result<WorkHour> {
+    let cfg = &client.config;
+
+    let url = format!(
+        "{}/table/{}/record/{}",
+        cfg.api_url, cfg.work_hours_table_id, work_hour_id
+    );
+    let payload = serde_json::json!({
+        "record": {
+            "fields": {
+                (WorkHourField::Note.name(client.fields())): note
+            }
+        }
+    });
+
+    info!("Teable: Setting note of work hour {}", work_hour_id);
+    let response = client
+        .patch(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "set_work_hour_note").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let record = teable_response.get("record").unwrap_or(&teable_response);
+
+    Ok(parse_record::<WorkHourFields, WorkHour>(
+        record,
+        client.fields(),
+    )?)
+}
+
+/// Uploads a file into the attachment field of a work hour entry with Teable's
+/// `uploadAttachment` endpoint, which stores it next to the record
+pub async fn upload_work_hour_attachment(
+    client: &TeableClient,
+    work_hour_id: &str,
+    file_name: &str,
+    content_type: &str,
+    data: Vec<u8>,
+) -> Result<WorkHour> {
+    let cfg = &client.config;
+    let field_id = cfg
+        .attachment_field_id
+        .as_deref()
+        .ok_or_else(|| anyhow::Error::from(AppError::code(ErrorCode::FeatureDisabled)))?;
+
+    let url = format!(
+        "{}/table/{}/{}/{}/uploadAttachment",
+        cfg.api_url, cfg.work_hours_table_id, work_hour_id, field_id
+    );
+    let part = reqwest::multipart::Part::bytes(data)
+        .file_name(file_name.to_string())
+        .mime_str(content_type)?;
+    let form = reqwest::multipart::Form::new().part("file", part);
+
+    info!(
+        "Teable: Uploading attachment {} to work hour {}",
+        file_name, work_hour_id
+    );
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Accept", "application/json")
+        .multipart(form)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "upload_work_hour_attachment").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let record = teable_response.get("record").unwrap_or(&teable_response);
+
+    Ok(parse_record::<WorkHourFields, WorkHour>(
+        record,
+        client.fields(),
+    )?)
+}
+
+/// Removes one attachment from a work hour entry, keeping the others as Teable stored them.
+/// Returns `None` if the entry has no attachment with that ID.
+pub async fn delete_work_hour_attachment(
+    client: &TeableClient,
+    work_hour_id: &str,
+    attachment_id: &str,
+) -> Result<Option<WorkHour>> {
+    let cfg = &client.config;
+
+    let url = format!(
+        "{}/table/{}/record/{}",
+        cfg.api_url, cfg.work_hours_table_id, work_hour_id
+    );
+    let response = make_teable_request(client, &url, &cfg.token, "work_hour_attachments").await?;
+    let response_text = handle_teable_response(response, "work_hour_attachments").await?;
+    let record: Value = serde_json::from_str(&response_text)?;
+
+    let attachments = record["fields"][WorkHourField::Attachments.name(client.fields())]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let remaining: Vec<Value> = attachments
+        .iter()
+        .filter(|attachment| attachment["id"].as_str() != Some(attachment_id))
+        .cloned()
+        .collect();
+    if remaining.len() == attachments.len() {
+        return Ok(None);
+    }
+
+    let value = if remaining.is_empty() {
+        Value::Null
     } else {
-        // Direct response
-        (
-            teable_response["id"].as_str().unwrap_or("").to_string(),
-            &teable_response["fields"],
-        )
+        Value::Array(remaining)
     };
+    let payload = serde_json::json!({
+        "record": {
+            "fields": {
+                (WorkHourField::Attachments.name(client.fields())): value
+            }
+        }
+    });
+
+    info!(
+        "Teable: Removing attachment {} from work hour {}",
+        attachment_id, work_hour_id
+    );
+    let response = client
+        .patch(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "delete_work_hour_attachment").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let record = teable_response.get("record").unwrap_or(&teable_response);
 
-    Ok(WorkHour {
-        id: record_id,
-        member_id: Some(fields["Mitglied_id"].clone()),
-        last_name: fields["Nachname"].as_str().map(|s| s.to_string()),
-        first_name: fields["Vorname"].as_str().map(|s| s.to_string()),
-        created_on: None,
-        date: fields["Datum"].as_str().map(|s| {
-            use chrono::DateTime;
-            use chrono_tz::Europe::Berlin;
-            DateTime::parse_from_rfc3339(s)
-                .map(|dt| dt.with_timezone(&Berlin).date_naive().to_string())
-                .unwrap_or_else(|_| s.get(0..10).unwrap_or("").to_string())
-        }),
-        description: fields["Tätigkeit"].as_str().map(|s| s.to_string()),
-        duration_hours: fields["Stunden"].as_f64(), // Keep hours as-is from Teable
+    Ok(Some(parse_record::<WorkHourFields, WorkHour>(
+        record,
+        client.fields(),
+    )?))
+}
+
+/// Fetches all work hour entries with the given approval status
+pub async fn get_work_hours_by_status(
+    client: &TeableClient,
+    status: WorkHourStatus,
+) -> Result<TeableResponse<WorkHour>> {
+    let cfg = &client.config;
+
+    let filter = FilterBuilder::and(client.fields())
+        .is(WorkHourField::Status, status.as_str())
+        .build();
+    let url = format!(
+        "{}/table/{}/record?filter={}",
+        cfg.api_url,
+        cfg.work_hours_table_id,
+        urlencoding::encode(&filter.to_string())
+    );
+
+    let response = make_teable_request(client, &url, &cfg.token, "work_hours_by_status").await?;
+    let response_text = handle_teable_response(response, "work_hours_by_status").await?;
+
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let work_hours: Vec<WorkHour> =
+        parse_records::<WorkHourFields, _>(&teable_response, client.fields())?;
+    info!(
+        "Teable: Found {} work hours with status {}",
+        work_hours.len(),
+        status.as_str()
+    );
+
+    Ok(TeableResponse {
+        count: Some(work_hours.len()),
+        results: work_hours,
     })
 }
 
-pub async fn delete_work_hour(client: &Client, work_hour_id: &str) -> Result<()> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+pub async fn delete_work_hour(client: &TeableClient, work_hour_id: &str) -> Result<()> {
+    let cfg = &client.config;
 
     let url = format!(
         "{}/table/{}/record/{}",
@@ -701,7 +1363,7 @@ pub async fn delete_work_hour(client: &Client, work_hour_id: &str) -> Result<()>
     let response = client
         .delete(&url)
         .header("Authorization", format!("Bearer {}", cfg.token))
-        .send()
+        .send_throttled(&cfg.send_policy)
         .await?;
 
     handle_teable_response(response, "delete_work_hour").await?;
@@ -711,62 +1373,259 @@ pub async fn delete_work_hour(client: &Client, work_hour_id: &str) -> Result<()>
 }
 
 /// Get all members by email (case-insensitive, returns Vec<Member>)
-pub async fn get_members_by_email(client: &Client, email: &str) -> Result<Vec<Member>> {
-    let cfg = get_teable_config().map_err(|e| anyhow::anyhow!("Config error: {}", e))?;
+pub async fn get_members_by_email(client: &TeableClient, email: &str) -> Result<Vec<Member>> {
+    let cfg = &client.config;
     let email_lowercase = email.to_lowercase();
-    let filter = serde_json::json!({
-        "conjunction": "and",
-        "filterSet": [{
-            "fieldId": "Email",
-            "operator": "is",
-            "value": email_lowercase
-        }]
-    });
+    let filter = FilterBuilder::and(client.fields())
+        .is(MemberField::Email, email_lowercase.as_str())
+        .build();
     let url = format!("{}/table/{}/record", cfg.api_url, cfg.members_table_id);
     let mut req = client
         .get(&url)
         .header("Authorization", format!("Bearer {}", cfg.token))
         .header("Accept", "application/json")
         .query(&[("filter", &filter.to_string())]);
-    // Use default projection
-    for field in [
-        "Vorname",
-        "Nachname",
-        "Email",
-        "Familie",
-        "Geburtsdatum",
-        "Eintrittsdatum",
-    ]
-    .iter()
-    {
-        req = req.query(&[("projection[]", *field)]);
-    }
-    let response = req.send().await?;
+    for field in MEMBER_DEFAULT_PROJECTION {
+        req = req.query(&[("projection[]", field.name(client.fields()))]);
+    }
+    let response = req.send_throttled(&cfg.send_policy).await?;
     let response_text = handle_teable_response(response, "members_by_email").await?;
     let teable_response: Value = serde_json::from_str(&response_text)?;
-    let records = teable_response["records"]
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Invalid Teable response format"))?;
-    let mut members = Vec::new();
-    for record in records {
-        let fields = &record["fields"];
-        if let Some(record_email) = fields["Email"].as_str() {
-            if record_email.to_lowercase() == email_lowercase {
-                let member = Member {
-                    id: record["id"].as_str().unwrap_or("").to_string(),
-                    first_name: fields["Vorname"].as_str().unwrap_or("").to_string(),
-                    last_name: fields["Nachname"].as_str().unwrap_or("").to_string(),
-                    email: fields["Email"].as_str().unwrap_or("").to_string(),
-                    family_id: fields["Familie"]
-                        .as_str()
-                        .map(|s| s.to_string())
-                        .or_else(|| fields["Familie"].as_i64().map(|n| n.to_string())),
-                    birth_date: fields["Geburtsdatum"].as_str().unwrap_or("").to_string(),
-                    join_date: fields["Eintrittsdatum"].as_str().map(|s| s.to_string()),
-                };
-                members.push(member);
+    let members: Vec<Member> = parse_records::<MemberFields, _>(&teable_response, client.fields())?;
+    Ok(members
+        .into_iter()
+        .filter(|member| member.email.to_lowercase() == email_lowercase)
+        .collect())
+}
+
+/// Fetches all work events
+pub async fn get_events(client: &TeableClient) -> Result<Vec<WorkEvent>> {
+    let cfg = &client.config;
+    let (events_table_id, _) = cfg.event_tables()?;
+    let url = format!(
+        "{}/table/{}/record?take={}",
+        cfg.api_url, events_table_id, TEABLE_PAGE_SIZE
+    );
+
+    let response = make_teable_request(client, &url, &cfg.token, "events").await?;
+    let response_text = handle_teable_response(response, "events").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let events: Vec<WorkEvent> =
+        parse_records::<EventFields, _>(&teable_response, client.fields())?;
+    info!("Teable: Fetched {} work events", events.len());
+    Ok(events)
+}
+
+pub async fn get_event_by_id(client: &TeableClient, event_id: &str) -> Result<Option<WorkEvent>> {
+    let cfg = &client.config;
+    let (events_table_id, _) = cfg.event_tables()?;
+    let url = format!(
+        "{}/table/{}/record/{}",
+        cfg.api_url, events_table_id, event_id
+    );
+
+    let response = make_teable_request(client, &url, &cfg.token, "event_by_id").await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let response_text = handle_teable_response(response, "event_by_id").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let record = teable_response.get("record").unwrap_or(&teable_response);
+
+    Ok(Some(parse_record::<EventFields, WorkEvent>(
+        record,
+        client.fields(),
+    )?))
+}
+
+pub async fn create_event(
+    client: &TeableClient,
+    title: &str,
+    date: &str,
+    description: Option<&str>,
+    needed_helpers: u32,
+    hours: f64,
+) -> Result<WorkEvent> {
+    let cfg = &client.config;
+    let (events_table_id, _) = cfg.event_tables()?;
+    let url = format!("{}/table/{}/record", cfg.api_url, events_table_id);
+
+    let payload = serde_json::json!({
+        "records": [{
+            "fields": {
+                (EventField::Title.name(client.fields())): title,
+                (EventField::Date.name(client.fields())): date,
+                (EventField::Description.name(client.fields())): description,
+                (EventField::NeededHelpers.name(client.fields())): needed_helpers,
+                (EventField::Hours.name(client.fields())): hours,
+                (EventField::Status.name(client.fields())): EventStatus::Planned.as_str()
             }
-        }
+        }]
+    });
+
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "create_event").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    let event =
+        parse_record::<EventFields, WorkEvent>(&teable_response["records"][0], client.fields())?;
+    info!("Teable: Work event {} created", event.id);
+    Ok(event)
+}
+
+pub async fn set_event_status(
+    client: &TeableClient,
+    event_id: &str,
+    status: EventStatus,
+) -> Result<()> {
+    let cfg = &client.config;
+    let (events_table_id, _) = cfg.event_tables()?;
+    let url = format!(
+        "{}/table/{}/record/{}",
+        cfg.api_url, events_table_id, event_id
+    );
+
+    let payload = serde_json::json!({
+        "record": { "fields": { (EventField::Status.name(client.fields())): status.as_str() } }
+    });
+
+    let response = client
+        .patch(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    handle_teable_response(response, "set_event_status").await?;
+    info!(
+        "Teable: Set status of work event {} to {}",
+        event_id,
+        status.as_str()
+    );
+    Ok(())
+}
+
+/// Fetches the sign-ups of one event, or of all events when `event_id` is `None`
+pub async fn get_event_signups(
+    client: &TeableClient,
+    event_id: Option<&str>,
+) -> Result<Vec<EventSignup>> {
+    let cfg = &client.config;
+    let (_, signups_table_id) = cfg.event_tables()?;
+    let mut url = format!(
+        "{}/table/{}/record?take={}",
+        cfg.api_url, signups_table_id, TEABLE_PAGE_SIZE
+    );
+    if let Some(event_id) = event_id {
+        let filter = FilterBuilder::and(client.fields())
+            .is(SignupField::Event, event_id)
+            .build();
+        url = format!(
+            "{}&filter={}",
+            url,
+            urlencoding::encode(&filter.to_string())
+        );
     }
-    Ok(members)
+
+    let response = make_teable_request(client, &url, &cfg.token, "event_signups").await?;
+    let response_text = handle_teable_response(response, "event_signups").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    Ok(parse_records::<EventSignupFields, _>(
+        &teable_response,
+        client.fields(),
+    )?)
+}
+
+pub async fn create_event_signup(
+    client: &TeableClient,
+    event_id: &str,
+    member: &Member,
+) -> Result<EventSignup> {
+    let cfg = &client.config;
+    let (_, signups_table_id) = cfg.event_tables()?;
+    let url = format!("{}/table/{}/record", cfg.api_url, signups_table_id);
+
+    let payload = serde_json::json!({
+        "records": [{
+            "fields": {
+                (SignupField::Event.name(client.fields())): {"id": event_id},
+                (SignupField::Member.name(client.fields())): {"id": member.id},
+                (SignupField::FirstName.name(client.fields())): member.first_name,
+                (SignupField::LastName.name(client.fields())): member.last_name
+            }
+        }]
+    });
+
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&payload)
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    let response_text = handle_teable_response(response, "create_event_signup").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    info!(
+        "Teable: Member {} signed up for work event {}",
+        member.id, event_id
+    );
+    Ok(parse_record::<EventSignupFields, EventSignup>(
+        &teable_response["records"][0],
+        client.fields(),
+    )?)
+}
+
+pub async fn delete_event_signup(client: &TeableClient, signup_id: &str) -> Result<()> {
+    let cfg = &client.config;
+    let (_, signups_table_id) = cfg.event_tables()?;
+    let url = format!(
+        "{}/table/{}/record/{}",
+        cfg.api_url, signups_table_id, signup_id
+    );
+
+    let response = client
+        .delete(&url)
+        .header("Authorization", format!("Bearer {}", cfg.token))
+        .send_throttled(&cfg.send_policy)
+        .await?;
+
+    handle_teable_response(response, "delete_event_signup").await?;
+    info!("Teable: Event sign-up {} deleted", signup_id);
+    Ok(())
+}
+
+/// Fetches the key/value settings table, `None` if no settings table is configured.
+/// Values and the optional `Jahr` may be entered as text or number fields.
+pub async fn get_settings(client: &TeableClient) -> Result<Option<Vec<Setting>>> {
+    let cfg = &client.config;
+    let Some(settings_table_id) = cfg.settings_table_id.as_deref() else {
+        return Ok(None);
+    };
+    let url = format!(
+        "{}/table/{}/record?take={}",
+        cfg.api_url, settings_table_id, TEABLE_PAGE_SIZE
+    );
+
+    let response = make_teable_request(client, &url, &cfg.token, "settings").await?;
+    let response_text = handle_teable_response(response, "settings").await?;
+    let teable_response: Value = serde_json::from_str(&response_text)?;
+    // Rows without key or value, e.g. empty ones, are skipped
+    let settings: Vec<Setting> =
+        parse_records::<SettingFields, Option<Setting>>(&teable_response, client.fields())?
+            .into_iter()
+            .flatten()
+            .collect();
+    debug!("Teable: Fetched {} settings", settings.len());
+    Ok(Some(settings))
 }
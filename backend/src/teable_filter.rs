@@ -0,0 +1,154 @@
+use crate::schema::{FieldNames, TeableField};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Time zone Teable compares date fields in; the calendar day members entered is the one in Berlin
+const TIME_ZONE: &str = "Europe/Berlin";
+
+/// How the conditions of a filter group are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Conjunction {
+    And,
+    Or,
+}
+
+/// Comparison of a field with a value, as Teable names them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Operator {
+    Is,
+    IsOnOrAfter,
+    IsOnOrBefore,
+}
+
+/// A Teable record filter: a condition on one field, or a group of filters
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Filter {
+    Condition {
+        #[serde(rename = "fieldId")]
+        field: String,
+        operator: Operator,
+        value: Value,
+    },
+    Group {
+        conjunction: Conjunction,
+        #[serde(rename = "filterSet")]
+        filters: Vec<Filter>,
+    },
+}
+
+/// The JSON Teable expects in the `filter` query parameter
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+/// Builds a filter group condition by condition, naming the fields as `names` does, e.g.
+/// `FilterBuilder::and(client.fields()).is(WorkHourField::Member, id).within_years(WorkHourField::Date, 2025, 2025)`
+#[derive(Debug, Clone)]
+pub struct FilterBuilder<'a> {
+    names: &'a FieldNames,
+    conjunction: Conjunction,
+    filters: Vec<Filter>,
+}
+
+impl<'a> FilterBuilder<'a> {
+    /// A group matching records that match all of its conditions
+    pub fn and(names: &'a FieldNames) -> Self {
+        Self {
+            names,
+            conjunction: Conjunction::And,
+            filters: Vec::new(),
+        }
+    }
+
+    /// A group matching records that match any of its conditions
+    pub fn or(names: &'a FieldNames) -> Self {
+        Self {
+            names,
+            conjunction: Conjunction::Or,
+            filters: Vec::new(),
+        }
+    }
+
+    pub fn condition<F: TeableField>(
+        mut self,
+        field: F,
+        operator: Operator,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.filters.push(Filter::Condition {
+            field: field.name(self.names).to_string(),
+            operator,
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn is<F: TeableField>(self, field: F, value: impl Into<Value>) -> Self {
+        self.condition(field, Operator::Is, value)
+    }
+
+    /// Matches `values` with a nested `or` group; an empty list adds nothing
+    pub fn any_of<F, V>(self, field: F, values: impl IntoIterator<Item = V>) -> Self
+    where
+        F: TeableField,
+        V: Into<Value>,
+    {
+        let any = values
+            .into_iter()
+            .fold(FilterBuilder::or(self.names), |any, value| {
+                any.is(field, value)
+            });
+        if any.filters.is_empty() {
+            return self;
+        }
+        self.group(any)
+    }
+
+    /// Dates on the calendar day `date` (`YYYY-MM-DD`)
+    pub fn on_day<F: TeableField>(self, field: F, date: &str) -> Self {
+        self.condition(
+            field,
+            Operator::Is,
+            exact_date(format!("{date}T00:00:00.000Z")),
+        )
+    }
+
+    /// Dates from January 1st of `from_year` through December 31st of `to_year`
+    pub fn within_years<F: TeableField>(self, field: F, from_year: i32, to_year: i32) -> Self {
+        self.condition(
+            field,
+            Operator::IsOnOrAfter,
+            exact_date(format!("{from_year}-01-01T00:00:00.000Z")),
+        )
+        .condition(
+            field,
+            Operator::IsOnOrBefore,
+            exact_date(format!("{to_year}-12-31T23:59:59.999Z")),
+        )
+    }
+
+    /// Nests another group, e.g. an `or` of members within an `and` with a date range
+    pub fn group(mut self, group: FilterBuilder) -> Self {
+        self.filters.push(group.build());
+        self
+    }
+
+    pub fn build(self) -> Filter {
+        Filter::Group {
+            conjunction: self.conjunction,
+            filters: self.filters,
+        }
+    }
+}
+
+/// Value of a date condition, compared in `TIME_ZONE`
+fn exact_date(timestamp: String) -> Value {
+    json!({ "mode": "exactDate", "exactDate": timestamp, "timeZone": TIME_ZONE })
+}
@@ -0,0 +1,382 @@
+use crate::attachment;
+use crate::events::{EventSignup, EventStatus, WorkEvent};
+use crate::models::{Member, WorkHour};
+use crate::rules::Setting;
+use crate::schema::{
+    self, EventField, FieldNames, MemberField, SettingField, SignupField, TeableField,
+    WorkHourField,
+};
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use tracing::warn;
+
+/// A record as Teable returns it. Teable leaves empty fields out, so the fields of every
+/// table are optional; a field of the wrong type fails the record with a `RecordError`.
+#[derive(Debug, Deserialize)]
+pub struct TeableRecord<F> {
+    #[serde(default)]
+    pub id: String,
+    pub fields: F,
+}
+
+/// Fields of the records of one Teable table. The serde renames are the default field names;
+/// overridden names (see `FieldNames`) are mapped back to them before deserializing.
+pub trait TableFields: DeserializeOwned {
+    /// Name of the table in error messages
+    const TABLE: &'static str;
+    type Field: TeableField;
+}
+
+/// A Teable record or response that doesn't have the expected shape
+#[derive(Debug)]
+pub struct RecordError {
+    pub table: &'static str,
+    /// `None` if the record itself couldn't be told apart, e.g. without `records` list
+    pub id: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.id {
+            Some(id) => write!(
+                f,
+                "Invalid record {} of the {} table: {}",
+                id, self.table, self.message
+            ),
+            None => write!(
+                f,
+                "Invalid response of the {} table: {}",
+                self.table, self.message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// Parses one record, e.g. the response to `GET /record/{id}`, with the field names of `names`
+pub fn parse_record<F, T>(record: &Value, names: &FieldNames) -> Result<T, RecordError>
+where
+    F: TableFields,
+    T: TryFrom<TeableRecord<F>, Error = RecordError>,
+{
+    let record = schema::with_default_names::<F::Field>(record, names);
+    let parsed: TeableRecord<F> =
+        serde_path_to_error::deserialize(&*record).map_err(|e| RecordError {
+            table: F::TABLE,
+            id: record["id"].as_str().map(str::to_string),
+            message: match e.path().to_string().as_str() {
+                "." => e.inner().to_string(),
+                path => format!("{path}: {}", e.inner()),
+            },
+        })?;
+    T::try_from(parsed)
+}
+
+/// Parses the `records` of a list response. Records that don't have the expected shape are
+/// logged and left out, so one broken row in Teable doesn't fail the whole list.
+pub fn parse_records<F, T>(response: &Value, names: &FieldNames) -> Result<Vec<T>, RecordError>
+where
+    F: TableFields,
+    T: TryFrom<TeableRecord<F>, Error = RecordError>,
+{
+    Ok(records_of::<F>(response)?
+        .iter()
+        .filter_map(|record| match parse_record::<F, T>(record, names) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                warn!("Teable: Skipping record: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Parses the `records` of a create response. Callers match them to what they created by
+/// position, so a record that doesn't have the expected shape fails them all.
+pub fn parse_created_records<F, T>(
+    response: &Value,
+    names: &FieldNames,
+) -> Result<Vec<T>, RecordError>
+where
+    F: TableFields,
+    T: TryFrom<TeableRecord<F>, Error = RecordError>,
+{
+    records_of::<F>(response)?
+        .iter()
+        .map(|record| parse_record::<F, T>(record, names))
+        .collect()
+}
+
+/// Records of a list response before parsing, e.g. to tell whether a page was full
+pub fn records_of<F: TableFields>(response: &Value) -> Result<&Vec<Value>, RecordError> {
+    response["records"].as_array().ok_or_else(|| RecordError {
+        table: F::TABLE,
+        id: None,
+        message: "no `records` list".to_string(),
+    })
+}
+
+fn require_id(table: &'static str, id: String) -> Result<String, RecordError> {
+    if id.is_empty() {
+        return Err(RecordError {
+            table,
+            id: None,
+            message: "record without `id`".to_string(),
+        });
+    }
+    Ok(id)
+}
+
+/// Text fields that were turned into number fields in Teable, or the other way round
+fn text_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(Value::Number(value)) => Ok(Some(value.to_string())),
+        Some(other) => Err(de::Error::invalid_type(
+            de::Unexpected::Other(&other.to_string()),
+            &"text or number",
+        )),
+    }
+}
+
+/// Teable returns dates as UTC timestamps; the calendar day in Europe/Berlin is the one entered
+pub fn berlin_date(value: &str) -> String {
+    use chrono::DateTime;
+    use chrono_tz::Europe::Berlin;
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Berlin).date_naive().to_string())
+        .unwrap_or_else(|_| value.get(0..10).unwrap_or("").to_string())
+}
+
+/// ID of a linked record field, which Teable returns as `{"id": ..}` or a list of those
+pub fn linked_record_id(value: &Value) -> Option<String> {
+    let link = value
+        .as_array()
+        .and_then(|links| links.first())
+        .unwrap_or(value);
+    link.get("id")
+        .and_then(|id| id.as_str())
+        .or_else(|| link.as_str())
+        .map(|id| id.to_string())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkHourFields {
+    /// Linked member record, kept as Teable returns it
+    #[serde(rename = "Mitglied_id", default)]
+    pub member_id: Value,
+    #[serde(rename = "Nachname")]
+    pub last_name: Option<String>,
+    #[serde(rename = "Vorname")]
+    pub first_name: Option<String>,
+    #[serde(rename = "Created on")]
+    pub created_on: Option<String>,
+    #[serde(rename = "Datum")]
+    pub date: Option<String>,
+    #[serde(rename = "Tätigkeit")]
+    pub description: Option<String>,
+    #[serde(rename = "Stunden")]
+    pub hours: Option<f64>,
+    #[serde(rename = "Status")]
+    pub status: Option<String>,
+    #[serde(rename = "Prüfkommentar")]
+    pub review_comment: Option<String>,
+    #[serde(rename = "Korrektur_von")]
+    pub corrects: Option<String>,
+    #[serde(rename = "Notiz")]
+    pub note: Option<String>,
+    #[serde(rename = "Anhänge", default)]
+    pub attachments: Value,
+    #[serde(rename = "Arbeitseinsatz_id")]
+    pub event_id: Option<String>,
+}
+
+impl TableFields for WorkHourFields {
+    const TABLE: &'static str = "work hours";
+    type Field = WorkHourField;
+}
+
+impl TryFrom<TeableRecord<WorkHourFields>> for WorkHour {
+    type Error = RecordError;
+
+    fn try_from(record: TeableRecord<WorkHourFields>) -> Result<Self, RecordError> {
+        let fields = record.fields;
+        Ok(WorkHour {
+            id: require_id(WorkHourFields::TABLE, record.id)?,
+            member_id: Some(fields.member_id),
+            last_name: fields.last_name,
+            first_name: fields.first_name,
+            created_on: fields.created_on,
+            date: fields.date.as_deref().map(berlin_date),
+            description: fields.description,
+            duration_hours: fields.hours,
+            status: fields.status,
+            review_comment: fields.review_comment,
+            corrects: fields.corrects.filter(|id| !id.is_empty()),
+            note: fields.note,
+            attachments: attachment::from_field(&fields.attachments),
+            event_id: fields.event_id.filter(|id| !id.is_empty()),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MemberFields {
+    #[serde(rename = "Vorname")]
+    pub first_name: Option<String>,
+    #[serde(rename = "Nachname")]
+    pub last_name: Option<String>,
+    #[serde(rename = "Email")]
+    pub email: Option<String>,
+    #[serde(rename = "Familie", default, deserialize_with = "text_or_number")]
+    pub family: Option<String>,
+    #[serde(rename = "Geburtsdatum")]
+    pub birth_date: Option<String>,
+    #[serde(rename = "Eintrittsdatum")]
+    pub join_date: Option<String>,
+    #[serde(rename = "Beitrag")]
+    pub contribution: Option<f64>,
+    /// Teable omits unchecked checkboxes
+    #[serde(rename = "Beitrag bezahlt")]
+    pub contribution_paid: Option<bool>,
+    #[serde(rename = "SEPA-Einzug")]
+    pub sepa_date: Option<String>,
+    #[serde(rename = "Sollstunden")]
+    pub required_hours: Option<f64>,
+}
+
+impl TableFields for MemberFields {
+    const TABLE: &'static str = "members";
+    type Field = MemberField;
+}
+
+impl TryFrom<TeableRecord<MemberFields>> for Member {
+    type Error = RecordError;
+
+    fn try_from(record: TeableRecord<MemberFields>) -> Result<Self, RecordError> {
+        let fields = record.fields;
+        Ok(Member {
+            id: require_id(MemberFields::TABLE, record.id)?,
+            first_name: fields.first_name.unwrap_or_default(),
+            last_name: fields.last_name.unwrap_or_default(),
+            email: fields.email.unwrap_or_default(),
+            family_id: fields.family,
+            birth_date: fields.birth_date.unwrap_or_default(),
+            join_date: fields.join_date,
+            contribution: fields.contribution,
+            contribution_paid: fields.contribution_paid.unwrap_or(false),
+            sepa_date: fields.sepa_date.as_deref().map(berlin_date),
+            required_hours: fields.required_hours,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventFields {
+    #[serde(rename = "Titel")]
+    pub title: Option<String>,
+    #[serde(rename = "Datum")]
+    pub date: Option<String>,
+    #[serde(rename = "Beschreibung")]
+    pub description: Option<String>,
+    #[serde(rename = "Benötigte Helfer")]
+    pub needed_helpers: Option<f64>,
+    #[serde(rename = "Stunden")]
+    pub hours: Option<f64>,
+    #[serde(rename = "Status")]
+    pub status: Option<String>,
+}
+
+impl TableFields for EventFields {
+    const TABLE: &'static str = "events";
+    type Field = EventField;
+}
+
+impl TryFrom<TeableRecord<EventFields>> for WorkEvent {
+    type Error = RecordError;
+
+    fn try_from(record: TeableRecord<EventFields>) -> Result<Self, RecordError> {
+        let fields = record.fields;
+        Ok(WorkEvent {
+            id: require_id(EventFields::TABLE, record.id)?,
+            title: fields.title.unwrap_or_default(),
+            date: fields.date.as_deref().map(berlin_date).unwrap_or_default(),
+            description: fields.description,
+            // Negative counts saturate to 0
+            needed_helpers: fields.needed_helpers.unwrap_or(0.0) as u32,
+            hours: fields.hours.unwrap_or(0.0),
+            status: EventStatus::from_teable(fields.status.as_deref()),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventSignupFields {
+    #[serde(rename = "Einsatz_id", default)]
+    pub event_id: Value,
+    #[serde(rename = "Mitglied_id", default)]
+    pub member_id: Value,
+    #[serde(rename = "Vorname")]
+    pub first_name: Option<String>,
+    #[serde(rename = "Nachname")]
+    pub last_name: Option<String>,
+}
+
+impl TableFields for EventSignupFields {
+    const TABLE: &'static str = "event sign-ups";
+    type Field = SignupField;
+}
+
+impl TryFrom<TeableRecord<EventSignupFields>> for EventSignup {
+    type Error = RecordError;
+
+    fn try_from(record: TeableRecord<EventSignupFields>) -> Result<Self, RecordError> {
+        let fields = record.fields;
+        let first_name = fields.first_name.unwrap_or_default();
+        let last_name = fields.last_name.unwrap_or_default();
+        Ok(EventSignup {
+            id: require_id(EventSignupFields::TABLE, record.id)?,
+            event_id: linked_record_id(&fields.event_id),
+            member_id: linked_record_id(&fields.member_id),
+            member_name: format!("{first_name} {last_name}").trim().to_string(),
+        })
+    }
+}
+
+/// Values and the optional `Jahr` may be entered as text or number fields
+#[derive(Debug, Deserialize)]
+pub struct SettingFields {
+    #[serde(rename = "Jahr", default, deserialize_with = "text_or_number")]
+    pub year: Option<String>,
+    #[serde(rename = "Schlüssel")]
+    pub key: Option<String>,
+    #[serde(rename = "Wert", default, deserialize_with = "text_or_number")]
+    pub value: Option<String>,
+}
+
+impl TableFields for SettingFields {
+    const TABLE: &'static str = "settings";
+    type Field = SettingField;
+}
+
+/// Rows of the settings table without key or value, e.g. empty ones, are `None`
+impl TryFrom<TeableRecord<SettingFields>> for Option<Setting> {
+    type Error = RecordError;
+
+    fn try_from(record: TeableRecord<SettingFields>) -> Result<Self, RecordError> {
+        let fields = record.fields;
+        Ok(fields.key.zip(fields.value).map(|(key, value)| Setting {
+            year: fields.year.and_then(|year| year.trim().parse().ok()),
+            key: key.trim().to_string(),
+            value,
+        }))
+    }
+}
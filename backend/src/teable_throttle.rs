@@ -0,0 +1,478 @@
+use crate::circuit_breaker;
+use crate::config::Config;
+use crate::metrics;
+use crate::request_id;
+use async_trait::async_trait;
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Method, Request, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant};
+use tracing::{debug, warn, Instrument};
+
+/// Rate-limited requests are retried this often before the 429 is returned to the caller
+pub const MAX_RETRIES: u32 = 4;
+/// Wait after a 429 without `Retry-After`; doubled for every further 429 in a row
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Spacing of requests after the first 429, grown on every further one
+const MIN_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_INTERVAL: Duration = Duration::from_secs(5);
+/// Below this many requests left in the window, the rest is spread until the reset
+const LOW_REMAINING: u32 = 10;
+
+/// Retries of idempotent requests that failed for a transient reason: a `502`, `503` or
+/// `504` from Teable or its proxy, a connection error or a timeout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, 0 disables them
+    pub attempts: u32,
+    /// Wait before the first retry, doubled for every further one
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 3,
+            base_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// `TEABLE_RETRY_ATTEMPTS` and `TEABLE_RETRY_BASE_DELAY_MS`
+    pub fn from_config(config: &Config) -> Self {
+        RetryPolicy {
+            attempts: config.teable_retry_attempts,
+            base_delay: Duration::from_millis(config.teable_retry_base_delay_ms),
+        }
+    }
+
+    /// Wait before retry `retry` (0 for the first): a random share between half and all of
+    /// the exponential backoff, so requests that failed together don't retry together
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(MAX_BACKOFF);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+/// How requests to a Teable host are retried and when they fail fast; each `TeableClient`
+/// takes it from its `Config`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SendPolicy {
+    pub retry: RetryPolicy,
+    /// Failed requests in a row after which the host's circuit opens
+    pub breaker_threshold: u32,
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for SendPolicy {
+    fn default() -> Self {
+        SendPolicy {
+            retry: RetryPolicy::default(),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SendPolicy {
+    /// `TEABLE_RETRY_*` and `TEABLE_BREAKER_THRESHOLD`, `TEABLE_BREAKER_COOLDOWN_SECS`
+    pub fn from_config(config: &Config) -> Self {
+        SendPolicy {
+            retry: RetryPolicy::from_config(config),
+            breaker_threshold: config.teable_breaker_threshold,
+            breaker_cooldown: Duration::from_secs(config.teable_breaker_cooldown_secs),
+        }
+    }
+}
+
+/// Requests that can be sent again without changing anything twice
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD)
+}
+
+/// The reason an attempt failed, if retrying it may succeed
+fn transient_failure(result: &reqwest::Result<Response>) -> Option<String> {
+    match result {
+        Ok(response)
+            if matches!(
+                response.status(),
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ) =>
+        {
+            Some(response.status().to_string())
+        }
+        Err(e) if e.is_connect() || e.is_timeout() => Some(e.to_string()),
+        _ => None,
+    }
+}
+
+/// Rate limit headers of a Teable response. Teable sends `X-RateLimit-*`; the IETF
+/// `RateLimit-*` names are accepted as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimitHeaders {
+    pub remaining: Option<u32>,
+    /// Time until the window resets
+    pub reset: Option<Duration>,
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitHeaders {
+    pub fn parse(headers: &HeaderMap) -> Self {
+        let number = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                headers
+                    .get(*name)?
+                    .to_str()
+                    .ok()?
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|value| value.is_finite() && *value >= 0.0)
+            })
+        };
+        let seconds = |value: f64| {
+            // Some servers send the reset as a Unix timestamp instead of a delay
+            let value = if value > 1_000_000_000.0 {
+                (value - chrono::Utc::now().timestamp() as f64).max(0.0)
+            } else {
+                value
+            };
+            Duration::from_secs_f64(value)
+        };
+
+        RateLimitHeaders {
+            remaining: number(&["x-ratelimit-remaining", "ratelimit-remaining"])
+                .map(|value| value as u32),
+            reset: number(&["x-ratelimit-reset", "ratelimit-reset"]).map(seconds),
+            retry_after: number(&["retry-after"]).map(seconds),
+        }
+    }
+}
+
+/// Back-pressure of the Teable client, for the board and for tuning bulk jobs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Type)]
+pub struct ThrottleStats {
+    pub requests: u64,
+    /// 429 responses received, including retried ones
+    pub rate_limited: u64,
+    pub retries: u64,
+    /// Requests that were still rate limited after `MAX_RETRIES` retries
+    pub gave_up: u64,
+    /// Retries of GET requests after a `502`, `503`, `504`, connection error or timeout
+    pub transient_retries: u64,
+    /// Requests currently waiting for their turn
+    pub queued: u32,
+    /// Total time requests spent waiting, in milliseconds
+    pub waited_ms: u64,
+    /// Current spacing between requests, 0 while Teable doesn't push back
+    pub interval_ms: u64,
+    /// Requests left in Teable's current window, if Teable reported it
+    pub remaining: Option<u32>,
+}
+
+#[derive(Debug, Default)]
+struct ThrottleState {
+    /// Earliest time the next request may start
+    next_slot: Option<Instant>,
+    interval: Duration,
+    /// 429s in a row, for the exponential backoff
+    consecutive_limited: u32,
+    stats: ThrottleStats,
+}
+
+/// Queues the requests to one Teable host so they slow down when Teable pushes back.
+///
+/// Requests take consecutive slots `interval` apart. The interval grows on every 429 and
+/// shrinks again with successful responses. When Teable reports only a few requests left,
+/// the rest of the window is spread evenly until it resets.
+#[derive(Debug, Default)]
+pub struct TeableThrottle {
+    state: Mutex<ThrottleState>,
+}
+
+impl TeableThrottle {
+    /// Waits for the next free slot
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            let slot = state.next_slot.map_or(now, |slot| slot.max(now));
+            state.next_slot = Some(slot + state.interval);
+            state.stats.requests += 1;
+            let wait = slot - now;
+            if !wait.is_zero() {
+                state.stats.queued += 1;
+                state.stats.waited_ms += wait.as_millis() as u64;
+            }
+            wait
+        };
+        if !wait.is_zero() {
+            // Also leaves the queue when the waiting request is cancelled
+            let _queued = Queued(self);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Adapts the pace to a response that wasn't rate limited
+    pub fn record_response(&self, limits: RateLimitHeaders) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_limited = 0;
+        state.stats.remaining = limits.remaining;
+
+        let spread = match (limits.remaining, limits.reset) {
+            (Some(remaining), Some(reset)) if remaining < LOW_REMAINING => {
+                Some((reset / (remaining + 1)).min(MAX_INTERVAL))
+            }
+            _ => None,
+        };
+        state.interval = match spread {
+            Some(spread) => spread.max(state.interval),
+            // Recover gradually, so a burst right after a 429 doesn't trigger the next one
+            None if state.interval > MIN_INTERVAL => state.interval * 3 / 4,
+            None => Duration::ZERO,
+        };
+        state.stats.interval_ms = state.interval.as_millis() as u64;
+    }
+
+    /// Pauses all requests after a 429 and returns how long to wait before retrying
+    pub fn record_rate_limited(&self, limits: RateLimitHeaders, retry: bool) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let backoff = DEFAULT_BACKOFF
+            .saturating_mul(2u32.saturating_pow(state.consecutive_limited))
+            .min(MAX_BACKOFF);
+        let pause = limits.retry_after.or(limits.reset).unwrap_or(backoff);
+        state.consecutive_limited += 1;
+        state.interval = (state.interval * 2).clamp(MIN_INTERVAL, MAX_INTERVAL);
+
+        let resume = Instant::now() + pause;
+        state.next_slot = Some(state.next_slot.map_or(resume, |slot| slot.max(resume)));
+        state.stats.rate_limited += 1;
+        state.stats.remaining = Some(0);
+        state.stats.interval_ms = state.interval.as_millis() as u64;
+        if retry {
+            state.stats.retries += 1;
+        } else {
+            state.stats.gave_up += 1;
+        }
+        pause
+    }
+
+    pub fn record_transient_retry(&self) {
+        self.state.lock().unwrap().stats.transient_retries += 1;
+    }
+
+    pub fn stats(&self) -> ThrottleStats {
+        self.state.lock().unwrap().stats.clone()
+    }
+}
+
+struct Queued<'a>(&'a TeableThrottle);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().stats.queued -= 1;
+    }
+}
+
+/// Throttles are per host, so a Teable instance and test servers don't slow each other down
+fn registry() -> &'static Mutex<HashMap<String, Arc<TeableThrottle>>> {
+    static THROTTLES: std::sync::OnceLock<Mutex<HashMap<String, Arc<TeableThrottle>>>> =
+        std::sync::OnceLock::new();
+    THROTTLES.get_or_init(Default::default)
+}
+
+/// `host:port` of `url`, the key of the per-host throttles and circuit breakers
+pub fn host_key(url: &reqwest::Url) -> String {
+    format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    )
+}
+
+/// The throttle of the host of `url`
+pub fn for_url(url: &reqwest::Url) -> Arc<TeableThrottle> {
+    registry()
+        .lock()
+        .unwrap()
+        .entry(host_key(url))
+        .or_default()
+        .clone()
+}
+
+/// Throttle and circuit breaker of one Teable host
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct HostStats {
+    pub host: String,
+    pub stats: ThrottleStats,
+    pub breaker: circuit_breaker::BreakerStats,
+}
+
+/// Back-pressure of all Teable hosts, with the retry limits in effect
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ThrottleOverview {
+    pub max_retries: u32,
+    pub transient_retry_attempts: u32,
+    pub hosts: Vec<HostStats>,
+}
+
+/// Back-pressure of every Teable host contacted so far
+pub fn all_stats() -> Vec<(String, ThrottleStats)> {
+    let mut stats: Vec<(String, ThrottleStats)> = registry()
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(host, throttle)| (host.clone(), throttle.stats()))
+        .collect();
+    stats.sort_by(|a, b| a.0.cmp(&b.0));
+    stats
+}
+
+#[async_trait]
+pub trait SendThrottled {
+    /// Like `send`, but queued behind the host's throttle and retried on 429 responses.
+    /// A request that is still rate limited after `MAX_RETRIES` returns the 429 response.
+    /// GET requests are also retried on transient failures, see [`RetryPolicy`]. Fails with
+    /// [`circuit_breaker::CircuitOpen`] without sending anything while Teable is down.
+    async fn send_throttled(self, policy: &SendPolicy) -> anyhow::Result<Response>;
+}
+
+#[async_trait]
+impl SendThrottled for RequestBuilder {
+    async fn send_throttled(self, policy: &SendPolicy) -> anyhow::Result<Response> {
+        let (client, request) = self.build_split();
+        let mut request = request?;
+        // Lets Teable's logs be matched with ours
+        if let Some(value) = request_id::current().and_then(|id| HeaderValue::from_str(&id).ok()) {
+            request
+                .headers_mut()
+                .insert(request_id::REQUEST_ID_HEADER, value);
+        }
+        let span = tracing::debug_span!(
+            "teable",
+            method = %request.method(),
+            path = %request.url().path()
+        );
+        let breaker = circuit_breaker::for_url(request.url(), policy);
+        breaker.allow()?;
+        let result = send_with_retries(client, request, policy.retry)
+            .instrument(span)
+            .await;
+        if transient_failure(&result).is_some() {
+            breaker.record_failure();
+        } else {
+            breaker.record_success();
+        }
+        Ok(result?)
+    }
+}
+
+/// Queues the request behind the host's throttle and retries it on 429 responses, and on
+/// transient failures if it is idempotent
+async fn send_with_retries(
+    client: reqwest::Client,
+    request: Request,
+    retry: RetryPolicy,
+) -> reqwest::Result<Response> {
+    let throttle = for_url(request.url());
+    let policy = if is_idempotent(request.method()) {
+        retry
+    } else {
+        RetryPolicy {
+            attempts: 0,
+            ..RetryPolicy::default()
+        }
+    };
+    let mut attempt = 0;
+    let mut transient_attempt = 0;
+    loop {
+        // Streaming bodies can't be cloned and are sent once
+        let Some(this_attempt) = request.try_clone() else {
+            throttle.acquire().await;
+            return execute(&client, request).await;
+        };
+        throttle.acquire().await;
+        let result = execute(&client, this_attempt).await;
+        if let Some(failure) = transient_failure(&result) {
+            if transient_attempt < policy.attempts {
+                let pause = policy.backoff(transient_attempt);
+                transient_attempt += 1;
+                throttle.record_transient_retry();
+                debug!(
+                    "Teable: {}, retry {} of {} in {:?}: {} {}",
+                    failure,
+                    transient_attempt,
+                    policy.attempts,
+                    pause,
+                    request.method(),
+                    request.url().path()
+                );
+                tokio::time::sleep(pause).await;
+                continue;
+            }
+            if policy.attempts > 0 {
+                warn!(
+                    "Teable: Still failing after {} retries ({}): {} {}",
+                    policy.attempts,
+                    failure,
+                    request.method(),
+                    request.url().path()
+                );
+            }
+        }
+
+        let response = result?;
+        let limits = RateLimitHeaders::parse(response.headers());
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            throttle.record_response(limits);
+            return Ok(response);
+        }
+
+        let retry = attempt < MAX_RETRIES;
+        let pause = throttle.record_rate_limited(limits, retry);
+        if !retry {
+            warn!(
+                "Teable: Still rate limited after {} retries: {} {}",
+                MAX_RETRIES,
+                request.method(),
+                request.url().path()
+            );
+            return Ok(response);
+        }
+        attempt += 1;
+        debug!(
+            "Teable: Rate limited, retry {} of {} in {:?}: {} {}",
+            attempt,
+            MAX_RETRIES,
+            pause,
+            request.method(),
+            request.url().path()
+        );
+    }
+}
+
+async fn execute(client: &reqwest::Client, request: Request) -> reqwest::Result<Response> {
+    let method = request.method().clone();
+    let started = std::time::Instant::now();
+    let result = client.execute(request).await;
+    metrics::record_teable_call(
+        method.as_str(),
+        result
+            .as_ref()
+            .ok()
+            .map(|response| response.status().as_u16()),
+        started.elapsed(),
+    );
+    result
+}
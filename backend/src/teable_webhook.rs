@@ -0,0 +1,66 @@
+use crate::app_events::TeableTable;
+use crate::config::Config;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use specta::Type;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header with the hex HMAC-SHA256 of the raw body, optionally prefixed with `sha256=`
+pub const SIGNATURE_HEADER: &str = "x-teable-signature";
+
+/// Records looked up per webhook to find the members they belong to; the rest is only
+/// pushed to the board
+pub const MAX_LOOKUPS: usize = 50;
+
+/// Body of `POST /webhooks/teable`, as configured in Teable's webhook settings
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeableWebhook {
+    /// e.g. `record.update`, only logged
+    #[serde(default)]
+    pub event: Option<String>,
+    pub table_id: String,
+    #[serde(default)]
+    pub record_ids: Vec<String>,
+}
+
+/// Answer to Teable
+#[derive(Debug, Serialize, Type)]
+pub struct WebhookReceipt {
+    /// The changed table isn't one the backend caches or watches
+    pub ignored: bool,
+}
+
+/// Checks the signature of a webhook against `TEABLE_WEBHOOK_SECRET`
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let signature = signature.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let Ok(mut mac) = HmacSha256::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Which of the configured tables a webhook is about; `None` for other tables of the base
+pub fn table_of(config: &Config, table_id: &str) -> Option<TeableTable> {
+    let is = |configured: Option<&String>| configured.is_some_and(|id| id == table_id);
+    if table_id == config.members_table_id {
+        Some(TeableTable::Members)
+    } else if table_id == config.work_hours_table_id {
+        Some(TeableTable::WorkHours)
+    } else if is(config.events_table_id.as_ref()) {
+        Some(TeableTable::Events)
+    } else if is(config.event_signups_table_id.as_ref()) {
+        Some(TeableTable::EventSignups)
+    } else if is(config.settings_table_id.as_ref()) {
+        Some(TeableTable::Settings)
+    } else {
+        None
+    }
+}
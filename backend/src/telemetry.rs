@@ -0,0 +1,142 @@
+use crate::config::{Config, FileStoreConfig};
+use crate::database::Database;
+use crate::schema::MemberField;
+use crate::teable;
+use crate::teable::TeableClient;
+use anyhow::Result;
+use serde::Serialize;
+use specta::Type;
+use std::collections::BTreeMap;
+use tracing::{debug, info};
+
+/// Version of the payload below; raised whenever a field changes meaning
+pub const SCHEMA_VERSION: u32 = 1;
+/// Feature usage covers the audit log of this many days
+const USAGE_DAYS: i64 = 30;
+
+/// Features an instance has configured
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+pub struct TelemetryFeatures {
+    pub work_events: bool,
+    pub settings_table: bool,
+    pub s3_storage: bool,
+    pub admin_views: bool,
+    pub metrics: bool,
+}
+
+impl TelemetryFeatures {
+    pub fn from_config(config: &Config) -> Self {
+        TelemetryFeatures {
+            work_events: config.events_table_id.is_some()
+                && config.event_signups_table_id.is_some(),
+            settings_table: config.settings_table_id.is_some(),
+            s3_storage: matches!(config.file_store, FileStoreConfig::S3(_)),
+            admin_views: !config.admin_views.is_empty(),
+            metrics: config.metrics_token.is_some(),
+        }
+    }
+}
+
+/// The complete anonymous report of an instance. Only sizes and counts, all bucketed;
+/// no names, emails, record IDs or club settings.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct TelemetryPayload {
+    pub schema_version: u32,
+    /// Random ID generated on first use, so the reports of one instance can be told apart
+    pub instance_id: String,
+    pub version: String,
+    /// Bucket of the Teable members, e.g. `100-249`
+    pub members: String,
+    /// Bucket of the login accounts
+    pub accounts: String,
+    pub features: TelemetryFeatures,
+    /// Bucket per audit action of the last 30 days, e.g. `work_hour_created: 10-49`
+    pub usage: BTreeMap<String, String>,
+}
+
+/// The report this instance would send, and whether it does
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct TelemetryPreview {
+    pub enabled: bool,
+    pub url: Option<String>,
+    /// RFC 3339
+    pub last_sent_at: Option<String>,
+    pub payload: TelemetryPayload,
+}
+
+/// Coarse size class, so a report can't identify a club by its exact member count
+pub fn bucket(count: u64) -> &'static str {
+    match count {
+        0 => "0",
+        1..=9 => "1-9",
+        10..=49 => "10-49",
+        50..=99 => "50-99",
+        100..=249 => "100-249",
+        250..=499 => "250-499",
+        500..=999 => "500-999",
+        _ => "1000+",
+    }
+}
+
+/// Gathers the report the instance would send next
+pub async fn collect(
+    client: &TeableClient,
+    database: &Database,
+    features: &TelemetryFeatures,
+) -> Result<TelemetryPayload> {
+    let (instance_id, _) = database.get_telemetry_state().await?;
+    let members = teable::get_all_members_with_projection(client, &[MemberField::Email])
+        .await?
+        .len();
+    let accounts = database.count_accounts().await?;
+    let since = chrono::Utc::now() - chrono::Duration::days(USAGE_DAYS);
+    let usage = database
+        .count_audit_actions_since(since)
+        .await?
+        .into_iter()
+        .map(|(action, count)| {
+            (
+                action.as_str().to_string(),
+                bucket(count as u64).to_string(),
+            )
+        })
+        .collect();
+
+    Ok(TelemetryPayload {
+        schema_version: SCHEMA_VERSION,
+        instance_id,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        members: bucket(members as u64).to_string(),
+        accounts: bucket(accounts as u64).to_string(),
+        features: features.clone(),
+        usage,
+    })
+}
+
+/// Sends a report if the last one is older than `interval`
+pub async fn send_if_due(
+    client: &TeableClient,
+    database: &Database,
+    url: &str,
+    interval: chrono::Duration,
+    features: &TelemetryFeatures,
+) -> Result<()> {
+    let (_, last_sent_at) = database.get_telemetry_state().await?;
+    let now = chrono::Utc::now();
+    if last_sent_at.is_some_and(|sent| now - sent < interval) {
+        debug!("Telemetry: Last report is recent, nothing to send");
+        return Ok(());
+    }
+
+    let payload = collect(client, database, features).await?;
+    client
+        .post(url)
+        .json(&payload)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await?
+        .error_for_status()?;
+    database.record_telemetry_sent(now).await?;
+    info!("Telemetry: Sent anonymous usage report to {}", url);
+    Ok(())
+}
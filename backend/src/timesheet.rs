@@ -0,0 +1,256 @@
+use crate::correction;
+use crate::export::{Column, Locale, TextBlock, TOTAL};
+use crate::models::{WorkHour, WorkHourEntry, WorkHourStatus};
+use crate::pdf::{parse_color, write_pdf, Font, PageContent, PAGE_HEIGHT, PAGE_WIDTH};
+use crate::utils::convert_work_hours_to_entries;
+use chrono::NaiveDate;
+
+/// Everything printed on a member's timesheet of one month
+#[derive(Debug, Clone)]
+pub struct TimesheetDocument<'a> {
+    pub club_name: &'a str,
+    pub club_address: Option<&'a str>,
+    /// `#rrggbb` or `#rgb`, used for the letterhead
+    pub primary_color: &'a str,
+    pub member_name: &'a str,
+    pub year: i32,
+    /// 1 to 12
+    pub month: u32,
+    /// Entries of the month in date order, see `month_entries`
+    pub entries: &'a [WorkHourEntry],
+    pub printed_on: NaiveDate,
+    /// Language of the form, its dates and numbers
+    pub locale: Locale,
+}
+
+/// Columns of the table of entries on a timesheet
+pub const COLUMNS: [Column; 4] = [
+    Column::new("Datum", "Date"),
+    Column::new("Tätigkeit", "Activity"),
+    Column::new("Stunden", "Hours"),
+    Column::new("Status", "Status"),
+];
+
+const TITLE: TextBlock = TextBlock::new(
+    "Arbeitsstundennachweis {month} {year}",
+    "Work Hours Timesheet {month} {year}",
+);
+const MEMBER: TextBlock = TextBlock::new("Mitglied: {member}", "Member: {member}");
+const NO_ENTRIES: TextBlock = TextBlock::new(
+    "Keine Arbeitsstunden in diesem Monat.",
+    "No work hours in this month.",
+);
+const MEMBER_SIGNATURE: TextBlock = TextBlock::new(
+    "Datum, Unterschrift Mitglied",
+    "Date, signature of the member",
+);
+const COORDINATOR_SIGNATURE: TextBlock = TextBlock::new(
+    "Datum, Unterschrift Platzwart",
+    "Date, signature of the groundskeeper",
+);
+const PRINTED_ON: TextBlock = TextBlock::new("Erstellt am {date}", "Created on {date}");
+
+const MARGIN: f32 = 60.0;
+/// Longer descriptions are cut off so they don't run into the hours column
+const DESCRIPTION_CHARS: usize = 48;
+/// Below this the signature lines start; further entries are only part of the total
+const TABLE_BOTTOM: f32 = 190.0;
+
+/// The month of a timesheet file name like `05.pdf` or `5.pdf`
+pub fn parse_timesheet_file(file: &str) -> Option<u32> {
+    let month = file.strip_suffix(".pdf")?;
+    if month.is_empty() || month.len() > 2 || !month.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    month.parse().ok().filter(|month| (1..=12).contains(month))
+}
+
+/// The entries of a month that belong on the timesheet, oldest first; rejected entries are
+/// left out, submitted ones are listed with their status
+pub fn month_entries(work_hours: &[WorkHour], year: i32, month: u32) -> Vec<WorkHourEntry> {
+    let prefix = format!("{year:04}-{month:02}-");
+    let mut entries: Vec<WorkHourEntry> = convert_work_hours_to_entries(work_hours, "Timesheet")
+        .into_iter()
+        .filter(|entry| entry.date.starts_with(&prefix))
+        .filter(|entry| entry.status != WorkHourStatus::Rejected)
+        .collect();
+    entries.sort_by(|a, b| a.date.cmp(&b.date).then(a.id.cmp(&b.id)));
+    entries
+}
+
+fn status_label(status: WorkHourStatus, locale: Locale) -> &'static str {
+    match (status, locale) {
+        (WorkHourStatus::Approved, Locale::De) => "genehmigt",
+        (WorkHourStatus::Approved, Locale::En) => "approved",
+        (WorkHourStatus::Submitted, Locale::De) => "offen",
+        (WorkHourStatus::Submitted, Locale::En) => "pending",
+        (WorkHourStatus::Rejected, Locale::De) => "abgelehnt",
+        (WorkHourStatus::Rejected, Locale::En) => "rejected",
+    }
+}
+
+/// `2025-05-03` in the format of `locale`; anything else is printed as is
+fn format_date(date: &str, locale: Locale) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| locale.format_date(date))
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// Renders the timesheet as a single A4 page PDF with lines for the signatures of the member
+/// and the groundskeeping coordinator
+pub fn render_pdf(document: &TimesheetDocument) -> Vec<u8> {
+    let mut content = PageContent::default();
+    let (r, g, b) = parse_color(document.primary_color);
+    let locale = document.locale;
+
+    // Letterhead
+    let mut y = PAGE_HEIGHT - 60.0;
+    content.fill_color(r, g, b);
+    content.text(MARGIN, y, Font::Bold, 16.0, document.club_name);
+    content.fill_color(0.4, 0.4, 0.4);
+    if let Some(address) = document.club_address {
+        y -= 14.0;
+        content.text(MARGIN, y, Font::Regular, 9.0, address);
+    }
+    y -= 10.0;
+    content.stroke_color(r, g, b);
+    content.line(MARGIN, y, PAGE_WIDTH - MARGIN, y, 1.5);
+
+    content.fill_color(0.0, 0.0, 0.0);
+    y -= 36.0;
+    content.text(
+        MARGIN,
+        y,
+        Font::Bold,
+        14.0,
+        &TITLE.render(
+            locale,
+            &[
+                ("month", locale.month_name(document.month)),
+                ("year", &document.year.to_string()),
+            ],
+        ),
+    );
+    y -= 22.0;
+    content.text(
+        MARGIN,
+        y,
+        Font::Regular,
+        11.0,
+        &MEMBER.render(locale, &[("member", document.member_name)]),
+    );
+
+    y -= 30.0;
+    let date_x = MARGIN;
+    let description_x = MARGIN + 75.0;
+    let hours_x = PAGE_WIDTH - MARGIN - 130.0;
+    let status_x = PAGE_WIDTH - MARGIN - 70.0;
+    let [date_column, description_column, hours_column, status_column] = COLUMNS;
+    for (x, column) in [
+        (date_x, date_column),
+        (description_x, description_column),
+        (hours_x, hours_column),
+        (status_x, status_column),
+    ] {
+        content.text(x, y, Font::Bold, 10.0, column.header(locale));
+    }
+    y -= 6.0;
+    content.stroke_color(0.6, 0.6, 0.6);
+    content.line(MARGIN, y, PAGE_WIDTH - MARGIN, y, 0.5);
+    y -= 14.0;
+
+    if document.entries.is_empty() {
+        content.fill_color(0.4, 0.4, 0.4);
+        content.text(
+            MARGIN,
+            y,
+            Font::Regular,
+            10.0,
+            &NO_ENTRIES.render(locale, &[]),
+        );
+        content.fill_color(0.0, 0.0, 0.0);
+        y -= 14.0;
+    }
+    for entry in document.entries {
+        // Keep room for the signatures; remaining entries are only part of the total
+        if y < TABLE_BOTTOM {
+            content.text(description_x, y, Font::Regular, 10.0, "…");
+            y -= 14.0;
+            break;
+        }
+        let description = match entry.corrects {
+            Some(_) => correction::label(&entry.description, locale),
+            None => entry.description.clone(),
+        };
+        let description: String = description.chars().take(DESCRIPTION_CHARS).collect();
+        content.text(
+            date_x,
+            y,
+            Font::Regular,
+            10.0,
+            &format_date(&entry.date, locale),
+        );
+        content.text(description_x, y, Font::Regular, 10.0, &description);
+        content.text(
+            hours_x,
+            y,
+            Font::Regular,
+            10.0,
+            &locale.format_number(entry.duration_hours),
+        );
+        content.text(
+            status_x,
+            y,
+            Font::Regular,
+            10.0,
+            status_label(entry.status, locale),
+        );
+        y -= 14.0;
+    }
+    y += 8.0;
+    content.line(MARGIN, y, PAGE_WIDTH - MARGIN, y, 0.5);
+    y -= 14.0;
+    let total: f64 = document
+        .entries
+        .iter()
+        .map(|entry| entry.duration_hours)
+        .sum();
+    content.text(date_x, y, Font::Bold, 10.0, TOTAL.header(locale));
+    content.text(hours_x, y, Font::Bold, 10.0, &locale.format_number(total));
+
+    // Signatures, at the same height however many entries there are
+    let signature_y = 110.0;
+    let line_width = 200.0;
+    let right_x = PAGE_WIDTH - MARGIN - line_width;
+    content.stroke_color(0.0, 0.0, 0.0);
+    content.line(MARGIN, signature_y, MARGIN + line_width, signature_y, 0.5);
+    content.line(right_x, signature_y, right_x + line_width, signature_y, 0.5);
+    content.text(
+        MARGIN,
+        signature_y - 13.0,
+        Font::Regular,
+        9.0,
+        &MEMBER_SIGNATURE.render(locale, &[]),
+    );
+    content.text(
+        right_x,
+        signature_y - 13.0,
+        Font::Regular,
+        9.0,
+        &COORDINATOR_SIGNATURE.render(locale, &[]),
+    );
+
+    content.fill_color(0.4, 0.4, 0.4);
+    content.text(
+        MARGIN,
+        50.0,
+        Font::Regular,
+        8.0,
+        &PRINTED_ON.render(
+            locale,
+            &[("date", &locale.format_date(document.printed_on))],
+        ),
+    );
+
+    write_pdf(&content.bytes, None)
+}
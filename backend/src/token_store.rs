@@ -1,10 +1,20 @@
+use crate::config::ResetTokenStoreKind;
+use crate::database::Database;
+use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::info;
 use uuid::Uuid;
 
+/// How long a password reset link stays valid
+pub const RESET_TOKEN_TTL: Duration = Duration::hours(24);
+/// How long a member sharing an email with others has to pick their profile after login
+pub const SELECTION_TOKEN_TTL: Duration = Duration::minutes(5);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResetToken {
     pub token: String,
@@ -13,83 +23,144 @@ pub struct ResetToken {
     pub expires_at: DateTime<Utc>,
 }
 
+/// Prefix of token subjects naming a login account instead of a Teable member
+const ACCOUNT_SUBJECT_PREFIX: &str = "account:";
+
+/// Subject of reset tokens for an account created by a family invitation. Such accounts
+/// have an email of their own, so the token can't name the Teable member, whose email
+/// belongs to another account.
+pub fn account_subject(user_id: i32) -> String {
+    format!("{ACCOUNT_SUBJECT_PREFIX}{user_id}")
+}
+
+/// The account ID of a subject created by `account_subject`
+pub fn parse_account_subject(subject: &str) -> Option<i32> {
+    subject.strip_prefix(ACCOUNT_SUBJECT_PREFIX)?.parse().ok()
+}
+
+/// Tokens of password reset and invitation links, issued for a Teable member ID or an
+/// `account_subject`. A subject has at most one; issuing a new one invalidates the previous
+/// link. Also keeps the IDs (`jti`) of selection tokens, so each works only once.
+#[async_trait]
+pub trait ResetTokenStore: Send + Sync {
+    /// Issues a token for the subject, valid for `RESET_TOKEN_TTL`
+    async fn create_reset_token(&self, member_id: &str) -> Result<String, sqlx::Error>;
+
+    /// Invalidates the token and returns its subject, `None` if it is unknown or expired
+    async fn consume_reset_token(&self, token: &str) -> Result<Option<String>, sqlx::Error>;
+
+    /// Issues the `jti` of a selection token, valid for `SELECTION_TOKEN_TTL`
+    async fn issue_selection_token(&self) -> Result<String, sqlx::Error>;
+
+    /// Invalidates a selection token; `false` if it was used already, expired or never issued
+    async fn consume_selection_token(&self, jti: &str) -> Result<bool, sqlx::Error>;
+
+    /// Removes expired tokens, returning how many were purged
+    async fn cleanup_expired_tokens(&self) -> Result<u64, sqlx::Error>;
+}
+
+pub type SharedResetTokenStore = Arc<dyn ResetTokenStore>;
+
+/// Builds the store selected by `RESET_TOKEN_STORE`
+pub fn from_kind(kind: ResetTokenStoreKind, database: Database) -> SharedResetTokenStore {
+    match kind {
+        ResetTokenStoreKind::Sqlite => {
+            info!("Token Store: Keeping reset tokens in SQLite");
+            Arc::new(SqliteTokenStore::new(database))
+        }
+        ResetTokenStoreKind::Memory => {
+            info!("Token Store: Keeping reset tokens in memory, they are lost on restart");
+            Arc::new(MemoryTokenStore::new())
+        }
+    }
+}
+
+fn new_token() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Tokens in memory; links sent before a restart stop working
 #[derive(Clone)]
-pub struct TokenStore {
+pub struct MemoryTokenStore {
     tokens: Arc<RwLock<HashMap<String, ResetToken>>>,
     user_tokens: Arc<RwLock<HashMap<String, String>>>, // user_id -> token_id mapping (changed to String keys)
+    selection_tokens: Arc<RwLock<HashMap<String, DateTime<Utc>>>>, // jti -> expiry
 }
 
-impl Default for TokenStore {
+impl Default for MemoryTokenStore {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl TokenStore {
+impl MemoryTokenStore {
     pub fn new() -> Self {
         Self {
             tokens: Arc::new(RwLock::new(HashMap::new())),
             user_tokens: Arc::new(RwLock::new(HashMap::new())),
+            selection_tokens: Arc::new(RwLock::new(HashMap::new())),
         }
     }
+}
 
-    pub async fn create_reset_token(&self, user_id: String) -> String {
-        let token_id = Uuid::new_v4().to_string();
+#[async_trait]
+impl ResetTokenStore for MemoryTokenStore {
+    async fn create_reset_token(&self, member_id: &str) -> Result<String, sqlx::Error> {
+        let token_id = new_token();
         let now = Utc::now();
-        let expires_at = now + Duration::hours(24);
 
         let reset_token = ResetToken {
             token: token_id.clone(),
-            user_id: user_id.clone(), // Clone before moving
+            user_id: member_id.to_string(),
             created_at: now,
-            expires_at,
+            expires_at: now + RESET_TOKEN_TTL,
         };
 
-        {
-            let mut tokens = self.tokens.write().await;
-            let mut user_tokens = self.user_tokens.write().await;
-
-            // Remove any existing token for this user
-            if let Some(old_token) = user_tokens.get(&user_id) {
-                tokens.remove(old_token);
-            }
+        let mut tokens = self.tokens.write().await;
+        let mut user_tokens = self.user_tokens.write().await;
 
-            tokens.insert(token_id.clone(), reset_token);
-            user_tokens.insert(user_id, token_id.clone());
+        // Remove any existing token for this user
+        if let Some(old_token) = user_tokens.get(member_id) {
+            tokens.remove(old_token);
         }
 
-        token_id
-    }
+        tokens.insert(token_id.clone(), reset_token);
+        user_tokens.insert(member_id.to_string(), token_id.clone());
 
-    pub async fn get_reset_token(&self, token: &str) -> Option<ResetToken> {
-        let tokens = self.tokens.read().await;
-        tokens.get(token).cloned()
+        Ok(token_id)
     }
 
-    pub async fn consume_reset_token(&self, token: &str) -> Option<ResetToken> {
+    async fn consume_reset_token(&self, token: &str) -> Result<Option<String>, sqlx::Error> {
         let mut tokens = self.tokens.write().await;
         let mut user_tokens = self.user_tokens.write().await;
 
-        if let Some(reset_token) = tokens.remove(token) {
-            user_tokens.remove(&reset_token.user_id);
-            Some(reset_token)
-        } else {
-            None
-        }
+        let Some(reset_token) = tokens.remove(token) else {
+            return Ok(None);
+        };
+        user_tokens.remove(&reset_token.user_id);
+        Ok((reset_token.expires_at > Utc::now()).then_some(reset_token.user_id))
     }
 
-    pub async fn is_token_valid(&self, token: &str) -> bool {
-        if let Some(reset_token) = self.get_reset_token(token).await {
-            reset_token.expires_at > Utc::now()
-        } else {
-            false
-        }
+    async fn issue_selection_token(&self) -> Result<String, sqlx::Error> {
+        let jti = new_token();
+        self.selection_tokens
+            .write()
+            .await
+            .insert(jti.clone(), Utc::now() + SELECTION_TOKEN_TTL);
+        Ok(jti)
     }
 
-    // Clean up expired tokens periodically
-    #[allow(dead_code)]
-    pub async fn cleanup_expired_tokens(&self) {
+    async fn consume_selection_token(&self, jti: &str) -> Result<bool, sqlx::Error> {
+        let expires_at = self.selection_tokens.write().await.remove(jti);
+        Ok(expires_at.is_some_and(|expires_at| expires_at > Utc::now()))
+    }
+
+    async fn cleanup_expired_tokens(&self) -> Result<u64, sqlx::Error> {
         let now = Utc::now();
+        let mut selection_tokens = self.selection_tokens.write().await;
+        let selection_count = selection_tokens.len();
+        selection_tokens.retain(|_, expires_at| *expires_at > now);
+        let expired_selections = (selection_count - selection_tokens.len()) as u64;
         let mut tokens = self.tokens.write().await;
         let mut user_tokens = self.user_tokens.write().await;
 
@@ -102,8 +173,60 @@ impl TokenStore {
             })
             .collect();
 
-        for token_id in expired_tokens {
-            tokens.remove(&token_id);
+        for token_id in &expired_tokens {
+            tokens.remove(token_id);
         }
+
+        Ok(expired_tokens.len() as u64 + expired_selections)
+    }
+}
+
+/// Tokens in the SQLite `reset_tokens` table, so links survive restarts. Only a SHA-256
+/// hash of each token is stored; a copy of the database doesn't contain working links.
+#[derive(Clone)]
+pub struct SqliteTokenStore {
+    database: Database,
+}
+
+impl SqliteTokenStore {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+}
+
+/// What the `reset_tokens` table stores instead of the token
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[async_trait]
+impl ResetTokenStore for SqliteTokenStore {
+    async fn create_reset_token(&self, member_id: &str) -> Result<String, sqlx::Error> {
+        let token = new_token();
+        self.database
+            .create_reset_token(member_id, &hash_token(&token), Utc::now() + RESET_TOKEN_TTL)
+            .await?;
+        Ok(token)
+    }
+
+    async fn consume_reset_token(&self, token: &str) -> Result<Option<String>, sqlx::Error> {
+        self.database.consume_reset_token(&hash_token(token)).await
+    }
+
+    async fn issue_selection_token(&self) -> Result<String, sqlx::Error> {
+        let jti = new_token();
+        self.database
+            .create_selection_token(&jti, Utc::now() + SELECTION_TOKEN_TTL)
+            .await?;
+        Ok(jti)
+    }
+
+    async fn consume_selection_token(&self, jti: &str) -> Result<bool, sqlx::Error> {
+        self.database.consume_selection_token(jti).await
+    }
+
+    async fn cleanup_expired_tokens(&self) -> Result<u64, sqlx::Error> {
+        Ok(self.database.delete_expired_reset_tokens().await?
+            + self.database.delete_expired_selection_tokens().await?)
     }
 }
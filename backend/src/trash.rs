@@ -0,0 +1,73 @@
+use crate::models::WorkHour;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use specta::Type;
+
+/// Deleted work hours can be restored for this many days before they are purged
+pub const RETENTION_DAYS: i64 = 30;
+
+/// Entries deleted before this point in time are no longer restorable
+pub fn retention_cutoff(now: DateTime<Utc>) -> DateTime<Utc> {
+    now - Duration::days(RETENTION_DAYS)
+}
+
+pub fn restorable_until(deleted_at: DateTime<Utc>) -> DateTime<Utc> {
+    deleted_at + Duration::days(RETENTION_DAYS)
+}
+
+/// Answer to deleting an entry
+#[derive(Debug, Serialize, Type)]
+pub struct WorkHourDeletion {
+    /// `None` if the entry can't be restored, see `DeletedWorkHour::from_work_hour`
+    pub restorable_until: Option<String>,
+}
+
+/// A member's deleted entries that can still be restored, newest first
+#[derive(Debug, Serialize, Type)]
+pub struct Trash {
+    pub retention_days: i64,
+    pub entries: Vec<DeletedWorkHour>,
+}
+
+/// Snapshot of a work hour entry deleted from Teable, kept so the deletion can be undone
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct DeletedWorkHour {
+    /// Teable record ID the entry had before it was deleted
+    pub work_hour_id: String,
+    pub member_id: String,
+    pub date: String,
+    pub description: String,
+    pub hours: f64,
+    pub status: Option<String>,
+    pub review_comment: Option<String>,
+    pub deleted_by: String,
+    /// RFC 3339 timestamps
+    pub deleted_at: String,
+    pub restorable_until: String,
+}
+
+impl DeletedWorkHour {
+    /// Returns None for records without owner, date or hours, which can't be re-created, and
+    /// for corrections, which the board books again instead
+    pub fn from_work_hour(
+        work_hour: &WorkHour,
+        deleted_by: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> Option<Self> {
+        if work_hour.corrects.is_some() {
+            return None;
+        }
+        Some(DeletedWorkHour {
+            work_hour_id: work_hour.id.clone(),
+            member_id: work_hour.get_member_id()?,
+            date: work_hour.date.clone()?,
+            description: work_hour.description.clone().unwrap_or_default(),
+            hours: work_hour.duration_hours?,
+            status: work_hour.status.clone(),
+            review_comment: work_hour.review_comment.clone(),
+            deleted_by: deleted_by.to_string(),
+            deleted_at: deleted_at.to_rfc3339(),
+            restorable_until: restorable_until(deleted_at).to_rfc3339(),
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use crate::config::Config;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use specta::Type;
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// Seconds a code is valid, the default of authenticator apps
+pub const PERIOD_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+/// 160 bits, as recommended by RFC 4226
+const SECRET_BYTES: usize = 20;
+/// Codes of the previous and next period are accepted too, for phone clocks that are a bit off
+const ALLOWED_DRIFT: i64 = 1;
+const NONCE_BYTES: usize = 12;
+
+/// A member's TOTP secret as stored in the `two_factor` table
+#[derive(Debug, Clone)]
+pub struct TwoFactorRecord {
+    pub member_id: String,
+    /// Encrypted by `SecretCipher`
+    pub encrypted_secret: String,
+    /// Set once the member confirmed the setup with a code; until then login doesn't ask
+    pub enabled_at: Option<String>,
+    /// Time step of the last accepted code, so a code can't be used twice
+    pub last_used_step: Option<i64>,
+}
+
+/// Answer of `POST /2fa/setup`: what the authenticator app needs
+#[derive(Debug, Serialize, Type)]
+pub struct TwoFactorSetupResponse {
+    /// Base32, for typing into the app by hand
+    pub secret: String,
+    /// `otpauth://totp/...`, shown as QR code
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// RFC 3339
+    pub enabled_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Type)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+/// A new random secret
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// RFC 4648 base32 without padding, as authenticator apps expect it
+pub fn base32_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[((buffer >> bits) & 0x1F) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[((buffer << (5 - bits)) & 0x1F) as usize] as char);
+    }
+    encoded
+}
+
+/// Key URI for authenticator apps, labelled `issuer:account`
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        urlencoding::encode(issuer),
+        urlencoding::encode(account),
+        base32_encode(secret),
+        urlencoding::encode(issuer),
+        DIGITS,
+        PERIOD_SECS
+    )
+}
+
+/// The code of a time step (RFC 6238 with HMAC-SHA1, as all common apps use)
+pub fn code_at(secret: &[u8], step: i64) -> String {
+    let mut mac =
+        <HmacSha1 as Mac>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0F) as usize;
+    let value = u32::from_be_bytes([
+        hash[offset] & 0x7F,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        value % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// The time step of a code matching `code` at `now` (Unix seconds), allowing for clock drift.
+/// Steps up to `last_used_step` are not accepted, so an intercepted code can't be replayed.
+pub fn verify_code(
+    secret: &[u8],
+    code: &str,
+    now: i64,
+    last_used_step: Option<i64>,
+) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let current = now.div_euclid(PERIOD_SECS);
+    (current - ALLOWED_DRIFT..=current + ALLOWED_DRIFT)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| constant_time_eq(code_at(secret, *step).as_bytes(), code.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Encrypts TOTP secrets with AES-256-GCM, so a copy of the database can't generate codes.
+/// The key is `TOTP_ENCRYPTION_KEY`, required at startup; changing it makes the stored
+/// secrets unreadable, and members have to set up their app again.
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &str) -> Self {
+        let key = Sha256::digest(key.as_bytes());
+        SecretCipher {
+            cipher: Aes256Gcm::new(&key),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        SecretCipher::new(&config.totp_encryption_key)
+    }
+
+    /// Hex of a random nonce followed by the ciphertext
+    pub fn encrypt(&self, secret: &[u8]) -> String {
+        let mut nonce = [0u8; NONCE_BYTES];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), secret)
+            .expect("AES-GCM encryption of a short secret can't fail");
+        hex::encode([&nonce[..], &ciphertext].concat())
+    }
+
+    /// `None` if `encrypted` was tampered with or encrypted with another key
+    pub fn decrypt(&self, encrypted: &str) -> Option<Vec<u8>> {
+        let bytes = hex::decode(encrypted).ok()?;
+        if bytes.len() <= NONCE_BYTES {
+            return None;
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_BYTES);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()
+    }
+}
@@ -1,7 +1,14 @@
 use crate::auth;
-use crate::models::{Member, WorkHour, WorkHourEntry};
+use crate::config::Config;
+use crate::database::QueuedWorkHour;
+use crate::error::AppError;
+use crate::export::Locale;
+use crate::models::{Member, WorkHour, WorkHourEntry, WorkHourStatus};
+use crate::rules::WorkHourRules;
+use crate::suggestions::normalize;
 use axum::http::{HeaderMap, StatusCode};
-use chrono::Datelike;
+use chrono::{Datelike, NaiveDate};
+use std::collections::HashMap;
 use tracing::{debug, info, warn};
 
 /// Converts a list of WorkHour to WorkHourEntry (no filtering)
@@ -28,6 +35,14 @@ pub fn convert_work_hours_to_entries(
                         date: date_norm,
                         description: description.clone(),
                         duration_hours: hours,
+                        status: WorkHourStatus::from_teable(wh.status.as_deref()),
+                        review_comment: wh.review_comment.clone(),
+                        pending_sync: false,
+                        sync_conflict: None,
+                        corrects: wh.corrects.clone(),
+                        note: wh.note.clone(),
+                        attachments: wh.attachments.clone(),
+                        event_id: wh.event_id.clone(),
                     })
                 },
                 _ => {
@@ -40,9 +55,337 @@ pub fn convert_work_hours_to_entries(
         .collect()
 }
 
-/// Calculates total hours from a list of work hour entries
+/// Sorts work hours fetched for several members by the member they belong to. Every one of
+/// `member_ids` gets an entry, empty if they have no work hours; others are left out.
+pub fn group_work_hours_by_member(
+    member_ids: &[String],
+    work_hours: Vec<WorkHour>,
+) -> HashMap<String, Vec<WorkHour>> {
+    let mut grouped: HashMap<String, Vec<WorkHour>> = member_ids
+        .iter()
+        .map(|member_id| (member_id.clone(), Vec::new()))
+        .collect();
+    for work_hour in work_hours {
+        if let Some(entries) = work_hour
+            .get_member_id()
+            .and_then(|member_id| grouped.get_mut(&member_id))
+        {
+            entries.push(work_hour);
+        }
+    }
+    grouped
+}
+
+/// Unit suffixes accepted after an hour value, longest first so "Stunden" isn't read as "Stunde"
+const HOUR_UNITS: [&str; 5] = ["stunden", "stunde", "std.", "std", "h"];
+
+/// Parses hours as typed by members: `2.5`, `2,5`, ` 2,5 Std ` or `3h`.
+///
+/// Grammar (after trimming whitespace, units case-insensitive):
+/// `[+-]? digit+ ([.,] digit+)? whitespace* (Stunden | Stunde | Std. | Std | h)?`
+/// Thousands separators, exponents and special values like `inf` are rejected.
+pub fn parse_hours(input: &str) -> Result<f64, String> {
+    let trimmed = input.trim();
+    let number = HOUR_UNITS
+        .iter()
+        .find_map(|unit| {
+            let split = trimmed.len().checked_sub(unit.len())?;
+            let suffix = trimmed.get(split..)?;
+            suffix.eq_ignore_ascii_case(unit).then(|| &trimmed[..split])
+        })
+        .unwrap_or(trimmed)
+        .trim_end();
+
+    let unsigned = number.strip_prefix(['+', '-']).unwrap_or(number);
+    let (integer, fraction) = match unsigned.split_once(['.', ',']) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let is_digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    if !is_digits(integer) || !fraction.is_none_or(is_digits) {
+        return Err(format!("Ungültige Stundenangabe: \"{input}\""));
+    }
+
+    number
+        .replacen(',', ".", 1)
+        .parse::<f64>()
+        .map_err(|_| format!("Ungültige Stundenangabe: \"{input}\""))
+}
+
+/// Checks the date format and that the year is still open for entries: the current year,
+/// plus the previous year during the January grace period. Returns the German error message.
+pub fn validate_work_hour_date(date: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let work_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| "Ungültiges Datumsformat. Bitte verwenden Sie YYYY-MM-DD.".to_string())?;
+
+    let current_year = today.year();
+    let in_grace_period = today.month() == 1;
+    let min_allowed_year = if in_grace_period {
+        current_year - 1
+    } else {
+        current_year
+    };
+
+    if work_date.year() < min_allowed_year {
+        return Err(if in_grace_period {
+            format!(
+                "Arbeitsstunden können nur für {} oder {} (Nachfrist bis Ende Januar) eingetragen werden.",
+                current_year,
+                current_year - 1
+            )
+        } else {
+            format!(
+                "Arbeitsstunden können nur für das aktuelle Jahr {current_year} eingetragen werden."
+            )
+        });
+    }
+
+    Ok(work_date)
+}
+
+/// Limits that reject implausible work hours, from `MAX_HOURS_PER_DAY` and
+/// `MAX_HOURS_PER_YEAR`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HourLimits {
+    /// Hours of one member on one day, all entries of that day together
+    pub per_day: f64,
+    /// Hours of one member in a calendar year; no limit when `None`
+    pub per_year: Option<f64>,
+}
+
+impl Default for HourLimits {
+    fn default() -> Self {
+        HourLimits {
+            per_day: 12.0,
+            per_year: None,
+        }
+    }
+}
+
+/// Why an entry was rejected by the `HourLimits`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HourLimitExceeded {
+    /// `logged` are the hours already entered on that day
+    Day { limit: f64, logged: f64 },
+    /// `logged` are the hours already entered in that year
+    Year { limit: f64, logged: f64, year: i32 },
+}
+
+impl HourLimitExceeded {
+    pub fn message(&self, locale: Locale) -> String {
+        let hours = |hours: &f64| locale.format_number(*hours);
+        match (self, locale) {
+            (HourLimitExceeded::Day { limit, logged }, Locale::De) if *logged > 0.0 => format!(
+                "Pro Tag können höchstens {} Stunden eingetragen werden. Für diesen Tag sind bereits {} Stunden eingetragen.",
+                hours(limit),
+                hours(logged)
+            ),
+            (HourLimitExceeded::Day { limit, .. }, Locale::De) => format!(
+                "Pro Tag können höchstens {} Stunden eingetragen werden.",
+                hours(limit)
+            ),
+            (HourLimitExceeded::Day { limit, logged }, Locale::En) if *logged > 0.0 => format!(
+                "At most {} hours can be entered per day. {} hours are already entered for this day.",
+                hours(limit),
+                hours(logged)
+            ),
+            (HourLimitExceeded::Day { limit, .. }, Locale::En) => format!(
+                "At most {} hours can be entered per day.",
+                hours(limit)
+            ),
+            (HourLimitExceeded::Year { limit, logged, year }, Locale::De) => format!(
+                "Pro Jahr können höchstens {} Stunden eingetragen werden. Für {} sind bereits {} Stunden eingetragen.",
+                hours(limit),
+                year,
+                hours(logged)
+            ),
+            (HourLimitExceeded::Year { limit, logged, year }, Locale::En) => format!(
+                "At most {} hours can be entered per year. {} hours are already entered for {}.",
+                hours(limit),
+                hours(logged),
+                year
+            ),
+        }
+    }
+
+    pub fn into_error(self, locale: Locale) -> AppError {
+        AppError::invalid(self.message(locale))
+    }
+}
+
+impl HourLimits {
+    pub fn from_config(config: &Config) -> Self {
+        HourLimits {
+            per_day: config.max_hours_per_day,
+            per_year: config.max_hours_per_year,
+        }
+    }
+
+    /// Checks an entry of `hours` on a day that already has `logged_on_day` hours, and, with a
+    /// yearly limit, in a year that already has `logged_in_year` hours
+    pub fn check(
+        &self,
+        hours: f64,
+        logged_on_day: f64,
+        year: i32,
+        logged_in_year: f64,
+    ) -> Result<(), HourLimitExceeded> {
+        // Entries are rounded to two decimals, so 11.999 hours plus 0.001 stay within 12
+        let exceeds = |total: f64, limit: f64| total - limit > 0.005;
+        if exceeds(logged_on_day + hours, self.per_day) {
+            return Err(HourLimitExceeded::Day {
+                limit: self.per_day,
+                logged: logged_on_day,
+            });
+        }
+        if let Some(limit) = self.per_year {
+            if exceeds(logged_in_year + hours, limit) {
+                return Err(HourLimitExceeded::Year {
+                    limit,
+                    logged: logged_in_year,
+                    year,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hours of entries that aren't rejected, leaving out `except`, the entry being edited.
+/// Rejected entries don't count toward the limits, the member may enter that work again.
+pub fn logged_hours(work_hours: &[WorkHour], except: Option<&str>) -> f64 {
+    work_hours
+        .iter()
+        .filter(|work_hour| Some(work_hour.id.as_str()) != except)
+        .filter(|work_hour| {
+            WorkHourStatus::from_teable(work_hour.status.as_deref()) != WorkHourStatus::Rejected
+        })
+        .filter_map(|work_hour| work_hour.duration_hours)
+        .sum()
+}
+
+/// Which entries a member may have on the same day, from `ENTRIES_PER_DAY`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EntryPolicy {
+    /// `one`: a single entry per member and day
+    OnePerDay,
+    /// `distinct` (default): several entries per day as long as their activities (`Tätigkeit`)
+    /// differ, e.g. Platzpflege in the morning and a work event in the evening. Their hours
+    /// together are capped by `HourLimits::per_day`.
+    #[default]
+    DistinctActivities,
+}
+
+impl EntryPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "one" => Some(EntryPolicy::OnePerDay),
+            "distinct" => Some(EntryPolicy::DistinctActivities),
+            _ => None,
+        }
+    }
+
+    /// Checks a new entry with `description` against the activities of the member's other
+    /// entries that day (see `day_activities`). Returns the German message of a duplicate.
+    pub fn check<'a>(
+        &self,
+        description: &str,
+        existing: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), &'static str> {
+        let mut existing = existing.into_iter();
+        match self {
+            EntryPolicy::OnePerDay if existing.next().is_some() => Err(
+                "Für dieses Datum existiert bereits ein Eintrag. Pro Person und Tag ist nur ein Eintrag erlaubt.",
+            ),
+            EntryPolicy::DistinctActivities
+                if existing.any(|other| normalize(other) == normalize(description)) =>
+            {
+                Err("Für dieses Datum existiert bereits ein Eintrag mit dieser Tätigkeit. Bitte ändere den bestehenden Eintrag.")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Activities of the entries of a day that count for `EntryPolicy::check`, leaving out
+/// `except`, the entry being edited. Rejected entries and the board's corrections don't block
+/// a new entry.
+pub fn day_activities<'a>(work_hours: &'a [WorkHour], except: Option<&str>) -> Vec<&'a str> {
+    work_hours
+        .iter()
+        .filter(|work_hour| Some(work_hour.id.as_str()) != except)
+        .filter(|work_hour| work_hour.corrects.is_none())
+        .filter(|work_hour| {
+            WorkHourStatus::from_teable(work_hour.status.as_deref()) != WorkHourStatus::Rejected
+        })
+        .map(|work_hour| work_hour.description.as_deref().unwrap_or(""))
+        .collect()
+}
+
+/// Prefix for the IDs of entries that are still waiting in the local write queue
+pub const QUEUED_ENTRY_PREFIX: &str = "queued-";
+
+/// Converts queued (not yet synced) entries of the given year to WorkHourEntry
+pub fn convert_queued_work_hours_to_entries(
+    queued: &[QueuedWorkHour],
+    year: i32,
+) -> Vec<WorkHourEntry> {
+    let year_prefix = format!("{year}-");
+    queued
+        .iter()
+        .filter(|q| q.date.starts_with(&year_prefix))
+        .map(|q| WorkHourEntry {
+            id: format!("{QUEUED_ENTRY_PREFIX}{}", q.id),
+            date: q.date.clone(),
+            description: q.description.clone(),
+            duration_hours: (q.hours * 100.0).round() / 100.0,
+            status: WorkHourStatus::Submitted,
+            review_comment: None,
+            pending_sync: true,
+            sync_conflict: q.conflict_reason.clone(),
+            corrects: None,
+            note: None,
+            attachments: Vec::new(),
+            event_id: None,
+        })
+        .collect()
+}
+
+/// Returns the queue ID if the given entry ID refers to a queued entry
+pub fn parse_queued_entry_id(id: &str) -> Option<i64> {
+    id.strip_prefix(QUEUED_ENTRY_PREFIX)?.parse().ok()
+}
+
+/// Calculates total hours from a list of work hour entries (approved entries only).
+/// Corrections count only with the entry they correct, so deleting that entry doesn't take
+/// the hours away twice.
 pub fn calculate_total_hours(entries: &[WorkHourEntry]) -> f64 {
-    entries.iter().map(|wh| wh.duration_hours).sum::<f64>()
+    let counted: Vec<&str> = entries
+        .iter()
+        .filter(|wh| wh.status.counts_toward_total() && wh.corrects.is_none())
+        .map(|wh| wh.id.as_str())
+        .collect();
+    entries
+        .iter()
+        .filter(|wh| wh.status.counts_toward_total())
+        .filter(|wh| {
+            wh.corrects
+                .as_deref()
+                .is_none_or(|original| counted.contains(&original))
+        })
+        .map(|wh| wh.duration_hours)
+        .sum::<f64>()
+}
+
+/// Calculates hours that are still waiting for board approval or for sync to Teable.
+/// Queued entries stuck on a conflict are left out since they will never be submitted.
+pub fn calculate_pending_hours(entries: &[WorkHourEntry]) -> f64 {
+    entries
+        .iter()
+        .filter(|wh| wh.status == WorkHourStatus::Submitted && wh.sync_conflict.is_none())
+        .map(|wh| wh.duration_hours)
+        .sum::<f64>()
 }
 
 /// Logs work hour entries for debugging
@@ -59,8 +402,31 @@ pub fn log_work_entries(entries: &[WorkHourEntry], prefix: &str) {
     }
 }
 
+/// The client's IP as reported by a reverse proxy: X-Forwarded-For (its first entry, the
+/// original client), X-Real-IP or CF-Connecting-IP (Cloudflare)
+pub fn client_ip(headers: &HeaderMap) -> Option<String> {
+    ["x-forwarded-for", "x-real-ip", "cf-connecting-ip"]
+        .into_iter()
+        .filter_map(|name| headers.get(name)?.to_str().ok())
+        .filter_map(|value| value.split(',').next())
+        .map(str::trim)
+        .find(|ip| !ip.is_empty())
+        .map(str::to_string)
+}
+
 /// Extracts and verifies user ID from Authorization header
-pub fn extract_user_id_from_headers(headers: &HeaderMap) -> Result<String, StatusCode> {
+pub fn extract_user_id_from_headers(
+    config: &Config,
+    headers: &HeaderMap,
+) -> Result<String, StatusCode> {
+    extract_claims_from_headers(config, headers).map(|claims| claims.sub)
+}
+
+/// Verifies the token of the Authorization header
+fn extract_claims_from_headers(
+    config: &Config,
+    headers: &HeaderMap,
+) -> Result<auth::AuthClaims, StatusCode> {
     let auth_header = headers
         .get("authorization")
         .ok_or(StatusCode::UNAUTHORIZED)?
@@ -74,7 +440,7 @@ pub fn extract_user_id_from_headers(headers: &HeaderMap) -> Result<String, Statu
         &auth_header[..std::cmp::min(auth_header.len(), 20)]
     );
 
-    match auth::verify_token(auth_header) {
+    match auth::verify_token(config, auth_header) {
         Ok(claims) => {
             info!("Auth: Token valid, user ID: {}", claims.sub);
 
@@ -84,7 +450,7 @@ pub fn extract_user_id_from_headers(headers: &HeaderMap) -> Result<String, Statu
                 return Err(StatusCode::UNAUTHORIZED);
             }
 
-            Ok(claims.sub)
+            Ok(claims)
         }
         Err(e) => {
             warn!("Auth: Token verification failed: {:?}", e);
@@ -93,9 +459,76 @@ pub fn extract_user_id_from_headers(headers: &HeaderMap) -> Result<String, Statu
     }
 }
 
+/// Extracts the user ID from the Authorization header and verifies the user is a board member
+/// signed in as themselves
+pub fn extract_admin_id_from_headers(
+    config: &Config,
+    headers: &HeaderMap,
+) -> Result<String, StatusCode> {
+    let claims = extract_claims_from_headers(config, headers)?;
+    if !config.is_admin(&claims.sub) {
+        warn!("Auth: User {} is not a board member, rejecting", claims.sub);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    // Seeing the app as someone else never grants their board rights
+    if let Some(impersonator) = &claims.impersonator {
+        warn!(
+            "Auth: Board member {} impersonates {}, rejecting board access",
+            impersonator, claims.sub
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(claims.sub)
+}
+
+/// Progress of a family toward its combined quota
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyQuota {
+    pub required: f64,
+    pub completed: f64,
+    pub remaining: f64,
+    pub percentage: f64,
+}
+
+/// Hours a family owes together: the requirements of its members, capped at
+/// `rules.family_max_hours`
+pub fn family_required_hours(member_required: &[f64], rules: &WorkHourRules) -> f64 {
+    let total: f64 = member_required.iter().sum();
+    match rules.family_max_hours {
+        Some(cap) => total.min(cap),
+        None => total,
+    }
+}
+
+/// Compares the hours of all family members against the family quota. Only the family
+/// total counts, so hours beyond one member's own requirement offset another's deficit.
+pub fn family_quota(
+    member_hours: &[f64],
+    member_required: &[f64],
+    rules: &WorkHourRules,
+) -> FamilyQuota {
+    let required = family_required_hours(member_required, rules);
+    let completed: f64 = member_hours.iter().sum();
+    FamilyQuota {
+        required,
+        completed,
+        remaining: (required - completed).max(0.0),
+        percentage: if required > 0.0 {
+            (completed / required) * 100.0
+        } else {
+            100.0 // If no hours required, consider it 100% complete
+        },
+    }
+}
+
 /// Checks if a member is eligible for work hours based on age restrictions
-/// Rules: Mandatory for members aged 16-70, starting the year after turning 16
-pub fn is_member_eligible_for_work_hours(member: &Member, current_year: i32) -> bool {
+/// Rules: Mandatory from the year after turning `rules.min_age` until the year of turning `rules.max_age`
+pub fn is_member_eligible_for_work_hours(
+    member: &Member,
+    current_year: i32,
+    rules: &WorkHourRules,
+) -> bool {
     debug!(
         "Called is_member_eligible_for_work_hours for {} {} (birth_date: {:?})",
         member.first_name, member.last_name, member.birth_date
@@ -115,7 +548,7 @@ pub fn is_member_eligible_for_work_hours(member: &Member, current_year: i32) ->
         let birth_date = dt.naive_utc().date();
         let birth_year = birth_date.year();
         let age_in_current_year = current_year - birth_year;
-        let eligible = (17..70).contains(&age_in_current_year);
+        let eligible = rules.is_required_at_age(age_in_current_year);
         debug!(
             "Age Check: {} {} - Birth: {}, Age in {}: {}, Eligible: {}",
             member.first_name,
@@ -138,14 +571,27 @@ pub fn is_member_eligible_for_work_hours(member: &Member, current_year: i32) ->
 }
 
 /// Gets work hours info including exemption reason for a member
-pub fn get_member_work_hours_info(member: &Member, current_year: i32) -> (f64, Option<String>) {
+pub fn get_member_work_hours_info(
+    member: &Member,
+    current_year: i32,
+    rules: &WorkHourRules,
+) -> (f64, Option<String>) {
     debug!(
         "Called get_member_work_hours_info for {} {} (birth_date: {:?}, join_date: {:?})",
         member.first_name, member.last_name, member.birth_date, member.join_date
     );
 
+    // Set by the board for this member, e.g. after an injury; replaces the rules
+    if let Some(hours) = member.required_hours {
+        let hours = hours.max(0.0);
+        return (
+            hours,
+            (hours == 0.0).then(|| "Befreiung durch den Vorstand".to_string()),
+        );
+    }
+
     // Check age eligibility first
-    if !is_member_eligible_for_work_hours(member, current_year) {
+    if !is_member_eligible_for_work_hours(member, current_year, rules) {
         debug!(
             "Member {} {} is exempt due to age",
             member.first_name, member.last_name
@@ -186,8 +632,8 @@ pub fn get_member_work_hours_info(member: &Member, current_year: i32) -> (f64, O
 
     // Member is eligible and joined before July 1st
     debug!(
-        "Member {} {} has 8 hours required",
-        member.first_name, member.last_name
+        "Member {} {} has {} hours required",
+        member.first_name, member.last_name, rules.required_hours
     );
-    (8.0, None)
+    (rules.required_hours, None)
 }
@@ -0,0 +1,126 @@
+use crate::error::{AppError, ErrorCode};
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use tracing::warn;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+/// Largest JSON body read by any handler; holds an import of `jobs::MAX_IMPORT_ENTRIES` entries.
+/// Attachment uploads raise it for their route.
+pub const MAX_JSON_BODY: usize = 1024 * 1024;
+/// Longest activity of a work hour entry
+pub const MAX_DESCRIPTION_CHARS: usize = 200;
+
+/// JSON body deserialized and then checked with its `#[validate(...)]` rules, before the
+/// handler runs. The first broken rule is answered with `400 INVALID_REQUEST` and its German
+/// message, a body over the limit with `413 PAYLOAD_TOO_LARGE`. A body that isn't JSON at
+/// all (no `Content-Type: application/json`) keeps axum's `415`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for ValidatedJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let path = request.uri().path().to_string();
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| {
+                warn!(
+                    "Validation: Rejected body of {}: {}",
+                    path,
+                    rejection.body_text()
+                );
+                match rejection.status() {
+                    StatusCode::PAYLOAD_TOO_LARGE => {
+                        AppError::code(ErrorCode::PayloadTooLarge).into_response()
+                    }
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE => rejection.into_response(),
+                    _ => AppError::invalid(rejection.body_text()).into_response(),
+                }
+            })?;
+        value.validate().map_err(|errors| {
+            let message = first_message(&errors);
+            warn!("Validation: Rejected body of {}: {}", path, message);
+            AppError::invalid(message).into_response()
+        })?;
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// Message of the first broken rule, by field name so the same body always gets the same one
+pub fn first_message(errors: &ValidationErrors) -> String {
+    let mut fields: Vec<_> = errors.errors().iter().collect();
+    fields.sort_by_key(|(field, _)| **field);
+    fields
+        .into_iter()
+        .find_map(|(field, kind)| match kind {
+            ValidationErrorsKind::Field(errors) => errors.first().map(|error| {
+                error
+                    .message
+                    .as_ref()
+                    .map(Cow::to_string)
+                    .unwrap_or_else(|| format!("Ungültiger Wert für {field}."))
+            }),
+            ValidationErrorsKind::Struct(errors) => Some(first_message(errors)),
+            ValidationErrorsKind::List(items) => items.values().next().map(|e| first_message(e)),
+        })
+        .unwrap_or_else(|| "Ungültige Anfrage.".to_string())
+}
+
+/// The `413` of the body limit layers is plain text; clients get the usual error body instead
+pub async fn payload_too_large_as_error(response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return AppError::code(ErrorCode::PayloadTooLarge).into_response();
+    }
+    response
+}
+
+fn error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+/// A work hour date as `YYYY-MM-DD`; whether its year is still open is up to the handler
+pub fn work_hour_date(date: &str) -> Result<(), ValidationError> {
+    if date.is_empty() {
+        return Err(error("required", "Datum fehlt."));
+    }
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|_| ())
+        .map_err(|_| {
+            error(
+                "date",
+                "Ungültiges Datumsformat. Bitte verwenden Sie YYYY-MM-DD.",
+            )
+        })
+}
+
+/// The activity of a work hour entry: not blank and at most `MAX_DESCRIPTION_CHARS` long
+pub fn work_hour_description(description: &str) -> Result<(), ValidationError> {
+    if description.trim().is_empty() {
+        return Err(error("required", "Tätigkeit fehlt."));
+    }
+    if description.chars().count() > MAX_DESCRIPTION_CHARS {
+        return Err(error(
+            "length",
+            format!("Die Tätigkeit darf höchstens {MAX_DESCRIPTION_CHARS} Zeichen lang sein."),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::models::{WorkHour, WorkHourStatus};
+use crate::utils::{calculate_total_hours, convert_work_hours_to_entries};
+use serde::Serialize;
+use specta::Type;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Differences below this are rounding noise of the floating point sums
+const TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    /// Among the member's records of the year, but not returned by the dashboard's query
+    MissingFromDashboard,
+    /// Returned by the dashboard's query, but not among the member's records of the year
+    OnlyInDashboard,
+    /// Left out by the dashboard because its date, activity or hours are empty
+    Incomplete,
+    /// Counted by the dashboard with hours rounded to two decimals
+    Rounded,
+}
+
+/// A record that contributes differently to the two totals
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct Discrepancy {
+    pub record_id: String,
+    pub kind: DiscrepancyKind,
+    pub dashboard_hours: f64,
+    pub teable_hours: f64,
+}
+
+/// Approved hours of a member as served by the dashboard, compared with a recount of the
+/// raw Teable records, so the board can answer "my hours are wrong" reports
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct TotalsVerification {
+    pub member_id: String,
+    pub year: i32,
+    pub dashboard_hours: f64,
+    pub teable_hours: f64,
+    /// Dashboard minus Teable
+    pub difference: f64,
+    pub consistent: bool,
+    /// Records counted in the recount
+    pub counted_record_ids: Vec<String>,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Compares the total the dashboard computes from `dashboard_records` (the member's
+/// records as fetched by the dashboard) with a recount of `year_records` (all records of
+/// the year, filtered by member here instead of by Teable)
+pub fn verify_totals(
+    member_id: &str,
+    year: i32,
+    dashboard_records: &[WorkHour],
+    year_records: &[WorkHour],
+) -> TotalsVerification {
+    let entries = convert_work_hours_to_entries(dashboard_records, "Verify");
+    let dashboard_hours = calculate_total_hours(&entries);
+    let served: BTreeMap<&str, f64> = entries
+        .iter()
+        .map(|entry| {
+            let hours = if entry.status.counts_toward_total() {
+                entry.duration_hours
+            } else {
+                0.0
+            };
+            (entry.id.as_str(), hours)
+        })
+        .collect();
+
+    let recounted: BTreeMap<String, f64> = year_records
+        .iter()
+        .filter(|record| record.get_member_id().as_deref() == Some(member_id))
+        .map(|record| {
+            let counts =
+                WorkHourStatus::from_teable(record.status.as_deref()).counts_toward_total();
+            let hours = record.duration_hours.filter(|_| counts).unwrap_or(0.0);
+            (record.id.to_string(), hours)
+        })
+        .collect();
+    let teable_hours: f64 = recounted.values().sum();
+
+    let fetched: BTreeSet<String> = dashboard_records
+        .iter()
+        .map(|record| record.id.to_string())
+        .collect();
+    let ids: BTreeSet<&str> = fetched
+        .iter()
+        .map(String::as_str)
+        .chain(recounted.keys().map(String::as_str))
+        .collect();
+
+    let mut discrepancies = Vec::new();
+    for id in ids {
+        let dashboard = served.get(id).copied().unwrap_or(0.0);
+        let teable = recounted.get(id).copied().unwrap_or(0.0);
+        if (dashboard - teable).abs() < f64::EPSILON {
+            continue;
+        }
+        let kind = if !fetched.contains(id) {
+            DiscrepancyKind::MissingFromDashboard
+        } else if !recounted.contains_key(id) {
+            DiscrepancyKind::OnlyInDashboard
+        } else if !served.contains_key(id) {
+            DiscrepancyKind::Incomplete
+        } else {
+            DiscrepancyKind::Rounded
+        };
+        discrepancies.push(Discrepancy {
+            record_id: id.to_string(),
+            kind,
+            dashboard_hours: dashboard,
+            teable_hours: teable,
+        });
+    }
+
+    let difference = dashboard_hours - teable_hours;
+    TotalsVerification {
+        member_id: member_id.to_string(),
+        year,
+        dashboard_hours,
+        teable_hours,
+        difference,
+        consistent: difference.abs() < TOLERANCE
+            && discrepancies
+                .iter()
+                .all(|d| d.kind == DiscrepancyKind::Rounded),
+        counted_record_ids: recounted
+            .iter()
+            .filter(|(_, hours)| **hours > 0.0)
+            .map(|(id, _)| id.clone())
+            .collect(),
+        discrepancies,
+    }
+}
@@ -0,0 +1,173 @@
+use crate::history::MAX_YEARS;
+use crate::models::WorkHourEntry;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::cmp::Ordering;
+
+pub const DEFAULT_PER_PAGE: u32 = 50;
+pub const MAX_PER_PAGE: u32 = 200;
+
+/// Query of `GET /arbeitsstunden`, e.g. `?year=2024&sort=-hours&page=2&per_page=20`
+#[derive(Debug, Default, Deserialize)]
+pub struct WorkHourListQuery {
+    /// Defaults to the current year; can't be combined with `from`/`to`
+    pub year: Option<i32>,
+    /// First day to include (`YYYY-MM-DD`); defaults to January 1st of the year of `to`
+    pub from: Option<NaiveDate>,
+    /// Last day to include; defaults to the end of the current year
+    pub to: Option<NaiveDate>,
+    /// `date`, `hours` or `description`, descending with a leading `-`; defaults to `-date`
+    pub sort: Option<String>,
+    /// Starts at 1
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Date,
+    Hours,
+    Description,
+}
+
+/// The validated query: which days to list and how
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkHourListing {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub sort: SortField,
+    pub descending: bool,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl WorkHourListQuery {
+    /// The listing to return, or the German error message for an invalid query
+    pub fn listing(&self, today: NaiveDate) -> Result<WorkHourListing, String> {
+        let year_range = |year: i32| {
+            NaiveDate::from_ymd_opt(year, 1, 1)
+                .zip(NaiveDate::from_ymd_opt(year, 12, 31))
+                .ok_or_else(|| format!("Ungültiges Jahr: {year}"))
+        };
+        let (from, to) = match (self.year, self.from, self.to) {
+            (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+                return Err("Bitte entweder ein Jahr oder einen Zeitraum angeben.".to_string())
+            }
+            (Some(year), None, None) => year_range(year)?,
+            (None, from, to) => {
+                let to = match to {
+                    Some(to) => to,
+                    None => year_range(today.year())?.1,
+                };
+                let from = match from {
+                    Some(from) => from,
+                    None => year_range(to.year())?.0,
+                };
+                (from, to)
+            }
+        };
+        if from.year() < 2000 || to.year() > today.year() {
+            return Err(format!(
+                "Die Jahre müssen zwischen 2000 und {} liegen.",
+                today.year()
+            ));
+        }
+        if from > to {
+            return Err("Der Beginn darf nicht nach dem Ende liegen.".to_string());
+        }
+        if to.year() - from.year() >= MAX_YEARS {
+            return Err(format!(
+                "Es können höchstens {MAX_YEARS} Jahre auf einmal abgefragt werden."
+            ));
+        }
+
+        let sort = self.sort.as_deref().unwrap_or("-date").trim();
+        let (descending, field) = match sort.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, sort),
+        };
+        let sort = match field {
+            "date" => SortField::Date,
+            "hours" => SortField::Hours,
+            "description" => SortField::Description,
+            _ => {
+                return Err(format!(
+                    "Unbekannte Sortierung: {sort}. Möglich sind date, hours und description."
+                ))
+            }
+        };
+
+        Ok(WorkHourListing {
+            from,
+            to,
+            sort,
+            descending,
+            page: self.page.unwrap_or(1).max(1),
+            per_page: self
+                .per_page
+                .unwrap_or(DEFAULT_PER_PAGE)
+                .clamp(1, MAX_PER_PAGE),
+        })
+    }
+}
+
+/// One page of a member's entries
+#[derive(Debug, Serialize, Type)]
+pub struct WorkHourPage {
+    pub entries: Vec<WorkHourEntry>,
+    pub page: u32,
+    pub per_page: u32,
+    /// Entries in the whole range, on all pages
+    pub total: u32,
+    pub total_pages: u32,
+}
+
+impl WorkHourListing {
+    /// Keeps the entries within `from`..=`to`, sorts them and cuts out the requested page.
+    /// Ties are ordered by date, newest first, then by ID, so pages don't overlap.
+    pub fn page_of(&self, entries: Vec<WorkHourEntry>) -> WorkHourPage {
+        let mut entries: Vec<WorkHourEntry> = entries
+            .into_iter()
+            .filter(|entry| {
+                NaiveDate::parse_from_str(entry.date.get(..10).unwrap_or(""), "%Y-%m-%d")
+                    .is_ok_and(|date| date >= self.from && date <= self.to)
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            let order = match self.sort {
+                SortField::Date => a.date.cmp(&b.date),
+                SortField::Hours => a
+                    .duration_hours
+                    .partial_cmp(&b.duration_hours)
+                    .unwrap_or(Ordering::Equal),
+                SortField::Description => a
+                    .description
+                    .to_lowercase()
+                    .cmp(&b.description.to_lowercase()),
+            };
+            let order = if self.descending {
+                order.reverse()
+            } else {
+                order
+            };
+            order
+                .then_with(|| b.date.cmp(&a.date))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        let total = entries.len() as u32;
+        let skip = (self.page - 1).saturating_mul(self.per_page) as usize;
+        WorkHourPage {
+            entries: entries
+                .into_iter()
+                .skip(skip)
+                .take(self.per_page as usize)
+                .collect(),
+            page: self.page,
+            per_page: self.per_page,
+            total,
+            total_pages: total.div_ceil(self.per_page),
+        }
+    }
+}
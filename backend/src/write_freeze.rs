@@ -0,0 +1,132 @@
+use crate::export::Locale;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Days in which members can't create, edit or delete work hours, e.g. while the board
+/// audits the past year in February. Board members can still make corrections.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct WriteFreeze {
+    /// First frozen day, club time
+    pub from: NaiveDate,
+    /// Last frozen day
+    pub until: NaiveDate,
+    /// Shown to members, e.g. "Prüfung der Arbeitsstunden"
+    pub reason: Option<String>,
+}
+
+/// Body of `PUT /admin/write-freeze`
+#[derive(Debug, Deserialize, Type)]
+pub struct SetWriteFreezeRequest {
+    pub from: NaiveDate,
+    pub until: NaiveDate,
+    pub reason: Option<String>,
+}
+
+/// Answer of `GET /write-freeze`, for a banner in the SPA
+#[derive(Debug, Serialize, Type)]
+pub struct WriteFreezeStatus {
+    /// The window in effect today, if any
+    pub active: Option<WriteFreeze>,
+    /// Window set by the board, which may lie in the future
+    pub scheduled: Option<WriteFreeze>,
+}
+
+/// A window repeating every year, from `WRITE_FREEZE_FROM` to `WRITE_FREEZE_UNTIL` (`MM-DD`).
+/// If `until` comes before `from`, the window spans the turn of the year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnnualFreeze {
+    from: (u32, u32),
+    until: (u32, u32),
+}
+
+impl AnnualFreeze {
+    /// `None` unless both are valid `MM-DD` days; February 29th isn't allowed, as it doesn't
+    /// exist every year
+    pub fn parse(from: &str, until: &str) -> Option<Self> {
+        let day = |value: &str| -> Option<(u32, u32)> {
+            let (month, day) = value.trim().split_once('-')?;
+            let day = (month.parse().ok()?, day.parse().ok()?);
+            NaiveDate::from_ymd_opt(2023, day.0, day.1).map(|_| day)
+        };
+        Some(AnnualFreeze {
+            from: day(from)?,
+            until: day(until)?,
+        })
+    }
+
+    fn date(year: i32, (month, day): (u32, u32)) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("validated by parse")
+    }
+
+    /// The occurrence of the window that contains `today`, if any
+    pub fn window_on(&self, today: NaiveDate) -> Option<WriteFreeze> {
+        let year = today.year();
+        let (from, until) = if self.from <= self.until {
+            (Self::date(year, self.from), Self::date(year, self.until))
+        } else if today >= Self::date(year, self.from) {
+            (
+                Self::date(year, self.from),
+                Self::date(year + 1, self.until),
+            )
+        } else {
+            (
+                Self::date(year - 1, self.from),
+                Self::date(year, self.until),
+            )
+        };
+        (from..=until).contains(&today).then_some(WriteFreeze {
+            from,
+            until,
+            reason: None,
+        })
+    }
+}
+
+impl WriteFreeze {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        (self.from..=self.until).contains(&date)
+    }
+
+    /// First day members can change work hours again
+    pub fn reopens_on(&self) -> NaiveDate {
+        self.until + Duration::days(1)
+    }
+
+    /// Why a write was refused, with the day editing is possible again
+    pub fn message(&self, locale: Locale) -> String {
+        let reason = self
+            .reason
+            .as_deref()
+            .map(|reason| format!(" ({reason})"))
+            .unwrap_or_default();
+        match locale {
+            Locale::De => format!(
+                "Arbeitsstunden können vom {} bis {} nicht eingetragen oder geändert werden{}. Ab dem {} ist das wieder möglich.",
+                self.from.format("%d.%m.%Y"),
+                self.until.format("%d.%m.%Y"),
+                reason,
+                self.reopens_on().format("%d.%m.%Y")
+            ),
+            Locale::En => format!(
+                "Work hours can't be entered or changed from {} to {}{}. Editing opens again on {}.",
+                self.from.format("%-d %B %Y"),
+                self.until.format("%-d %B %Y"),
+                reason,
+                self.reopens_on().format("%-d %B %Y")
+            ),
+        }
+    }
+}
+
+/// The window in effect on `today`: the one set by the board, or else the annual one
+pub fn active_freeze(
+    scheduled: Option<&WriteFreeze>,
+    annual: Option<AnnualFreeze>,
+    today: NaiveDate,
+) -> Option<WriteFreeze> {
+    scheduled
+        .filter(|freeze| freeze.contains(today))
+        .cloned()
+        .or_else(|| annual.and_then(|annual| annual.window_on(today)))
+}
@@ -0,0 +1,129 @@
+use crate::models::Member;
+use crate::rules::WorkHourRules;
+use crate::utils::{family_quota, get_member_work_hours_info};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{BTreeMap, HashMap};
+
+/// Body of `POST /admin/notify/year-summary/:year`
+#[derive(Debug, Deserialize, Type)]
+pub struct YearSummaryRequest {
+    /// Charged per missing hour, in euros; 0 leaves the charge out of the emails
+    pub fee_per_missing_hour: f64,
+}
+
+impl YearSummaryRequest {
+    /// Returns the German error message for an invalid fee
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.fee_per_missing_hour.is_finite() || self.fee_per_missing_hour < 0.0 {
+            return Err("Die Gebühr pro fehlender Stunde darf nicht negativ sein.".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The hours of a member's family, which owes its quota together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct FamilyYearSummary {
+    pub name: String,
+    pub required: f64,
+    pub completed: f64,
+    pub missing: f64,
+}
+
+/// What one member is told about a year
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct YearSummary {
+    pub member_id: String,
+    pub name: String,
+    pub email: String,
+    /// Approved hours of the member
+    pub hours: f64,
+    pub required: f64,
+    pub exemption_reason: Option<String>,
+    pub family: Option<FamilyYearSummary>,
+    /// Missing hours of the member, or of the family for family members
+    pub missing: f64,
+    /// `missing` times the fee, in euros; billed once per family
+    pub charge: f64,
+}
+
+/// Payload of a `JobKind::YearSummary` job, with the summaries as they were when the board
+/// started it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YearSummaryBatch {
+    pub year: i32,
+    pub summaries: Vec<YearSummary>,
+}
+
+/// The summaries of every member with an email address, from their approved hours of
+/// `year`. Family members see the quota of their family, as on the dashboard.
+pub fn summarize(
+    members: &[Member],
+    hours_by_member: &HashMap<String, f64>,
+    year: i32,
+    rules: &WorkHourRules,
+    fee_per_missing_hour: f64,
+) -> Vec<YearSummary> {
+    let mut families: BTreeMap<&str, Vec<&Member>> = BTreeMap::new();
+    for member in members {
+        if let Some(family) = member.family_id.as_deref().map(str::trim) {
+            if !family.is_empty() {
+                families.entry(family).or_default().push(member);
+            }
+        }
+    }
+    let hours_of = |member: &Member| hours_by_member.get(&member.id).copied().unwrap_or(0.0);
+    let family_summaries: HashMap<&str, FamilyYearSummary> = families
+        .into_iter()
+        .map(|(name, family)| {
+            let member_hours: Vec<f64> = family.iter().map(|member| hours_of(member)).collect();
+            let member_required: Vec<f64> = family
+                .iter()
+                .map(|member| get_member_work_hours_info(member, year, rules).0)
+                .collect();
+            let quota = family_quota(&member_hours, &member_required, rules);
+            let summary = FamilyYearSummary {
+                name: name.to_string(),
+                required: quota.required,
+                completed: quota.completed,
+                missing: quota.remaining,
+            };
+            (name, summary)
+        })
+        .collect();
+
+    members
+        .iter()
+        .filter(|member| !member.email.trim().is_empty())
+        .map(|member| {
+            let hours = hours_of(member);
+            let (required, exemption_reason) = get_member_work_hours_info(member, year, rules);
+            let family = member
+                .family_id
+                .as_deref()
+                .and_then(|family| family_summaries.get(family.trim()))
+                .cloned();
+            let missing = match &family {
+                Some(family) => family.missing,
+                None => (required - hours).max(0.0),
+            };
+            YearSummary {
+                member_id: member.id.clone(),
+                name: member.name(),
+                email: member.email.trim().to_string(),
+                hours,
+                required,
+                exemption_reason,
+                family,
+                missing,
+                charge: (missing * fee_per_missing_hour * 100.0).round() / 100.0,
+            }
+        })
+        .collect()
+}
+
+/// Euros as shown in the emails, e.g. `37,50 €`
+pub fn format_euros(amount: f64) -> String {
+    format!("{:.2} €", amount).replace('.', ",")
+}
@@ -57,8 +57,8 @@ export const AuthProvider = ({ children }: AuthProviderProps) => {
             console.log('🔍 AuthContext: Verifying token:', token?.substring(0, 20) + '...');
             const response = await backendService.verifyToken();
             console.log('🔍 AuthContext: Token verification response:', response);
-            if (response.success && (response as any).user) {
-                setUser((response as any).user);
+            if (response.success && response.data?.user) {
+                setUser(response.data.user);
             } else {
                 throw new Error(response.message || 'Token-Überprüfung fehlgeschlagen');
             }
@@ -52,50 +52,6 @@ const Dashboard = () => {
                 console.log('🚀 Creating new work hours entry:', formData);
                 console.log('🚀 Stunden value:', formData.Stunden, 'type:', typeof formData.Stunden);
 
-                // Check for existing entry on the same date (client-side check)
-                let existingEntries: WorkHourEntry[] = [];
-
-                // Get all entries from both personal and family data
-                if (dashboardData?.personal?.entries) {
-                    existingEntries = dashboardData.personal.entries;
-                }
-                if (dashboardData?.family?.memberContributions) {
-                    const memberEntries = dashboardData.family.memberContributions
-                        .filter((m: MemberContribution) => m.id === user?.id)
-                        .flatMap((m: MemberContribution) => m.entries || []);
-                    existingEntries = existingEntries.concat(memberEntries);
-                }
-
-                console.log('🔍 Checking for duplicates. Existing entries:', existingEntries.length);
-                console.log('🔍 Looking for date:', formData.Datum, 'name:', formData.Vorname, formData.Nachname);
-                console.log('🔍 All existing entries:', existingEntries.map(e => ({ id: e.id, Datum: e.Datum })));
-
-                const sameDate = existingEntries.find(entry => {
-                    // Since the dashboard data doesn't include names, we'll check by date only
-                    // This is still valid because each person can only have one entry per day
-                    const entryDate = entry.Datum;
-                    const formDate = formData.Datum;
-
-                    const dateMatch = entryDate === formDate;
-
-                    console.log('🔍 Comparing dates only (names not available in dashboard data):', {
-                        existingDate: entryDate,
-                        newDate: formDate,
-                        dateMatch: dateMatch
-                    });
-
-                    if (dateMatch) {
-                        console.log('🔍 Found matching date:', entry);
-                    }
-                    return dateMatch;
-                });
-
-                if (sameDate) {
-                    console.log('❌ Duplicate entry found, blocking creation');
-                    toast.error('Für dieses Datum existiert bereits ein Eintrag. Pro Person und Tag ist nur ein Eintrag erlaubt.');
-                    return;
-                }
-
                 // Use util to detect duplicates across family/personal entries (current member only)
                 const allExistingEntries = [
                     ...(dashboardData?.personal?.entries || []),
@@ -105,7 +61,7 @@ const Dashboard = () => {
                 ];
 
                 if (hasDuplicateEntry(allExistingEntries, formData)) {
-                    toast.error('Für dieses Datum existiert bereits ein Eintrag. Pro Person und Tag ist nur ein Eintrag erlaubt.');
+                    toast.error('Für dieses Datum existiert bereits ein Eintrag mit dieser Tätigkeit. Bitte ändere den bestehenden Eintrag.');
                     return;
                 }
 
@@ -129,9 +85,9 @@ const Dashboard = () => {
             } else {
                 console.log('🚀 Updating work hours entry:', editingRow.id, formData);
 
-                // For updates, check if we're changing the date and if so, check for duplicates
-                if (editingRow.Datum !== formData.Datum) {
-                    console.log('🔍 Date changed from', editingRow.Datum, 'to', formData.Datum, '- checking for duplicates');
+                // For updates, check for duplicates if the date or activity changes
+                if (editingRow.Datum !== formData.Datum || editingRow.Tätigkeit !== formData.Tätigkeit) {
+                    console.log('🔍 Date or activity changed - checking for duplicates');
 
                     // Consolidate entries and use the util to check for duplicates (excluding the edited entry)
                     const allExistingEntries = [
@@ -143,7 +99,7 @@ const Dashboard = () => {
 
                     if (hasDuplicateEntry(allExistingEntries, formData, editingRow.id)) {
                         console.log('❌ Duplicate entry found for new date, blocking update');
-                        toast.error('Für dieses Datum existiert bereits ein Eintrag. Pro Person und Tag ist nur ein Eintrag erlaubt.');
+                        toast.error('Für dieses Datum existiert bereits ein Eintrag mit dieser Tätigkeit. Bitte ändere den bestehenden Eintrag.');
                         return;
                     }
                 }
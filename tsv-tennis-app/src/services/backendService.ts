@@ -1,20 +1,31 @@
 import axios, { AxiosInstance } from 'axios';
 import type {
-  LoginResponse,
+  ErrorCode,
   LoginResponseVariant,
   CreateWorkHourRequest,
   DashboardResponse,
+  SessionResponse,
   WorkHourEntry
 } from '@/types';
 
-// Generic API result type with optional data payload
+// Envelope of the backend's JSON responses (`ApiResponse` in the generated types), with
+// the data of the endpoint in `data`
 interface ApiResult<T = undefined> {
   success: boolean;
   data?: T;
+  code?: ErrorCode;
   message?: string;
+  error?: string;
 }
 
-type ApiError = { success: false; message: string };
+type ApiError = { success: false; code?: ErrorCode; message: string; error?: string };
+
+// The `data` of a successful response, with `success` kept next to it for the callers
+type Unwrapped<T> = T & { success: true };
+
+function unwrap<T>(result: ApiResult<T>): Unwrapped<T> {
+  return { ...(result.data as T), success: true };
+}
 
 class BackendService {
   private api: AxiosInstance;
@@ -46,48 +57,66 @@ class BackendService {
         return Promise.reject(error);
       }
     );
+
+    // The backend re-issues tokens of members that were merged into another record, and
+    // with sliding expiration tokens that are about to expire
+    this.api.interceptors.response.use(
+      (response) => {
+        const refreshedToken = response.headers['x-refreshed-token'];
+        if (refreshedToken) {
+          localStorage.setItem('authToken', refreshedToken);
+        }
+        return response;
+      },
+      (error) => {
+        return Promise.reject(error);
+      }
+    );
   }
 
   // Authentication methods
-  async login(email: string, password: string): Promise<LoginResponseVariant | ApiError> {
+  async login(email: string, password: string): Promise<Unwrapped<LoginResponseVariant> | ApiError> {
     try {
       // Normalize email to lowercase for case-insensitive authentication
       const normalizedEmail = email.toLowerCase().trim();
-      const response = await this.api.post<LoginResponseVariant>('/login', { email: normalizedEmail, password });
-      return response.data;
+      const response = await this.api.post<ApiResult<LoginResponseVariant>>('/login', { email: normalizedEmail, password });
+      return unwrap(response.data);
     } catch (error: any) {
       console.error('Login error:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Anmeldung fehlgeschlagen'
       };
     }
   }
 
-  async selectMember(memberId: string, selectionToken: string): Promise<LoginResponse | ApiError> {
+  async selectMember(memberId: string, selectionToken: string): Promise<Unwrapped<LoginResponseVariant> | ApiError> {
     try {
-      const response = await this.api.post<LoginResponse>('/select-member', {
+      const response = await this.api.post<ApiResult<LoginResponseVariant>>('/select-member', {
         member_id: memberId,
         selection_token: selectionToken
       });
-      return response.data;
+      return unwrap(response.data);
     } catch (error: any) {
       console.error('Member selection error:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Mitgliederauswahl fehlgeschlagen'
       };
     }
   }
 
-  async verifyToken(): Promise<ApiResult> {
+  async verifyToken(): Promise<ApiResult<SessionResponse>> {
     try {
-      const response = await this.api.get<ApiResult>('/verify-token');
+      const response = await this.api.get<ApiResult<SessionResponse>>('/user');
       return response.data;
     } catch (error: any) {
       console.error('Token verification error:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Token-Überprüfung fehlgeschlagen'
       };
     }
@@ -97,12 +126,13 @@ class BackendService {
     try {
       // Normalize email to lowercase for case-insensitive password reset
       const normalizedEmail = email.toLowerCase().trim();
-      const response = await this.api.post<ApiResult>('/forgotPassword', { email: normalizedEmail });
+      const response = await this.api.post<ApiResult>('/forgot-password', { email: normalizedEmail });
       return response.data;
     } catch (error: any) {
       console.error('Forgot password error:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'E-Mail konnte nicht gesendet werden'
       };
     }
@@ -110,7 +140,7 @@ class BackendService {
 
   async resetPassword(token: string, password: string, userId: string): Promise<ApiResult | ApiError> {
     try {
-      const response = await this.api.post<ApiResult>('/resetPassword', {
+      const response = await this.api.post<ApiResult>('/reset-password', {
         token,
         password,
         userId
@@ -120,20 +150,22 @@ class BackendService {
       console.error('Reset password error:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Passwort-Zurücksetzung fehlgeschlagen'
       };
     }
   }
 
   // Dashboard methods
-  async getDashboard(year: number): Promise<DashboardResponse | ApiError> {
+  async getDashboard(year: number): Promise<Unwrapped<DashboardResponse> | ApiError> {
     try {
-      const response = await this.api.get<DashboardResponse>(`/dashboard/${year}`);
-      return response.data;
+      const response = await this.api.get<ApiResult<DashboardResponse>>(`/dashboard/${year}`);
+      return unwrap(response.data);
     } catch (error: any) {
       console.error('Dashboard error:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Dashboard-Daten konnten nicht geladen werden'
       };
     }
@@ -147,6 +179,7 @@ class BackendService {
       console.error('Error creating work hours:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Arbeitsstunden konnten nicht erstellt werden'
       };
     }
@@ -160,6 +193,7 @@ class BackendService {
       console.error('Error updating work hours:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Arbeitsstunden konnten nicht aktualisiert werden'
       };
     }
@@ -173,6 +207,7 @@ class BackendService {
       console.error('Error deleting work hours:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Arbeitsstunden konnten nicht gelöscht werden'
       };
     }
@@ -186,6 +221,7 @@ class BackendService {
       console.error('Error fetching work hour:', error);
       return {
         success: false,
+        code: error.response?.data?.code,
         message: error.response?.data?.message || 'Arbeitsstunde konnte nicht geladen werden'
       };
     }
@@ -2,6 +2,8 @@
 // Re-export all types from the auto-generated types.ts file
 
 export type {
+    ErrorCode,
+    ApiResponse,
     LoginRequest,
     LoginResponse,
     LoginResponseVariant,
@@ -11,12 +13,43 @@ export type {
     ForgotPasswordRequest,
     ResetPasswordRequest,
     UserResponse,
+    SessionResponse,
+    SessionUser,
     CreateWorkHourRequest,
     WorkHourResponse,
+    BulkWorkHourEntry,
+    BulkCreateWorkHoursRequest,
+    BulkWorkHourItem,
+    BulkItemError,
+    BulkItemResult,
+    BulkResult,
     WorkHourEntry,
+    WorkHourStatus,
+    ReviewWorkHourRequest,
+    ReviewQueueEntry,
+    OrphanedAccount,
+    MemberWithoutAccount,
+    ConsistencyReport,
+    ActivityKind,
+    ActivityEntry,
+    HeatmapWeek,
+    WorkHourHeatmap,
+    GuestSession,
+    CreateGuestSessionRequest,
+    GuestFeeSummary,
+    EventStatus,
+    WorkEvent,
+    EventSignup,
+    EventOverview,
+    CreateEventRequest,
+    EventAttendance,
+    CompleteEventRequest,
+    WorkHourRules,
     DashboardResponse,
     FamilyData,
     PersonalData,
     FamilyMember,
     MemberContribution,
+    DuesStatus,
+    DuesResponse,
 } from './types';
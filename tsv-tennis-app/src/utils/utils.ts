@@ -1,21 +1,29 @@
 // Shared utility functions for the frontend
 import type { WorkHourEntry } from '../types';
 
+// Activities are compared like the backend does, so "Hecke schneiden " and "hecke schneiden" match
+const normalizeActivity = (value: unknown): string =>
+    String(value ?? '').split(/\s+/).filter(Boolean).join(' ').toLowerCase();
+
+// Several entries per day are allowed as long as their activities differ (ENTRIES_PER_DAY=distinct
+// in the backend); with the stricter policy the backend's message is shown instead
 export function hasDuplicateEntry(
     existingEntries: WorkHourEntry[] = [],
-    formData: { Datum?: string;[key: string]: unknown },
+    formData: { Datum?: string; Tätigkeit?: unknown;[key: string]: unknown },
     editingId?: string | number
 ) {
     if (!existingEntries || existingEntries.length === 0) return false;
     const newDate = formData.Datum;
     if (!newDate) return false;
+    const newActivity = normalizeActivity(formData.Tätigkeit);
 
     return existingEntries.some((e: WorkHourEntry) => {
         if (!e || !e.Datum) return false;
         if (editingId && (e.id === editingId || String(e.id) === String(editingId))) return false;
+        // Rejected entries don't block a new entry
+        if (e.status === 'abgelehnt') return false;
 
-        // Otherwise fall back to date-only match
-        return e.Datum === newDate;
+        return e.Datum === newDate && normalizeActivity(e.Tätigkeit) === newActivity;
     });
 }
 